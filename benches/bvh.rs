@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use lox::{
+    core::{Mesh as LoxMesh, MeshMut},
+    FaceHandle, VertexHandle,
+};
+use meshup_core::core::editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh};
+
+fn cat_mesh() -> EditableMesh {
+    let mesh = bevy_obj::load_obj_from_bytes(include_bytes!("../src/assets/mesh/cat.obj"))
+        .unwrap()
+        .transformed_by(Transform::from_scale(Vec3::splat(0.1)))
+        .with_duplicated_vertices()
+        .with_computed_flat_normals();
+
+    EditableMesh::from(&mesh)
+}
+
+/// Roughly a million triangles.
+fn dense_sphere_mesh() -> EditableMesh {
+    let mesh = Sphere::new(1.0).mesh().uv(1000, 500);
+
+    EditableMesh::from(&mesh)
+}
+
+/// Every hundredth vertex, the equivalent of a local sculpt dab.
+fn moved_vertices(mesh: &EditableMesh) -> Vec<VertexHandle> {
+    mesh.structure.vertex_handles().step_by(100).collect()
+}
+
+fn displace(mesh: &mut EditableMesh, vertices: &[VertexHandle]) {
+    for vertex_handle in vertices.iter().cloned() {
+        let position = mesh.vertex_positions[vertex_handle];
+        mesh.vertex_positions[vertex_handle] = position * 1.05;
+    }
+}
+
+fn bench_mesh(c: &mut Criterion, name: &str, mut mesh: EditableMesh) {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+
+    group.bench_function("build", |b| {
+        b.iter(|| BoundingVolumeHierarchy::from(black_box(&mesh)))
+    });
+
+    let vertices = moved_vertices(&mesh);
+    displace(&mut mesh, &vertices);
+
+    group.bench_function("refit", |b| {
+        b.iter_batched(
+            || BoundingVolumeHierarchy::from(&mesh),
+            |mut bvh| bvh.refit(&mesh, vertices.iter().cloned()),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("refit_all", |b| {
+        b.iter_batched(
+            || BoundingVolumeHierarchy::from(&mesh),
+            |mut bvh| bvh.refit_all(&mesh),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.bench_function("sah_cost", |b| {
+        let bvh = BoundingVolumeHierarchy::from(&mesh);
+        b.iter(|| black_box(&bvh).sah_cost())
+    });
+
+    // Replace a small patch of faces around one point, like an extrusion would
+    let bvh = BoundingVolumeHierarchy::from(&mesh);
+    let center = mesh.vertex_positions[mesh.structure.vertex_handles().next().unwrap()];
+    let mut radius = 0.001;
+    let removed: Vec<FaceHandle> = loop {
+        let faces = bvh.faces_in_sphere(center, radius, &GlobalTransform::IDENTITY, &mesh);
+        if faces.len() >= 16 {
+            break faces;
+        }
+        radius *= 2.0;
+    };
+
+    for face_handle in removed.iter().cloned() {
+        mesh.structure.remove_face(face_handle);
+    }
+
+    group.bench_function("rebuild_region", |b| {
+        b.iter_batched(
+            || bvh.clone(),
+            |mut bvh| bvh.rebuild_region(&mesh, removed.iter().cloned(), []),
+            BatchSize::LargeInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn bvh_benchmarks(c: &mut Criterion) {
+    bench_mesh(c, "bvh_cat", cat_mesh());
+    bench_mesh(c, "bvh_million_triangles", dense_sphere_mesh());
+}
+
+criterion_group!(benches, bvh_benchmarks);
+criterion_main!(benches);
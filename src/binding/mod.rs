@@ -10,12 +10,31 @@ use unsafe_world::UnsafeWorld;
 use wasm_bindgen::prelude::*;
 
 use crate::core::{
-    editable_mesh::EditableMeshBundle,
-    editor::{ActiveTool, Focused, Tools, UserSpace, ViewportMaterial},
-    grid::Grid3d,
+    camera_bookmarks::{CameraBookmarks, CameraBookmarksPlugin},
+    edit_overlay::EditOverlaySettings,
+    editable_mesh::{EditableMesh, EditableMeshBundle, SelectMode, SurfaceMaterial},
+    editor::{Cursor3d, Focused, UserSpace, ViewportMaterial},
+    fly_camera::{FlyCameraPlugin, FlyCameraSettings},
+    formats::{self, gltf::GltfExporter, ply, stl, FormatError},
+    grid::{Grid3d, GridAxis, GridPlane, GridSettings, GridUnits},
     highlight::Highlight,
-    interaction::InteractionMode,
-    tools::ToolType,
+    interaction::{Hovered, InteractionMode, InteractionPlugin},
+    keymap::{EditorAction, InputBinding, Keymap, KeymapPreset},
+    pan_orbit_camera::{
+        CameraProjectionKind, PanOrbitCameraPlugin, PanOrbitState, PrimaryCamera, ViewAxis,
+    },
+    project::{MeshData, ProjectFile},
+    shading::{ShadingMode, ViewportShading},
+    snapping::{SnapIncrementMode, SnapSettings, SnapTarget},
+    stats::StatsPlugin,
+    tools::{
+        cursor::CursorTool,
+        measure::MeasureTool,
+        modal::{ModalTransformKind, ModalTransformPlugin},
+        proportional::{ProportionalEditing, ProportionalFalloff},
+        transform_space::{PivotPoint, TransformFrame, TransformOrientation, TransformSpace},
+        ToolType,
+    },
 };
 
 use super::core::editor::EditorPlugin;
@@ -293,300 +312,1497 @@ pub fn is_grid_active() -> bool {
 }
 
 #[wasm_bindgen]
-pub fn set_entity_transform(entity_index: u32, transport_transform: &transport::Transform) {
+pub fn set_grid_units(units: GridUnits) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
-        return;
-    };
+    world.resource_mut::<GridSettings>().units = units;
+    wakeup_world(&world);
+}
 
-    *entity.get_mut::<Transform>().unwrap() = (*transport_transform).into();
+#[wasm_bindgen]
+pub fn get_grid_units() -> GridUnits {
+    let Some(world) = world() else {
+        return GridUnits::default();
+    };
 
-    wakeup_world(&world);
+    world.resource::<GridSettings>().units
 }
 
+/// Distance between the finest lines at the closest level of detail, in the grid units
 #[wasm_bindgen]
-pub fn set_active_tool(tool_type: ToolType) {
+pub fn set_grid_spacing(spacing: f32) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let tool = match world.get_resource::<Tools>().unwrap().map.get(&tool_type) {
-        Some(tool) => tool.clone(),
-        None => return,
-    };
-
-    let mut active_tool = world.get_resource_mut::<ActiveTool>().unwrap();
+    if spacing <= 0.0 {
+        return;
+    }
 
-    let mut last_active_tool_cleanup = None;
-    let new_active_tool_startup = tool.startup_system;
+    world.resource_mut::<GridSettings>().spacing = spacing;
+    wakeup_world(&world);
+}
 
-    if let Some(active_tool) = &active_tool.0 {
-        last_active_tool_cleanup = active_tool.cleanup_system.clone();
-    }
+#[wasm_bindgen]
+pub fn get_grid_spacing() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
+    };
 
-    active_tool.0 = Some(tool);
+    world.resource::<GridSettings>().spacing
+}
 
-    if let Some(last_active_tool_cleanup) = last_active_tool_cleanup {
-        world.run_system(last_active_tool_cleanup).unwrap();
-    }
+/// Spacing of the finest lines currently drawn, in world units. Also what moves snap to.
+#[wasm_bindgen]
+pub fn get_visible_grid_spacing() -> f32 {
+    let Some(mut world) = world_mut() else {
+        return 0.0;
+    };
 
-    if let Some(new_active_tool_startup) = new_active_tool_startup {
-        world.run_system(new_active_tool_startup).unwrap();
-    }
+    let mut query = world.query_filtered::<&PanOrbitState, With<PrimaryCamera>>();
+    let radius = query.get_single(&world).map_or(0.0, |state| state.radius);
 
-    wakeup_world(&world);
+    world.resource::<GridSettings>().spacing_at(radius)
 }
 
 #[wasm_bindgen]
-pub fn unset_active_tool() {
+pub fn set_grid_subdivisions(subdivisions: u32) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let mut active_tool = world.get_resource_mut::<ActiveTool>().unwrap();
-
-    let mut last_active_tool_cleanup = None;
+    world.resource_mut::<GridSettings>().subdivisions =
+        subdivisions.clamp(1, GridSettings::MAX_SUBDIVISIONS);
+    wakeup_world(&world);
+}
 
-    if let Some(active_tool) = &active_tool.0 {
-        if let Some(cleanup_system) = active_tool.cleanup_system {
-            last_active_tool_cleanup = Some(cleanup_system);
-        }
-    }
+#[wasm_bindgen]
+pub fn get_grid_subdivisions() -> u32 {
+    let Some(world) = world() else {
+        return 0;
+    };
 
-    active_tool.0 = None;
+    world.resource::<GridSettings>().subdivisions
+}
 
-    if let Some(cleanup_system) = last_active_tool_cleanup {
-        world.run_system(cleanup_system).unwrap();
-    }
+#[wasm_bindgen]
+pub fn set_grid_fade_distance(distance: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
 
+    world.resource_mut::<GridSettings>().fade_distance = distance.max(0.0);
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn get_root_entities() -> Vec<u32> {
-    let Some(mut world) = world_mut() else {
-        return vec![];
+pub fn get_grid_fade_distance() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
     };
 
-    let mut root_entities = world.query_filtered::<Entity, (With<UserSpace>, Without<Parent>)>();
+    world.resource::<GridSettings>().fade_distance
+}
 
-    let ids: Vec<u32> = root_entities
-        .iter(&mut world)
-        .map(|entity| entity.index())
-        .collect();
-    return ids;
+#[wasm_bindgen]
+pub fn set_grid_color(color: &transport::Color) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<GridSettings>().color = (*color).into();
+    wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn get_entity_children(entity_index: u32) -> Vec<u32> {
+pub fn get_grid_color() -> transport::Color {
     let Some(world) = world() else {
-        return vec![];
+        return default();
     };
 
-    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
-        return vec![];
-    };
-    match entity.get::<Children>() {
-        Some(children) => children.iter().map(|entity| entity.index()).collect(),
-        None => vec![],
-    }
+    world.resource::<GridSettings>().color.into()
 }
 
 #[wasm_bindgen]
-pub fn toggle_entity_visibility(entity_index: u32, visible: bool) {
+pub fn set_grid_axis_lines(enabled: bool) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
-        return;
+    world.resource_mut::<GridSettings>().axis_lines = enabled;
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn has_grid_axis_lines() -> bool {
+    let Some(world) = world() else {
+        return false;
     };
 
-    let mut visibility = entity.get_mut::<Visibility>().unwrap();
+    world.resource::<GridSettings>().axis_lines
+}
 
-    if visible {
-        *visibility = Visibility::Inherited;
-    } else {
-        *visibility = Visibility::Hidden;
-    }
+#[wasm_bindgen]
+pub fn set_grid_axis_color(axis: GridAxis, color: &transport::Color) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
 
+    world.resource_mut::<GridSettings>().axis_colors[axis as usize] = (*color).into();
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn get_entity_visibility(entity_index: u32) -> bool {
+pub fn get_grid_axis_color(axis: GridAxis) -> transport::Color {
     let Some(world) = world() else {
-        return false;
+        return default();
     };
 
-    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
-        return false;
+    world.resource::<GridSettings>().axis_color(axis).into()
+}
+
+/// Lets the grid switch to coarser or finer lines as the camera zooms
+#[wasm_bindgen]
+pub fn set_grid_adaptive(adaptive: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
     };
 
-    let visibility = entity.get::<Visibility>().unwrap();
+    world.resource_mut::<GridSettings>().adaptive = adaptive;
+    wakeup_world(&world);
+}
 
-    if *visibility == Visibility::Hidden {
+#[wasm_bindgen]
+pub fn is_grid_adaptive() -> bool {
+    let Some(world) = world() else {
         return false;
-    } else {
-        return true;
-    }
+    };
+
+    world.resource::<GridSettings>().adaptive
 }
 
+/// Shows the grid on the plane facing orthographic views along the X or Z axis
 #[wasm_bindgen]
-pub fn get_active_entity() -> i32 {
+pub fn set_grid_side_views(enabled: bool) {
     let Some(mut world) = world_mut() else {
-        return 0;
+        return;
     };
 
-    let mut active_entity = world.query_filtered::<Entity, With<Focused>>();
-
-    match active_entity.get_single(&world) {
-        Ok(entity) => entity.index() as i32,
-        Err(_) => -1,
-    }
+    world.resource_mut::<GridSettings>().side_view_grids = enabled;
+    wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn is_entity_focused(entity_index: u32) -> bool {
+pub fn has_grid_side_views() -> bool {
     let Some(world) = world() else {
         return false;
     };
 
-    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
-        return false;
+    world.resource::<GridSettings>().side_view_grids
+}
+
+/// Plane the grid is currently drawn on
+#[wasm_bindgen]
+pub fn get_grid_plane() -> GridPlane {
+    let Some(mut world) = world_mut() else {
+        return GridPlane::default();
     };
 
-    entity.get::<Focused>().is_some()
+    let mut query = world.query_filtered::<&GridPlane, With<Grid3d>>();
+    query.get_single(&world).copied().unwrap_or_default()
 }
 
 #[wasm_bindgen]
-pub fn focus_entity(entity_index: u32) {
+pub fn set_shading_mode(mode: ShadingMode) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let mut query = world.query_filtered::<Entity, With<Focused>>();
+    world.resource_mut::<ViewportShading>().mode = mode;
+    wakeup_world(&world);
+}
 
-    match query.get_single(&world) {
-        Ok(entity) => {
-            let Some(mut entity_ref) = world.get_entity_mut(entity) else {
-                return;
-            };
+#[wasm_bindgen]
+pub fn get_shading_mode() -> ShadingMode {
+    let Some(world) = world() else {
+        return ShadingMode::default();
+    };
 
-            entity_ref.remove::<Focused>();
-        }
-        _ => {}
-    }
+    world.resource::<ViewportShading>().mode
+}
 
-    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+/// Darkens concave areas and lightens convex edges in solid and x-ray shading
+#[wasm_bindgen]
+pub fn set_cavity(enabled: bool) {
+    let Some(mut world) = world_mut() else {
         return;
     };
 
-    entity.insert(Focused);
-
+    world.resource_mut::<ViewportShading>().cavity = enabled;
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn unfocus() {
+pub fn is_cavity_enabled() -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<ViewportShading>().cavity
+}
+
+/// Opacity of the surfaces in x-ray shading, from 0 to 1
+#[wasm_bindgen]
+pub fn set_xray_alpha(alpha: f32) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let mut query = world.query_filtered::<Entity, With<Focused>>();
-
-    match query.get_single(&world) {
-        Ok(entity) => {
-            let Some(mut entity_ref) = world.get_entity_mut(entity) else {
-                return;
-            };
+    world.resource_mut::<ViewportShading>().xray_alpha = alpha.clamp(0.0, 1.0);
+    wakeup_world(&world);
+}
 
-            entity_ref.remove::<Focused>();
-        }
-        _ => {}
-    }
+#[wasm_bindgen]
+pub fn get_xray_alpha() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
+    };
 
-    wakeup_world(&world);
+    world.resource::<ViewportShading>().xray_alpha
 }
 
 #[wasm_bindgen]
-pub fn set_interaction_mode(mode: InteractionMode) {
+pub fn set_normals_overlay(enabled: bool) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    // For interaction modes asides InteractionMode::Object, there must be a focused enitity for the mode to be valid.
-    let mut focused_entity = world.query_filtered::<(), With<Focused>>();
+    world.resource_mut::<ViewportShading>().normals_overlay = enabled;
+    wakeup_world(&world);
+}
 
-    match &mode {
-        InteractionMode::Object => {}
-        _ => {
-            if let Err(_) = focused_entity.get_single(&world) {
-                return;
-            }
-            // focused_entity.get_single(&world)
-            // .expect("Setting interaction mode to any other than InteractionMode::Object requires a focused entity, as interaction operations will be done relative to that entity");
-        }
+#[wasm_bindgen]
+pub fn has_normals_overlay() -> bool {
+    let Some(world) = world() else {
+        return false;
     };
 
-    let mut interaction_mode = world.get_resource_mut::<InteractionMode>().unwrap();
+    world.resource::<ViewportShading>().normals_overlay
+}
 
-    *interaction_mode = mode;
+/// World length of the lines drawn by the normals overlay
+#[wasm_bindgen]
+pub fn set_normal_length(length: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
 
+    world.resource_mut::<ViewportShading>().normal_length = length.max(0.0);
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn get_interaction_mode() -> InteractionMode {
-    let world = world().unwrap();
-
-    let interaction_mode = world.get_resource::<InteractionMode>().unwrap();
+pub fn get_normal_length() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
+    };
 
-    interaction_mode.clone()
+    world.resource::<ViewportShading>().normal_length
 }
 
+/// Draws the edges of the focused meshes over their surfaces
 #[wasm_bindgen]
-pub fn highlight_entity(entity_index: u32) {
+pub fn set_focused_wireframe(enabled: bool) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
-        return;
+    world.resource_mut::<ViewportShading>().focused_wireframe = enabled;
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn has_focused_wireframe() -> bool {
+    let Some(world) = world() else {
+        return false;
     };
 
-    entity.insert(Highlight);
+    world.resource::<ViewportShading>().focused_wireframe
+}
+
+/// Lines along the vertex normals of the focused meshes in edit mode
+#[wasm_bindgen]
+pub fn set_overlay_vertex_normals(enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
 
+    world.resource_mut::<EditOverlaySettings>().vertex_normals = enabled;
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn unhighlight_entity(entity_index: u32) {
+pub fn has_overlay_vertex_normals() -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<EditOverlaySettings>().vertex_normals
+}
+
+/// Lines along the face normals of the focused meshes in edit mode
+#[wasm_bindgen]
+pub fn set_overlay_face_normals(enabled: bool) {
     let Some(mut world) = world_mut() else {
         return;
     };
 
-    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
-        return;
+    world.resource_mut::<EditOverlaySettings>().face_normals = enabled;
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn has_overlay_face_normals() -> bool {
+    let Some(world) = world() else {
+        return false;
     };
 
-    entity.remove::<Highlight>();
+    world.resource::<EditOverlaySettings>().face_normals
+}
 
+/// Hides the vertices, edges and faces behind the surfaces in edit mode, except in x-ray
+#[wasm_bindgen]
+pub fn set_hide_occluded_elements(enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<EditOverlaySettings>().hide_occluded = enabled;
     wakeup_world(&world);
 }
 
 #[wasm_bindgen]
-pub fn is_highlighted(entity_index: u32) -> bool {
+pub fn is_hiding_occluded_elements() -> bool {
     let Some(world) = world() else {
         return false;
     };
 
-    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
-        return false;
+    world.resource::<EditOverlaySettings>().hide_occluded
+}
+
+#[wasm_bindgen]
+pub fn get_scene_stats() -> transport::SceneStats {
+    let Some(mut world) = world_mut() else {
+        return transport::SceneStats::default();
     };
 
-    entity.get::<Highlight>().is_some()
+    (&StatsPlugin::collect(&mut world)).into()
+}
+
+/// Shows or hides the statistics overlay, also toggled with Shift+F12 by default
+#[wasm_bindgen]
+pub fn toggle_stats_overlay(visible: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    StatsPlugin::set_visible(&mut world, visible);
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn is_stats_overlay_visible() -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    StatsPlugin::is_visible(&mut world)
+}
+
+#[wasm_bindgen]
+pub fn set_entity_transform(entity_index: u32, transport_transform: &transport::Transform) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+        return;
+    };
+
+    *entity.get_mut::<Transform>().unwrap() = (*transport_transform).into();
+
+    wakeup_world(&world);
+}
+
+/// Material the mesh is exported with. The viewport keeps drawing it with the shading mode.
+#[wasm_bindgen]
+pub fn set_entity_material(
+    entity_index: u32,
+    base_color: &transport::Color,
+    metallic: f32,
+    roughness: f32,
+) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let entity = Entity::from_raw(entity_index);
+    let Some(surface_material) = world.get::<SurfaceMaterial>(entity).cloned() else {
+        return;
+    };
+
+    let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+    let material = StandardMaterial {
+        base_color: (*base_color).into(),
+        metallic: metallic.clamp(0.0, 1.0),
+        perceptual_roughness: roughness.clamp(0.0, 1.0),
+        ..default()
+    };
+    match materials.get_mut(&surface_material.0) {
+        Some(existing) => *existing = material,
+        None => {
+            let handle = materials.add(material);
+            world.entity_mut(entity).insert(SurfaceMaterial(handle));
+        }
+    }
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn set_active_tool(tool_type: ToolType) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    EditorPlugin::set_active_tool(&mut world, tool_type);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn unset_active_tool() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    EditorPlugin::unset_active_tool(&mut world);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_root_entities() -> Vec<u32> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    let mut root_entities = world.query_filtered::<Entity, (With<UserSpace>, Without<Parent>)>();
+
+    let ids: Vec<u32> = root_entities
+        .iter(&mut world)
+        .map(|entity| entity.index())
+        .collect();
+    return ids;
+}
+
+#[wasm_bindgen]
+pub fn get_entity_children(entity_index: u32) -> Vec<u32> {
+    let Some(world) = world() else {
+        return vec![];
+    };
+
+    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
+        return vec![];
+    };
+    match entity.get::<Children>() {
+        Some(children) => children.iter().map(|entity| entity.index()).collect(),
+        None => vec![],
+    }
+}
+
+#[wasm_bindgen]
+pub fn toggle_entity_visibility(entity_index: u32, visible: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+        return;
+    };
+
+    let mut visibility = entity.get_mut::<Visibility>().unwrap();
+
+    if visible {
+        *visibility = Visibility::Inherited;
+    } else {
+        *visibility = Visibility::Hidden;
+    }
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_entity_visibility(entity_index: u32) -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
+        return false;
+    };
+
+    let visibility = entity.get::<Visibility>().unwrap();
+
+    if *visibility == Visibility::Hidden {
+        return false;
+    } else {
+        return true;
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_active_entity() -> i32 {
+    let Some(mut world) = world_mut() else {
+        return 0;
+    };
+
+    let mut active_entity = world.query_filtered::<Entity, With<Focused>>();
+
+    match active_entity.get_single(&world) {
+        Ok(entity) => entity.index() as i32,
+        Err(_) => -1,
+    }
+}
+
+#[wasm_bindgen]
+pub fn is_entity_focused(entity_index: u32) -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
+        return false;
+    };
+
+    entity.get::<Focused>().is_some()
+}
+
+#[wasm_bindgen]
+pub fn focus_entity(entity_index: u32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let mut query = world.query_filtered::<Entity, With<Focused>>();
+
+    match query.get_single(&world) {
+        Ok(entity) => {
+            let Some(mut entity_ref) = world.get_entity_mut(entity) else {
+                return;
+            };
+
+            entity_ref.remove::<Focused>();
+        }
+        _ => {}
+    }
+
+    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+        return;
+    };
+
+    entity.insert(Focused);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn unfocus() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let mut query = world.query_filtered::<Entity, With<Focused>>();
+
+    match query.get_single(&world) {
+        Ok(entity) => {
+            let Some(mut entity_ref) = world.get_entity_mut(entity) else {
+                return;
+            };
+
+            entity_ref.remove::<Focused>();
+        }
+        _ => {}
+    }
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn set_interaction_mode(mode: InteractionMode) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    InteractionPlugin::set_interaction_mode(&mut world, mode);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_interaction_mode() -> InteractionMode {
+    let world = world().unwrap();
+
+    let interaction_mode = world.get_resource::<InteractionMode>().unwrap();
+
+    interaction_mode.clone()
+}
+
+#[wasm_bindgen]
+pub fn set_select_mode(mode: SelectMode) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    *world.get_resource_mut::<SelectMode>().unwrap() = mode;
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_select_mode() -> SelectMode {
+    let world = world().unwrap();
+
+    world.get_resource::<SelectMode>().unwrap().clone()
+}
+
+#[wasm_bindgen]
+pub fn highlight_entity(entity_index: u32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+        return;
+    };
+
+    entity.insert(Highlight);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn unhighlight_entity(entity_index: u32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let Some(mut entity) = world.get_entity_mut(Entity::from_raw(entity_index)) else {
+        return;
+    };
+
+    entity.remove::<Highlight>();
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn is_highlighted(entity_index: u32) -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    let Some(entity) = world.get_entity(Entity::from_raw(entity_index)) else {
+        return false;
+    };
+
+    entity.get::<Highlight>().is_some()
+}
+
+#[wasm_bindgen]
+pub fn get_hovered_entity() -> i32 {
+    let Some(mut world) = world_mut() else {
+        return -1;
+    };
+
+    let mut hovered_entity = world.query_filtered::<Entity, With<Hovered>>();
+
+    match hovered_entity.get_single(&world) {
+        Ok(entity) => entity.index() as i32,
+        Err(_) => -1,
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_entities_in_screen_rect(min_x: f32, min_y: f32, max_x: f32, max_y: f32) -> Vec<u32> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    InteractionPlugin::entities_in_screen_rect(
+        &mut world,
+        Vec2::new(min_x, min_y),
+        Vec2::new(max_x, max_y),
+    )
+    .into_iter()
+    .map(|entity| entity.index())
+    .collect()
+}
+
+#[wasm_bindgen]
+pub fn get_camera_projection() -> Option<CameraProjectionKind> {
+    let Some(mut world) = world_mut() else {
+        return None;
+    };
+
+    PanOrbitCameraPlugin::projection_kind(&mut world)
+}
+
+#[wasm_bindgen]
+pub fn set_camera_projection(kind: CameraProjectionKind) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    PanOrbitCameraPlugin::set_projection(&mut world, kind);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn toggle_camera_projection() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    PanOrbitCameraPlugin::toggle_projection(&mut world);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn snap_camera_view(axis: ViewAxis) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    PanOrbitCameraPlugin::snap_to_axis(&mut world, axis);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn frame_selected() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    EditorPlugin::frame_selected(&mut world);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn frame_all() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    EditorPlugin::frame_all(&mut world);
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn save_camera_bookmark(name: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    CameraBookmarksPlugin::save(&mut world, &name)
+}
+
+#[wasm_bindgen]
+pub fn recall_camera_bookmark(name: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    let recalled = CameraBookmarksPlugin::recall(&mut world, &name);
+
+    wakeup_world(&world);
+
+    recalled
+}
+
+#[wasm_bindgen]
+pub fn list_camera_bookmarks() -> Vec<String> {
+    let Some(world) = world() else {
+        return vec![];
+    };
+
+    world.resource::<CameraBookmarks>().names()
+}
+
+#[wasm_bindgen]
+pub fn rename_camera_bookmark(from: String, to: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    world.resource_mut::<CameraBookmarks>().rename(&from, &to)
+}
+
+#[wasm_bindgen]
+pub fn delete_camera_bookmark(name: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    world.resource_mut::<CameraBookmarks>().remove(&name)
+}
+
+#[wasm_bindgen]
+pub fn set_turntable(enabled: bool, degrees_per_second: Option<f32>) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    CameraBookmarksPlugin::set_turntable(
+        &mut world,
+        enabled,
+        degrees_per_second.map(f32::to_radians),
+    );
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn set_keymap_preset(preset: KeymapPreset) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.insert_resource(Keymap::preset(preset));
+}
+
+/// Serialised keymap, one `action = binding` line per binding
+#[wasm_bindgen]
+pub fn get_keymap() -> String {
+    let Some(world) = world() else {
+        return String::new();
+    };
+
+    world.resource::<Keymap>().to_text()
+}
+
+/// Replaces the keymap with a serialised one. Returns the parse error, if any.
+#[wasm_bindgen]
+pub fn set_keymap(text: String) -> Option<String> {
+    let Some(mut world) = world_mut() else {
+        return None;
+    };
+
+    match Keymap::from_text(&text) {
+        Ok(keymap) => {
+            world.insert_resource(keymap);
+            None
+        }
+        Err(error) => Some(error.to_string()),
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_action_bindings(action: EditorAction) -> Vec<String> {
+    let Some(world) = world() else {
+        return vec![];
+    };
+
+    world
+        .resource::<Keymap>()
+        .bindings_for(action)
+        .map(|binding| binding.to_string())
+        .collect()
+}
+
+/// Replaces the bindings of an action, written like `Ctrl+Shift+KeyZ`. Nothing changes if one
+/// of them cannot be parsed.
+#[wasm_bindgen]
+pub fn rebind_action(action: EditorAction, bindings: Vec<String>) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    let Some(bindings) = bindings
+        .iter()
+        .map(|binding| InputBinding::parse(binding))
+        .collect::<Option<Vec<_>>>()
+    else {
+        return false;
+    };
+
+    world.resource_mut::<Keymap>().rebind(action, bindings);
+
+    true
+}
+
+#[wasm_bindgen]
+pub fn get_binding_action(binding: String) -> Option<EditorAction> {
+    let world = world()?;
+
+    world
+        .resource::<Keymap>()
+        .action_for(&InputBinding::parse(&binding)?)
+}
+
+/// Enters or leaves fly navigation. Leaving keeps the camera where it is and orbits around the
+/// surface in front of it.
+#[wasm_bindgen]
+pub fn set_fly_mode(enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    if enabled {
+        FlyCameraPlugin::enter(&mut world);
+    } else {
+        FlyCameraPlugin::exit(&mut world, false);
+    }
+
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn is_fly_mode() -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    FlyCameraPlugin::is_active(&mut world)
+}
+
+/// Fly speed in world units per second
+#[wasm_bindgen]
+pub fn get_fly_speed() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
+    };
+
+    world.resource::<FlyCameraSettings>().speed
+}
+
+#[wasm_bindgen]
+pub fn set_fly_speed(speed: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let mut settings = world.resource_mut::<FlyCameraSettings>();
+    settings.speed = speed.clamp(settings.min_speed, settings.max_speed);
+}
+
+#[wasm_bindgen]
+pub fn set_snapping_enabled(enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<SnapSettings>().enabled = enabled;
+}
+
+#[wasm_bindgen]
+pub fn is_snapping_enabled() -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<SnapSettings>().enabled
+}
+
+#[wasm_bindgen]
+pub fn set_snap_target(target: SnapTarget, enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let mut settings = world.resource_mut::<SnapSettings>();
+    if enabled {
+        settings.targets.insert(target);
+    } else {
+        settings.targets.remove(&target);
+    }
+}
+
+#[wasm_bindgen]
+pub fn is_snap_target_enabled(target: SnapTarget) -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<SnapSettings>().targets.contains(&target)
+}
+
+#[wasm_bindgen]
+pub fn set_snap_increment_mode(mode: SnapIncrementMode) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<SnapSettings>().increment_mode = mode;
+}
+
+/// World units. Without an increment, moves snap to the spacing of the visible grid.
+#[wasm_bindgen]
+pub fn set_translation_snap_increment(increment: Option<f32>) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<SnapSettings>().translation_increment = increment;
+}
+
+#[wasm_bindgen]
+pub fn set_scale_snap_increment(increment: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<SnapSettings>().scale_increment = increment;
+}
+
+#[wasm_bindgen]
+pub fn set_rotation_snap_increment(degrees: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<SnapSettings>().rotation_increment = degrees.to_radians();
+}
+
+/// Lets edit-mode transforms also move the vertices around the selection
+#[wasm_bindgen]
+pub fn set_proportional_editing(enabled: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<ProportionalEditing>().enabled = enabled;
+}
+
+#[wasm_bindgen]
+pub fn is_proportional_editing() -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<ProportionalEditing>().enabled
+}
+
+#[wasm_bindgen]
+pub fn set_proportional_falloff(falloff: ProportionalFalloff) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<ProportionalEditing>().falloff = falloff;
+}
+
+#[wasm_bindgen]
+pub fn get_proportional_falloff() -> ProportionalFalloff {
+    let Some(world) = world() else {
+        return ProportionalFalloff::Smooth;
+    };
+
+    world.resource::<ProportionalEditing>().falloff
+}
+
+/// World units
+#[wasm_bindgen]
+pub fn set_proportional_radius(radius: f32) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<ProportionalEditing>().radius = radius.max(0.0);
+}
+
+#[wasm_bindgen]
+pub fn get_proportional_radius() -> f32 {
+    let Some(world) = world() else {
+        return 0.0;
+    };
+
+    world.resource::<ProportionalEditing>().radius
+}
+
+/// Measures the falloff along the edges instead of in a straight line
+#[wasm_bindgen]
+pub fn set_proportional_connected_only(connected_only: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<ProportionalEditing>().connected_only = connected_only;
+}
+
+#[wasm_bindgen]
+pub fn is_proportional_connected_only() -> bool {
+    let Some(world) = world() else {
+        return false;
+    };
+
+    world.resource::<ProportionalEditing>().connected_only
+}
+
+/// Starts moving, rotating or scaling the focused entity, or its selected vertices in edit mode,
+/// from the keyboard and pointer. Returns false if nothing is focused or selected, or another
+/// modal transform is running.
+#[wasm_bindgen]
+pub fn begin_modal_transform(kind: ModalTransformKind) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    let started = ModalTransformPlugin::begin(&mut world, kind);
+    wakeup_world(&world);
+    started
+}
+
+/// Confirms the running modal transform, or restores the original transform when cancelling
+#[wasm_bindgen]
+pub fn end_modal_transform(cancel: bool) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    ModalTransformPlugin::finish(&mut world, cancel);
+    wakeup_world(&world);
+}
+
+/// Header text of the running modal transform, like `Move  X: 2.5000  Y: 0.0000  Z: 0.0000`
+#[wasm_bindgen]
+pub fn get_modal_transform_readout() -> Option<String> {
+    let world = world()?;
+
+    ModalTransformPlugin::readout(&world)
+}
+
+#[wasm_bindgen]
+pub fn set_transform_orientation(orientation: TransformOrientation) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<TransformSpace>().orientation = orientation;
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_transform_orientation() -> TransformOrientation {
+    let Some(world) = world() else {
+        return TransformOrientation::Global;
+    };
+
+    world.resource::<TransformSpace>().orientation
+}
+
+#[wasm_bindgen]
+pub fn set_pivot_point(pivot: PivotPoint) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    world.resource_mut::<TransformSpace>().pivot = pivot;
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn get_pivot_point() -> PivotPoint {
+    let Some(world) = world() else {
+        return PivotPoint::MedianPoint;
+    };
+
+    world.resource::<TransformSpace>().pivot
+}
+
+/// Saves the current gizmo orientation under `name` and switches to it
+#[wasm_bindgen]
+pub fn save_custom_orientation(name: String) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let rotation = world.resource::<TransformFrame>().orientation;
+    let mut space = world.resource_mut::<TransformSpace>();
+    space.save_custom(&name, rotation);
+    space.orientation = TransformOrientation::Custom;
+}
+
+/// Switches to a saved orientation. Returns false if there is none with this name.
+#[wasm_bindgen]
+pub fn select_custom_orientation(name: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    let selected = world.resource_mut::<TransformSpace>().select_custom(&name);
+    wakeup_world(&world);
+    selected
+}
+
+#[wasm_bindgen]
+pub fn remove_custom_orientation(name: String) -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    world.resource_mut::<TransformSpace>().remove_custom(&name)
+}
+
+#[wasm_bindgen]
+pub fn get_custom_orientations() -> Vec<String> {
+    let Some(world) = world() else {
+        return vec![];
+    };
+
+    world
+        .resource::<TransformSpace>()
+        .custom_orientations
+        .iter()
+        .map(|orientation| orientation.name.clone())
+        .collect()
+}
+
+/// Position and orientation of the 3D cursor, with a unit scale
+#[wasm_bindgen]
+pub fn get_cursor_3d() -> transport::Transform {
+    let Some(world) = world() else {
+        return transport::Transform::default();
+    };
+
+    let cursor = world.resource::<Cursor3d>();
+    Transform::from_translation(cursor.position)
+        .with_rotation(cursor.orientation)
+        .into()
+}
+
+/// Moves the 3D cursor. The scale is ignored.
+#[wasm_bindgen]
+pub fn set_cursor_3d(transport_transform: &transport::Transform) {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    let transform: Transform = (*transport_transform).into();
+    let mut cursor = world.resource_mut::<Cursor3d>();
+    cursor.position = transform.translation;
+    cursor.orientation = transform.rotation.normalize();
+    wakeup_world(&world);
+}
+
+#[wasm_bindgen]
+pub fn reset_cursor_3d() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    CursorTool::reset(&mut world);
+    wakeup_world(&world);
+}
+
+/// Moves the 3D cursor to the median of the selection. Returns false if nothing is selected.
+#[wasm_bindgen]
+pub fn snap_cursor_to_selection() -> bool {
+    let Some(mut world) = world_mut() else {
+        return false;
+    };
+
+    let snapped = CursorTool::snap_to_selection(&mut world);
+    wakeup_world(&world);
+    snapped
+}
+
+/// Distance in world units between the first two points of the measure tool
+#[wasm_bindgen]
+pub fn get_measurement_distance() -> Option<f32> {
+    let world = world()?;
+
+    MeasureTool::distance(&world)
+}
+
+/// Angle in degrees at the second point of the measure tool, once three points are placed
+#[wasm_bindgen]
+pub fn get_measurement_angle() -> Option<f32> {
+    let world = world()?;
+
+    MeasureTool::angle(&world)
+}
+
+#[wasm_bindgen]
+pub fn clear_measurement() {
+    let Some(mut world) = world_mut() else {
+        return;
+    };
+
+    MeasureTool::clear(&mut world);
+    wakeup_world(&world);
+}
+
+/// Runs `measure` on the editable mesh of an entity and its world transform
+fn measure_mesh<T>(
+    entity_index: u32,
+    measure: impl FnOnce(&EditableMesh, &GlobalTransform) -> Option<T>,
+) -> Option<T> {
+    let world = world()?;
+    let entity = world.get_entity(Entity::from_raw(entity_index))?;
+
+    measure(
+        entity.get::<EditableMesh>()?,
+        entity.get::<GlobalTransform>()?,
+    )
+}
+
+/// Total area of the faces of a mesh, in square world units
+#[wasm_bindgen]
+pub fn get_mesh_surface_area(entity_index: u32) -> Option<f32> {
+    measure_mesh(entity_index, |mesh, transform| {
+        Some(mesh.surface_area(transform))
+    })
+}
+
+/// Enclosed volume of a mesh in cubic world units, `None` if the mesh has holes
+#[wasm_bindgen]
+pub fn get_mesh_volume(entity_index: u32) -> Option<f32> {
+    measure_mesh(entity_index, EditableMesh::volume)
+}
+
+/// Center of mass of a mesh in world space, of its volume if it is closed, else of its surface
+#[wasm_bindgen]
+pub fn get_mesh_centroid(entity_index: u32) -> Option<transport::Vec3> {
+    measure_mesh(entity_index, EditableMesh::centroid).map(Into::into)
+}
+
+/// Size of the world axis aligned box around a mesh, in world units
+#[wasm_bindgen]
+pub fn get_mesh_dimensions(entity_index: u32) -> Option<transport::Vec3> {
+    measure_mesh(entity_index, EditableMesh::world_bounds).map(|(min, max)| (max - min).into())
+}
+
+/// Saves every user entity and the editor state in the native project format
+#[wasm_bindgen]
+pub fn save_scene() -> Vec<u8> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    ProjectFile::capture(&mut world).to_bytes()
+}
+
+/// Replaces the scene with one saved by [`save_scene`], possibly by an older version. Returns
+/// the error, if any, in which case the scene is left as it was.
+#[wasm_bindgen]
+pub fn load_scene(bytes: &[u8]) -> Option<String> {
+    let Some(mut world) = world_mut() else {
+        return None;
+    };
+
+    let result = ProjectFile::from_bytes(bytes).and_then(|project| project.restore(&mut world));
+    wakeup_world(&world);
+
+    result.err().map(|error| error.to_string())
+}
+
+/// Exports the visible user entities as binary glTF
+#[wasm_bindgen]
+pub fn export_glb(options: transport::GltfExportOptions) -> Vec<u8> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    GltfExporter::export_glb(&mut world, &options.into())
+}
+
+/// Exports the visible user entities as glTF JSON, with the buffer embedded
+#[wasm_bindgen]
+pub fn export_gltf(options: transport::GltfExportOptions) -> String {
+    let Some(mut world) = world_mut() else {
+        return String::new();
+    };
+
+    GltfExporter::export_gltf(&mut world, &options.into())
+}
+
+/// Spawns a mesh read from a file like the primitives, or returns why it could not be read
+fn spawn_mesh_file(data: Result<MeshData, FormatError>, name: String) -> Option<String> {
+    let Some(mut world) = world_mut() else {
+        return None;
+    };
+
+    let material = world
+        .get_resource_mut::<ViewportMaterial>()
+        .unwrap()
+        .0
+        .clone();
+
+    let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
+
+    let editable_mesh = match data.and_then(|data| formats::mesh_bundle(&data, &mut meshes)) {
+        Ok(bundle) => EditableMeshBundle { material, ..bundle },
+        Err(error) => return Some(error.to_string()),
+    };
+    world.spawn((editable_mesh, Name::from(name), UserSpace));
+
+    wakeup_world(&world);
+    None
+}
+
+/// Imports a binary or ASCII STL file, welding the triangles into one mesh. Returns the error,
+/// if any.
+#[wasm_bindgen]
+pub fn import_stl(
+    bytes: &[u8],
+    name: String,
+    options: transport::MeshImportOptions,
+) -> Option<String> {
+    spawn_mesh_file(stl::read(bytes, &options.into()), name)
+}
+
+/// Imports the faces and vertex colours of a PLY file. Returns the error, if any.
+#[wasm_bindgen]
+pub fn import_ply(
+    bytes: &[u8],
+    name: String,
+    options: transport::MeshImportOptions,
+) -> Option<String> {
+    spawn_mesh_file(ply::read(bytes, &options.into()), name)
+}
+
+#[wasm_bindgen]
+pub fn export_stl(options: transport::MeshExportOptions) -> Vec<u8> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    stl::export(&mut world, &options.into())
+}
+
+#[wasm_bindgen]
+pub fn export_ply(options: transport::MeshExportOptions) -> Vec<u8> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    ply::export(&mut world, &options.into())
+}
+
+/// Names of the meshes an export would write with holes, to warn about before printing
+#[wasm_bindgen]
+pub fn get_open_meshes(selected_only: bool) -> Vec<String> {
+    let Some(mut world) = world_mut() else {
+        return vec![];
+    };
+
+    formats::open_meshes(&mut world, selected_only)
 }
@@ -86,6 +86,94 @@ impl Into<bevy::transform::components::Transform> for Transform {
     }
 }
 
+/// sRGB color with alpha, each channel from 0 to 1
+#[derive(Clone, Copy, Default, Debug)]
+#[wasm_bindgen]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+#[wasm_bindgen]
+impl Color {
+    #[wasm_bindgen(constructor)]
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+}
+
+impl From<bevy::render::color::Color> for Color {
+    fn from(color: bevy::render::color::Color) -> Self {
+        let [r, g, b, a] = color.as_rgba_f32();
+        Self { r, g, b, a }
+    }
+}
+
+impl Into<bevy::render::color::Color> for Color {
+    fn into(self) -> bevy::render::color::Color {
+        bevy::render::color::Color::rgba(self.r, self.g, self.b, self.a)
+    }
+}
+
+/// Scene statistics, counts of the selection next to those of the whole scene. Times are in
+/// milliseconds.
+#[derive(Clone, Copy, Default, Debug)]
+#[wasm_bindgen]
+pub struct SceneStats {
+    pub objects: u32,
+    pub vertices: u32,
+    pub edges: u32,
+    pub faces: u32,
+    pub triangles: u32,
+    pub selected_objects: u32,
+    pub selected_vertices: u32,
+    pub selected_edges: u32,
+    pub selected_faces: u32,
+    pub selected_triangles: u32,
+    pub bvh_nodes: u32,
+    pub bvh_leaves: u32,
+    pub bvh_depth: u32,
+    /// Approximate, in bytes
+    pub mesh_memory: f64,
+    pub bvh_memory: f64,
+    pub fps: f64,
+    pub frame_time: f64,
+    pub update_time: f64,
+    pub render_time: f64,
+    pub min_frame_time: f64,
+    pub max_frame_time: f64,
+}
+
+impl From<&crate::core::stats::SceneStats> for SceneStats {
+    fn from(stats: &crate::core::stats::SceneStats) -> Self {
+        Self {
+            objects: stats.scene.objects as u32,
+            vertices: stats.scene.vertices as u32,
+            edges: stats.scene.edges as u32,
+            faces: stats.scene.faces as u32,
+            triangles: stats.scene.triangles as u32,
+            selected_objects: stats.selection.objects as u32,
+            selected_vertices: stats.selection.vertices as u32,
+            selected_edges: stats.selection.edges as u32,
+            selected_faces: stats.selection.faces as u32,
+            selected_triangles: stats.selection.triangles as u32,
+            bvh_nodes: stats.bvh.nodes as u32,
+            bvh_leaves: stats.bvh.leaves as u32,
+            bvh_depth: stats.bvh.depth,
+            mesh_memory: stats.mesh_memory as f64,
+            bvh_memory: stats.bvh.memory as f64,
+            fps: stats.timing.fps,
+            frame_time: stats.timing.frame_time,
+            update_time: stats.timing.update_time,
+            render_time: stats.timing.render_time,
+            min_frame_time: stats.timing.min_frame_time,
+            max_frame_time: stats.timing.max_frame_time,
+        }
+    }
+}
+
 #[wasm_bindgen]
 impl UvSphereOptions {
     #[wasm_bindgen(constructor)]
@@ -158,3 +246,102 @@ pub enum EntityEvent {
     ChildrenChanged,
     Despawned,
 }
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct GltfExportOptions {
+    pub selected_only: bool,
+    pub apply_transforms: bool,
+    pub up_axis: crate::core::formats::gltf::UpAxis,
+}
+
+#[wasm_bindgen]
+impl GltfExportOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        selected_only: bool,
+        apply_transforms: bool,
+        up_axis: crate::core::formats::gltf::UpAxis,
+    ) -> Self {
+        Self {
+            selected_only,
+            apply_transforms,
+            up_axis,
+        }
+    }
+}
+
+impl From<GltfExportOptions> for crate::core::formats::gltf::GltfExportOptions {
+    fn from(options: GltfExportOptions) -> Self {
+        Self {
+            selected_only: options.selected_only,
+            apply_transforms: options.apply_transforms,
+            up_axis: options.up_axis,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct MeshImportOptions {
+    pub unit: crate::core::formats::FileUnit,
+    pub up_axis: crate::core::formats::gltf::UpAxis,
+}
+
+#[wasm_bindgen]
+impl MeshImportOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        unit: crate::core::formats::FileUnit,
+        up_axis: crate::core::formats::gltf::UpAxis,
+    ) -> Self {
+        Self { unit, up_axis }
+    }
+}
+
+impl From<MeshImportOptions> for crate::core::formats::MeshImportOptions {
+    fn from(options: MeshImportOptions) -> Self {
+        Self {
+            unit: options.unit,
+            up_axis: options.up_axis,
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct MeshExportOptions {
+    pub selected_only: bool,
+    pub encoding: crate::core::formats::Encoding,
+    pub unit: crate::core::formats::FileUnit,
+    pub up_axis: crate::core::formats::gltf::UpAxis,
+}
+
+#[wasm_bindgen]
+impl MeshExportOptions {
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        selected_only: bool,
+        encoding: crate::core::formats::Encoding,
+        unit: crate::core::formats::FileUnit,
+        up_axis: crate::core::formats::gltf::UpAxis,
+    ) -> Self {
+        Self {
+            selected_only,
+            encoding,
+            unit,
+            up_axis,
+        }
+    }
+}
+
+impl From<MeshExportOptions> for crate::core::formats::MeshExportOptions {
+    fn from(options: MeshExportOptions) -> Self {
+        Self {
+            selected_only: options.selected_only,
+            encoding: options.encoding,
+            unit: options.unit,
+            up_axis: options.up_axis,
+        }
+    }
+}
@@ -0,0 +1,225 @@
+use bevy::{prelude::*, window::RequestRedraw};
+
+use super::{
+    fly_camera::FlyCamera,
+    pan_orbit_camera::{
+        wrap_angle, CameraProjectionKind, PanOrbitCameraPlugin, PanOrbitCameraUpdate,
+        PanOrbitState, PanOrbitTransition, PanOrbitView, PrimaryCamera,
+    },
+};
+
+/// Named camera viewpoints, and the turntable mode of the primary camera
+pub struct CameraBookmarksPlugin;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CameraBookmark {
+    pub name: String,
+    pub view: PanOrbitView,
+    pub projection: CameraProjectionKind,
+}
+
+/// Bookmarks in the order they were saved. Names are unique and not blank.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct CameraBookmarks(pub Vec<CameraBookmark>);
+
+/// Continuously orbits the primary camera around its center while present
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct Turntable {
+    /// Radians per second, positive turns counter-clockwise seen from above
+    pub angular_speed: f32,
+}
+
+impl Default for Turntable {
+    fn default() -> Self {
+        Self {
+            angular_speed: 20f32.to_radians(),
+        }
+    }
+}
+
+impl CameraBookmarks {
+    pub fn get(&self, name: &str) -> Option<&CameraBookmark> {
+        self.0.iter().find(|bookmark| bookmark.name == name)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|bookmark| bookmark.name.clone())
+            .collect()
+    }
+
+    fn is_valid_name(name: &str) -> bool {
+        !name.trim().is_empty()
+    }
+
+    /// Saves a bookmark, replacing the one with the same name if any. Returns false if the name is
+    /// blank.
+    pub fn insert(&mut self, bookmark: CameraBookmark) -> bool {
+        if !Self::is_valid_name(&bookmark.name) {
+            return false;
+        }
+
+        match self.0.iter_mut().find(|b| b.name == bookmark.name) {
+            Some(existing) => *existing = bookmark,
+            None => self.0.push(bookmark),
+        }
+        true
+    }
+
+    /// Returns false if there is no bookmark called `from`, or if `to` is blank or already taken
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        if !Self::is_valid_name(to) || (from != to && self.get(to).is_some()) {
+            return false;
+        }
+
+        match self.0.iter_mut().find(|bookmark| bookmark.name == from) {
+            Some(bookmark) => {
+                bookmark.name = to.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.0.len();
+        self.0.retain(|bookmark| bookmark.name != name);
+        self.0.len() != count
+    }
+}
+
+impl Plugin for CameraBookmarksPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraBookmarks>().add_systems(
+            Update,
+            Self::turntable
+                .run_if(resource_exists::<Turntable>)
+                .after(PanOrbitCameraUpdate),
+        );
+    }
+}
+
+impl CameraBookmarksPlugin {
+    fn turntable(
+        time: Res<Time>,
+        turntable: Res<Turntable>,
+        mut q_camera: Query<
+            (&mut PanOrbitState, &mut Transform),
+            (
+                With<PrimaryCamera>,
+                Without<PanOrbitTransition>,
+                Without<FlyCamera>,
+            ),
+        >,
+        mut redraw: EventWriter<RequestRedraw>,
+    ) {
+        for (mut state, mut transform) in q_camera.iter_mut() {
+            let view = PanOrbitView {
+                yaw: wrap_angle(state.yaw + turntable.angular_speed * time.delta_seconds()),
+                ..state.view()
+            };
+            state.set_view(view);
+            state.apply_to(&mut transform);
+        }
+
+        // The app only updates on input, keep it spinning
+        redraw.send(RequestRedraw);
+    }
+
+    /// Bookmarks the current view of the primary camera under `name`. Returns false if the name
+    /// is blank or there is no primary camera.
+    pub fn save(world: &mut World, name: &str) -> bool {
+        let Some(projection) = PanOrbitCameraPlugin::projection_kind(world) else {
+            return false;
+        };
+
+        let mut q_camera = world.query_filtered::<&PanOrbitState, With<PrimaryCamera>>();
+        let Ok(state) = q_camera.get_single(world) else {
+            return false;
+        };
+
+        let bookmark = CameraBookmark {
+            name: name.to_string(),
+            view: state.view(),
+            projection,
+        };
+
+        world.resource_mut::<CameraBookmarks>().insert(bookmark)
+    }
+
+    /// Moves the primary camera back to a bookmark. Returns false if there is no such bookmark.
+    pub fn recall(world: &mut World, name: &str) -> bool {
+        let Some(bookmark) = world.resource::<CameraBookmarks>().get(name).cloned() else {
+            return false;
+        };
+
+        PanOrbitCameraPlugin::set_projection(world, bookmark.projection);
+        PanOrbitCameraPlugin::transition_to(world, bookmark.view);
+
+        true
+    }
+
+    pub fn set_turntable(world: &mut World, enabled: bool, angular_speed: Option<f32>) {
+        if !enabled {
+            world.remove_resource::<Turntable>();
+            return;
+        }
+
+        let mut turntable = world
+            .get_resource::<Turntable>()
+            .copied()
+            .unwrap_or_default();
+        if let Some(angular_speed) = angular_speed {
+            turntable.angular_speed = angular_speed;
+        }
+
+        world.insert_resource(turntable);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{CameraBookmark, CameraBookmarks};
+    use crate::core::pan_orbit_camera::{CameraProjectionKind, PanOrbitView};
+
+    fn bookmark(name: &str, yaw: f32) -> CameraBookmark {
+        CameraBookmark {
+            name: name.to_string(),
+            view: PanOrbitView {
+                center: Vec3::ZERO,
+                radius: 10.0,
+                yaw,
+                pitch: 0.0,
+            },
+            projection: CameraProjectionKind::Perspective,
+        }
+    }
+
+    #[test]
+    fn test_bookmarks_insert_rename_remove() {
+        let mut bookmarks = CameraBookmarks::default();
+
+        assert!(bookmarks.insert(bookmark("front", 0.0)));
+        assert!(bookmarks.insert(bookmark("side", 1.0)));
+        // Same name overwrites in place
+        assert!(bookmarks.insert(bookmark("front", 0.5)));
+        assert!(!bookmarks.insert(bookmark(" ", 0.0)));
+
+        assert_eq!(bookmarks.names(), vec!["front", "side"]);
+        assert_eq!(bookmarks.get("front").unwrap().view.yaw, 0.5);
+
+        assert!(!bookmarks.rename("front", "side"));
+        assert!(!bookmarks.rename("missing", "other"));
+        assert!(!bookmarks.rename("side", ""));
+        assert!(!bookmarks.rename("side", "  "));
+        assert!(bookmarks.rename("side", "profile"));
+        assert_eq!(bookmarks.names(), vec!["front", "profile"]);
+
+        assert!(bookmarks.remove("front"));
+        assert!(!bookmarks.remove("front"));
+        assert_eq!(bookmarks.names(), vec!["profile"]);
+    }
+}
@@ -1,5 +1,8 @@
 use bevy::{
-    math::bounding::RayCast3d,
+    math::{
+        bounding::{Aabb3d, BoundingVolume, RayCast3d},
+        Affine3A,
+    },
     prelude::*,
     render::{mesh::Indices, render_asset::RenderAssetUsages},
 };
@@ -189,10 +192,6 @@ impl Torus {
             None => None,
         }
     }
-    
-    pub fn intersects_ray(&self, ray: &RayCast3d) -> bool {
-        self.intersets_ray_at(ray).is_some()
-    }
 }
 
 pub struct Triangle3d {
@@ -237,22 +236,98 @@ impl Triangle3d {
 
         None
     }
+
+    /// Returns the point on the triangle closest to `point`, and its barycentric coordinates
+    /// with respect to the triangle's vertices.
+    /// Reference: Real-Time Collision Detection, Christer Ericson, 5.1.5
+    pub fn closest_point(&self, point: Vec3) -> (Vec3, Vec3) {
+        let [a, b, c] = self.vertices;
+
+        let ab = b - a;
+        let ac = c - a;
+        let ap = point - a;
+
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= 0.0 && d2 <= 0.0 {
+            return (a, Vec3::X);
+        }
+
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= 0.0 && d4 <= d3 {
+            return (b, Vec3::Y);
+        }
+
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+            let v = d1 / (d1 - d3);
+            return (a + ab * v, Vec3::new(1.0 - v, v, 0.0));
+        }
+
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= 0.0 && d5 <= d6 {
+            return (c, Vec3::Z);
+        }
+
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+            let w = d2 / (d2 - d6);
+            return (a + ac * w, Vec3::new(1.0 - w, 0.0, w));
+        }
+
+        let va = d3 * d6 - d5 * d4;
+        if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return (b + (c - b) * w, Vec3::new(0.0, 1.0 - w, w));
+        }
+
+        let denom = 1.0 / (va + vb + vc);
+        let v = vb * denom;
+        let w = vc * denom;
+
+        (a + ab * v + ac * w, Vec3::new(1.0 - v - w, v, w))
+    }
 }
 
-/// Input vertices must be in counter-clockwise order.
-pub fn ray_intersects_convex_plane_at(
-    ray: &RayCast3d,
-    transform: &Transform,
-    vertices: &[Vec3],
-) -> Option<f32> {
+/// Returns the axis aligned box enclosing `aabb` after it is transformed by `affine`.
+pub fn transform_aabb(aabb: &Aabb3d, affine: &Affine3A) -> Aabb3d {
+    let center = affine.transform_point3(aabb.center());
+    let half_size = aabb.half_size();
+
+    let matrix = affine.matrix3;
+    let half_size = Vec3::new(
+        Vec3::from(matrix.row(0)).abs().dot(half_size),
+        Vec3::from(matrix.row(1)).abs().dot(half_size),
+        Vec3::from(matrix.row(2)).abs().dot(half_size),
+    );
+
+    Aabb3d::new(center, half_size)
+}
+
+/// Squared distance from a point to the closest point of a segment.
+pub fn point_segment_distance_squared(point: Vec3, start: Vec3, end: Vec3) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+
+    if length_squared <= f32::EPSILON {
+        return point.distance_squared(start);
+    }
+
+    let t = ((point - start).dot(segment) / length_squared).clamp(0.0, 1.0);
+    point.distance_squared(start + segment * t)
+}
+
+/// Input vertices must be in counter-clockwise order, and in the same space as the ray.
+pub fn ray_intersects_convex_plane_at(ray: &RayCast3d, vertices: &[Vec3]) -> Option<f32> {
     let first = vertices[0];
-    for triangle in vertices[1..].windows(2).map(|vertices| {
-        Triangle3d::new([
-            first.clone() * transform.scale + transform.translation,
-            vertices[0] * transform.scale + transform.translation,
-            vertices[1] * transform.scale + transform.translation,
-        ])
-    }) {
+    for triangle in vertices[1..]
+        .windows(2)
+        .map(|vertices| Triangle3d::new([first.clone(), vertices[0], vertices[1]]))
+    {
         let result = triangle.intersects_ray_at(ray);
 
         if let Some(t) = result {
@@ -288,21 +363,52 @@ mod tests {
             1000.0,
         );
 
-        let transform = Transform::IDENTITY;
-
-        assert_eq!(
-            ray_intersects_convex_plane_at(&ray, &transform, &vertices),
-            Some(1.0)
-        );
+        assert_eq!(ray_intersects_convex_plane_at(&ray, &vertices), Some(1.0));
 
         let ray = RayCast3d::from_ray(
             Ray3d::new(Vec3::new(0.5, -0.5, 1.0), Vec3::new(0.0, 0.0, -1.0)),
             1000.0,
         );
-        assert_eq!(
-            ray_intersects_convex_plane_at(&ray, &transform, &vertices),
-            None
+        assert_eq!(ray_intersects_convex_plane_at(&ray, &vertices), None);
+    }
+
+    #[test]
+    fn test_triangle_closest_point() {
+        let triangle = Triangle3d::new([
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        ]);
+
+        // Above the interior
+        let (point, barycentric) = triangle.closest_point(Vec3::new(0.25, 0.25, 2.0));
+        assert!(point.distance(Vec3::new(0.25, 0.25, 0.0)) < 1e-6);
+        assert!(barycentric.distance(Vec3::new(0.5, 0.25, 0.25)) < 1e-6);
+
+        // Past a vertex
+        let (point, barycentric) = triangle.closest_point(Vec3::new(2.0, -1.0, 0.0));
+        assert_eq!(point, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(barycentric, Vec3::Y);
+
+        // Beside the hypotenuse
+        let (point, barycentric) = triangle.closest_point(Vec3::new(1.0, 1.0, 0.0));
+        assert!(point.distance(Vec3::new(0.5, 0.5, 0.0)) < 1e-6);
+        assert!(barycentric.distance(Vec3::new(0.0, 0.5, 0.5)) < 1e-6);
+    }
+
+    #[test]
+    fn test_transform_aabb() {
+        let aabb = Aabb3d::new(Vec3::ZERO, Vec3::splat(1.0));
+        let affine = Affine3A::from_scale_rotation_translation(
+            Vec3::new(2.0, 1.0, 1.0),
+            Quat::from_rotation_z(f32::consts::FRAC_PI_2),
+            Vec3::new(0.0, 0.0, 5.0),
         );
+
+        let transformed = transform_aabb(&aabb, &affine);
+
+        assert!(transformed.min.distance(Vec3::new(-1.0, -2.0, 4.0)) < 1e-5);
+        assert!(transformed.max.distance(Vec3::new(1.0, 2.0, 6.0)) < 1e-5);
     }
 
     #[test]
@@ -315,7 +421,7 @@ mod tests {
             },
             1000.,
         );
-        assert!(torus.intersects_ray(&ray));
+        assert!(torus.intersets_ray_at(&ray).is_some());
         assert_eq!(torus.intersets_ray_at(&ray), Some(0.5));
     }
 
@@ -329,7 +435,7 @@ mod tests {
             },
             1000.,
         );
-        assert!(torus.intersects_ray(&ray));
+        assert!(torus.intersets_ray_at(&ray).is_some());
         assert_eq!(torus.intersets_ray_at(&ray), Some(8.5));
     }
 
@@ -348,7 +454,7 @@ mod tests {
             },
             1000.,
         );
-        assert!(torus.intersects_ray(&ray));
+        assert!(torus.intersets_ray_at(&ray).is_some());
         assert_eq!(torus.intersets_ray_at(&ray), Some(0.5));
     }
 }
@@ -0,0 +1,849 @@
+use bevy::{
+    asset::load_internal_asset,
+    core_pipeline::core_3d::Transparent3d,
+    ecs::system::lifetimeless::SRes,
+    pbr::{
+        MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
+    },
+    prelude::*,
+    render::{
+        mesh::{GpuBufferInfo, Indices, MeshVertexBufferLayout, PrimitiveTopology},
+        render_asset::{RenderAssetUsages, RenderAssets},
+        render_phase::{
+            AddRenderCommand, DrawFunctions, PhaseItem, RenderCommand, RenderCommandResult,
+            RenderPhase, SetItemPipeline,
+        },
+        render_resource::{
+            Buffer, BufferDescriptor, BufferUsages, CompareFunction, PipelineCache,
+            RenderPipelineDescriptor, SpecializedMeshPipeline, SpecializedMeshPipelineError,
+            SpecializedMeshPipelines, VertexAttribute, VertexBufferLayout, VertexFormat,
+            VertexStepMode,
+        },
+        renderer::{RenderDevice, RenderQueue},
+        view::{ExtractedView, NoFrustumCulling, VisibilitySystems},
+        Extract, ExtractSchedule, Render, RenderApp, RenderSet,
+    },
+    transform::TransformSystem,
+    utils::{HashMap, HashSet},
+};
+use bytemuck::{Pod, Zeroable};
+use lox::{
+    core::Mesh as LoxMesh,
+    EdgeHandle, FaceHandle, Handle as LoxHandle, VertexHandle,
+};
+
+use super::{
+    editable_mesh::{ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh, SelectMode},
+    editor::Focused,
+    interaction::InteractionMode,
+    pan_orbit_camera::PrimaryCamera,
+    shading::{ShadingMode, ViewportShading, WireframeOverlay},
+    tools::cursor::world_normal,
+};
+
+/// Draws the elements of the focused meshes in edit mode: vertex dots, edges, face centre dots
+/// and a tint on the selected faces, plus optional normal lines.
+///
+/// Everything is drawn by a single instanced draw call. The instances are rebuilt, and their GPU
+/// buffer rewritten, only when a focused mesh, its selection or the settings change.
+pub struct EditOverlayPlugin;
+
+const EDIT_OVERLAY_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x2f6b_0e14_97c3_4a5d_b8e2_61d9_0c47_a3f5);
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct EditOverlaySettings {
+    /// Diameter of the vertex and face centre dots, in pixels
+    pub dot_size: f32,
+    /// Width of the edges and normal lines, in pixels
+    pub line_width: f32,
+    pub vertex_normals: bool,
+    pub face_normals: bool,
+    /// Hides the elements behind the surfaces, except in x-ray
+    pub hide_occluded: bool,
+    pub unselected_color: Color,
+    pub selected_color: Color,
+    /// Drawn over the selected faces, mostly transparent
+    pub face_tint: Color,
+    pub vertex_normal_color: Color,
+    pub face_normal_color: Color,
+}
+
+impl Default for EditOverlaySettings {
+    fn default() -> Self {
+        Self {
+            dot_size: 6.0,
+            line_width: 1.5,
+            vertex_normals: false,
+            face_normals: false,
+            hide_occluded: true,
+            unselected_color: Color::rgb(0.05, 0.05, 0.05),
+            selected_color: Color::rgb_u8(255, 170, 64),
+            face_tint: Color::rgba_u8(255, 170, 64, 50),
+            vertex_normal_color: Color::rgb(0.55, 0.45, 0.95),
+            face_normal_color: Color::rgb(0.35, 0.85, 0.95),
+        }
+    }
+}
+
+/// How an instance is drawn, must match the constants in `edit_overlay.wgsl`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum OverlayKind {
+    Dot,
+    Line,
+    Triangle,
+}
+
+/// One dot, line or triangle of the overlay, in world space
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+#[repr(C)]
+pub struct OverlayInstance {
+    /// First point, and the size in pixels of dots and lines
+    pub a: Vec4,
+    /// Second point, and the [`OverlayKind`]
+    pub b: Vec4,
+    pub c: Vec4,
+    /// Linear RGBA
+    pub color: Vec4,
+}
+
+impl OverlayInstance {
+    pub fn dot(position: Vec3, size: f32, color: Color) -> Self {
+        Self::new(OverlayKind::Dot, [position; 3], size, color)
+    }
+
+    pub fn line(start: Vec3, end: Vec3, width: f32, color: Color) -> Self {
+        Self::new(OverlayKind::Line, [start, end, end], width, color)
+    }
+
+    pub fn triangle(corners: [Vec3; 3], color: Color) -> Self {
+        Self::new(OverlayKind::Triangle, corners, 0.0, color)
+    }
+
+    fn new(kind: OverlayKind, [a, b, c]: [Vec3; 3], size: f32, color: Color) -> Self {
+        Self {
+            a: a.extend(size),
+            b: b.extend(kind as u32 as f32),
+            c: c.extend(0.0),
+            color: Vec4::from(color.as_linear_rgba_f32()),
+        }
+    }
+
+    pub fn kind(&self) -> OverlayKind {
+        match self.b.w as u32 {
+            0 => OverlayKind::Dot,
+            1 => OverlayKind::Line,
+            _ => OverlayKind::Triangle,
+        }
+    }
+}
+
+/// Elements of a mesh drawn as selected. Besides the active ones, edges between active vertices
+/// and the edges and corners of active faces are selected too.
+#[derive(Clone, Debug, Default)]
+pub struct OverlaySelection {
+    pub vertices: HashSet<VertexHandle>,
+    pub edges: HashSet<EdgeHandle>,
+    pub faces: HashSet<FaceHandle>,
+}
+
+impl OverlaySelection {
+    pub fn new(
+        mesh: &EditableMesh,
+        vertices: &ActiveVertices,
+        edges: &ActiveEdges,
+        active_faces: &ActiveFaces,
+    ) -> Self {
+        let faces: HashSet<FaceHandle> = active_faces
+            .iter()
+            .map(|index| FaceHandle::new(*index))
+            .filter(|face| mesh.structure.contains_face(*face))
+            .collect();
+
+        let mut selected_edges: HashSet<EdgeHandle> = edges
+            .iter()
+            .map(|index| EdgeHandle::new(*index))
+            .filter(|edge| mesh.structure.contains_edge(*edge))
+            .collect();
+        for face in faces.iter() {
+            selected_edges.extend(
+                mesh.structure
+                    .get_ref(*face)
+                    .adjacent_edges()
+                    .map(|edge| edge.handle()),
+            );
+        }
+        for edge in mesh.structure.edges() {
+            if edge
+                .endpoints()
+                .iter()
+                .all(|vertex| vertices.contains(&vertex.handle().idx()))
+            {
+                selected_edges.insert(edge.handle());
+            }
+        }
+
+        Self {
+            vertices: mesh.selected_vertices(vertices, edges, active_faces),
+            edges: selected_edges,
+            faces,
+        }
+    }
+}
+
+impl EditOverlaySettings {
+    /// Everything drawn for one mesh, back to front: the face tint, the normals, the edges, then
+    /// the dots of the current select mode. Selected elements come after the unselected ones so
+    /// they stay on top.
+    pub fn mesh_instances(
+        &self,
+        mesh: &EditableMesh,
+        transform: &GlobalTransform,
+        selection: &OverlaySelection,
+        select_mode: SelectMode,
+        normal_length: f32,
+    ) -> Vec<OverlayInstance> {
+        let position =
+            |vertex: VertexHandle| transform.transform_point(mesh.vertex_positions[vertex]);
+        let corners = |face: FaceHandle| -> Vec<Vec3> {
+            mesh.structure
+                .get_ref(face)
+                .adjacent_vertices()
+                .map(|vertex| position(vertex.handle()))
+                .collect()
+        };
+        let center = |corners: &[Vec3]| corners.iter().sum::<Vec3>() / corners.len().max(1) as f32;
+        let color = |selected: bool| {
+            if selected {
+                self.selected_color
+            } else {
+                self.unselected_color
+            }
+        };
+
+        let mut instances = vec![];
+
+        // Fanned out from the first corner, faces are convex
+        for face in mesh.structure.face_handles() {
+            if !selection.faces.contains(&face) {
+                continue;
+            }
+            let corners = corners(face);
+            for pair in corners.windows(2).skip(1) {
+                instances.push(OverlayInstance::triangle(
+                    [corners[0], pair[0], pair[1]],
+                    self.face_tint,
+                ));
+            }
+        }
+
+        if self.face_normals {
+            for face in mesh.structure.face_handles() {
+                let Some(normal) = world_normal(transform, mesh.face_normals[face]) else {
+                    continue;
+                };
+                let start = center(&corners(face));
+                instances.push(OverlayInstance::line(
+                    start,
+                    start + normal * normal_length,
+                    self.line_width,
+                    self.face_normal_color,
+                ));
+            }
+        }
+
+        if self.vertex_normals {
+            for vertex in mesh.structure.vertex_handles() {
+                let Some(normal) = world_normal(transform, mesh.vertex_normals[vertex]) else {
+                    continue;
+                };
+                let start = position(vertex);
+                instances.push(OverlayInstance::line(
+                    start,
+                    start + normal * normal_length,
+                    self.line_width,
+                    self.vertex_normal_color,
+                ));
+            }
+        }
+
+        for selected in [false, true] {
+            for edge in mesh.structure.edges() {
+                if selection.edges.contains(&edge.handle()) != selected {
+                    continue;
+                }
+                let [start, end] = edge.endpoints().map(|vertex| position(vertex.handle()));
+                instances.push(OverlayInstance::line(
+                    start,
+                    end,
+                    self.line_width,
+                    color(selected),
+                ));
+            }
+        }
+
+        for selected in [false, true] {
+            match select_mode {
+                SelectMode::Vertices => {
+                    for vertex in mesh.structure.vertex_handles() {
+                        if selection.vertices.contains(&vertex) == selected {
+                            instances.push(OverlayInstance::dot(
+                                position(vertex),
+                                self.dot_size,
+                                color(selected),
+                            ));
+                        }
+                    }
+                }
+                SelectMode::Faces => {
+                    for face in mesh.structure.face_handles() {
+                        if selection.faces.contains(&face) == selected {
+                            instances.push(OverlayInstance::dot(
+                                center(&corners(face)),
+                                self.dot_size,
+                                color(selected),
+                            ));
+                        }
+                    }
+                }
+                SelectMode::Edges => {}
+            }
+        }
+
+        instances
+    }
+}
+
+/// Dots, lines and triangles drawn over the scene on the primary camera. The edit overlay is the
+/// one with the elements of the focused meshes.
+#[derive(Component, Default)]
+pub struct EditOverlay {
+    pub instances: Vec<OverlayInstance>,
+    pub hide_occluded: bool,
+}
+
+impl Plugin for EditOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            EDIT_OVERLAY_SHADER_HANDLE,
+            "edit_overlay.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.init_resource::<EditOverlaySettings>()
+            .add_systems(Startup, Self::setup_overlay)
+            .add_systems(
+                PostUpdate,
+                Self::update_overlay
+                    .after(TransformSystem::TransformPropagate)
+                    .after(VisibilitySystems::VisibilityPropagate),
+            );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .add_render_command::<Transparent3d, DrawEditOverlay>()
+            .init_resource::<SpecializedMeshPipelines<EditOverlayPipeline>>()
+            .init_resource::<EditOverlayUploads>()
+            .init_resource::<EditOverlayBuffers>()
+            .add_systems(ExtractSchedule, extract_edit_overlays)
+            .add_systems(
+                Render,
+                (
+                    queue_edit_overlays.in_set(RenderSet::QueueMeshes),
+                    prepare_edit_overlay_buffers.in_set(RenderSet::PrepareResources),
+                ),
+            );
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<EditOverlayPipeline>();
+    }
+}
+
+impl EditOverlayPlugin {
+    fn setup_overlay(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+        commands.spawn((
+            meshes.add(create_overlay_base()),
+            SpatialBundle::default(),
+            EditOverlay::default(),
+            NoFrustumCulling,
+        ));
+    }
+
+    /// Rebuilds the instances when anything they are made of changed
+    fn update_overlay(
+        settings: Res<EditOverlaySettings>,
+        shading: Res<ViewportShading>,
+        mode: Res<InteractionMode>,
+        select_mode: Res<SelectMode>,
+        mut unfocused: RemovedComponents<Focused>,
+        meshes: Query<
+            (
+                Ref<EditableMesh>,
+                Ref<GlobalTransform>,
+                Ref<InheritedVisibility>,
+                Ref<ActiveVertices>,
+                Ref<ActiveEdges>,
+                Ref<ActiveFaces>,
+                Ref<Focused>,
+            ),
+            With<Focused>,
+        >,
+        mut overlay: Query<&mut EditOverlay, Without<WireframeOverlay>>,
+    ) {
+        let Ok(mut overlay) = overlay.get_single_mut() else {
+            return;
+        };
+
+        let focus_removed = unfocused.read().count() > 0;
+        let meshes_changed = meshes.iter().any(
+            |(mesh, transform, visibility, vertices, edges, faces, focused)| {
+                mesh.is_changed()
+                    || transform.is_changed()
+                    || visibility.is_changed()
+                    || vertices.is_changed()
+                    || edges.is_changed()
+                    || faces.is_changed()
+                    || focused.is_added()
+            },
+        );
+        if !settings.is_changed()
+            && !shading.is_changed()
+            && !mode.is_changed()
+            && !select_mode.is_changed()
+            && !focus_removed
+            && !meshes_changed
+        {
+            return;
+        }
+
+        let mut instances = vec![];
+        if *mode == InteractionMode::Edit {
+            for (mesh, transform, visibility, vertices, edges, faces, _) in meshes.iter() {
+                if !visibility.get() {
+                    continue;
+                }
+
+                let selection = OverlaySelection::new(&mesh, &vertices, &edges, &faces);
+                instances.extend(settings.mesh_instances(
+                    &mesh,
+                    &transform,
+                    &selection,
+                    *select_mode,
+                    shading.normal_length,
+                ));
+            }
+        }
+
+        overlay.instances = instances;
+        // Surfaces are see-through in x-ray, so is everything behind them
+        overlay.hide_occluded = settings.hide_occluded && shading.mode != ShadingMode::XRay;
+    }
+}
+
+/// A quad whose corners are picked by the shader depending on the [`OverlayKind`]: the
+/// positions weigh the three points of a triangle, the UVs place the corners of dots and lines.
+/// The second triangle collapses for triangle instances.
+pub fn create_overlay_base() -> Mesh {
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        vec![
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+            [0.0, 0.0, 1.0f32],
+        ],
+    )
+    .with_inserted_attribute(
+        Mesh::ATTRIBUTE_UV_0,
+        vec![[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0f32]],
+    )
+    .with_inserted_indices(Indices::U16(vec![0, 1, 2, 0, 2, 3]))
+}
+
+/// Render world copy of an [`EditOverlay`], its instances live in [`EditOverlayBuffers`]
+#[derive(Component, Clone, Copy)]
+struct ExtractedEditOverlay {
+    instance_count: usize,
+    hide_occluded: bool,
+}
+
+/// Instances of the overlays changed this frame, waiting to be written to their buffer
+#[derive(Resource, Default, Deref, DerefMut)]
+struct EditOverlayUploads(Vec<(Entity, Vec<OverlayInstance>)>);
+
+struct OverlayBuffer {
+    buffer: Buffer,
+    length: usize,
+    /// Instances the buffer has room for
+    capacity: usize,
+}
+
+/// Instance buffers by overlay entity. Kept as a resource since the render world entities do
+/// not outlive a frame.
+#[derive(Resource, Default, Deref, DerefMut)]
+struct EditOverlayBuffers(HashMap<Entity, OverlayBuffer>);
+
+fn extract_edit_overlays(
+    mut commands: Commands,
+    mut uploads: ResMut<EditOverlayUploads>,
+    overlays: Extract<Query<(Entity, Ref<EditOverlay>)>>,
+) {
+    for (entity, overlay) in overlays.iter() {
+        commands.get_or_spawn(entity).insert(ExtractedEditOverlay {
+            instance_count: overlay.instances.len(),
+            hide_occluded: overlay.hide_occluded,
+        });
+
+        if overlay.is_changed() {
+            uploads.push((entity, overlay.instances.clone()));
+        }
+    }
+}
+
+fn prepare_edit_overlay_buffers(
+    mut uploads: ResMut<EditOverlayUploads>,
+    mut buffers: ResMut<EditOverlayBuffers>,
+    overlays: Query<(), With<ExtractedEditOverlay>>,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+) {
+    for (entity, instances) in uploads.drain(..) {
+        if instances.is_empty() {
+            buffers.remove(&entity);
+            continue;
+        }
+
+        let contents: &[u8] = bytemuck::cast_slice(instances.as_slice());
+        match buffers.get_mut(&entity) {
+            Some(overlay_buffer) if overlay_buffer.capacity >= instances.len() => {
+                render_queue.write_buffer(&overlay_buffer.buffer, 0, contents);
+                overlay_buffer.length = instances.len();
+            }
+            _ => {
+                // Room to grow, so a slightly larger selection does not reallocate
+                let capacity = instances.len().next_power_of_two();
+                let buffer = render_device.create_buffer(&BufferDescriptor {
+                    label: Some("Edit Overlay Instance Buffer"),
+                    size: (capacity * std::mem::size_of::<OverlayInstance>()) as u64,
+                    usage: BufferUsages::VERTEX | BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                });
+                render_queue.write_buffer(&buffer, 0, contents);
+
+                buffers.insert(
+                    entity,
+                    OverlayBuffer {
+                        buffer,
+                        length: instances.len(),
+                        capacity,
+                    },
+                );
+            }
+        }
+    }
+
+    // Overlays despawned in the main world
+    buffers.retain(|entity, _| overlays.contains(*entity));
+}
+
+fn queue_edit_overlays(
+    transparent_3d_draw_functions: Res<DrawFunctions<Transparent3d>>,
+    overlay_pipeline: Res<EditOverlayPipeline>,
+    msaa: Res<Msaa>,
+    mut pipelines: ResMut<SpecializedMeshPipelines<EditOverlayPipeline>>,
+    pipeline_cache: Res<PipelineCache>,
+    meshes: Res<RenderAssets<Mesh>>,
+    render_mesh_instances: Res<RenderMeshInstances>,
+    overlays: Query<(Entity, &ExtractedEditOverlay)>,
+    mut views: Query<(&ExtractedView, &mut RenderPhase<Transparent3d>), With<PrimaryCamera>>,
+) {
+    let draw_overlay = transparent_3d_draw_functions.read().id::<DrawEditOverlay>();
+    let msaa_key = MeshPipelineKey::from_msaa_samples(msaa.samples());
+
+    for (view, mut transparent_phase) in &mut views {
+        let view_key = msaa_key | MeshPipelineKey::from_hdr(view.hdr);
+
+        for (entity, overlay) in &overlays {
+            if overlay.instance_count == 0 {
+                continue;
+            }
+            let Some(mesh_instance) = render_mesh_instances.get(&entity) else {
+                continue;
+            };
+            let Some(mesh) = meshes.get(mesh_instance.mesh_asset_id) else {
+                continue;
+            };
+
+            let key = EditOverlayPipelineKey {
+                mesh_key: view_key
+                    | MeshPipelineKey::from_primitive_topology(mesh.primitive_topology)
+                    | MeshPipelineKey::BLEND_ALPHA,
+                hide_occluded: overlay.hide_occluded,
+            };
+
+            let pipeline = pipelines
+                .specialize(&pipeline_cache, &overlay_pipeline, key, &mesh.layout)
+                .unwrap();
+
+            transparent_phase.add(Transparent3d {
+                entity,
+                pipeline,
+                draw_function: draw_overlay,
+                // Sorted back to front, the overlay goes over everything else
+                distance: f32::MAX,
+                batch_range: 0..1,
+                dynamic_offset: None,
+            });
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct EditOverlayPipelineKey {
+    mesh_key: MeshPipelineKey,
+    hide_occluded: bool,
+}
+
+#[derive(Resource)]
+struct EditOverlayPipeline {
+    mesh_pipeline: MeshPipeline,
+}
+
+impl FromWorld for EditOverlayPipeline {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh_pipeline: world.resource::<MeshPipeline>().clone(),
+        }
+    }
+}
+
+impl SpecializedMeshPipeline for EditOverlayPipeline {
+    type Key = EditOverlayPipelineKey;
+
+    fn specialize(
+        &self,
+        key: Self::Key,
+        layout: &MeshVertexBufferLayout,
+    ) -> Result<RenderPipelineDescriptor, SpecializedMeshPipelineError> {
+        let mut descriptor = self.mesh_pipeline.specialize(key.mesh_key, layout)?;
+        descriptor.label = Some("edit_overlay_pipeline".into());
+        descriptor.vertex.shader = EDIT_OVERLAY_SHADER_HANDLE;
+        descriptor.vertex.buffers.push(VertexBufferLayout {
+            array_stride: std::mem::size_of::<OverlayInstance>() as u64,
+            step_mode: VertexStepMode::Instance,
+            attributes: (0..4)
+                .map(|i| VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: i * 16,
+                    shader_location: 10 + i as u32,
+                })
+                .collect(),
+        });
+        descriptor.fragment.as_mut().unwrap().shader = EDIT_OVERLAY_SHADER_HANDLE;
+        // Dots and lines are turned to face the camera, whatever their winding
+        descriptor.primitive.cull_mode = None;
+
+        if !key.hide_occluded {
+            if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+                depth_stencil.depth_compare = CompareFunction::Always;
+            }
+        }
+
+        Ok(descriptor)
+    }
+}
+
+type DrawEditOverlay = (
+    SetItemPipeline,
+    SetMeshViewBindGroup<0>,
+    SetMeshBindGroup<1>,
+    DrawOverlayInstanced,
+);
+
+struct DrawOverlayInstanced;
+
+impl<P: PhaseItem> RenderCommand<P> for DrawOverlayInstanced {
+    type Param = (
+        SRes<RenderAssets<Mesh>>,
+        SRes<RenderMeshInstances>,
+        SRes<EditOverlayBuffers>,
+    );
+    type ViewQuery = ();
+    type ItemQuery = ();
+
+    #[inline]
+    fn render<'w>(
+        item: &P,
+        _view: bevy::ecs::query::ROQueryItem<'w, Self::ViewQuery>,
+        _query: Option<bevy::ecs::query::ROQueryItem<'w, Self::ItemQuery>>,
+        (meshes, render_mesh_instances, buffers): bevy::ecs::system::SystemParamItem<
+            'w,
+            '_,
+            Self::Param,
+        >,
+        pass: &mut bevy::render::render_phase::TrackedRenderPass<'w>,
+    ) -> RenderCommandResult {
+        let Some(mesh_instance) = render_mesh_instances.get(&item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(gpu_mesh) = meshes.into_inner().get(mesh_instance.mesh_asset_id) else {
+            return RenderCommandResult::Failure;
+        };
+        let Some(instances) = buffers.into_inner().get(&item.entity()) else {
+            return RenderCommandResult::Failure;
+        };
+
+        pass.set_vertex_buffer(0, gpu_mesh.vertex_buffer.slice(..));
+        pass.set_vertex_buffer(1, instances.buffer.slice(..));
+
+        match &gpu_mesh.buffer_info {
+            GpuBufferInfo::Indexed {
+                buffer,
+                index_format,
+                count,
+            } => {
+                pass.set_index_buffer(buffer.slice(..), 0, *index_format);
+                pass.draw_indexed(0..*count, 0, 0..instances.length as u32);
+            }
+            GpuBufferInfo::NonIndexed => {
+                pass.draw(0..gpu_mesh.vertex_count, 0..instances.length as u32);
+            }
+        }
+        RenderCommandResult::Success
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use lox::{core::Mesh as LoxMesh, Handle as LoxHandle};
+
+    use super::{EditOverlaySettings, OverlayInstance, OverlayKind, OverlaySelection};
+    use crate::core::editable_mesh::{
+        ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh, SelectMode,
+    };
+
+    fn count(instances: &[OverlayInstance], kind: OverlayKind, color: Color) -> usize {
+        let color = Vec4::from(color.as_linear_rgba_f32());
+        instances
+            .iter()
+            .filter(|instance| instance.kind() == kind && instance.color == color)
+            .count()
+    }
+
+    #[test]
+    fn test_selected_face_overlay() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let face = mesh.structure.face_handles().next().unwrap();
+
+        let selection = OverlaySelection::new(
+            &mesh,
+            &ActiveVertices::default(),
+            &ActiveEdges::default(),
+            &ActiveFaces([face.idx()].into_iter().collect()),
+        );
+        assert_eq!(selection.faces.len(), 1);
+        assert_eq!(selection.edges.len(), 3);
+        assert_eq!(selection.vertices.len(), 3);
+
+        let settings = EditOverlaySettings::default();
+        let instances = settings.mesh_instances(
+            &mesh,
+            &GlobalTransform::IDENTITY,
+            &selection,
+            SelectMode::Faces,
+            0.1,
+        );
+
+        let num_edges = mesh.structure.num_edges() as usize;
+        let num_faces = mesh.structure.num_faces() as usize;
+        assert_eq!(
+            count(&instances, OverlayKind::Triangle, settings.face_tint),
+            1
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Line, settings.selected_color),
+            3
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Line, settings.unselected_color),
+            num_edges - 3
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Dot, settings.selected_color),
+            1
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Dot, settings.unselected_color),
+            num_faces - 1
+        );
+        // The tint is drawn first, the selected dot last
+        assert_eq!(instances.first().unwrap().kind(), OverlayKind::Triangle);
+        assert_eq!(
+            instances.last().unwrap().color,
+            Vec4::from(settings.selected_color.as_linear_rgba_f32())
+        );
+    }
+
+    #[test]
+    fn test_vertices_select_their_edge_and_normals() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let edge = mesh.structure.edges().next().unwrap();
+        let endpoints = edge.endpoints().map(|vertex| vertex.handle().idx());
+
+        let selection = OverlaySelection::new(
+            &mesh,
+            &ActiveVertices(endpoints.into_iter().collect()),
+            &ActiveEdges::default(),
+            &ActiveFaces::default(),
+        );
+        assert_eq!(selection.vertices.len(), 2);
+        assert!(selection.edges.contains(&edge.handle()));
+
+        let settings = EditOverlaySettings {
+            vertex_normals: true,
+            face_normals: true,
+            ..default()
+        };
+        let transform = GlobalTransform::from_scale(Vec3::splat(2.0));
+        let instances =
+            settings.mesh_instances(&mesh, &transform, &selection, SelectMode::Vertices, 0.5);
+
+        let num_vertices = mesh.structure.num_vertices() as usize;
+        assert_eq!(
+            count(&instances, OverlayKind::Dot, settings.selected_color),
+            2
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Line, settings.vertex_normal_color),
+            num_vertices
+        );
+        assert_eq!(
+            count(&instances, OverlayKind::Line, settings.face_normal_color),
+            mesh.structure.num_faces() as usize
+        );
+
+        // Normals keep their length in world space
+        let normal = instances
+            .iter()
+            .find(|instance| {
+                instance.color == Vec4::from(settings.vertex_normal_color.as_linear_rgba_f32())
+            })
+            .unwrap();
+        assert!(((normal.b.truncate() - normal.a.truncate()).length() - 0.5).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,95 @@
+#import bevy_pbr::mesh_view_bindings::view
+
+// Same order as `OverlayKind`
+const KIND_DOT: u32 = 0u;
+const KIND_LINE: u32 = 1u;
+const KIND_TRIANGLE: u32 = 2u;
+
+// Pulls the elements in front of the surfaces they lie on
+const DEPTH_BIAS: f32 = 0.002;
+
+struct Vertex {
+    // Weights of the three points of a triangle
+    @location(0) weights: vec3<f32>,
+    // Corner of a dot, or end and side of a line
+    @location(2) corner: vec2<f32>,
+    @location(10) i_a: vec4<f32>,
+    @location(11) i_b: vec4<f32>,
+    @location(12) i_c: vec4<f32>,
+    @location(13) i_color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    @location(1) corner: vec2<f32>,
+    @location(2) @interpolate(flat) kind: u32,
+};
+
+fn to_clip(position: vec3<f32>) -> vec4<f32> {
+    return view.view_proj * vec4<f32>(position, 1.0);
+}
+
+// Moves a clip position by a number of pixels on the screen
+fn offset_pixels(clip: vec4<f32>, pixels: vec2<f32>) -> vec4<f32> {
+    return clip + vec4<f32>(2.0 * pixels / view.viewport.zw * clip.w, 0.0, 0.0);
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let kind = u32(vertex.i_b.w);
+    let size = vertex.i_a.w;
+
+    var clip: vec4<f32>;
+    if kind == KIND_DOT {
+        clip = offset_pixels(to_clip(vertex.i_a.xyz), vertex.corner * 0.5 * size);
+    } else if kind == KIND_LINE {
+        let start = to_clip(vertex.i_a.xyz);
+        let end = to_clip(vertex.i_b.xyz);
+
+        // Widened across its direction on the screen
+        let screen = (end.xy / end.w - start.xy / start.w) * view.viewport.zw;
+        var direction = vec2<f32>(1.0, 0.0);
+        if length(screen) > 1e-6 {
+            direction = normalize(screen);
+        }
+        let across = vec2<f32>(-direction.y, direction.x);
+
+        clip = select(start, end, vertex.corner.x > 0.0);
+        clip = offset_pixels(clip, across * vertex.corner.y * 0.5 * size);
+    } else {
+        clip = to_clip(
+            vertex.i_a.xyz * vertex.weights.x
+                + vertex.i_b.xyz * vertex.weights.y
+                + vertex.i_c.xyz * vertex.weights.z
+        );
+    }
+
+    // Reversed depth, larger is closer
+    clip.z *= 1.0 + DEPTH_BIAS;
+
+    var out: VertexOutput;
+    out.clip_position = clip;
+    out.color = vertex.i_color;
+    out.corner = vertex.corner;
+    out.kind = kind;
+    return out;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Outside of the branch, derivatives need uniform control flow
+    let radius = length(in.corner);
+    let smoothing = fwidth(radius);
+
+    var alpha = in.color.a;
+    if in.kind == KIND_DOT {
+        // Round dots with a smooth edge
+        alpha *= 1.0 - smoothstep(1.0 - smoothing, 1.0, radius);
+        if alpha <= 0.0 {
+            discard;
+        }
+    }
+
+    return vec4<f32>(in.color.rgb, alpha);
+}
@@ -1,44 +1,213 @@
 use core::panic;
-use std::collections::VecDeque;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
 
 use bevy::{
-    log::{info, warn},
+    log::warn,
     math::{
-        bounding::{Aabb3d, BoundingVolume, IntersectsVolume, RayCast3d},
-        Mat4, Quat, Vec3,
+        bounding::{Aabb3d, BoundingVolume, RayCast3d},
+        Affine3A, Mat4, Quat, Vec2, Vec3,
     },
-    prelude::{Color, Component, Gizmos, Query, Transform, With},
-    render::primitives::Aabb,
+    prelude::{Camera, Color, Component, Direction3d, GlobalTransform, Gizmos, Query, With},
+    render::primitives::{Aabb, Frustum},
+    utils::HashSet,
 };
 use lox::{
-    core::{Mesh as LoxMesh, Orientable},
-    map::{DenseMap, PropStore, PropStoreMut},
-    FaceHandle, Handle,
+    core::Mesh as LoxMesh,
+    hsize,
+    map::{DenseMap, PropMap, PropStore, PropStoreMut},
+    FaceHandle, VertexHandle,
 };
 
 use crate::core::editor::Focused;
 
-use crate::core::dim3::ray_intersects_convex_plane_at;
+use crate::{
+    core::dim3::{ray_intersects_convex_plane_at, transform_aabb, Triangle3d},
+    utils,
+};
 
 use super::EditableMesh;
 
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct BoundingVolumeHierarchy {
     pub nodes: Vec<Node>,
+    /// Parent of every node, indexed like `nodes`. The root's parent is [`Self::NO_PARENT`].
+    parents: Vec<u32>,
+    /// Leaf node currently holding each face.
+    face_leaves: DenseMap<FaceHandle, u32>,
+    /// Node slots released by subtree rebuilds, reused before `nodes` grows.
+    free_nodes: Vec<u32>,
+    /// SAH cost of the tree right after its last full build.
+    build_cost: f32,
 }
 
 impl Default for BoundingVolumeHierarchy {
     fn default() -> Self {
-        Self { nodes: Vec::new() }
+        Self {
+            nodes: Vec::new(),
+            parents: Vec::new(),
+            face_leaves: DenseMap::new(),
+            free_nodes: Vec::new(),
+            build_cost: 0.0,
+        }
+    }
+}
+
+/// Centroids and bounds of the faces taking part in a build, shared by the node splits.
+struct PrimitiveCache {
+    centroids: DenseMap<FaceHandle, Vec3>,
+    aabbs: DenseMap<FaceHandle, Aabb3d>,
+}
+
+impl PrimitiveCache {
+    fn new(mesh: &EditableMesh, faces: impl IntoIterator<Item = FaceHandle>) -> Self {
+        let faces = faces.into_iter();
+        let capacity = faces.size_hint().0;
+
+        let mut cache = Self {
+            centroids: DenseMap::with_capacity(capacity as hsize),
+            aabbs: DenseMap::with_capacity(capacity as hsize),
+        };
+
+        for face_handle in faces {
+            let (centroid, aabb) = face_bounds(mesh, face_handle);
+            cache.centroids.insert(face_handle, centroid);
+            cache.aabbs.insert(face_handle, aabb);
+        }
+
+        cache
+    }
+}
+
+/// Returns the centroid and bounding box of a face.
+fn face_bounds(mesh: &EditableMesh, face_handle: FaceHandle) -> (Vec3, Aabb3d) {
+    let face = mesh.structure.get_ref(face_handle);
+
+    let mut vertices = face
+        .adjacent_vertices()
+        .map(|v| mesh.vertex_positions[v.handle()]);
+
+    let first = vertices.next().unwrap();
+
+    let (sum, count, min, max) = vertices.fold(
+        (first, 1u32, first, first),
+        |(sum, count, min, max), position| {
+            (
+                sum + position,
+                count + 1,
+                position.min(min),
+                position.max(max),
+            )
+        },
+    );
+
+    (sum / count as f32, Aabb3d { min, max })
+}
+
+/// The point of a mesh surface closest to a query point.
+#[derive(Clone, Copy, Debug)]
+pub struct SurfacePoint {
+    pub face: FaceHandle,
+    /// World-space position of the point.
+    pub position: Vec3,
+    /// World-space distance from the query point.
+    pub distance: f32,
+    /// Vertices of the face triangle the point lies on. Polygons are fanned from their first vertex.
+    pub triangle: [VertexHandle; 3],
+    /// Barycentric coordinates of the point with respect to `triangle`.
+    pub barycentric: Vec3,
+}
+
+/// Node waiting in a best-first traversal, ordered so the closest node is popped first.
+#[derive(Clone, Copy, PartialEq)]
+struct NodeDistance {
+    distance_squared: f32,
+    node: u32,
+}
+
+impl Eq for NodeDistance {}
+
+impl Ord for NodeDistance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance_squared.total_cmp(&self.distance_squared)
+    }
+}
+
+impl PartialOrd for NodeDistance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Vertex candidate in a k-nearest search, ordered so the farthest one is popped first.
+#[derive(Clone, Copy, PartialEq)]
+struct VertexDistance {
+    distance_squared: f32,
+    vertex: VertexHandle,
+}
+
+impl Eq for VertexDistance {}
+
+impl Ord for VertexDistance {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared.total_cmp(&other.distance_squared)
+    }
+}
+
+impl PartialOrd for VertexDistance {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
+/// World-space triangles of a face, fanned from its first vertex.
+fn world_triangles(
+    mesh: &EditableMesh,
+    face_handle: FaceHandle,
+    affine: &Affine3A,
+) -> Vec<([VertexHandle; 3], Triangle3d)> {
+    let vertices: Vec<VertexHandle> = mesh
+        .structure
+        .get_ref(face_handle)
+        .adjacent_vertices()
+        .map(|v| v.handle())
+        .collect();
+
+    let world = |vertex_handle: VertexHandle| {
+        affine.transform_point3(mesh.vertex_positions[vertex_handle])
+    };
+
+    vertices[1..]
+        .windows(2)
+        .map(|pair| {
+            let handles = [vertices[0], pair[0], pair[1]];
+            (handles, Triangle3d::new(handles.map(world)))
+        })
+        .collect()
+}
+
+fn frustum_contains_point(frustum: &Frustum, point: Vec3) -> bool {
+    let point = point.extend(1.0);
+
+    frustum
+        .half_spaces
+        .iter()
+        .all(|half_space| half_space.normal_d().dot(point) >= 0.0)
+}
+
+fn aabb_eq(a: &Aabb3d, b: &Aabb3d) -> bool {
+    a.min == b.min && a.max == b.max
+}
+
 #[derive(Clone, Copy, Default, Debug)]
 struct Bucket {
     primitive_count: u32,
     aabb: Option<Aabb3d>,
 }
 
+#[derive(Clone)]
 pub enum Node {
     NonLeaf {
         left: u32,
@@ -52,18 +221,14 @@ pub enum Node {
 }
 
 impl Node {
-    pub fn intersects_ray(&self, ray: &RayCast3d, transform: &Transform) -> Option<f32> {
+    /// Expects the ray in the mesh's local space.
+    pub fn intersects_ray(&self, ray: &RayCast3d) -> Option<f32> {
         let aabb = match self {
             Node::Leaf { aabb, .. } => aabb,
             Node::NonLeaf { aabb, .. } => aabb,
         };
 
-        let aabb = Aabb3d::new(
-            aabb.center() * transform.scale + transform.translation,
-            aabb.half_size() * transform.scale,
-        );
-
-        ray.aabb_intersection_at(&aabb)
+        ray.aabb_intersection_at(aabb)
     }
 
     pub fn is_leaf(&self) -> bool {
@@ -93,17 +258,21 @@ impl Node {
             Node::NonLeaf { aabb, .. } => aabb.clone(),
         }
     }
+
+    fn set_aabb(&mut self, value: Aabb3d) {
+        match self {
+            Node::Leaf { aabb, .. } => *aabb = value,
+            Node::NonLeaf { aabb, .. } => *aabb = value,
+        }
+    }
 }
 
 pub fn bvh_debug_system(
-    query: Query<(&BoundingVolumeHierarchy, &Transform), With<Focused>>,
+    query: Query<(&BoundingVolumeHierarchy, &GlobalTransform), With<Focused>>,
     mut gizmo: Gizmos,
 ) {
     for (bvh, transform) in query.iter() {
-        for node in bvh.nodes.iter() {
-            if !node.is_leaf() {
-                continue;
-            }
+        for node in bvh.leaves() {
             let aabb: Aabb3d = node.aabb();
 
             let transform = transform.compute_matrix()
@@ -119,23 +288,468 @@ pub fn bvh_debug_system(
 
 impl BoundingVolumeHierarchy {
     const BUCKET_COUNT: usize = 16;
+    const MAXIMUM_PRIMITIVE_PER_LEAF: usize = 8;
+    const NO_PARENT: u32 = u32::MAX;
+
+    /// How much the SAH cost may grow over the build-time cost before [`Self::needs_rebuild`] asks for a full rebuild.
+    pub const REBUILD_COST_RATIO: f32 = 1.5;
 
     pub fn new() -> Self {
         Self::default()
     }
 
-    /// Does fast ray intersection test. Does not consider the primitives in the leaf node.
-    pub fn intersects_ray_at_fast(&self, ray: &RayCast3d, transform: &Transform) -> Option<f32> {
-        let transformed_ray = RayCast3d::new(
-            transform.rotation.mul_vec3(ray.ray.origin.into()),
-            transform
-                .rotation
-                .mul_vec3(ray.ray.direction.into())
-                .try_into()
-                .unwrap(),
-            ray.max,
+    /// Iterates over the leaves reachable from the root.
+    pub fn leaves(&self) -> impl Iterator<Item = &Node> {
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![0u32]
+        };
+
+        std::iter::from_fn(move || {
+            while let Some(index) = stack.pop() {
+                let node = &self.nodes[index as usize];
+
+                match node.children() {
+                    Some([left, right]) => {
+                        stack.push(right);
+                        stack.push(left);
+                    }
+                    None => return Some(node),
+                }
+            }
+            None
+        })
+    }
+
+    /// Number of nodes in use, not counting the released slots.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - self.free_nodes.len()
+    }
+
+    /// Number of nodes on the longest path from the root to a leaf, both included. Zero for an empty tree.
+    pub fn depth(&self) -> u32 {
+        let mut stack = if self.nodes.is_empty() {
+            vec![]
+        } else {
+            vec![(0u32, 1u32)]
+        };
+        let mut depth = 0;
+
+        while let Some((index, level)) = stack.pop() {
+            depth = depth.max(level);
+            if let Some([left, right]) = self.nodes[index as usize].children() {
+                stack.push((left, level + 1));
+                stack.push((right, level + 1));
+            }
+        }
+
+        depth
+    }
+
+    /// Approximate heap memory held by the tree, in bytes.
+    pub fn memory_usage(&self) -> usize {
+        let primitives: usize = self
+            .leaves()
+            .filter_map(|leaf| leaf.primitives())
+            .map(|primitives| primitives.capacity())
+            .sum();
+
+        self.nodes.capacity() * std::mem::size_of::<Node>()
+            + primitives * std::mem::size_of::<FaceHandle>()
+            + (self.parents.capacity() + self.free_nodes.capacity()) * std::mem::size_of::<u32>()
+            // One leaf index per face
+            + primitives * std::mem::size_of::<u32>()
+    }
+
+    /// Surface area heuristic cost of the tree, relative to the root's surface area.
+    /// Lower is better; it grows as refits loosen the node bounds.
+    pub fn sah_cost(&self) -> f32 {
+        let Some(root) = self.nodes.first() else {
+            return 0.0;
+        };
+
+        let root_area = root.aabb().visible_area();
+
+        if root_area <= f32::EPSILON {
+            return 0.0;
+        }
+
+        let mut cost = 0.0;
+        let mut stack = vec![0u32];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            let relative_area = node.aabb().visible_area() / root_area;
+
+            match node {
+                Node::NonLeaf { left, right, .. } => {
+                    cost += relative_area;
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+                Node::Leaf { primitive_list, .. } => {
+                    cost += relative_area * primitive_list.len() as f32;
+                }
+            }
+        }
+
+        cost
+    }
+
+    /// Whether incremental updates have degraded the tree enough that a full rebuild pays off.
+    pub fn needs_rebuild(&self) -> bool {
+        self.sah_cost() > self.build_cost * Self::REBUILD_COST_RATIO
+    }
+
+    /// Updates the bounds of the nodes holding faces adjacent to `moved_vertices`, bottom-up.
+    /// The tree structure is kept as is, so this is only valid when the topology did not change.
+    pub fn refit(
+        &mut self,
+        mesh: &EditableMesh,
+        moved_vertices: impl IntoIterator<Item = VertexHandle>,
+    ) {
+        let mut dirty_leaves = HashSet::new();
+
+        for vertex_handle in moved_vertices {
+            for face in mesh.structure.get_ref(vertex_handle).adjacent_faces() {
+                if let Some(leaf) = self.face_leaves.get(face.handle()) {
+                    dirty_leaves.insert(*leaf);
+                }
+            }
+        }
+
+        for leaf in dirty_leaves {
+            self.refit_leaf(leaf, mesh);
+            self.refit_ancestors(leaf);
+        }
+    }
+
+    /// Recomputes the bounds of every node from the current vertex positions.
+    pub fn refit_all(&mut self, mesh: &EditableMesh) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        // Children are visited before their parents by walking a pre-order list backwards
+        let mut order = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![0u32];
+
+        while let Some(index) = stack.pop() {
+            order.push(index);
+            if let Some([left, right]) = self.nodes[index as usize].children() {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+
+        for index in order.into_iter().rev() {
+            match self.nodes[index as usize].children() {
+                Some([left, right]) => {
+                    let aabb = self.nodes[left as usize]
+                        .aabb()
+                        .merge(&self.nodes[right as usize].aabb());
+                    self.nodes[index as usize].set_aabb(aabb);
+                }
+                None => self.refit_leaf(index, mesh),
+            }
+        }
+    }
+
+    /// Refits the tree for moved vertices, falling back to a full rebuild when the
+    /// refitted tree has drifted too far from the build-time quality.
+    pub fn refit_or_rebuild(
+        &mut self,
+        mesh: &EditableMesh,
+        moved_vertices: impl IntoIterator<Item = VertexHandle>,
+    ) {
+        self.refit(mesh, moved_vertices);
+
+        if self.needs_rebuild() {
+            *self = Self::from(mesh);
+        }
+    }
+
+    /// Updates the tree after a local topology change. Removed faces are dropped from their leaves,
+    /// added faces are inserted where they grow the tree the least, and the smallest subtree
+    /// containing every touched leaf is rebuilt with the SAH.
+    pub fn rebuild_region(
+        &mut self,
+        mesh: &EditableMesh,
+        removed_faces: impl IntoIterator<Item = FaceHandle>,
+        added_faces: impl IntoIterator<Item = FaceHandle>,
+    ) {
+        if self.nodes.is_empty() {
+            *self = Self::from(mesh);
+            return;
+        }
+
+        let mut touched_leaves = HashSet::new();
+
+        for face_handle in removed_faces {
+            let Some(leaf) = self.face_leaves.remove(face_handle) else {
+                continue;
+            };
+
+            if let Node::Leaf { primitive_list, .. } = &mut self.nodes[leaf as usize] {
+                primitive_list.retain(|handle| *handle != face_handle);
+            }
+
+            touched_leaves.insert(leaf);
+        }
+
+        for face_handle in added_faces {
+            let (_, aabb) = face_bounds(mesh, face_handle);
+            let leaf = self.find_insertion_leaf(&aabb);
+
+            if let Node::Leaf { primitive_list, .. } = &mut self.nodes[leaf as usize] {
+                primitive_list.push(face_handle);
+            }
+
+            self.face_leaves.insert(face_handle, leaf);
+            touched_leaves.insert(leaf);
+        }
+
+        let Some(subtree_root) = self.common_ancestor(touched_leaves.into_iter()) else {
+            return;
+        };
+
+        self.rebuild_subtree(subtree_root, mesh);
+    }
+
+    /// Rebuilds the subtree rooted at `node_index` from scratch, keeping the rest of the tree intact.
+    pub fn rebuild_subtree(&mut self, node_index: u32, mesh: &EditableMesh) {
+        let previous_aabb = self.nodes[node_index as usize].aabb();
+        let mut primitives = Vec::new();
+        let mut stack = vec![node_index];
+
+        while let Some(index) = stack.pop() {
+            let node = std::mem::replace(
+                &mut self.nodes[index as usize],
+                Node::Leaf {
+                    aabb: Aabb3d {
+                        min: Vec3::ZERO,
+                        max: Vec3::ZERO,
+                    },
+                    primitive_list: Vec::new(),
+                },
+            );
+
+            match node {
+                Node::NonLeaf { left, right, .. } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+                Node::Leaf { primitive_list, .. } => primitives.extend(primitive_list),
+            }
+
+            if index != node_index {
+                self.free_nodes.push(index);
+            }
+        }
+
+        let cache = PrimitiveCache::new(mesh, primitives.iter().cloned());
+
+        let aabb = cache
+            .aabbs
+            .values()
+            .cloned()
+            .reduce(|acc, curr| acc.merge(&curr))
+            .unwrap_or(previous_aabb);
+
+        for face_handle in primitives.iter().cloned() {
+            self.face_leaves.insert(face_handle, node_index);
+        }
+
+        self.nodes[node_index as usize] = Node::Leaf {
+            aabb,
+            primitive_list: primitives,
+        };
+
+        self.split_recursively(node_index, &cache);
+        self.refit_ancestors(node_index);
+    }
+
+    fn refit_leaf(&mut self, leaf: u32, mesh: &EditableMesh) {
+        let Node::Leaf {
+            aabb,
+            primitive_list,
+        } = &mut self.nodes[leaf as usize]
+        else {
+            return;
+        };
+
+        if let Some(refitted) = primitive_list
+            .iter()
+            .map(|face_handle| face_bounds(mesh, *face_handle).1)
+            .reduce(|acc, curr| acc.merge(&curr))
+        {
+            *aabb = refitted;
+        }
+    }
+
+    /// Propagates a node's bounds up to the root, stopping early once a parent's bounds no longer change.
+    fn refit_ancestors(&mut self, node_index: u32) {
+        let mut current = node_index;
+
+        while let Some(&parent) = self.parents.get(current as usize) {
+            if parent == Self::NO_PARENT {
+                break;
+            }
+
+            let [left, right] = self.nodes[parent as usize].children().unwrap();
+            let refitted = self.nodes[left as usize]
+                .aabb()
+                .merge(&self.nodes[right as usize].aabb());
+
+            if aabb_eq(&refitted, &self.nodes[parent as usize].aabb()) {
+                break;
+            }
+
+            self.nodes[parent as usize].set_aabb(refitted);
+            current = parent;
+        }
+    }
+
+    /// Walks down from the root, always taking the child whose surface area grows the least.
+    fn find_insertion_leaf(&self, aabb: &Aabb3d) -> u32 {
+        let mut current = 0u32;
+
+        while let Some([left, right]) = self.nodes[current as usize].children() {
+            let growth = |index: u32| {
+                let node_aabb = self.nodes[index as usize].aabb();
+                node_aabb.merge(aabb).visible_area() - node_aabb.visible_area()
+            };
+
+            current = if growth(left) <= growth(right) {
+                left
+            } else {
+                right
+            };
+        }
+
+        current
+    }
+
+    fn depth_of(&self, node_index: u32) -> u32 {
+        let mut depth = 0;
+        let mut current = node_index;
+
+        while self.parents[current as usize] != Self::NO_PARENT {
+            current = self.parents[current as usize];
+            depth += 1;
+        }
+
+        depth
+    }
+
+    fn common_ancestor(&self, nodes: impl Iterator<Item = u32>) -> Option<u32> {
+        nodes.reduce(|a, b| {
+            let (mut a, mut b) = (a, b);
+            let (mut depth_a, mut depth_b) = (self.depth_of(a), self.depth_of(b));
+
+            while depth_a > depth_b {
+                a = self.parents[a as usize];
+                depth_a -= 1;
+            }
+            while depth_b > depth_a {
+                b = self.parents[b as usize];
+                depth_b -= 1;
+            }
+            while a != b {
+                a = self.parents[a as usize];
+                b = self.parents[b as usize];
+            }
+
+            a
+        })
+    }
+
+    fn allocate_node(&mut self, node: Node, parent: u32) -> u32 {
+        match self.free_nodes.pop() {
+            Some(index) => {
+                self.nodes[index as usize] = node;
+                self.parents[index as usize] = parent;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.parents.push(parent);
+                self.nodes.len() as u32 - 1
+            }
+        }
+    }
+
+    /// Splits a leaf until every leaf below it holds at most [`Self::MAXIMUM_PRIMITIVE_PER_LEAF`] primitives.
+    fn split_recursively(&mut self, node_index: u32, cache: &PrimitiveCache) {
+        let mut queue = VecDeque::<u32>::new();
+
+        queue.push_back(node_index);
+
+        while let Some(top) = queue.pop_front() {
+            let Node::Leaf { primitive_list, .. } = &self.nodes[top as usize] else {
+                panic!("This should not happen, likely a bug in the code");
+            };
+
+            let primitive_count = primitive_list.len();
+
+            if primitive_count <= Self::MAXIMUM_PRIMITIVE_PER_LEAF {
+                continue;
+            }
+
+            match self.split_node(top, cache) {
+                Some((left, right)) => {
+                    queue.push_back(left);
+                    queue.push_back(right);
+                }
+                None => {
+                    warn!(
+                        "Failed to split node, leaving at size {} when max is {}. Consider increasing the maximum primitive per leaf",
+                        primitive_count,
+                        Self::MAXIMUM_PRIMITIVE_PER_LEAF
+                    );
+                }
+            };
+        }
+    }
+
+    /// Maps a world-space ray into the local space of a mesh with the given transform.
+    /// Returns the local ray and the factor that converts local distances back into world distances.
+    pub fn local_ray(ray: &RayCast3d, transform: &GlobalTransform) -> Option<(RayCast3d, f32)> {
+        let world_to_local = transform.affine().inverse();
+
+        let origin = world_to_local.transform_point3(ray.ray.origin);
+        let direction = world_to_local.transform_vector3(ray.ray.direction.into());
+
+        // Scale stretches the direction, so a local distance covers less (or more) of the world
+        let local_per_world = direction.length();
+
+        if !local_per_world.is_normal() {
+            return None;
+        }
+
+        let local_ray = RayCast3d::new(
+            origin,
+            Direction3d::new(direction).ok()?,
+            ray.max * local_per_world,
         );
 
+        Some((local_ray, local_per_world.recip()))
+    }
+
+    /// Does fast ray intersection test. Does not consider the primitives in the leaf node.
+    /// The ray is in world space, and so is the returned distance.
+    pub fn intersects_ray_at_fast(
+        &self,
+        ray: &RayCast3d,
+        transform: &GlobalTransform,
+    ) -> Option<f32> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let (transformed_ray, world_per_local) = Self::local_ray(ray, transform)?;
+
         let mut stack = VecDeque::<u32>::new();
 
         stack.push_back(0);
@@ -146,7 +760,7 @@ impl BoundingVolumeHierarchy {
             let top = stack.pop_back().unwrap();
             let node = &self.nodes[top as usize];
 
-            match node.intersects_ray(&transformed_ray, transform) {
+            match node.intersects_ray(&transformed_ray) {
                 Some(t) => {
                     if let Some(value) = closest_t {
                         if t > value {
@@ -161,8 +775,8 @@ impl BoundingVolumeHierarchy {
                         let left_node = &self.nodes[left as usize];
                         let right_node = &self.nodes[right as usize];
 
-                        let left_t = left_node.intersects_ray(&transformed_ray, transform);
-                        let right_t = right_node.intersects_ray(&transformed_ray, transform);
+                        let left_t = left_node.intersects_ray(&transformed_ray);
+                        let right_t = right_node.intersects_ray(&transformed_ray);
 
                         match (left_t, right_t) {
                             (Some(left_t), Some(right_t)) => {
@@ -185,24 +799,21 @@ impl BoundingVolumeHierarchy {
             }
         }
 
-        closest_t
+        closest_t.map(|t| t * world_per_local)
     }
 
+    /// Finds the closest face hit by a world-space ray, and the world-space distance to it.
     pub fn intersects_ray_at(
         &self,
         ray: &RayCast3d,
-        transform: &Transform,
+        transform: &GlobalTransform,
         mesh: &EditableMesh,
     ) -> Option<(FaceHandle, f32)> {
-        let transformed_ray = RayCast3d::new(
-            transform.rotation.mul_vec3(ray.ray.origin.into()),
-            transform
-                .rotation
-                .mul_vec3(ray.ray.direction.into())
-                .try_into()
-                .unwrap(),
-            ray.max,
-        );
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let (transformed_ray, world_per_local) = Self::local_ray(ray, transform)?;
 
         let mut stack = VecDeque::<u32>::new();
 
@@ -214,7 +825,7 @@ impl BoundingVolumeHierarchy {
             let top = stack.pop_back().unwrap();
             let node = &self.nodes[top as usize];
 
-            match node.intersects_ray(&transformed_ray, transform) {
+            match node.intersects_ray(&transformed_ray) {
                 Some(t) => {
                     if let Some((_, value)) = closest {
                         if t > value {
@@ -229,11 +840,7 @@ impl BoundingVolumeHierarchy {
                                 .map(|v| mesh.vertex_positions[v.handle()])
                                 .collect();
 
-                            match ray_intersects_convex_plane_at(
-                                &transformed_ray,
-                                &transform,
-                                &vertices,
-                            ) {
+                            match ray_intersects_convex_plane_at(&transformed_ray, &vertices) {
                                 Some(t) => {
                                     if let Some((_, value)) = closest {
                                         if t < value {
@@ -252,8 +859,8 @@ impl BoundingVolumeHierarchy {
                         let left_node = &self.nodes[left as usize];
                         let right_node = &self.nodes[right as usize];
 
-                        let left_t = left_node.intersects_ray(&transformed_ray, transform);
-                        let right_t = right_node.intersects_ray(&transformed_ray, transform);
+                        let left_t = left_node.intersects_ray(&transformed_ray);
+                        let right_t = right_node.intersects_ray(&transformed_ray);
 
                         match (left_t, right_t) {
                             (Some(left_t), Some(right_t)) => {
@@ -276,15 +883,340 @@ impl BoundingVolumeHierarchy {
             }
         }
 
+        closest.map(|(face_handle, t)| (face_handle, t * world_per_local))
+    }
+
+    fn world_aabb(&self, node_index: u32, affine: &Affine3A) -> Aabb3d {
+        transform_aabb(&self.nodes[node_index as usize].aabb(), affine)
+    }
+
+    /// Finds the point on the mesh surface closest to a world-space point.
+    pub fn closest_point(
+        &self,
+        point: Vec3,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Option<SurfacePoint> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let affine = transform.affine();
+
+        let mut queue = BinaryHeap::new();
+        queue.push(NodeDistance {
+            distance_squared: 0.0,
+            node: 0,
+        });
+
+        let mut closest = Option::<SurfacePoint>::None;
+
+        while let Some(NodeDistance {
+            distance_squared,
+            node,
+        }) = queue.pop()
+        {
+            if let Some(closest) = &closest {
+                if distance_squared >= closest.distance * closest.distance {
+                    break;
+                }
+            }
+
+            match &self.nodes[node as usize] {
+                Node::NonLeaf { left, right, .. } => {
+                    for child in [*left, *right] {
+                        let aabb = self.world_aabb(child, &affine);
+                        queue.push(NodeDistance {
+                            distance_squared: aabb.closest_point(point).distance_squared(point),
+                            node: child,
+                        });
+                    }
+                }
+                Node::Leaf { primitive_list, .. } => {
+                    for face_handle in primitive_list.iter().cloned() {
+                        for (handles, triangle) in world_triangles(mesh, face_handle, &affine) {
+                            let (position, barycentric) = triangle.closest_point(point);
+                            let distance = position.distance(point);
+
+                            if closest.map_or(true, |closest| distance < closest.distance) {
+                                closest = Some(SurfacePoint {
+                                    face: face_handle,
+                                    position,
+                                    distance,
+                                    triangle: handles,
+                                    barycentric,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         closest
     }
 
-    fn split_node(
-        &mut self,
-        node_index: u32,
-        face_centroid_cache: &DenseMap<FaceHandle, Vec3>,
-        face_aabb_cache: &DenseMap<FaceHandle, Aabb3d>,
-    ) -> Option<(u32, u32)> {
+    /// Collects the faces that touch a world-space sphere, e.g. the area under a sculpt brush.
+    pub fn faces_in_sphere(
+        &self,
+        center: Vec3,
+        radius: f32,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<FaceHandle> {
+        let mut faces = Vec::new();
+
+        if self.nodes.is_empty() {
+            return faces;
+        }
+
+        let affine = transform.affine();
+        let radius_squared = radius * radius;
+        let mut stack = vec![0u32];
+
+        while let Some(node) = stack.pop() {
+            let aabb = self.world_aabb(node, &affine);
+
+            if aabb.closest_point(center).distance_squared(center) > radius_squared {
+                continue;
+            }
+
+            match &self.nodes[node as usize] {
+                Node::NonLeaf { left, right, .. } => {
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+                Node::Leaf { primitive_list, .. } => {
+                    for face_handle in primitive_list.iter().cloned() {
+                        let touches = world_triangles(mesh, face_handle, &affine)
+                            .iter()
+                            .any(|(_, triangle)| {
+                                triangle.closest_point(center).0.distance_squared(center)
+                                    <= radius_squared
+                            });
+
+                        if touches {
+                            faces.push(face_handle);
+                        }
+                    }
+                }
+            }
+        }
+
+        faces
+    }
+
+    /// Walks the leaves whose bounds intersect a world-space frustum.
+    fn frustum_leaves<'a>(
+        &'a self,
+        frustum: &Frustum,
+        affine: &Affine3A,
+    ) -> impl Iterator<Item = &'a Vec<FaceHandle>> {
+        let mut leaves = Vec::new();
+
+        if self.nodes.is_empty() {
+            return leaves.into_iter();
+        }
+
+        let mut stack = vec![0u32];
+
+        while let Some(node) = stack.pop() {
+            let aabb = self.nodes[node as usize].aabb();
+
+            if !frustum.intersects_obb(
+                &Aabb::from_min_max(aabb.min, aabb.max),
+                affine,
+                true,
+                true,
+            ) {
+                continue;
+            }
+
+            match &self.nodes[node as usize] {
+                Node::NonLeaf { left, right, .. } => {
+                    stack.push(*left);
+                    stack.push(*right);
+                }
+                Node::Leaf { primitive_list, .. } => leaves.push(primitive_list),
+            }
+        }
+
+        leaves.into_iter()
+    }
+
+    /// Collects the faces lying completely inside a world-space frustum, e.g. a camera's view.
+    pub fn faces_in_frustum(
+        &self,
+        frustum: &Frustum,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<FaceHandle> {
+        let affine = transform.affine();
+
+        self.frustum_leaves(frustum, &affine)
+            .flatten()
+            .cloned()
+            .filter(|face_handle| {
+                mesh.structure
+                    .get_ref(*face_handle)
+                    .adjacent_vertices()
+                    .all(|v| {
+                        frustum_contains_point(
+                            frustum,
+                            affine.transform_point3(mesh.vertex_positions[v.handle()]),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    /// Collects the vertices lying inside a world-space frustum.
+    pub fn vertices_in_frustum(
+        &self,
+        frustum: &Frustum,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<VertexHandle> {
+        let affine = transform.affine();
+        let mut visited = HashSet::new();
+
+        self.frustum_leaves(frustum, &affine)
+            .flatten()
+            .flat_map(|face_handle| mesh.structure.get_ref(*face_handle).adjacent_vertices())
+            .map(|v| v.handle())
+            .filter(|vertex_handle| visited.insert(*vertex_handle))
+            .filter(|vertex_handle| {
+                frustum_contains_point(
+                    frustum,
+                    affine.transform_point3(mesh.vertex_positions[*vertex_handle]),
+                )
+            })
+            .collect()
+    }
+
+    /// Collects the faces inside a screen rectangle, for box selection. `min` and `max` are viewport coordinates.
+    pub fn faces_in_screen_rect(
+        &self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        far: f32,
+        min: Vec2,
+        max: Vec2,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<FaceHandle> {
+        let Some(frustum) =
+            utils::projection::screen_rect_frustum(camera, camera_transform, far, min, max)
+        else {
+            return vec![];
+        };
+
+        self.faces_in_frustum(&frustum, transform, mesh)
+    }
+
+    /// Collects the vertices inside a screen rectangle, for box selection. `min` and `max` are viewport coordinates.
+    pub fn vertices_in_screen_rect(
+        &self,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        far: f32,
+        min: Vec2,
+        max: Vec2,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<VertexHandle> {
+        let Some(frustum) =
+            utils::projection::screen_rect_frustum(camera, camera_transform, far, min, max)
+        else {
+            return vec![];
+        };
+
+        self.vertices_in_frustum(&frustum, transform, mesh)
+    }
+
+    /// Finds the `k` vertices closest to a world-space point, nearest first, with their world-space distances.
+    /// Only vertices that belong to at least one face are considered.
+    pub fn nearest_vertices(
+        &self,
+        point: Vec3,
+        k: usize,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+    ) -> Vec<(VertexHandle, f32)> {
+        if self.nodes.is_empty() || k == 0 {
+            return vec![];
+        }
+
+        let affine = transform.affine();
+
+        let mut queue = BinaryHeap::new();
+        queue.push(NodeDistance {
+            distance_squared: 0.0,
+            node: 0,
+        });
+
+        let mut nearest = BinaryHeap::<VertexDistance>::with_capacity(k + 1);
+        let mut visited = HashSet::new();
+
+        while let Some(NodeDistance {
+            distance_squared,
+            node,
+        }) = queue.pop()
+        {
+            if nearest.len() == k
+                && distance_squared >= nearest.peek().unwrap().distance_squared
+            {
+                break;
+            }
+
+            match &self.nodes[node as usize] {
+                Node::NonLeaf { left, right, .. } => {
+                    for child in [*left, *right] {
+                        let aabb = self.world_aabb(child, &affine);
+                        queue.push(NodeDistance {
+                            distance_squared: aabb.closest_point(point).distance_squared(point),
+                            node: child,
+                        });
+                    }
+                }
+                Node::Leaf { primitive_list, .. } => {
+                    for face_handle in primitive_list.iter().cloned() {
+                        for vertex in mesh.structure.get_ref(face_handle).adjacent_vertices() {
+                            let vertex_handle = vertex.handle();
+
+                            if !visited.insert(vertex_handle) {
+                                continue;
+                            }
+
+                            let distance_squared = affine
+                                .transform_point3(mesh.vertex_positions[vertex_handle])
+                                .distance_squared(point);
+
+                            nearest.push(VertexDistance {
+                                distance_squared,
+                                vertex: vertex_handle,
+                            });
+
+                            if nearest.len() > k {
+                                nearest.pop();
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|candidate| (candidate.vertex, candidate.distance_squared.sqrt()))
+            .collect()
+    }
+
+    fn split_node(&mut self, node_index: u32, cache: &PrimitiveCache) -> Option<(u32, u32)> {
+        let face_centroid_cache = &cache.centroids;
+        let face_aabb_cache = &cache.aabbs;
+
         let root = &self.nodes[node_index as usize];
 
         let (root_aabb, root_primitive_list) = match root {
@@ -439,18 +1371,34 @@ impl BoundingVolumeHierarchy {
         assert_eq!(left_primitive_list.len(), best_split_left_size as usize);
         assert_eq!(right_primitive_list.len(), best_split_right_size as usize);
 
-        let left_node_index = self.nodes.len() as u32;
-        let right_node_index = left_node_index + 1;
+        let left_node_index = self.allocate_node(
+            Node::Leaf {
+                aabb: best_split_left_aabb,
+                primitive_list: Vec::new(),
+            },
+            node_index,
+        );
 
-        self.nodes.push(Node::Leaf {
-            aabb: best_split_left_aabb,
-            primitive_list: left_primitive_list,
-        });
+        let right_node_index = self.allocate_node(
+            Node::Leaf {
+                aabb: best_split_right_aabb,
+                primitive_list: Vec::new(),
+            },
+            node_index,
+        );
 
-        self.nodes.push(Node::Leaf {
-            aabb: best_split_right_aabb,
-            primitive_list: right_primitive_list,
-        });
+        for (leaf, primitives) in [
+            (left_node_index, left_primitive_list),
+            (right_node_index, right_primitive_list),
+        ] {
+            for face_handle in primitives.iter().cloned() {
+                self.face_leaves.insert(face_handle, leaf);
+            }
+
+            if let Node::Leaf { primitive_list, .. } = &mut self.nodes[leaf as usize] {
+                *primitive_list = primitives;
+            }
+        }
 
         self.nodes[node_index as usize] = Node::NonLeaf {
             left: left_node_index,
@@ -464,99 +1412,180 @@ impl BoundingVolumeHierarchy {
 
 impl From<&EditableMesh> for BoundingVolumeHierarchy {
     fn from(mesh: &EditableMesh) -> Self {
-        let maximum_primitive_per_leaf = 8u32;
+        let cache = PrimitiveCache::new(mesh, mesh.structure.face_handles());
+
+        let mut face_aabbs = cache.aabbs.values();
+        let first = face_aabbs.next().unwrap().clone();
+
+        let root_aabb = face_aabbs.fold(first, |acc, curr| acc.merge(curr));
+
+        let mut bvh = BoundingVolumeHierarchy::new();
+
+        let primitive_list: Vec<FaceHandle> = mesh.structure.face_handles().collect();
 
-        let mut face_centroid_cache =
-            DenseMap::<FaceHandle, Vec3>::with_capacity(mesh.structure.num_faces());
-        let mut face_aabb_cache =
-            DenseMap::<FaceHandle, Aabb3d>::with_capacity(mesh.structure.num_faces());
+        for face_handle in primitive_list.iter().cloned() {
+            bvh.face_leaves.insert(face_handle, 0);
+        }
 
-        for face in mesh.structure.faces() {
-            let vertex_count = face.adjacent_vertices().count();
+        bvh.allocate_node(
+            Node::Leaf {
+                aabb: root_aabb,
+                primitive_list,
+            },
+            Self::NO_PARENT,
+        );
 
-            let centroid = face
-                .adjacent_vertices()
-                .map(|v| mesh.vertex_positions[v.handle()])
-                .fold(Vec3::ZERO, |accumulator, position| position + accumulator)
-                / vertex_count as f32;
+        bvh.split_recursively(0, &cache);
 
-            face_centroid_cache.insert(face.handle(), centroid);
+        bvh.build_cost = bvh.sah_cost();
 
-            let mut vertices = face.adjacent_vertices();
+        return bvh;
+    }
+}
 
-            let first = mesh.vertex_positions[vertices.next().unwrap().handle()];
+#[cfg(test)]
+mod test {
+    use bevy::{
+        math::bounding::{BoundingVolume, RayCast3d},
+        prelude::{
+            Cuboid, Direction3d, GlobalTransform, Mat4, Meshable, Quat, Ray3d, Sphere, Transform,
+            Vec3,
+        },
+        render::primitives::Frustum,
+    };
+    use lox::{
+        core::{Mesh as LoxMesh, MeshMut},
+        map::PropStoreMut,
+        FaceHandle, VertexHandle,
+    };
+
+    use crate::core::editable_mesh::EditableMesh;
 
-            let (min, max) = vertices
-                .map(|v| mesh.vertex_positions[v.handle()])
-                .fold((first, first), |(min, max), position| {
-                    (position.min(min), position.max(max))
-                });
+    use super::{BoundingVolumeHierarchy, Node};
 
-            face_aabb_cache.insert(face.handle(), Aabb3d { min, max });
+    fn sphere() -> EditableMesh {
+        EditableMesh::from(&Sphere::new(1.0).mesh().ico(4).unwrap())
+    }
+
+    /// Checks that every reachable node contains its children and every face is held by the leaf it is mapped to.
+    fn assert_consistent(bvh: &BoundingVolumeHierarchy, mesh: &EditableMesh) {
+        let mut stack = vec![0u32];
+        let mut face_count = 0;
+
+        while let Some(index) = stack.pop() {
+            match &bvh.nodes[index as usize] {
+                Node::NonLeaf { left, right, aabb } => {
+                    for child in [*left, *right] {
+                        assert!(aabb.contains(&bvh.nodes[child as usize].aabb()));
+                        assert_eq!(bvh.parents[child as usize], index);
+                        stack.push(child);
+                    }
+                }
+                Node::Leaf {
+                    aabb,
+                    primitive_list,
+                } => {
+                    for face_handle in primitive_list.iter().cloned() {
+                        assert_eq!(bvh.face_leaves[face_handle], index);
+                        let (_, face_aabb) = super::face_bounds(mesh, face_handle);
+                        assert!(aabb.contains(&face_aabb));
+                    }
+                    face_count += primitive_list.len();
+                }
+            }
         }
 
-        let mut face_aabbs = face_aabb_cache.values();
-        let first = face_aabbs.next().unwrap().clone();
+        assert_eq!(face_count, mesh.structure.num_faces() as usize);
+    }
 
-        let root_aabb = face_aabb_cache
-            .values()
-            .fold(first, |acc, curr| acc.merge(curr));
+    #[test]
+    fn test_refit_after_moving_vertices() {
+        let mut mesh = sphere();
+        let mut bvh = BoundingVolumeHierarchy::from(&mesh);
 
-        let mut bvh = BoundingVolumeHierarchy::new();
+        let moved: Vec<VertexHandle> = mesh.structure.vertex_handles().take(20).collect();
 
-        let node = Node::Leaf {
-            aabb: root_aabb,
-            primitive_list: mesh.structure.face_handles().collect(),
-        };
+        for vertex_handle in moved.iter().cloned() {
+            mesh.vertex_positions[vertex_handle] *= 3.0;
+        }
 
-        bvh.nodes.push(node);
+        bvh.refit(&mesh, moved.iter().cloned());
 
-        let mut queue = VecDeque::<u32>::new();
+        assert_consistent(&bvh, &mesh);
+        assert!(bvh.nodes[0].aabb().max.max_element() > 1.5);
+    }
 
-        queue.push_back(0);
+    #[test]
+    fn test_refit_all_matches_fresh_bounds() {
+        let mut mesh = sphere();
+        let mut bvh = BoundingVolumeHierarchy::from(&mesh);
 
-        while !queue.is_empty() {
-            let top = queue.pop_front().unwrap();
+        let vertices: Vec<VertexHandle> = mesh.structure.vertex_handles().collect();
+        for vertex_handle in vertices {
+            mesh.vertex_positions[vertex_handle] += Vec3::new(5.0, 0.0, 0.0);
+        }
 
-            let node = bvh.nodes.get(top as usize).unwrap();
+        bvh.refit_all(&mesh);
 
-            let Node::Leaf { primitive_list, .. } = node else {
-                panic!("This should not happen, likely a bug in the code");
-            };
+        assert_consistent(&bvh, &mesh);
 
-            let primitive_count = primitive_list.len();
+        let fresh = BoundingVolumeHierarchy::from(&mesh);
+        let (refitted_root, fresh_root) = (bvh.nodes[0].aabb(), fresh.nodes[0].aabb());
+        assert!((refitted_root.min - fresh_root.min).length() < 1e-5);
+        assert!((refitted_root.max - fresh_root.max).length() < 1e-5);
+    }
 
-            if primitive_count <= maximum_primitive_per_leaf as usize {
-                continue;
-            }
+    #[test]
+    fn test_rebuild_region_after_topology_change() {
+        let mut mesh = sphere();
+        let mut bvh = BoundingVolumeHierarchy::from(&mesh);
 
-            match bvh.split_node(top, &face_centroid_cache, &face_aabb_cache) {
-                Some((left, right)) => {
-                    queue.push_back(left);
-                    queue.push_back(right);
-                }
-                None => {
-                    warn!(
-                        "Failed to split node, leaving at size {} when max is {}. Consider increasing the maximum primitive per leaf",
-                        primitive_count, maximum_primitive_per_leaf
-                    );
-                }
-            };
+        let removed: Vec<FaceHandle> = mesh.structure.face_handles().take(4).collect();
+        for face_handle in removed.iter().cloned() {
+            mesh.structure.remove_face(face_handle);
         }
 
-        info!("{}, {}", bvh.nodes.len(), mesh.structure.num_faces());
+        let vertices = [
+            mesh.structure.add_vertex(),
+            mesh.structure.add_vertex(),
+            mesh.structure.add_vertex(),
+        ];
+        for (vertex_handle, position) in vertices.iter().zip([
+            Vec3::new(2.0, 2.0, 2.0),
+            Vec3::new(2.5, 2.0, 2.0),
+            Vec3::new(2.0, 2.5, 2.0),
+        ]) {
+            mesh.vertex_positions.insert(*vertex_handle, position);
+        }
+        let added = mesh.structure.add_face(&vertices);
 
-        return bvh;
+        bvh.rebuild_region(&mesh, removed.iter().cloned(), [added]);
+
+        assert_consistent(&bvh, &mesh);
+        assert!(bvh.nodes[0].aabb().max.x >= 2.5);
     }
-}
 
-#[cfg(test)]
-mod test {
-    use bevy::prelude::{Meshable, Sphere};
+    #[test]
+    fn test_sah_cost_triggers_rebuild() {
+        let mut mesh = sphere();
+        let mut bvh = BoundingVolumeHierarchy::from(&mesh);
 
-    use crate::core::editable_mesh::EditableMesh;
+        assert!(!bvh.needs_rebuild());
 
-    use super::BoundingVolumeHierarchy;
+        // Scrambling positions keeps the tree structure but makes its nodes overlap heavily
+        let vertices: Vec<VertexHandle> = mesh.structure.vertex_handles().collect();
+        for (i, vertex_handle) in vertices.iter().cloned().enumerate() {
+            let position = mesh.vertex_positions[vertex_handle];
+            mesh.vertex_positions[vertex_handle] = if i % 2 == 0 { -position } else { position };
+        }
+
+        bvh.refit_all(&mesh);
+        assert!(bvh.needs_rebuild());
+
+        bvh.refit_or_rebuild(&mesh, vertices.iter().cloned());
+        assert!(!bvh.needs_rebuild());
+        assert_consistent(&bvh, &mesh);
+    }
 
     #[test]
     fn test_bvh_from_mesh() {
@@ -568,4 +1597,241 @@ mod test {
 
         print!("{}", bvh.nodes.len());
     }
+
+    #[test]
+    fn test_tree_statistics() {
+        let mesh = sphere();
+        let bvh = BoundingVolumeHierarchy::from(&mesh);
+
+        let faces = mesh.structure.num_faces() as usize;
+
+        // Every inner node has two children
+        let leaves = bvh.leaves().count();
+        assert!(leaves * BoundingVolumeHierarchy::MAXIMUM_PRIMITIVE_PER_LEAF >= faces);
+        assert_eq!(bvh.node_count(), 2 * leaves - 1);
+        assert!(2usize.pow(bvh.depth() - 1) >= leaves);
+
+        assert!(bvh.memory_usage() >= faces * std::mem::size_of::<FaceHandle>());
+        assert_eq!(BoundingVolumeHierarchy::new().depth(), 0);
+    }
+
+    fn unit_cube() -> (EditableMesh, BoundingVolumeHierarchy) {
+        let mesh = EditableMesh::from(&Cuboid::from_size(Vec3::ONE).mesh());
+        let bvh = BoundingVolumeHierarchy::from(&mesh);
+        (mesh, bvh)
+    }
+
+    fn ray(origin: Vec3, direction: Vec3) -> RayCast3d {
+        RayCast3d::from_ray(
+            Ray3d {
+                origin,
+                direction: Direction3d::new(direction).unwrap(),
+            },
+            1000.0,
+        )
+    }
+
+    fn assert_hit_at(
+        mesh: &EditableMesh,
+        bvh: &BoundingVolumeHierarchy,
+        ray: &RayCast3d,
+        transform: &GlobalTransform,
+        expected: f32,
+    ) {
+        let (_, t) = bvh.intersects_ray_at(ray, transform, mesh).unwrap();
+        assert!((t - expected).abs() < 1e-4, "expected {expected}, got {t}");
+
+        let t = bvh.intersects_ray_at_fast(ray, transform).unwrap();
+        assert!(t <= expected + 1e-4, "fast test went past the surface: {t}");
+    }
+
+    #[test]
+    fn test_ray_intersection_translated() {
+        let (mesh, bvh) = unit_cube();
+        let transform = GlobalTransform::from(Transform::from_xyz(5.0, 0.0, 0.0));
+
+        assert_hit_at(
+            &mesh,
+            &bvh,
+            &ray(Vec3::new(5.1, 0.2, 10.0), Vec3::NEG_Z),
+            &transform,
+            9.5,
+        );
+        assert!(bvh
+            .intersects_ray_at(&ray(Vec3::new(0.0, 0.0, 10.0), Vec3::NEG_Z), &transform, &mesh)
+            .is_none());
+    }
+
+    #[test]
+    fn test_ray_intersection_rotated() {
+        let (mesh, bvh) = unit_cube();
+        let transform = GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_y(
+            std::f32::consts::FRAC_PI_4,
+        )));
+
+        // The cube now points an edge at the ray, which hits the face just right of it
+        assert_hit_at(
+            &mesh,
+            &bvh,
+            &ray(Vec3::new(0.1, 0.2, 10.0), Vec3::NEG_Z),
+            &transform,
+            10.0 - (std::f32::consts::FRAC_1_SQRT_2 - 0.1),
+        );
+    }
+
+    #[test]
+    fn test_ray_intersection_non_uniformly_scaled() {
+        let (mesh, bvh) = unit_cube();
+        let transform = GlobalTransform::from(Transform::from_scale(Vec3::new(1.0, 1.0, 4.0)));
+
+        assert_hit_at(
+            &mesh,
+            &bvh,
+            &ray(Vec3::new(0.1, 0.2, 10.0), Vec3::NEG_Z),
+            &transform,
+            8.0,
+        );
+        assert_hit_at(
+            &mesh,
+            &bvh,
+            &ray(Vec3::new(10.0, 0.2, 1.5), Vec3::NEG_X),
+            &transform,
+            9.5,
+        );
+        assert!(bvh
+            .intersects_ray_at(&ray(Vec3::new(0.0, 0.6, 10.0), Vec3::NEG_Z), &transform, &mesh)
+            .is_none());
+    }
+
+    #[test]
+    fn test_ray_intersection_parented() {
+        let (mesh, bvh) = unit_cube();
+        let parent = GlobalTransform::from(
+            Transform::from_xyz(0.0, 0.0, -3.0).with_scale(Vec3::splat(2.0)),
+        );
+        let transform = parent.mul_transform(Transform::from_xyz(1.0, 0.0, 0.0));
+
+        // Child centre ends up at (2, 0, -3) with a half size of 1
+        assert_hit_at(
+            &mesh,
+            &bvh,
+            &ray(Vec3::new(2.1, 0.2, 10.0), Vec3::NEG_Z),
+            &transform,
+            12.0,
+        );
+    }
+
+    #[test]
+    fn test_closest_point() {
+        let (mesh, bvh) = unit_cube();
+        let transform = GlobalTransform::from(Transform::from_xyz(0.0, 3.0, 0.0));
+
+        let surface_point = bvh
+            .closest_point(Vec3::new(2.0, 3.2, 0.1), &transform, &mesh)
+            .unwrap();
+
+        assert!(surface_point.position.distance(Vec3::new(0.5, 3.2, 0.1)) < 1e-5);
+        assert!((surface_point.distance - 1.5).abs() < 1e-5);
+
+        // The barycentrics have to reproduce the position from the triangle's vertices
+        let reconstructed = surface_point
+            .triangle
+            .iter()
+            .zip(surface_point.barycentric.to_array())
+            .map(|(vertex_handle, weight)| {
+                transform.transform_point(mesh.vertex_positions[*vertex_handle]) * weight
+            })
+            .sum::<Vec3>();
+        assert!(reconstructed.distance(surface_point.position) < 1e-5);
+    }
+
+    #[test]
+    fn test_faces_in_sphere() {
+        let (mesh, bvh) = unit_cube();
+
+        let faces = bvh.faces_in_sphere(
+            Vec3::new(0.5, 0.0, 0.0),
+            0.1,
+            &GlobalTransform::IDENTITY,
+            &mesh,
+        );
+
+        // Only the two triangles of the +X side
+        assert_eq!(faces.len(), 2);
+
+        let faces = bvh.faces_in_sphere(
+            Vec3::new(0.5, 0.0, 0.0),
+            10.0,
+            &GlobalTransform::IDENTITY,
+            &mesh,
+        );
+        assert_eq!(faces.len(), mesh.structure.num_faces() as usize);
+    }
+
+    #[test]
+    fn test_faces_and_vertices_in_frustum() {
+        let (mesh, bvh) = unit_cube();
+
+        let view = Mat4::from_translation(Vec3::new(0.0, 0.0, -10.0));
+        let frustum = |left: f32, right: f32| {
+            Frustum::from_view_projection(
+                &(Mat4::orthographic_rh(left, right, -0.6, 0.6, 0.1, 100.0) * view),
+            )
+        };
+
+        let everything = frustum(-0.6, 0.6);
+        assert_eq!(
+            bvh.faces_in_frustum(&everything, &GlobalTransform::IDENTITY, &mesh)
+                .len(),
+            mesh.structure.num_faces() as usize
+        );
+
+        let right_side = frustum(0.2, 0.6);
+        assert_eq!(
+            bvh.faces_in_frustum(&right_side, &GlobalTransform::IDENTITY, &mesh)
+                .len(),
+            2
+        );
+
+        let vertices = bvh.vertices_in_frustum(&right_side, &GlobalTransform::IDENTITY, &mesh);
+        assert!(!vertices.is_empty());
+        assert!(vertices
+            .iter()
+            .all(|vertex_handle| (mesh.vertex_positions[*vertex_handle].x - 0.5).abs() < 1e-6));
+
+        // Moving the cube out of the frustum leaves nothing inside
+        let moved = GlobalTransform::from(Transform::from_xyz(5.0, 0.0, 0.0));
+        assert!(bvh.faces_in_frustum(&everything, &moved, &mesh).is_empty());
+    }
+
+    #[test]
+    fn test_nearest_vertices() {
+        let mesh = sphere();
+        let bvh = BoundingVolumeHierarchy::from(&mesh);
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(1.0, 0.0, 0.0).with_scale(Vec3::new(2.0, 1.0, 1.0)),
+        );
+        let point = Vec3::new(2.5, 0.3, 0.2);
+
+        let nearest = bvh.nearest_vertices(point, 5, &transform, &mesh);
+
+        let mut brute_force: Vec<(VertexHandle, f32)> = mesh
+            .structure
+            .vertex_handles()
+            .map(|vertex_handle| {
+                (
+                    vertex_handle,
+                    transform
+                        .transform_point(mesh.vertex_positions[vertex_handle])
+                        .distance(point),
+                )
+            })
+            .collect();
+        brute_force.sort_by(|a, b| a.1.total_cmp(&b.1));
+
+        assert_eq!(nearest.len(), 5);
+        for ((_, distance), (_, expected)) in nearest.iter().zip(brute_force.iter()) {
+            assert!((distance - expected).abs() < 1e-5);
+        }
+    }
 }
@@ -11,18 +11,22 @@ use bevy::{
 };
 use bvh::BoundingVolumeHierarchy;
 use lox::{
-    core::{half_edge::PolyConfig, HalfEdgeMesh, MeshMut},
+    core::{half_edge::PolyConfig, HalfEdgeMesh, Mesh as LoxMesh, MeshMut},
     leer::Empty,
     map::{DenseMap, PropStoreMut},
-    FaceHandle, Handle as LoxHandle, VertexHandle,
+    EdgeHandle, FaceHandle, Handle as LoxHandle, VertexHandle,
 };
+use wasm_bindgen::prelude::*;
 
 use super::{
+    dim3,
     editor::Focused,
     interaction::{InteractionCache, InteractionSet},
+    shading::ViewportShadingMaterial,
 };
 
-#[derive(Resource, Copy, Clone, Debug)]
+#[wasm_bindgen]
+#[derive(Resource, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum SelectMode {
     Faces,
     Edges,
@@ -38,11 +42,30 @@ pub struct ActiveEdges(pub HashSet<u32>);
 #[derive(Component, DerefMut, Deref, Default)]
 pub struct ActiveFaces(pub HashSet<u32>);
 
+impl Extend<VertexHandle> for ActiveVertices {
+    fn extend<T: IntoIterator<Item = VertexHandle>>(&mut self, iter: T) {
+        self.0.extend(iter.into_iter().map(|handle| handle.idx()));
+    }
+}
+
+impl Extend<EdgeHandle> for ActiveEdges {
+    fn extend<T: IntoIterator<Item = EdgeHandle>>(&mut self, iter: T) {
+        self.0.extend(iter.into_iter().map(|handle| handle.idx()));
+    }
+}
+
+impl Extend<FaceHandle> for ActiveFaces {
+    fn extend<T: IntoIterator<Item = FaceHandle>>(&mut self, iter: T) {
+        self.0.extend(iter.into_iter().map(|handle| handle.idx()));
+    }
+}
+
 #[derive(Bundle, Default)]
 pub struct EditableMeshBundle {
     pub mesh: Handle<Mesh>,
     pub editable_mesh: EditableMesh,
-    pub material: Handle<StandardMaterial>,
+    pub material: Handle<ViewportShadingMaterial>,
+    pub surface_material: SurfaceMaterial,
     pub active_edges: ActiveEdges,
     pub active_vertices: ActiveVertices,
     pub active_faces: ActiveFaces,
@@ -54,6 +77,11 @@ pub struct EditableMeshBundle {
     pub bvh: BoundingVolumeHierarchy,
 }
 
+/// Material assigned to a mesh, written by the exporters. The viewport draws every mesh with the
+/// shared [`ViewportShadingMaterial`] instead, so this one is never rendered.
+#[derive(Component, Clone, Debug, Default)]
+pub struct SurfaceMaterial(pub Handle<StandardMaterial>);
+
 #[derive(Component)]
 pub struct EditableMesh {
     pub structure: HalfEdgeMesh<PolyConfig>,
@@ -73,6 +101,269 @@ impl Default for EditableMesh {
     }
 }
 
+impl EditableMesh {
+    /// Vertices touched by the active elements: the active vertices themselves, the endpoints of
+    /// the active edges and the corners of the active faces. Stale indices are skipped.
+    pub fn selected_vertices(
+        &self,
+        vertices: &ActiveVertices,
+        edges: &ActiveEdges,
+        faces: &ActiveFaces,
+    ) -> HashSet<VertexHandle> {
+        let mut selected: HashSet<VertexHandle> = vertices
+            .iter()
+            .map(|index| VertexHandle::new(*index))
+            .filter(|handle| self.structure.contains_vertex(*handle))
+            .collect();
+
+        for edge_handle in edges.iter().map(|index| EdgeHandle::new(*index)) {
+            if self.structure.contains_edge(edge_handle) {
+                selected.extend(
+                    self.structure
+                        .get_ref(edge_handle)
+                        .endpoints()
+                        .map(|v| v.handle()),
+                );
+            }
+        }
+
+        for face_handle in faces.iter().map(|index| FaceHandle::new(*index)) {
+            if self.structure.contains_face(face_handle) {
+                selected.extend(
+                    self.structure
+                        .get_ref(face_handle)
+                        .adjacent_vertices()
+                        .map(|v| v.handle()),
+                );
+            }
+        }
+
+        selected
+    }
+
+    /// Normal of a face from its corners, scaled by twice its area. Works for any polygon.
+    pub fn face_area_normal(&self, face_handle: FaceHandle) -> Vec3 {
+        let corners: Vec<Vec3> = self
+            .structure
+            .get_ref(face_handle)
+            .adjacent_vertices()
+            .map(|vertex| self.vertex_positions[vertex.handle()])
+            .collect();
+
+        // Newell's method
+        corners
+            .iter()
+            .zip(corners.iter().cycle().skip(1))
+            .map(|(a, b)| a.cross(*b))
+            .sum()
+    }
+
+    /// Recomputes the normals of every face
+    pub fn compute_face_normals(&mut self) {
+        let faces: Vec<FaceHandle> = self.structure.face_handles().collect();
+
+        for face_handle in faces {
+            let normal = self.face_area_normal(face_handle).normalize_or_zero();
+            self.face_normals.insert(face_handle, normal);
+        }
+    }
+
+    /// Recomputes the normals around moved vertices: of the faces touching them, and of the
+    /// vertices of those faces as the area weighted average of their faces
+    pub fn update_normals(&mut self, moved_vertices: impl IntoIterator<Item = VertexHandle>) {
+        let mut faces = HashSet::new();
+        for vertex_handle in moved_vertices {
+            faces.extend(
+                self.structure
+                    .get_ref(vertex_handle)
+                    .adjacent_faces()
+                    .map(|face| face.handle()),
+            );
+        }
+
+        let mut vertices = HashSet::new();
+        for face_handle in faces.iter().copied() {
+            let normal = self.face_area_normal(face_handle).normalize_or_zero();
+            self.face_normals.insert(face_handle, normal);
+            vertices.extend(
+                self.structure
+                    .get_ref(face_handle)
+                    .adjacent_vertices()
+                    .map(|vertex| vertex.handle()),
+            );
+        }
+
+        for vertex_handle in vertices {
+            let normal: Vec3 = self
+                .structure
+                .get_ref(vertex_handle)
+                .adjacent_faces()
+                .map(|face| self.face_area_normal(face.handle()))
+                .sum();
+
+            if let Some(normal) = normal.try_normalize() {
+                self.vertex_normals[vertex_handle] = normal;
+            }
+        }
+    }
+
+    /// Copies positions and normals of `vertices` to the render mesh. Vertices keep the index
+    /// of the attribute they were created from, see [`EditableMesh::from`].
+    pub fn write_render_mesh(&self, mesh: &mut Mesh, vertices: &[VertexHandle]) {
+        for (attribute, values) in [
+            (Mesh::ATTRIBUTE_POSITION, &self.vertex_positions),
+            (Mesh::ATTRIBUTE_NORMAL, &self.vertex_normals),
+        ] {
+            let Some(VertexAttributeValues::Float32x3(attribute)) = mesh.attribute_mut(attribute)
+            else {
+                continue;
+            };
+
+            for vertex_handle in vertices {
+                if let Some(value) = attribute.get_mut(vertex_handle.idx() as usize) {
+                    *value = values[*vertex_handle].to_array();
+                }
+            }
+        }
+    }
+
+    /// Corners of every face in world space
+    fn world_faces<'a>(
+        &'a self,
+        transform: &'a GlobalTransform,
+    ) -> impl Iterator<Item = Vec<Vec3>> + 'a {
+        self.structure.face_handles().map(move |face_handle| {
+            self.structure
+                .get_ref(face_handle)
+                .adjacent_vertices()
+                .map(|vertex| transform.transform_point(self.vertex_positions[vertex.handle()]))
+                .collect()
+        })
+    }
+
+    /// Triangles fanned out of the first corner of every face, in world space
+    fn world_triangles<'a>(
+        &'a self,
+        transform: &'a GlobalTransform,
+    ) -> impl Iterator<Item = [Vec3; 3]> + 'a {
+        self.world_faces(transform).flat_map(|corners| {
+            (1..corners.len().saturating_sub(1))
+                .map(|i| [corners[0], corners[i], corners[i + 1]])
+                .collect::<Vec<_>>()
+        })
+    }
+
+    /// Total area of the faces in world units
+    pub fn surface_area(&self, transform: &GlobalTransform) -> f32 {
+        self.world_triangles(transform)
+            .map(|[a, b, c]| (b - a).cross(c - a).length() * 0.5)
+            .sum()
+    }
+
+    /// Whether the surface has no holes. Imported meshes split vertices along seams, so edges
+    /// are matched by position: every edge has to be walked once in each direction.
+    pub fn is_closed(&self) -> bool {
+        let key = |position: Vec3| position.to_array().map(f32::to_bits);
+        let mut open_edges: HashSet<([u32; 3], [u32; 3])> = HashSet::new();
+
+        for face_handle in self.structure.face_handles() {
+            let corners: Vec<[u32; 3]> = self
+                .structure
+                .get_ref(face_handle)
+                .adjacent_vertices()
+                .map(|vertex| key(self.vertex_positions[vertex.handle()]))
+                .collect();
+
+            for (from, to) in corners.iter().zip(corners.iter().cycle().skip(1)) {
+                // The twin cancels the edge out, a second edge in the same direction stays open
+                if !open_edges.remove(&(*to, *from)) && !open_edges.insert((*from, *to)) {
+                    return false;
+                }
+            }
+        }
+
+        open_edges.is_empty()
+    }
+
+    /// Enclosed volume in world units, summing the signed tetrahedra between the origin and
+    /// every triangle. `None` if the mesh is not closed, see [`EditableMesh::is_closed`].
+    pub fn volume(&self, transform: &GlobalTransform) -> Option<f32> {
+        if !self.is_closed() {
+            return None;
+        }
+
+        let volume: f32 = self
+            .world_triangles(transform)
+            .map(|[a, b, c]| a.dot(b.cross(c)) / 6.0)
+            .sum();
+
+        // Inside out meshes, or mirrored by the transform
+        Some(volume.abs())
+    }
+
+    /// Center of mass in world space. Of the enclosed volume for closed meshes, otherwise of the
+    /// surface. `None` for a mesh without area.
+    pub fn centroid(&self, transform: &GlobalTransform) -> Option<Vec3> {
+        if self.is_closed() {
+            let (weighted, volume) = self.world_triangles(transform).fold(
+                (Vec3::ZERO, 0.0),
+                |(weighted, volume), [a, b, c]| {
+                    // Tetrahedron with the origin, whose centroid is a quarter of its corners
+                    let signed = a.dot(b.cross(c)) / 6.0;
+                    (weighted + (a + b + c) / 4.0 * signed, volume + signed)
+                },
+            );
+
+            if volume.abs() > f32::EPSILON {
+                return Some(weighted / volume);
+            }
+        }
+
+        let (weighted, area) = self.world_triangles(transform).fold(
+            (Vec3::ZERO, 0.0),
+            |(weighted, area), [a, b, c]| {
+                let triangle = (b - a).cross(c - a).length() * 0.5;
+                (weighted + (a + b + c) / 3.0 * triangle, area + triangle)
+            },
+        );
+
+        (area > f32::EPSILON).then(|| weighted / area)
+    }
+
+    /// Corners of the world space axis aligned box around the vertices, or `None` for an empty
+    /// mesh. Its size gives the dimensions of the mesh in world units.
+    pub fn world_bounds(&self, transform: &GlobalTransform) -> Option<(Vec3, Vec3)> {
+        self.structure
+            .vertex_handles()
+            .map(|vertex| transform.transform_point(self.vertex_positions[vertex]))
+            .fold(None, |bounds, position| match bounds {
+                None => Some((position, position)),
+                Some((min, max)) => Some((min.min(position), max.max(position))),
+            })
+    }
+}
+
+/// Brings the normals, the BVH and the render mesh up to date after vertices moved. The caller
+/// should also remove the [`Aabb`](bevy::render::primitives::Aabb) of the entity, so Bevy
+/// recomputes it for frustum culling.
+pub fn update_moved_vertices(
+    mesh: &mut EditableMesh,
+    bvh: &mut BoundingVolumeHierarchy,
+    render_mesh: Option<&mut Mesh>,
+    moved_vertices: &[VertexHandle],
+) {
+    if moved_vertices.is_empty() {
+        return;
+    }
+
+    mesh.update_normals(moved_vertices.iter().copied());
+    bvh.refit_or_rebuild(mesh, moved_vertices.iter().copied());
+
+    if let Some(render_mesh) = render_mesh {
+        mesh.write_render_mesh(render_mesh, moved_vertices);
+    }
+}
+
 impl EditableMeshBundle {
     pub fn from_mesh(raw_mesh: Mesh, meshes: &mut Assets<Mesh>) -> Self {
         let editable_mesh = EditableMesh::from(&raw_mesh);
@@ -140,6 +431,7 @@ impl From<&Mesh> for EditableMesh {
                     editable_mesh.structure.add_face(&handles);
                 });
 
+            editable_mesh.compute_face_normals();
             return editable_mesh;
         };
 
@@ -181,6 +473,7 @@ impl From<&Mesh> for EditableMesh {
             }
         }
 
+        editable_mesh.compute_face_normals();
         editable_mesh
     }
 }
@@ -199,13 +492,17 @@ impl Plugin for EditableMeshPlugin {
 }
 
 impl EditableMeshPlugin {
+    /// Selects the element under the cursor after a click in edit mode. Holding shift toggles
+    /// the element in the current selection instead of replacing it.
     fn update_active(
         select_mode: Res<SelectMode>,
+        keyboard: Res<ButtonInput<KeyCode>>,
         mut focused: Query<
             (
                 &EditableMesh,
-                &Transform,
-                &InteractionCache,
+                &BoundingVolumeHierarchy,
+                &GlobalTransform,
+                Ref<InteractionCache>,
                 &mut ActiveVertices,
                 &mut ActiveFaces,
                 &mut ActiveEdges,
@@ -215,6 +512,7 @@ impl EditableMeshPlugin {
     ) {
         let Ok((
             editable_mesh,
+            bvh,
             transform,
             intersection_cache,
             mut active_vertices,
@@ -224,9 +522,63 @@ impl EditableMeshPlugin {
         else {
             return;
         };
+
+        if !intersection_cache.is_changed() {
+            return;
+        }
+
+        let extend = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+        if !extend {
+            active_vertices.clear();
+            active_edges.clear();
+            active_faces.clear();
+        }
+
+        let Some((face_handle, point)) = **intersection_cache else {
+            return;
+        };
+
+        let toggle = |set: &mut HashSet<u32>, index: u32| {
+            if !set.remove(&index) {
+                set.insert(index);
+            }
+        };
+
         match *select_mode {
-            SelectMode::Vertices => {}
-            _ => {}
+            SelectMode::Vertices => {
+                let Some((vertex_handle, _)) = bvh
+                    .nearest_vertices(point, 1, transform, editable_mesh)
+                    .first()
+                    .cloned()
+                else {
+                    return;
+                };
+                toggle(&mut active_vertices, vertex_handle.idx());
+            }
+            SelectMode::Edges => {
+                let affine = transform.affine();
+
+                let closest_edge = editable_mesh
+                    .structure
+                    .get_ref(face_handle)
+                    .adjacent_edges()
+                    .map(|edge| {
+                        let [start, end] = edge.endpoints().map(|v| {
+                            affine.transform_point3(editable_mesh.vertex_positions[v.handle()])
+                        });
+                        (
+                            edge.handle(),
+                            dim3::point_segment_distance_squared(point, start, end),
+                        )
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1));
+
+                if let Some((edge_handle, _)) = closest_edge {
+                    toggle(&mut active_edges, edge_handle.idx());
+                }
+            }
+            SelectMode::Faces => toggle(&mut active_faces, face_handle.idx()),
         };
     }
 }
@@ -234,7 +586,7 @@ impl EditableMeshPlugin {
 #[cfg(test)]
 mod test {
     use bevy::prelude::*;
-    use lox::core::Mesh as LoxMesh;
+    use lox::{core::Mesh as LoxMesh, VertexHandle};
 
     use super::EditableMesh;
 
@@ -249,4 +601,82 @@ mod test {
             editable_mesh.structure.num_vertices() as usize
         );
     }
+
+    #[test]
+    fn test_face_normals_follow_moved_vertices() {
+        let mesh: Mesh = Cuboid::from_size(Vec3::splat(1.0)).mesh();
+        let mut editable_mesh: EditableMesh = (&mesh).into();
+
+        // Split vertices keep the normal of their side, which the faces must agree with
+        for face_handle in editable_mesh.structure.face_handles() {
+            let face_normal = editable_mesh.face_normals[face_handle];
+            for vertex in editable_mesh
+                .structure
+                .get_ref(face_handle)
+                .adjacent_vertices()
+            {
+                let vertex_normal = editable_mesh.vertex_normals[vertex.handle()];
+                assert!((face_normal - vertex_normal).length() < 1e-5);
+            }
+        }
+
+        // Pushing the +Y side up along X tilts its normal towards -X
+        let top: Vec<VertexHandle> = editable_mesh
+            .structure
+            .vertex_handles()
+            .filter(|vertex| {
+                editable_mesh.vertex_normals[*vertex].y > 0.5
+                    && editable_mesh.vertex_positions[*vertex].x > 0.0
+            })
+            .collect();
+        for vertex in top.iter() {
+            editable_mesh.vertex_positions[*vertex].y += 1.0;
+        }
+        editable_mesh.update_normals(top.iter().copied());
+
+        for vertex in top.iter() {
+            let normal = editable_mesh.vertex_normals[*vertex];
+            assert!(normal.x < -0.1 && normal.y > 0.1);
+            assert!((normal.length() - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_measurements_of_cuboid() {
+        let mesh: Mesh = Cuboid::new(1.0, 2.0, 3.0).mesh();
+        let editable_mesh: EditableMesh = (&mesh).into();
+        let identity = GlobalTransform::IDENTITY;
+
+        assert!(editable_mesh.is_closed());
+        assert!((editable_mesh.surface_area(&identity) - 22.0).abs() < 1e-4);
+        assert!((editable_mesh.volume(&identity).unwrap() - 6.0).abs() < 1e-4);
+        assert!(editable_mesh.centroid(&identity).unwrap().length() < 1e-5);
+
+        let (min, max) = editable_mesh.world_bounds(&identity).unwrap();
+        assert!((max - min - Vec3::new(1.0, 2.0, 3.0)).length() < 1e-5);
+
+        // Measured in world units, a mirroring scale does not make the volume negative
+        let transform = GlobalTransform::from(
+            Transform::from_xyz(5.0, 0.0, 0.0).with_scale(Vec3::new(-2.0, 1.0, 1.0)),
+        );
+        assert!((editable_mesh.volume(&transform).unwrap() - 12.0).abs() < 1e-4);
+        assert!((editable_mesh.surface_area(&transform) - 32.0).abs() < 1e-4);
+        assert!((editable_mesh.centroid(&transform).unwrap() - Vec3::X * 5.0).length() < 1e-4);
+
+        let (min, max) = editable_mesh.world_bounds(&transform).unwrap();
+        assert!((max - min - Vec3::new(2.0, 2.0, 3.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_open_mesh_has_no_volume() {
+        let mesh: Mesh = Plane3d::default().mesh().size(2.0, 2.0).build();
+        let editable_mesh: EditableMesh = (&mesh).into();
+        let identity = GlobalTransform::IDENTITY;
+
+        assert!(!editable_mesh.is_closed());
+        assert_eq!(editable_mesh.volume(&identity), None);
+        assert!((editable_mesh.surface_area(&identity) - 4.0).abs() < 1e-5);
+        // Falls back to the center of the surface
+        assert!(editable_mesh.centroid(&identity).unwrap().length() < 1e-5);
+    }
 }
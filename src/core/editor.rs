@@ -1,24 +1,44 @@
 use bevy::{
-    ecs::system::SystemId, prelude::*, render::view::RenderLayers, utils::HashMap,
-    window::WindowResolution, winit::WinitSettings,
+    ecs::system::SystemId,
+    math::bounding::{Aabb3d, BoundingVolume},
+    prelude::*,
+    render::view::RenderLayers,
+    utils::HashMap,
+    window::WindowResolution,
+    winit::WinitSettings,
 };
 
 use bevy_obj::ObjPlugin;
-use lox::{core::Mesh as LoxMesh, Handle as LoxHandle};
 
 use crate::utils;
 
 use super::{
+    camera_bookmarks::CameraBookmarksPlugin,
+    edit_overlay::EditOverlayPlugin,
     editable_mesh::{
-        bvh::bvh_debug_system, ActiveEdges, EditableMesh, EditableMeshBundle, EditableMeshPlugin,
+        bvh::{bvh_debug_system, BoundingVolumeHierarchy},
+        ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh, EditableMeshBundle,
+        EditableMeshPlugin,
     },
+    fly_camera::FlyCameraPlugin,
     fps::FpsPlugin,
+    gestures::GesturePlugin,
     gizmos::{CustomGizmoPlugin, GizmoPlaneDistance, GizmoScaleToViewportRatio},
     grid::GridPlugin,
     highlight::HighlightPlugin,
-    interaction::{InteractionPlugin, InteractionSet},
+    interaction::{InteractionMode, InteractionPlugin, InteractionSet},
+    keymap::KeymapPlugin,
+    navigation_gizmo::NavigationGizmoPlugin,
     pan_orbit_camera::{PanOrbitCameraPlugin, PanOrbitCameraUpdate, PrimaryCamera},
-    tools::{self, ToolSet, ToolType},
+    scene_bvh,
+    shading::{ShadingPlugin, ViewportShading, ViewportShadingMaterial},
+    snapping::SnappingPlugin,
+    stats::StatsPlugin,
+    tools::{
+        self, measure::MeasurePlugin, modal::ModalTransformPlugin,
+        proportional::ProportionalEditing, transform_space::TransformSpacePlugin, ToolSet,
+        ToolType,
+    },
 };
 
 pub struct EditorPlugin {
@@ -45,7 +65,7 @@ pub struct Tools {
 }
 
 #[derive(Resource, Default)]
-pub struct ViewportMaterial(pub Handle<StandardMaterial>);
+pub struct ViewportMaterial(pub Handle<ViewportShadingMaterial>);
 
 #[derive(Resource, Default)]
 pub struct ActiveTool(pub Option<Tool>);
@@ -53,7 +73,7 @@ pub struct ActiveTool(pub Option<Tool>);
 #[derive(Component)]
 pub struct Focused;
 
-#[derive(Resource, Default)]
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
 pub struct Cursor3d {
     pub position: Vec3,
     pub orientation: Quat,
@@ -70,27 +90,34 @@ impl Plugin for EditorPlugin {
                 }),
                 ..default()
             }),
-            FpsPlugin,
-            PanOrbitCameraPlugin,
+            (FpsPlugin, StatsPlugin),
+            GesturePlugin,
+            (PanOrbitCameraPlugin, FlyCameraPlugin),
             CustomGizmoPlugin,
             GridPlugin,
             EditableMeshPlugin,
             InteractionPlugin,
-            // HighlightPlugin::<StandardMaterial>::default(),
+            HighlightPlugin,
+            NavigationGizmoPlugin,
+            CameraBookmarksPlugin,
+            KeymapPlugin,
+            (
+                SnappingPlugin,
+                ModalTransformPlugin,
+                TransformSpacePlugin,
+                MeasurePlugin,
+            ),
+            (ShadingPlugin, EditOverlayPlugin),
             ObjPlugin,
         ))
         .insert_resource(WinitSettings::desktop_app())
         .insert_resource(ActiveTool::default())
         .insert_resource(Tools::default())
         .insert_resource(Cursor3d::default())
+        .insert_resource(ProportionalEditing::default())
         .insert_resource(ClearColor(Color::rgb_u8(63, 63, 63)))
         .add_systems(Startup, Self::init_default_scene)
-        .add_systems(
-            Update,
-            (
-                // Self::wireframe_focused,
-                 bvh_debug_system).after(ToolSet::Update),
-        )
+        .add_systems(Update, bvh_debug_system.after(ToolSet::Update))
         .add_systems(
             Update,
             (Self::sync_light_with_camera, Self::draw_cursor_3d).after(PanOrbitCameraUpdate),
@@ -109,16 +136,12 @@ impl Plugin for EditorPlugin {
 
 impl EditorPlugin {
     fn init_data(world: &mut World) {
+        let material = ViewportShadingMaterial::from(world.resource::<ViewportShading>());
         let mut materials = world
-            .get_resource_mut::<Assets<StandardMaterial>>()
+            .get_resource_mut::<Assets<ViewportShadingMaterial>>()
             .unwrap();
 
-        let viewport_material = materials.add(StandardMaterial {
-            cull_mode: None,
-            double_sided: true,
-            base_color: Color::rgb_linear(0.35, 0.35, 0.35),
-            ..default()
-        });
+        let viewport_material = materials.add(material);
 
         world.insert_resource(ViewportMaterial(viewport_material));
     }
@@ -147,6 +170,15 @@ impl EditorPlugin {
 
         // Rotation
         let rotation_tool_update = world.register_system(tools::general::Rotation::update_system);
+        let rotation_tool_cleanup = world.register_system(tools::general::Rotation::cleanup_system);
+
+        // 3D cursor
+        let cursor_tool_update = world.register_system(tools::cursor::CursorTool::update_system);
+
+        // Ruler and protractor
+        let measure_tool_update = world.register_system(tools::measure::MeasureTool::update_system);
+        let measure_tool_cleanup =
+            world.register_system(tools::measure::MeasureTool::cleanup_system);
 
         let mut tool_registry = world.get_resource_mut::<Tools>().unwrap();
 
@@ -173,9 +205,27 @@ impl EditorPlugin {
             Tool {
                 startup_system: None,
                 update_system: Some(rotation_tool_update),
+                cleanup_system: Some(rotation_tool_cleanup),
+            },
+        );
+
+        tool_registry.map.insert(
+            ToolType::Cursor,
+            Tool {
+                startup_system: None,
+                update_system: Some(cursor_tool_update),
                 cleanup_system: None,
             },
         );
+
+        tool_registry.map.insert(
+            ToolType::Measure,
+            Tool {
+                startup_system: None,
+                update_system: Some(measure_tool_update),
+                cleanup_system: Some(measure_tool_cleanup),
+            },
+        );
     }
     fn draw_cursor_3d(
         cursor: Res<Cursor3d>,
@@ -205,7 +255,8 @@ impl EditorPlugin {
             Color::WHITE,
         );
 
-        for axis in [Direction3d::X, Direction3d::Y, Direction3d::Z] {
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let axis = cursor.orientation * axis;
             gizmo.line(
                 cursor_position + axis * offset,
                 cursor_position + axis * (axis_height + offset),
@@ -278,30 +329,159 @@ impl EditorPlugin {
         }
     }
 
-    fn wireframe_focused(
-        mut gizmo: Gizmos,
-        query: Query<(&Transform, &EditableMesh, &ActiveEdges), With<Focused>>,
-    ) {
-        let Ok((transform, mesh, active_edges)) = query.get_single() else {
-            return;
+    /// World bounds of the user-space entities with a BVH, optionally only the focused ones
+    fn entity_bounds(world: &mut World, focused_only: bool) -> Option<Aabb3d> {
+        let mut query = world.query_filtered::<(
+            &BoundingVolumeHierarchy,
+            &GlobalTransform,
+            Has<Focused>,
+        ), With<UserSpace>>();
+
+        query
+            .iter(world)
+            .filter(|(_, _, focused)| *focused || !focused_only)
+            .filter_map(|(bvh, transform, _)| {
+                let root = bvh.nodes.first()?;
+                Some(scene_bvh::world_aabb(&root.aabb(), transform))
+            })
+            .reduce(|a, b| a.merge(&b))
+    }
+
+    /// World bounds of the active vertices, edges and faces of the focused mesh
+    fn active_elements_bounds(world: &mut World) -> Option<Aabb3d> {
+        let mut query = world.query_filtered::<(
+            &EditableMesh,
+            &GlobalTransform,
+            &ActiveVertices,
+            &ActiveEdges,
+            &ActiveFaces,
+        ), With<Focused>>();
+
+        let (mesh, transform, vertices, edges, faces) = query.get_single(world).ok()?;
+        let affine = transform.affine();
+
+        mesh.selected_vertices(vertices, edges, faces)
+            .into_iter()
+            .map(|vertex| affine.transform_point3(mesh.vertex_positions[vertex]))
+            .fold(None, |bounds, point| match bounds {
+                Some(Aabb3d { min, max }) => Some(Aabb3d {
+                    min: min.min(point),
+                    max: max.max(point),
+                }),
+                None => Some(Aabb3d {
+                    min: point,
+                    max: point,
+                }),
+            })
+    }
+
+    /// Brings the selection into view: the active elements in edit mode, the focused entity otherwise
+    pub fn frame_selected(world: &mut World) {
+        let edit_mode = world
+            .get_resource::<InteractionMode>()
+            .is_some_and(|mode| *mode != InteractionMode::Object);
+
+        let bounds = if edit_mode {
+            Self::active_elements_bounds(world)
+        } else {
+            Self::entity_bounds(world, true)
         };
 
-        let transform = transform.compute_matrix();
+        if let Some(bounds) = bounds {
+            PanOrbitCameraPlugin::frame_aabb(world, bounds);
+        }
+    }
 
-        for edge in mesh.structure.edges() {
-            let [start, end] = edge
-                .endpoints()
-                .map(|v| (transform * mesh.vertex_positions[v.handle()].extend(1.0)).truncate());
+    /// Brings every user-space entity into view
+    pub fn frame_all(world: &mut World) {
+        if let Some(bounds) = Self::entity_bounds(world, false) {
+            PanOrbitCameraPlugin::frame_aabb(world, bounds);
+        }
+    }
 
-            gizmo.line(
-                start,
-                end,
-                if active_edges.contains(&edge.handle().idx()) {
-                    Color::WHITE
-                } else {
-                    Color::ORANGE_RED
-                },
-            );
+    /// Activates a tool, running the cleanup of the previous one and the startup of the new one.
+    /// Does nothing if the tool is not registered.
+    pub fn set_active_tool(world: &mut World, tool_type: ToolType) {
+        let tool = match world.resource::<Tools>().map.get(&tool_type) {
+            Some(tool) => tool.clone(),
+            None => return,
+        };
+
+        let mut active_tool = world.resource_mut::<ActiveTool>();
+
+        let last_active_tool_cleanup = active_tool
+            .0
+            .as_ref()
+            .and_then(|active_tool| active_tool.cleanup_system);
+        let new_active_tool_startup = tool.startup_system;
+
+        active_tool.0 = Some(tool);
+
+        if let Some(last_active_tool_cleanup) = last_active_tool_cleanup {
+            world.run_system(last_active_tool_cleanup).unwrap();
+        }
+
+        if let Some(new_active_tool_startup) = new_active_tool_startup {
+            world.run_system(new_active_tool_startup).unwrap();
+        }
+    }
+
+    pub fn unset_active_tool(world: &mut World) {
+        let mut active_tool = world.resource_mut::<ActiveTool>();
+
+        let last_active_tool_cleanup = active_tool
+            .0
+            .take()
+            .and_then(|active_tool| active_tool.cleanup_system);
+
+        if let Some(cleanup_system) = last_active_tool_cleanup {
+            world.run_system(cleanup_system).unwrap();
+        }
+    }
+
+    /// Despawns the focused user-space entities with their children, in object mode only
+    pub fn delete_selected(world: &mut World) {
+        if world
+            .get_resource::<InteractionMode>()
+            .is_some_and(|mode| *mode != InteractionMode::Object)
+        {
+            return;
+        }
+
+        let mut query = world.query_filtered::<Entity, (With<UserSpace>, With<Focused>)>();
+        let entities: Vec<Entity> = query.iter(world).collect();
+
+        for entity in entities {
+            if let Some(entity) = world.get_entity_mut(entity) {
+                entity.despawn_recursive();
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{EditorPlugin, Focused, UserSpace};
+    use crate::core::{highlight::Highlight, interaction::InteractionMode};
+
+    #[test]
+    fn test_delete_selected_keeps_unfocused_entities() {
+        let mut world = World::new();
+        world.insert_resource(InteractionMode::Object);
+
+        let focused = world.spawn((UserSpace, Focused)).id();
+        let child = world.spawn(UserSpace).id();
+        world.entity_mut(focused).add_child(child);
+        let highlighted = world.spawn((UserSpace, Highlight)).id();
+        let other = world.spawn(UserSpace).id();
+
+        EditorPlugin::delete_selected(&mut world);
+
+        assert!(world.get_entity(focused).is_none());
+        assert!(world.get_entity(child).is_none());
+        assert!(world.get_entity(highlighted).is_some());
+        assert!(world.get_entity(other).is_some());
+    }
+}
@@ -0,0 +1,334 @@
+use std::f32::consts::FRAC_PI_2;
+
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    math::bounding::RayCast3d,
+    prelude::*,
+    window::{CursorGrabMode, PrimaryWindow, RequestRedraw},
+};
+
+use super::{
+    camera_bookmarks::Turntable,
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh},
+    keymap::{EditorAction, InputCapture},
+    pan_orbit_camera::{
+        CameraProjectionKind, PanOrbitCameraPlugin, PanOrbitCameraUpdate, PanOrbitState,
+        PanOrbitTransition, PanOrbitView, PrimaryCamera,
+    },
+    scene_bvh::SceneBoundingVolumeHierarchy,
+};
+
+/// First-person navigation of the primary camera: WASD/QE to move, the mouse to look around and
+/// the scroll wheel to change the speed. Takes over from the pan-orbit controller while active.
+pub struct FlyCameraPlugin;
+
+/// Present on the primary camera while flying
+#[derive(Component, Clone, Copy, Debug)]
+pub struct FlyCamera {
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Orbit view when fly mode was entered, restored when cancelling
+    pub entered_from: PanOrbitView,
+}
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct FlyCameraSettings {
+    /// World units per second
+    pub speed: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    /// Speed factor per scroll line
+    pub speed_step: f32,
+    /// Speed factor while Shift is held
+    pub boost: f32,
+    /// Radians per pixel of mouse motion
+    pub look_sensitivity: f32,
+}
+
+impl Default for FlyCameraSettings {
+    fn default() -> Self {
+        Self {
+            speed: 5.0,
+            min_speed: 0.05,
+            max_speed: 500.0,
+            speed_step: 1.2,
+            boost: 4.0,
+            look_sensitivity: 0.1f32.to_radians(),
+        }
+    }
+}
+
+/// Movement keys, in camera space except for up and down which follow the world
+const FLY_KEYS: &[(KeyCode, Vec3)] = &[
+    (KeyCode::KeyW, Vec3::NEG_Z),
+    (KeyCode::KeyS, Vec3::Z),
+    (KeyCode::KeyA, Vec3::NEG_X),
+    (KeyCode::KeyD, Vec3::X),
+    (KeyCode::KeyE, Vec3::Y),
+    (KeyCode::KeyQ, Vec3::NEG_Y),
+];
+
+/// Stops just short of looking straight up or down, where yaw is undefined
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+impl FlyCamera {
+    pub fn rotation(&self) -> Quat {
+        Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0)
+    }
+
+    /// Orbit view around the point `distance` ahead of the camera, placing the camera exactly where it is
+    pub fn orbit_view(transform: &Transform, distance: f32) -> PanOrbitView {
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+
+        PanOrbitView {
+            center: transform.translation + transform.forward() * distance,
+            radius: distance,
+            yaw,
+            pitch,
+        }
+    }
+}
+
+impl FlyCameraSettings {
+    /// Scales the speed by `speed_step` per scroll line
+    pub fn scroll_speed(&mut self, lines: f32) {
+        self.speed =
+            (self.speed * self.speed_step.powf(lines)).clamp(self.min_speed, self.max_speed);
+    }
+}
+
+impl Plugin for FlyCameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FlyCameraSettings>().add_systems(
+            Update,
+            Self::fly_camera_controller
+                .run_if(any_with_component::<FlyCamera>)
+                .in_set(PanOrbitCameraUpdate),
+        );
+    }
+}
+
+impl FlyCameraPlugin {
+    fn fly_camera_controller(
+        mut commands: Commands,
+        time: Res<Time>,
+        keys: Res<ButtonInput<KeyCode>>,
+        mut settings: ResMut<FlyCameraSettings>,
+        mut evr_motion: EventReader<MouseMotion>,
+        mut evr_scroll: EventReader<MouseWheel>,
+        mut q_camera: Query<(&mut FlyCamera, &mut Transform), With<PrimaryCamera>>,
+        mut redraw: EventWriter<RequestRedraw>,
+    ) {
+        let Ok((mut fly, mut transform)) = q_camera.get_single_mut() else {
+            return;
+        };
+
+        if keys.just_pressed(KeyCode::Escape) {
+            commands.add(|world: &mut World| Self::exit(world, true));
+            return;
+        }
+        if keys.just_pressed(KeyCode::Enter) {
+            commands.add(|world: &mut World| Self::exit(world, false));
+            return;
+        }
+
+        let scroll_lines: f32 = evr_scroll
+            .read()
+            .map(|ev| match ev.unit {
+                MouseScrollUnit::Line => ev.y,
+                MouseScrollUnit::Pixel => ev.y / 16.0,
+            })
+            .sum();
+        if scroll_lines != 0.0 {
+            settings.scroll_speed(scroll_lines);
+        }
+
+        let motion: Vec2 = evr_motion.read().map(|ev| ev.delta).sum();
+        if motion != Vec2::ZERO {
+            fly.yaw -= motion.x * settings.look_sensitivity;
+            fly.pitch =
+                (fly.pitch - motion.y * settings.look_sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+            transform.rotation = fly.rotation();
+        }
+
+        let direction: Vec3 = FLY_KEYS
+            .iter()
+            .filter(|(key, _)| keys.pressed(*key))
+            .map(|(_, direction)| *direction)
+            .sum();
+        if direction == Vec3::ZERO {
+            return;
+        }
+
+        let mut speed = settings.speed;
+        if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            speed *= settings.boost;
+        }
+
+        let planar = transform.rotation * Vec3::new(direction.x, 0.0, direction.z);
+        let vertical = Vec3::Y * direction.y;
+        transform.translation +=
+            (planar + vertical).normalize_or_zero() * speed * time.delta_seconds();
+
+        // The app only updates on input, keep moving while the keys are held
+        redraw.send(RequestRedraw);
+    }
+
+    pub fn is_active(world: &mut World) -> bool {
+        let mut q_camera = world.query_filtered::<(), (With<FlyCamera>, With<PrimaryCamera>)>();
+        q_camera.get_single(world).is_ok()
+    }
+
+    /// Starts flying from the current view of the primary camera. Switches to perspective, as
+    /// moving forward does nothing in orthographic.
+    pub fn enter(world: &mut World) {
+        if Self::is_active(world) {
+            return;
+        }
+
+        PanOrbitCameraPlugin::set_projection(world, CameraProjectionKind::Perspective);
+        world.remove_resource::<Turntable>();
+
+        let mut q_camera =
+            world.query_filtered::<(Entity, &PanOrbitState, &Transform), With<PrimaryCamera>>();
+        let Ok((entity, state, transform)) = q_camera.get_single(world) else {
+            return;
+        };
+
+        let (yaw, pitch, _) = transform.rotation.to_euler(EulerRot::YXZ);
+        let fly = FlyCamera {
+            yaw,
+            pitch: pitch.clamp(-MAX_PITCH, MAX_PITCH),
+            entered_from: state.view(),
+        };
+
+        world
+            .entity_mut(entity)
+            .remove::<PanOrbitTransition>()
+            .insert(fly);
+
+        // Movement keys would otherwise switch tools
+        world.insert_resource(InputCapture {
+            passthrough: vec![
+                EditorAction::ToggleFly,
+                EditorAction::ToggleFps,
+                EditorAction::ToggleStats,
+            ],
+            scroll: true,
+        });
+
+        Self::grab_cursor(world, true);
+    }
+
+    /// Hands the camera back to the pan-orbit controller. The new orbit center is the surface in
+    /// front of the camera, or the previous orbit distance ahead if there is none, so the camera
+    /// does not move. When cancelling, the camera then eases back to where fly mode was entered.
+    pub fn exit(world: &mut World, cancel: bool) {
+        let mut q_camera =
+            world.query_filtered::<(Entity, &FlyCamera, &PanOrbitState, &Transform), With<PrimaryCamera>>();
+        let Ok((entity, fly, state, transform)) = q_camera.get_single(world) else {
+            return;
+        };
+        let (entity, entered_from, radius, transform) =
+            (entity, fly.entered_from, state.radius, *transform);
+        let distance = Self::surface_distance(world, &transform).unwrap_or(radius);
+
+        let mut state = world.get_mut::<PanOrbitState>(entity).unwrap();
+        let distance = distance.clamp(state.min_radius, state.max_radius);
+        state.set_view(FlyCamera::orbit_view(&transform, distance));
+        state.upside_down = false;
+
+        world.entity_mut(entity).remove::<FlyCamera>();
+        world.remove_resource::<InputCapture>();
+
+        Self::grab_cursor(world, false);
+
+        if cancel {
+            PanOrbitCameraPlugin::transition_to(world, entered_from);
+        }
+    }
+
+    pub fn toggle(world: &mut World) {
+        if Self::is_active(world) {
+            Self::exit(world, false);
+        } else {
+            Self::enter(world);
+        }
+    }
+
+    /// Distance to the closest user mesh straight ahead of the camera
+    fn surface_distance(world: &mut World, transform: &Transform) -> Option<f32> {
+        let ray = RayCast3d::from_ray(
+            Ray3d::new(transform.translation, *transform.forward()),
+            1000.0,
+        );
+
+        let mut q_meshes =
+            world.query::<(&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh)>();
+        let scene_bvh = world.get_resource::<SceneBoundingVolumeHierarchy>()?;
+
+        scene_bvh
+            .cast_ray(&ray, |entity| {
+                let (bvh, transform, mesh) = q_meshes.get(world, entity).ok()?;
+                bvh.intersects_ray_at(&ray, transform, mesh)
+            })
+            .map(|(_, _, distance)| distance)
+    }
+
+    fn grab_cursor(world: &mut World, grab: bool) {
+        let mut q_window = world.query_filtered::<&mut Window, With<PrimaryWindow>>();
+        let Ok(mut window) = q_window.get_single_mut(world) else {
+            return;
+        };
+
+        if grab {
+            window.cursor.grab_mode = CursorGrabMode::Locked;
+            window.cursor.visible = false;
+        } else {
+            window.cursor.grab_mode = CursorGrabMode::None;
+            window.cursor.visible = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{FlyCamera, FlyCameraSettings};
+    use crate::core::pan_orbit_camera::PanOrbitState;
+
+    #[test]
+    fn test_orbit_view_keeps_camera_in_place() {
+        let transform = Transform::from_xyz(3.0, 2.0, -4.0).with_rotation(Quat::from_euler(
+            EulerRot::YXZ,
+            0.7,
+            -0.3,
+            0.0,
+        ));
+
+        let mut state = PanOrbitState::default();
+        state.set_view(FlyCamera::orbit_view(&transform, 6.0));
+
+        let mut orbit_transform = Transform::default();
+        state.apply_to(&mut orbit_transform);
+
+        assert!((orbit_transform.translation - transform.translation).length() < 1e-4);
+        assert!(orbit_transform.rotation.angle_between(transform.rotation) < 1e-4);
+        assert!(((state.center - transform.translation).length() - 6.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_scroll_speed_is_clamped() {
+        let mut settings = FlyCameraSettings::default();
+
+        settings.scroll_speed(1.0);
+        assert!((settings.speed - 6.0).abs() < 1e-4);
+
+        settings.scroll_speed(-100.0);
+        assert_eq!(settings.speed, settings.min_speed);
+
+        settings.scroll_speed(100.0);
+        assert_eq!(settings.speed, settings.max_speed);
+    }
+}
@@ -0,0 +1,870 @@
+use std::{f32::consts::FRAC_PI_2, fmt};
+
+use bevy::{asset::AssetId, prelude::*, utils::HashMap};
+use wasm_bindgen::prelude::*;
+
+use crate::core::{
+    editable_mesh::{EditableMesh, SurfaceMaterial},
+    editor::{Focused, UserSpace},
+    highlight::Highlight,
+    project::MeshData,
+};
+
+use super::baked;
+
+/// Writes the visible [`UserSpace`] entities as glTF 2.0, either binary (`.glb`) or JSON with the
+/// buffer embedded (`.gltf`). Meshes are triangulated, with normals, and UVs and vertex colours
+/// when their render mesh has them. Entities with a [`SurfaceMaterial`] get the factors of its
+/// [`StandardMaterial`], textures are not written.
+pub struct GltfExporter;
+
+/// Axis pointing up in the exported file. glTF is Y up, Z up is for tools that ignore it.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UpAxis {
+    #[default]
+    Y,
+    Z,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GltfExportOptions {
+    /// Only the focused and highlighted entities
+    pub selected_only: bool,
+    /// Bakes the world transforms into the vertices, leaving every node at the root with an
+    /// identity transform
+    pub apply_transforms: bool,
+    pub up_axis: UpAxis,
+}
+
+/// Entity written as a node
+struct ExportNode {
+    name: String,
+    /// Index of the closest exported ancestor
+    parent: Option<usize>,
+    /// Relative to the parent node
+    transform: Transform,
+    global: GlobalTransform,
+    mesh: Option<MeshData>,
+    material: Option<AssetId<StandardMaterial>>,
+}
+
+// Accessor component types and buffer view targets from the specification
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNLIT_EXTENSION: &str = "KHR_materials_unlit";
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_JSON_CHUNK: &[u8; 4] = b"JSON";
+const GLB_BIN_CHUNK: &[u8; 4] = b"BIN\0";
+
+impl UpAxis {
+    /// Rotation from the Y up world of the editor
+    pub fn rotation(self) -> Quat {
+        match self {
+            UpAxis::Y => Quat::IDENTITY,
+            // Y goes to Z, and Z to -Y
+            UpAxis::Z => Quat::from_rotation_x(FRAC_PI_2),
+        }
+    }
+}
+
+impl GltfExporter {
+    /// Binary glTF, the JSON and the buffer in one file
+    pub fn export_glb(world: &mut World, options: &GltfExportOptions) -> Vec<u8> {
+        let (document, buffer) = Self::document(world, options, false);
+
+        let mut json = document.to_string().into_bytes();
+        // Chunks are 4-byte aligned, the JSON with spaces and the buffer with zeros
+        json.resize(json.len().next_multiple_of(4), b' ');
+        let mut bin = buffer;
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let mut chunks = vec![(GLB_JSON_CHUNK, json)];
+        if !bin.is_empty() {
+            chunks.push((GLB_BIN_CHUNK, bin));
+        }
+        let length = 12 + chunks.iter().map(|(_, data)| 8 + data.len()).sum::<usize>();
+
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(GLB_MAGIC);
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        for (kind, data) in chunks {
+            glb.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            glb.extend_from_slice(kind);
+            glb.extend_from_slice(&data);
+        }
+        glb
+    }
+
+    /// JSON glTF, with the buffer in a base64 data URI
+    pub fn export_gltf(world: &mut World, options: &GltfExportOptions) -> String {
+        Self::document(world, options, true).0.to_string()
+    }
+
+    /// The glTF JSON and its buffer, embedded into the JSON if `embed_buffer`
+    fn document(
+        world: &mut World,
+        options: &GltfExportOptions,
+        embed_buffer: bool,
+    ) -> (Json, Vec<u8>) {
+        let nodes = Self::collect(world, options);
+        let standard_materials = world.resource::<Assets<StandardMaterial>>();
+
+        let mut document = DocumentBuilder::default();
+        let mut material_indices: HashMap<AssetId<StandardMaterial>, usize> = HashMap::new();
+        let mut unlit = false;
+
+        let mut node_json: Vec<Vec<(&'static str, Json)>> = vec![];
+        for node in &nodes {
+            let mut fields = vec![("name", Json::String(node.name.clone()))];
+
+            let transform = node.transform;
+            if transform.translation != Vec3::ZERO {
+                fields.push((
+                    "translation",
+                    Json::floats(transform.translation.to_array()),
+                ));
+            }
+            if transform.rotation != Quat::IDENTITY {
+                fields.push(("rotation", Json::floats(transform.rotation.to_array())));
+            }
+            if transform.scale != Vec3::ONE {
+                fields.push(("scale", Json::floats(transform.scale.to_array())));
+            }
+
+            let material = node.material.and_then(|id| {
+                if let Some(index) = material_indices.get(&id) {
+                    return Some(*index);
+                }
+                let material = standard_materials.get(id)?;
+                unlit |= material.unlit;
+                document.materials.push(material_json(material));
+                material_indices.insert(id, document.materials.len() - 1);
+                Some(document.materials.len() - 1)
+            });
+
+            if let Some(mesh) = node.mesh.as_ref().filter(|mesh| !mesh.faces.is_empty()) {
+                let primitive = document.primitive(mesh, material);
+                fields.push(("mesh", Json::Int(document.meshes.len())));
+                document.meshes.push(Json::object([
+                    ("name", Json::String(node.name.clone())),
+                    ("primitives", Json::Array(vec![primitive])),
+                ]));
+            }
+
+            node_json.push(fields);
+        }
+
+        // Children are listed by their parent
+        let mut children: Vec<Vec<Json>> = vec![vec![]; nodes.len()];
+        let mut roots = vec![];
+        for (index, node) in nodes.iter().enumerate() {
+            match node.parent {
+                Some(parent) => children[parent].push(Json::Int(index)),
+                None => roots.push(Json::Int(index)),
+            }
+        }
+        for (fields, children) in node_json.iter_mut().zip(children) {
+            if !children.is_empty() {
+                fields.push(("children", Json::Array(children)));
+            }
+        }
+
+        let mut root = vec![
+            (
+                "asset",
+                Json::object([
+                    ("version", Json::String("2.0".to_string())),
+                    ("generator", Json::String("meshup".to_string())),
+                ]),
+            ),
+            ("scene", Json::Int(0)),
+            (
+                "scenes",
+                Json::Array(vec![Json::object([("nodes", Json::Array(roots))])]),
+            ),
+            (
+                "nodes",
+                Json::Array(node_json.into_iter().map(Json::Object).collect()),
+            ),
+        ];
+        if unlit {
+            root.push((
+                "extensionsUsed",
+                Json::Array(vec![Json::String(UNLIT_EXTENSION.to_string())]),
+            ));
+        }
+        for (name, values) in [
+            ("meshes", document.meshes),
+            ("materials", document.materials),
+            ("accessors", document.accessors),
+            ("bufferViews", document.buffer_views),
+        ] {
+            if !values.is_empty() {
+                root.push((name, Json::Array(values)));
+            }
+        }
+
+        // Buffers may not be empty
+        if !document.buffer.is_empty() {
+            let mut buffer = vec![("byteLength", Json::Int(document.buffer.len()))];
+            if embed_buffer {
+                buffer.push((
+                    "uri",
+                    Json::String(format!(
+                        "data:application/octet-stream;base64,{}",
+                        base64(&document.buffer)
+                    )),
+                ));
+            }
+            root.push(("buffers", Json::Array(vec![Json::Object(buffer)])));
+        }
+
+        (Json::Object(root), document.buffer)
+    }
+
+    /// The exported entities, parents before their children
+    fn collect(world: &mut World, options: &GltfExportOptions) -> Vec<ExportNode> {
+        let mut q_entities = world.query_filtered::<(
+            Entity,
+            Option<&Parent>,
+            Option<&Children>,
+            &InheritedVisibility,
+            Has<Focused>,
+            Has<Highlight>,
+        ), With<UserSpace>>();
+
+        let mut parents: HashMap<Entity, Option<Entity>> = HashMap::new();
+        let mut children: HashMap<Entity, Vec<Entity>> = HashMap::new();
+        let mut exported: Vec<Entity> = vec![];
+        for (entity, parent, entity_children, visibility, focused, highlighted) in
+            q_entities.iter(world)
+        {
+            parents.insert(entity, parent.map(Parent::get));
+            if let Some(entity_children) = entity_children {
+                children.insert(entity, entity_children.to_vec());
+            }
+            if visibility.get() && (!options.selected_only || focused || highlighted) {
+                exported.push(entity);
+            }
+        }
+
+        // Depth first from the roots, so ancestors come first
+        let mut roots: Vec<Entity> = parents
+            .iter()
+            .filter(|(_, parent)| parent.map_or(true, |parent| !parents.contains_key(&parent)))
+            .map(|(entity, _)| *entity)
+            .collect();
+        roots.sort_by(|a, b| b.cmp(a));
+
+        let mut order = vec![];
+        let mut stack = roots;
+        while let Some(entity) = stack.pop() {
+            order.push(entity);
+            if let Some(entity_children) = children.get(&entity) {
+                stack.extend(
+                    entity_children
+                        .iter()
+                        .rev()
+                        .filter(|child| parents.contains_key(*child)),
+                );
+            }
+        }
+
+        let mut q_data = world.query::<(
+            Option<&Name>,
+            &GlobalTransform,
+            Option<&EditableMesh>,
+            Option<&Handle<Mesh>>,
+            Option<&SurfaceMaterial>,
+        )>();
+        let meshes = world.resource::<Assets<Mesh>>();
+        let up = GlobalTransform::from(Transform::from_rotation(options.up_axis.rotation()));
+
+        let mut indices: HashMap<Entity, usize> = HashMap::new();
+        let mut nodes: Vec<ExportNode> = vec![];
+
+        for entity in order.into_iter().filter(|entity| exported.contains(entity)) {
+            let Ok((name, global, mesh, render_mesh, material)) = q_data.get(world, entity) else {
+                continue;
+            };
+
+            let parent = match options.apply_transforms {
+                true => None,
+                false => std::iter::successors(parents[&entity], |ancestor| {
+                    parents.get(ancestor).copied().flatten()
+                })
+                .find_map(|ancestor| indices.get(&ancestor).copied()),
+            };
+
+            let transform = match (options.apply_transforms, parent) {
+                (true, _) => Transform::IDENTITY,
+                (false, Some(parent)) => global.reparented_to(&nodes[parent].global),
+                (false, None) => (up * *global).compute_transform(),
+            };
+
+            let mesh = mesh.map(|mesh| {
+                let data = MeshData::new(mesh, render_mesh.and_then(|handle| meshes.get(handle)));
+                match options.apply_transforms {
+                    true => baked(data, &(up * *global)),
+                    false => data,
+                }
+            });
+
+            indices.insert(entity, nodes.len());
+            nodes.push(ExportNode {
+                name: name.map_or_else(String::new, |name| name.to_string()),
+                parent,
+                transform,
+                global: *global,
+                mesh,
+                material: material.map(|material| material.0.id()),
+            });
+        }
+
+        nodes
+    }
+}
+
+fn material_json(material: &StandardMaterial) -> Json {
+    let mut fields = vec![(
+        "pbrMetallicRoughness",
+        Json::object([
+            (
+                "baseColorFactor",
+                Json::floats(material.base_color.as_linear_rgba_f32()),
+            ),
+            ("metallicFactor", Json::Float(material.metallic)),
+            (
+                "roughnessFactor",
+                Json::Float(material.perceptual_roughness),
+            ),
+        ]),
+    )];
+
+    let [r, g, b, _] = material.emissive.as_linear_rgba_f32();
+    if [r, g, b] != [0.0; 3] {
+        fields.push(("emissiveFactor", Json::floats([r, g, b])));
+    }
+
+    match material.alpha_mode {
+        AlphaMode::Opaque => {}
+        AlphaMode::Mask(cutoff) => {
+            fields.push(("alphaMode", Json::String("MASK".to_string())));
+            fields.push(("alphaCutoff", Json::Float(cutoff)));
+        }
+        // glTF has no other blending modes
+        _ => fields.push(("alphaMode", Json::String("BLEND".to_string()))),
+    }
+    if material.double_sided {
+        fields.push(("doubleSided", Json::Bool(true)));
+    }
+    if material.unlit {
+        fields.push((
+            "extensions",
+            Json::Object(vec![(UNLIT_EXTENSION, Json::Object(vec![]))]),
+        ));
+    }
+
+    Json::Object(fields)
+}
+
+/// Accumulates the accessors and their data
+#[derive(Default)]
+struct DocumentBuilder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Json>,
+    accessors: Vec<Json>,
+    meshes: Vec<Json>,
+    materials: Vec<Json>,
+}
+
+impl DocumentBuilder {
+    /// Triangle list primitive of a mesh, with its attributes
+    fn primitive(&mut self, mesh: &MeshData, material: Option<usize>) -> Json {
+        let (min, max) = mesh.positions.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), position| (min.min(*position), max.max(*position)),
+        );
+
+        let mut attributes = vec![
+            (
+                "POSITION",
+                self.accessor(
+                    mesh.positions.iter().flat_map(|p| p.to_array()),
+                    mesh.positions.len(),
+                    "VEC3",
+                    Some((min.to_array().to_vec(), max.to_array().to_vec())),
+                ),
+            ),
+            (
+                "NORMAL",
+                self.accessor(
+                    mesh.normals.iter().flat_map(|n| n.to_array()),
+                    mesh.normals.len(),
+                    "VEC3",
+                    None,
+                ),
+            ),
+        ];
+        if let Some(uvs) = &mesh.uvs {
+            let accessor = self.accessor(
+                uvs.iter().flat_map(|uv| uv.to_array()),
+                uvs.len(),
+                "VEC2",
+                None,
+            );
+            attributes.push(("TEXCOORD_0", accessor));
+        }
+        if let Some(colors) = &mesh.colors {
+            let accessor =
+                self.accessor(colors.iter().flatten().copied(), colors.len(), "VEC4", None);
+            attributes.push(("COLOR_0", accessor));
+        }
+
+        let triangles = mesh.triangles();
+        let view = self.buffer_view(
+            triangles.iter().flat_map(|index| index.to_le_bytes()),
+            ELEMENT_ARRAY_BUFFER,
+        );
+        let indices = self.push_accessor(Json::object([
+            ("bufferView", Json::Int(view)),
+            ("componentType", Json::Int(UNSIGNED_INT as usize)),
+            ("count", Json::Int(triangles.len())),
+            ("type", Json::String("SCALAR".to_string())),
+        ]));
+
+        let mut primitive = vec![
+            (
+                "attributes",
+                Json::Object(
+                    attributes
+                        .into_iter()
+                        .map(|(name, accessor)| (name, Json::Int(accessor)))
+                        .collect(),
+                ),
+            ),
+            ("indices", Json::Int(indices)),
+        ];
+        if let Some(material) = material {
+            primitive.push(("material", Json::Int(material)));
+        }
+        Json::Object(primitive)
+    }
+
+    /// Float vertex attribute of `count` elements of `kind`, like `VEC3`
+    fn accessor(
+        &mut self,
+        values: impl Iterator<Item = f32>,
+        count: usize,
+        kind: &str,
+        bounds: Option<(Vec<f32>, Vec<f32>)>,
+    ) -> usize {
+        let view = self.buffer_view(values.flat_map(f32::to_le_bytes), ARRAY_BUFFER);
+
+        let mut fields = vec![
+            ("bufferView", Json::Int(view)),
+            ("componentType", Json::Int(FLOAT as usize)),
+            ("count", Json::Int(count)),
+            ("type", Json::String(kind.to_string())),
+        ];
+        if let Some((min, max)) = bounds {
+            fields.push(("min", Json::floats(min)));
+            fields.push(("max", Json::floats(max)));
+        }
+        self.push_accessor(Json::Object(fields))
+    }
+
+    fn push_accessor(&mut self, accessor: Json) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// Appends bytes to the buffer, every view starting 4-byte aligned
+    fn buffer_view(&mut self, bytes: impl Iterator<Item = u8>, target: u32) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend(bytes);
+        let length = self.buffer.len() - offset;
+        self.buffer.resize(self.buffer.len().next_multiple_of(4), 0);
+
+        self.buffer_views.push(Json::object([
+            ("buffer", Json::Int(0)),
+            ("byteOffset", Json::Int(offset)),
+            ("byteLength", Json::Int(length)),
+            ("target", Json::Int(target as usize)),
+        ]));
+        self.buffer_views.len() - 1
+    }
+}
+
+/// Just enough JSON for glTF
+#[derive(Clone, Debug, PartialEq)]
+enum Json {
+    Bool(bool),
+    Int(usize),
+    Float(f32),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl Json {
+    fn object<const N: usize>(fields: [(&'static str, Json); N]) -> Self {
+        Json::Object(Vec::from(fields))
+    }
+
+    fn floats(values: impl IntoIterator<Item = f32>) -> Self {
+        Json::Array(values.into_iter().map(Json::Float).collect())
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Bool(value) => write!(f, "{value}"),
+            Json::Int(value) => write!(f, "{value}"),
+            // JSON has no infinities or NaN
+            Json::Float(value) if !value.is_finite() => write!(f, "0"),
+            Json::Float(value) => write!(f, "{value}"),
+            Json::String(value) => {
+                write!(f, "\"")?;
+                for c in value.chars() {
+                    match c {
+                        '"' => write!(f, "\\\"")?,
+                        '\\' => write!(f, "\\\\")?,
+                        c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                write!(f, "\"")
+            }
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(fields) => {
+                write!(f, "{{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}:{value}", Json::String(name.to_string()))?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, byte)| {
+            group | ((*byte as u32) << (16 - 8 * i))
+        });
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[((group >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, path::PathBuf, thread, time::Duration};
+
+    use bevy::{
+        asset::LoadState,
+        gltf::{Gltf, GltfMesh, GltfNode, GltfPlugin},
+        prelude::*,
+        render::mesh::VertexAttributeValues,
+    };
+
+    use super::{GltfExportOptions, GltfExporter, UpAxis};
+    use crate::core::{
+        editable_mesh::{EditableMesh, EditableMeshBundle, SurfaceMaterial},
+        editor::{Focused, UserSpace},
+        formats::mesh_bundle,
+        project::MeshData,
+    };
+
+    /// App that can load what it exported, reading from its own temporary directory
+    fn test_app(name: &str) -> (App, PathBuf) {
+        let directory = std::env::temp_dir().join(format!("meshup-{name}-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            TransformPlugin,
+            HierarchyPlugin,
+            AssetPlugin {
+                file_path: directory.to_string_lossy().into_owned(),
+                ..default()
+            },
+            GltfPlugin::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .init_asset::<Scene>()
+        .init_asset::<AnimationClip>();
+        app.finish();
+        app.cleanup();
+
+        (app, directory)
+    }
+
+    fn spawn_cuboid(app: &mut App, name: &str, transform: Transform) -> Entity {
+        let render_mesh = Cuboid::new(1.0, 2.0, 3.0).mesh();
+        let mesh: EditableMesh = (&render_mesh).into();
+        let handle = app.world.resource_mut::<Assets<Mesh>>().add(render_mesh);
+
+        app.world
+            .spawn((
+                Name::new(name.to_string()),
+                mesh,
+                handle,
+                TransformBundle::from_transform(transform),
+                InheritedVisibility::VISIBLE,
+                UserSpace,
+            ))
+            .id()
+    }
+
+    /// Writes the file and waits for the glTF loader of Bevy to read it back
+    fn load(app: &mut App, directory: &PathBuf, file: &str, bytes: &[u8]) -> Handle<Gltf> {
+        fs::write(directory.join(file), bytes).unwrap();
+        let handle: Handle<Gltf> = app.world.resource::<AssetServer>().load(file.to_string());
+
+        for _ in 0..1000 {
+            app.update();
+
+            let asset_server = app.world.resource::<AssetServer>();
+            if asset_server.is_loaded_with_dependencies(&handle) {
+                return handle;
+            }
+            assert_ne!(asset_server.load_state(&handle), LoadState::Failed);
+            thread::sleep(Duration::from_millis(5));
+        }
+        panic!("{file} did not load");
+    }
+
+    /// Bounds of the positions of the first primitive
+    fn bounds(app: &App, mesh: &Handle<GltfMesh>) -> (Vec3, Vec3) {
+        let mesh = app.world.resource::<Assets<GltfMesh>>().get(mesh).unwrap();
+        let mesh = app
+            .world
+            .resource::<Assets<Mesh>>()
+            .get(&mesh.primitives[0].mesh)
+            .unwrap();
+
+        match mesh.attribute(Mesh::ATTRIBUTE_POSITION) {
+            Some(VertexAttributeValues::Float32x3(positions)) => {
+                positions
+                    .iter()
+                    .fold((Vec3::MAX, Vec3::MIN), |(min, max), position| {
+                        let position = Vec3::from_array(*position);
+                        (min.min(position), max.max(position))
+                    })
+            }
+            _ => panic!("no positions"),
+        }
+    }
+
+    #[test]
+    fn test_glb_keeps_hierarchy_geometry_and_materials() {
+        let (mut app, directory) = test_app("glb");
+
+        let material = app
+            .world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::rgba_linear(0.2, 0.4, 0.6, 1.0),
+                metallic: 0.3,
+                perceptual_roughness: 0.7,
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            });
+
+        let group = app
+            .world
+            .spawn((
+                Name::new("Group"),
+                TransformBundle::from_transform(Transform::from_xyz(0.0, 1.0, 0.0)),
+                InheritedVisibility::VISIBLE,
+                UserSpace,
+            ))
+            .id();
+        let cuboid = spawn_cuboid(
+            &mut app,
+            "Box",
+            Transform::from_xyz(1.0, 0.0, 0.0).with_scale(Vec3::splat(2.0)),
+        );
+        app.world
+            .entity_mut(cuboid)
+            .insert(SurfaceMaterial(material));
+        app.world.entity_mut(group).add_child(cuboid);
+        let hidden = spawn_cuboid(&mut app, "Hidden", Transform::IDENTITY);
+        app.world
+            .entity_mut(hidden)
+            .insert(InheritedVisibility::HIDDEN);
+        app.world
+            .entity_mut(group)
+            .insert(InheritedVisibility::VISIBLE);
+        app.update();
+
+        let glb = GltfExporter::export_glb(&mut app.world, &GltfExportOptions::default());
+        let handle = load(&mut app, &directory, "scene.glb", &glb);
+
+        let gltf = app.world.resource::<Assets<Gltf>>().get(&handle).unwrap();
+        assert_eq!(gltf.nodes.len(), 2);
+        assert!(!gltf.named_nodes.contains_key("Hidden"));
+
+        let nodes = app.world.resource::<Assets<GltfNode>>();
+        let group = nodes.get(&gltf.named_nodes["Group"]).unwrap();
+        assert_eq!(group.transform.translation, Vec3::Y);
+        assert_eq!(group.children.len(), 1);
+
+        let cuboid = &group.children[0];
+        assert!(cuboid.transform.translation.abs_diff_eq(Vec3::X, 1e-6));
+        assert!(cuboid.transform.scale.abs_diff_eq(Vec3::splat(2.0), 1e-6));
+
+        let mesh_handle = cuboid.mesh.clone().unwrap();
+        let gltf_mesh = app
+            .world
+            .resource::<Assets<GltfMesh>>()
+            .get(&mesh_handle)
+            .unwrap();
+        let primitive = &gltf_mesh.primitives[0];
+        let mesh = app
+            .world
+            .resource::<Assets<Mesh>>()
+            .get(&primitive.mesh)
+            .unwrap();
+        assert_eq!(mesh.count_vertices(), 24);
+        assert_eq!(mesh.indices().unwrap().len(), 36);
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_NORMAL).is_some());
+        assert!(mesh.attribute(Mesh::ATTRIBUTE_UV_0).is_some());
+
+        let material = app
+            .world
+            .resource::<Assets<StandardMaterial>>()
+            .get(primitive.material.as_ref().unwrap())
+            .unwrap();
+        let [r, g, b, a] = material.base_color.as_linear_rgba_f32();
+        assert!(Vec4::new(r, g, b, a).abs_diff_eq(Vec4::new(0.2, 0.4, 0.6, 1.0), 1e-6));
+        assert_eq!(material.metallic, 0.3);
+        assert_eq!(material.perceptual_roughness, 0.7);
+        assert!(material.double_sided);
+
+        let (min, max) = bounds(&app, &mesh_handle);
+        assert_eq!(
+            (min, max),
+            (Vec3::new(-0.5, -1.0, -1.5), Vec3::new(0.5, 1.0, 1.5))
+        );
+    }
+
+    #[test]
+    fn test_gltf_bakes_selected_meshes_into_z_up() {
+        let (mut app, directory) = test_app("gltf");
+
+        let selected = spawn_cuboid(
+            &mut app,
+            "Selected",
+            Transform::from_xyz(1.0, 1.0, 0.0).with_scale(Vec3::splat(2.0)),
+        );
+        app.world.entity_mut(selected).insert(Focused);
+        spawn_cuboid(&mut app, "Other", Transform::IDENTITY);
+        app.update();
+
+        let gltf = GltfExporter::export_gltf(
+            &mut app.world,
+            &GltfExportOptions {
+                selected_only: true,
+                apply_transforms: true,
+                up_axis: UpAxis::Z,
+            },
+        );
+        let handle = load(&mut app, &directory, "scene.gltf", gltf.as_bytes());
+
+        let gltf = app.world.resource::<Assets<Gltf>>().get(&handle).unwrap();
+        assert_eq!(gltf.nodes.len(), 1);
+        let node = app
+            .world
+            .resource::<Assets<GltfNode>>()
+            .get(&gltf.named_nodes["Selected"])
+            .unwrap();
+        assert_eq!(node.transform, Transform::IDENTITY);
+
+        // Y up becomes Z up
+        let (min, max) = bounds(&app, node.mesh.as_ref().unwrap());
+        assert!(min.abs_diff_eq(Vec3::new(0.0, -3.0, -1.0), 1e-5));
+        assert!(max.abs_diff_eq(Vec3::new(2.0, 3.0, 3.0), 1e-5));
+    }
+
+    #[test]
+    fn test_imported_mesh_keeps_its_surface_material() {
+        let (mut app, directory) = test_app("material");
+
+        // As read from a file
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::ONE).mesh()).into();
+        let data = MeshData::new(&mesh, None);
+        let mut meshes = app.world.resource_mut::<Assets<Mesh>>();
+        let bundle = mesh_bundle(&data, &mut meshes).unwrap();
+        let material = app
+            .world
+            .resource_mut::<Assets<StandardMaterial>>()
+            .add(StandardMaterial {
+                base_color: Color::rgba_linear(0.8, 0.1, 0.1, 1.0),
+                metallic: 0.9,
+                perceptual_roughness: 0.2,
+                ..default()
+            });
+        app.world.spawn((
+            EditableMeshBundle {
+                surface_material: SurfaceMaterial(material),
+                inherited_visibility: InheritedVisibility::VISIBLE,
+                ..bundle
+            },
+            Name::new("Imported"),
+            UserSpace,
+        ));
+        app.update();
+
+        let glb = GltfExporter::export_glb(&mut app.world, &GltfExportOptions::default());
+        let handle = load(&mut app, &directory, "imported.glb", &glb);
+
+        let gltf = app.world.resource::<Assets<Gltf>>().get(&handle).unwrap();
+        assert_eq!(gltf.materials.len(), 1);
+        let material = app
+            .world
+            .resource::<Assets<StandardMaterial>>()
+            .get(&gltf.materials[0])
+            .unwrap();
+        let [r, g, b, a] = material.base_color.as_linear_rgba_f32();
+        assert!(Vec4::new(r, g, b, a).abs_diff_eq(Vec4::new(0.8, 0.1, 0.1, 1.0), 1e-6));
+        assert_eq!(material.metallic, 0.9);
+        assert_eq!(material.perceptual_roughness, 0.2);
+    }
+}
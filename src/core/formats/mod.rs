@@ -0,0 +1,458 @@
+pub mod gltf;
+pub mod ply;
+pub mod stl;
+
+use std::fmt;
+
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use wasm_bindgen::prelude::*;
+
+use gltf::UpAxis;
+
+use super::{
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh, EditableMeshBundle},
+    editor::{Focused, UserSpace},
+    highlight::Highlight,
+    project::MeshData,
+    tools::cursor::world_normal,
+};
+
+/// Whether a mesh file is written as text or as binary, for the formats that have both
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Binary,
+    Ascii,
+}
+
+/// Length unit of the numbers in a mesh file. The files do not say, slicers assume millimeters.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FileUnit {
+    #[default]
+    Millimeters,
+    Inches,
+    Meters,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshImportOptions {
+    pub unit: FileUnit,
+    /// Axis pointing up in the file
+    pub up_axis: UpAxis,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MeshExportOptions {
+    /// Only the focused and highlighted meshes
+    pub selected_only: bool,
+    pub encoding: Encoding,
+    pub unit: FileUnit,
+    /// Axis pointing up in the file
+    pub up_axis: UpAxis,
+}
+
+/// Mesh about to be written, in world space converted to the units and axes of the file
+pub struct ExportedMesh {
+    pub name: String,
+    pub data: MeshData,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormatError {
+    /// The file ends before the data it announces
+    UnexpectedEnd,
+    /// Names what could not be read
+    InvalidValue(&'static str),
+    /// Valid for the format, but not read by this editor
+    Unsupported(&'static str),
+    /// No face is left to build a mesh from
+    Empty,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::UnexpectedEnd => write!(f, "file is truncated"),
+            FormatError::InvalidValue(name) => write!(f, "invalid {name} in file"),
+            FormatError::Unsupported(name) => write!(f, "unsupported {name}"),
+            FormatError::Empty => write!(f, "file has no faces"),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl FileUnit {
+    /// World units, meters, per unit of the file
+    pub fn unit_length(self) -> f32 {
+        match self {
+            FileUnit::Millimeters => 0.001,
+            FileUnit::Inches => 0.0254,
+            FileUnit::Meters => 1.0,
+        }
+    }
+}
+
+impl MeshImportOptions {
+    /// World position of a position read from the file
+    pub fn to_world(&self, position: Vec3) -> Vec3 {
+        self.up_axis.rotation().inverse() * position * self.unit.unit_length()
+    }
+}
+
+impl MeshExportOptions {
+    /// From world space to the space of the file
+    pub fn file_transform(&self) -> GlobalTransform {
+        Transform::from_rotation(self.up_axis.rotation())
+            .with_scale(Vec3::splat(1.0 / self.unit.unit_length()))
+            .into()
+    }
+}
+
+/// The visible user meshes, or the selected ones, with `f` applied to each
+fn exported<T>(
+    world: &mut World,
+    selected_only: bool,
+    mut f: impl FnMut(Option<&Name>, &GlobalTransform, &EditableMesh, Option<&Mesh>) -> T,
+) -> Vec<T> {
+    let mut q_meshes = world.query_filtered::<(
+        Option<&Name>,
+        &GlobalTransform,
+        &EditableMesh,
+        Option<&Handle<Mesh>>,
+        &InheritedVisibility,
+        Has<Focused>,
+        Has<Highlight>,
+    ), With<UserSpace>>();
+    let meshes = world.resource::<Assets<Mesh>>();
+
+    q_meshes
+        .iter(world)
+        .filter(|(_, _, _, _, visibility, focused, highlighted)| {
+            visibility.get() && (!selected_only || *focused || *highlighted)
+        })
+        .map(|(name, transform, mesh, render_mesh, ..)| {
+            f(
+                name,
+                transform,
+                mesh,
+                render_mesh.and_then(|handle| meshes.get(handle)),
+            )
+        })
+        .collect()
+}
+
+/// Meshes to write in a file, with their transforms and the options applied
+pub fn exported_meshes(world: &mut World, options: &MeshExportOptions) -> Vec<ExportedMesh> {
+    let file_transform = options.file_transform();
+
+    exported(
+        world,
+        options.selected_only,
+        |name, transform, mesh, render_mesh| ExportedMesh {
+            name: name.map_or_else(String::new, |name| name.to_string()),
+            data: baked(
+                MeshData::new(mesh, render_mesh),
+                &(file_transform * *transform),
+            ),
+        },
+    )
+}
+
+/// Names of the meshes that would be exported with holes, see [`EditableMesh::is_closed`].
+/// Slicers cannot tell the inside of those from the outside, so they are checked before
+/// exporting for printing.
+pub fn open_meshes(world: &mut World, selected_only: bool) -> Vec<String> {
+    exported(world, selected_only, |name, _, mesh, _| {
+        (!mesh.is_closed()).then(|| name.map_or_else(String::new, |name| name.to_string()))
+    })
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Mesh data moved into world space. Mirroring transforms turn the faces inside out, so their
+/// winding is reversed.
+fn baked(mut data: MeshData, transform: &GlobalTransform) -> MeshData {
+    for position in &mut data.positions {
+        *position = transform.transform_point(*position);
+    }
+    for normal in data.normals.iter_mut().chain(&mut data.face_normals) {
+        *normal = world_normal(transform, *normal).unwrap_or(*normal);
+    }
+    if transform.affine().matrix3.determinant() < 0.0 {
+        for corners in &mut data.faces {
+            corners.reverse();
+        }
+    }
+    data
+}
+
+/// Merges the vertices at the same position, so that faces which only touch in the file share
+/// edges in the half-edge structure. Corners that collapse are removed. A face wound against its
+/// neighbours is flipped, and faces left with less than three corners or that would give an edge
+/// a third face are dropped with a warning. Normals are smooth, averaged over the faces around
+/// each vertex.
+pub fn weld(
+    positions: &[Vec3],
+    colors: Option<&[[f32; 4]]>,
+    faces: &[Vec<u32>],
+) -> Result<MeshData, FormatError> {
+    // Negative zero is the same position
+    let key = |position: Vec3| {
+        position
+            .to_array()
+            .map(|c| if c == 0.0 { 0 } else { c.to_bits() })
+    };
+
+    let mut welded_indices: HashMap<[u32; 3], u32> = HashMap::new();
+    let welded: Vec<u32> = positions
+        .iter()
+        .map(|position| {
+            let next = welded_indices.len() as u32;
+            *welded_indices.entry(key(*position)).or_insert(next)
+        })
+        .collect();
+
+    let mut edges: HashSet<(u32, u32)> = HashSet::new();
+    let mut data = MeshData::default();
+    let mut dropped = 0;
+    // Only vertices used by a face are kept
+    let mut vertices: HashMap<u32, u32> = HashMap::new();
+
+    for corners in faces {
+        let mut face: Vec<(u32, u32)> = Vec::with_capacity(corners.len());
+        for corner in corners {
+            let welded_corner = *welded
+                .get(*corner as usize)
+                .ok_or(FormatError::InvalidValue("face"))?;
+            if face.last().map(|(welded, _)| *welded) != Some(welded_corner) {
+                face.push((welded_corner, *corner));
+            }
+        }
+        while face.len() > 1 && face[0].0 == face[face.len() - 1].0 {
+            face.pop();
+        }
+
+        // Corners visited twice would pinch the face
+        let mut distinct: Vec<u32> = face.iter().map(|(welded, _)| *welded).collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        if face.len() < 3 || distinct.len() < face.len() {
+            dropped += 1;
+            continue;
+        }
+
+        let face_edges = |face: &[(u32, u32)]| -> Vec<(u32, u32)> {
+            face.iter()
+                .zip(face.iter().cycle().skip(1))
+                .map(|((from, _), (to, _))| (*from, *to))
+                .collect()
+        };
+        let mut directed = face_edges(&face);
+        // An edge already taken in the same direction means the face is wound against the one
+        // across it. When the flipped face clashes too, an edge would get a third face.
+        if directed.iter().any(|edge| edges.contains(edge)) {
+            face.reverse();
+            directed = face_edges(&face);
+            if directed.iter().any(|edge| edges.contains(edge)) {
+                dropped += 1;
+                continue;
+            }
+        }
+        edges.extend(directed);
+
+        let indices = face
+            .iter()
+            .map(|(welded_corner, corner)| {
+                *vertices.entry(*welded_corner).or_insert_with(|| {
+                    data.positions.push(positions[*corner as usize]);
+                    if let Some(colors) = colors {
+                        let color = colors.get(*corner as usize).copied().unwrap_or([1.0; 4]);
+                        data.colors.get_or_insert_with(Vec::new).push(color);
+                    }
+                    data.positions.len() as u32 - 1
+                })
+            })
+            .collect();
+        data.faces.push(indices);
+    }
+
+    if dropped > 0 {
+        warn!("Dropped {dropped} degenerate or non-manifold faces while welding");
+    }
+    if data.faces.is_empty() {
+        return Err(FormatError::Empty);
+    }
+
+    // Newell's method, the length is twice the area so larger faces weigh more
+    let area_normals: Vec<Vec3> = data
+        .faces
+        .iter()
+        .map(|corners| {
+            corners
+                .iter()
+                .zip(corners.iter().cycle().skip(1))
+                .map(|(from, to)| {
+                    let (a, b) = (data.positions[*from as usize], data.positions[*to as usize]);
+                    Vec3::new(
+                        (a.y - b.y) * (a.z + b.z),
+                        (a.z - b.z) * (a.x + b.x),
+                        (a.x - b.x) * (a.y + b.y),
+                    )
+                })
+                .sum()
+        })
+        .collect();
+
+    let mut normals = vec![Vec3::ZERO; data.positions.len()];
+    for (corners, normal) in data.faces.iter().zip(&area_normals) {
+        for corner in corners {
+            normals[*corner as usize] += *normal;
+        }
+    }
+    data.normals = normals
+        .into_iter()
+        .map(|normal| normal.try_normalize().unwrap_or(Vec3::Y))
+        .collect();
+    data.face_normals = area_normals
+        .into_iter()
+        .map(|normal| normal.try_normalize().unwrap_or(Vec3::Y))
+        .collect();
+
+    Ok(data)
+}
+
+/// Bundle of a mesh read from a file, without its material
+pub fn mesh_bundle(
+    data: &MeshData,
+    meshes: &mut Assets<Mesh>,
+) -> Result<EditableMeshBundle, FormatError> {
+    let editable_mesh = data
+        .editable_mesh()
+        .map_err(|_| FormatError::InvalidValue("face"))?;
+    let bvh = BoundingVolumeHierarchy::from(&editable_mesh);
+
+    Ok(EditableMeshBundle {
+        editable_mesh,
+        bvh,
+        mesh: meshes.add(data.render_mesh()),
+        ..default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{
+        exported_meshes, gltf::UpAxis, open_meshes, weld, FileUnit, FormatError, MeshExportOptions,
+    };
+    use crate::core::{editable_mesh::EditableMesh, editor::UserSpace};
+
+    #[test]
+    fn test_weld_connects_faces_and_drops_degenerate_ones() {
+        // Two triangles of a square, given as separate corners, and one collapsed triangle
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::Y,
+            Vec3::X,
+        ];
+        let faces = [vec![0, 1, 2], vec![3, 4, 5], vec![1, 6, 2]];
+
+        let data = weld(&positions, None, &faces).unwrap();
+        assert_eq!(data.positions.len(), 4);
+        assert_eq!(data.faces, vec![vec![0, 1, 2], vec![0, 2, 3]]);
+        assert!(data.normals.iter().all(|normal| *normal == Vec3::Z));
+        assert!(data.colors.is_none());
+
+        assert_eq!(
+            weld(&positions, None, &[vec![0, 1, 7]]).err(),
+            Some(FormatError::InvalidValue("face"))
+        );
+        assert_eq!(
+            weld(&positions, None, &[vec![1, 6, 2]]).err(),
+            Some(FormatError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_weld_flips_mis_wound_faces() {
+        let positions = [
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::Y,
+            Vec3::new(1.0, 0.0, 1.0),
+        ];
+        // The second triangle of the square is wound clockwise, and the third would give the
+        // diagonal a third face
+        let faces = [vec![0, 1, 2], vec![0, 3, 2], vec![0, 2, 4]];
+
+        let data = weld(&positions, None, &faces).unwrap();
+        assert_eq!(data.faces, vec![vec![0, 1, 2], vec![2, 3, 0]]);
+        assert!(data.normals.iter().all(|normal| *normal == Vec3::Z));
+        assert!(data.editable_mesh().is_ok());
+    }
+
+    #[test]
+    fn test_export_applies_units_and_up_axis() {
+        let mut world = World::new();
+        world.insert_resource(Assets::<Mesh>::default());
+
+        let closed: EditableMesh = (&Cuboid::new(1.0, 2.0, 3.0).mesh()).into();
+        world.spawn((
+            Name::new("Closed"),
+            closed,
+            GlobalTransform::from_translation(Vec3::new(0.0, 1.0, 0.0)),
+            InheritedVisibility::VISIBLE,
+            UserSpace,
+        ));
+        let open: EditableMesh = (&Plane3d::default().mesh().size(1.0, 1.0).build()).into();
+        world.spawn((
+            Name::new("Open"),
+            open,
+            GlobalTransform::IDENTITY,
+            InheritedVisibility::HIDDEN,
+            UserSpace,
+        ));
+
+        let meshes = exported_meshes(
+            &mut world,
+            &MeshExportOptions {
+                unit: FileUnit::Millimeters,
+                up_axis: UpAxis::Z,
+                ..default()
+            },
+        );
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0].name, "Closed");
+
+        let (min, max) = meshes[0]
+            .data
+            .positions
+            .iter()
+            .fold((Vec3::MAX, Vec3::MIN), |(min, max), position| {
+                (min.min(*position), max.max(*position))
+            });
+        assert!(min.abs_diff_eq(Vec3::new(-500.0, -1500.0, 0.0), 1e-2));
+        assert!(max.abs_diff_eq(Vec3::new(500.0, 1500.0, 2000.0), 1e-2));
+
+        // Hidden meshes are not exported, so not checked either
+        assert!(open_meshes(&mut world, false).is_empty());
+        let mut q_visibility = world.query::<&mut InheritedVisibility>();
+        for mut visibility in q_visibility.iter_mut(&mut world) {
+            *visibility = InheritedVisibility::VISIBLE;
+        }
+        assert_eq!(open_meshes(&mut world, false), vec!["Open".to_string()]);
+    }
+}
@@ -0,0 +1,539 @@
+use std::str::SplitAsciiWhitespace;
+
+use bevy::prelude::*;
+
+use crate::core::project::MeshData;
+
+use super::{
+    exported_meshes, weld, Encoding, ExportedMesh, FormatError, MeshExportOptions,
+    MeshImportOptions,
+};
+
+/// Number types of the properties
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(String, Scalar),
+    /// Count type, then item type
+    List(String, Scalar, Scalar),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Values after the header, read in the order the header declares them
+enum Body<'a> {
+    Ascii(SplitAsciiWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+/// Faces listing more corners do not fit the `uchar` count and are written as triangles
+const MAX_CORNERS: usize = u8::MAX as usize;
+
+const COLOR_PROPERTIES: [&str; 4] = ["red", "green", "blue", "alpha"];
+
+impl Scalar {
+    fn from_name(name: &str) -> Result<Self, FormatError> {
+        match name {
+            "char" | "int8" => Ok(Scalar::I8),
+            "uchar" | "uint8" => Ok(Scalar::U8),
+            "short" | "int16" => Ok(Scalar::I16),
+            "ushort" | "uint16" => Ok(Scalar::U16),
+            "int" | "int32" => Ok(Scalar::I32),
+            "uint" | "uint32" => Ok(Scalar::U32),
+            "float" | "float32" => Ok(Scalar::F32),
+            "double" | "float64" => Ok(Scalar::F64),
+            _ => Err(FormatError::InvalidValue("property type")),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// Full intensity of a colour channel of this type
+    fn full_intensity(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List(name, _, _) => name,
+        }
+    }
+}
+
+impl<'a> Body<'a> {
+    fn value(&mut self, scalar: Scalar) -> Result<f64, FormatError> {
+        let (bytes, big_endian) = match self {
+            Body::Ascii(tokens) => {
+                return tokens
+                    .next()
+                    .ok_or(FormatError::UnexpectedEnd)?
+                    .parse()
+                    .map_err(|_| FormatError::InvalidValue("value"));
+            }
+            Body::Binary { bytes, big_endian } => (bytes, *big_endian),
+        };
+
+        let remaining: &'a [u8] = *bytes;
+        let (value, rest) = remaining
+            .split_at_checked(scalar.size())
+            .ok_or(FormatError::UnexpectedEnd)?;
+        *bytes = rest;
+
+        let mut le = [0; 8];
+        le[..value.len()].copy_from_slice(value);
+        if big_endian {
+            le[..value.len()].reverse();
+        }
+        let [a, b, c, d, ..] = le;
+
+        Ok(match scalar {
+            Scalar::I8 => a as i8 as f64,
+            Scalar::U8 => a as f64,
+            Scalar::I16 => i16::from_le_bytes([a, b]) as f64,
+            Scalar::U16 => u16::from_le_bytes([a, b]) as f64,
+            Scalar::I32 => i32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::U32 => u32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F32 => f32::from_le_bytes([a, b, c, d]) as f64,
+            Scalar::F64 => f64::from_le_bytes(le),
+        })
+    }
+}
+
+/// Welded mesh of the `vertex` and `face` elements of an ASCII or binary PLY file, with the
+/// vertex colours if it has some. Other elements are skipped.
+pub fn read(bytes: &[u8], options: &MeshImportOptions) -> Result<MeshData, FormatError> {
+    let (format, elements, body) = read_header(bytes)?;
+    let mut body = match format {
+        Format::Ascii => Body::Ascii(
+            std::str::from_utf8(body)
+                .map_err(|_| FormatError::InvalidValue("text"))?
+                .split_ascii_whitespace(),
+        ),
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => Body::Binary {
+            bytes: body,
+            big_endian: format == Format::BinaryBigEndian,
+        },
+    };
+
+    let mut positions: Vec<Vec3> = vec![];
+    let mut colors: Option<Vec<[f32; 4]>> = None;
+    let mut faces: Vec<Vec<u32>> = vec![];
+
+    for element in &elements {
+        let is_vertex = element.name == "vertex";
+        let is_face = element.name == "face";
+
+        if is_vertex {
+            for axis in ["x", "y", "z"] {
+                if !element
+                    .properties
+                    .iter()
+                    .any(|property| property.name() == axis)
+                {
+                    return Err(FormatError::InvalidValue("vertex"));
+                }
+            }
+            if element
+                .properties
+                .iter()
+                .any(|property| COLOR_PROPERTIES.contains(&property.name()))
+            {
+                colors = Some(vec![]);
+            }
+        }
+        // Nothing to skip either
+        if element.properties.is_empty() {
+            continue;
+        }
+
+        for _ in 0..element.count {
+            let mut position = Vec3::ZERO;
+            let mut color = [1.0; 4];
+
+            for property in &element.properties {
+                match property {
+                    Property::Scalar(name, scalar) => {
+                        let value = body.value(*scalar)?;
+                        if !is_vertex {
+                            continue;
+                        }
+                        match name.as_str() {
+                            "x" => position.x = value as f32,
+                            "y" => position.y = value as f32,
+                            "z" => position.z = value as f32,
+                            name => {
+                                if let Some(channel) =
+                                    COLOR_PROPERTIES.iter().position(|channel| *channel == name)
+                                {
+                                    color[channel] = (value / scalar.full_intensity()) as f32;
+                                }
+                            }
+                        }
+                    }
+                    Property::List(name, count, item) => {
+                        let count = body.value(*count)?;
+                        if count < 0.0 || count.fract() != 0.0 {
+                            return Err(FormatError::InvalidValue("list"));
+                        }
+
+                        let mut corners = vec![];
+                        for _ in 0..count as usize {
+                            let value = body.value(*item)?;
+                            if is_face && (name == "vertex_indices" || name == "vertex_index") {
+                                if value < 0.0 || value.fract() != 0.0 || value > u32::MAX as f64 {
+                                    return Err(FormatError::InvalidValue("face"));
+                                }
+                                corners.push(value as u32);
+                            }
+                        }
+                        if !corners.is_empty() {
+                            faces.push(corners);
+                        }
+                    }
+                }
+            }
+
+            if is_vertex {
+                positions.push(options.to_world(position));
+                if let Some(colors) = &mut colors {
+                    let [r, g, b, a] = color;
+                    colors.push(Color::rgba(r, g, b, a).as_linear_rgba_f32());
+                }
+            }
+        }
+    }
+
+    weld(&positions, colors.as_deref(), &faces)
+}
+
+/// Format, elements and the bytes after the header
+fn read_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, &[u8]), FormatError> {
+    const END: &[u8] = b"end_header";
+
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or(FormatError::InvalidValue("header"))?;
+    let body_start = bytes[end..]
+        .iter()
+        .position(|byte| *byte == b'\n')
+        .map_or(bytes.len(), |newline| end + newline + 1);
+    let header =
+        std::str::from_utf8(&bytes[..end]).map_err(|_| FormatError::InvalidValue("header"))?;
+
+    let mut lines = header.lines().map(str::split_ascii_whitespace);
+    if lines.next().and_then(|mut words| words.next()) != Some("ply") {
+        return Err(FormatError::InvalidValue("header"));
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = vec![];
+    for mut words in lines {
+        let invalid = FormatError::InvalidValue("header");
+
+        match words.next() {
+            Some("format") => {
+                format = Some(match words.next() {
+                    Some("ascii") => Format::Ascii,
+                    Some("binary_little_endian") => Format::BinaryLittleEndian,
+                    Some("binary_big_endian") => Format::BinaryBigEndian,
+                    _ => return Err(FormatError::Unsupported("PLY format")),
+                });
+            }
+            Some("element") => {
+                let name = words.next().ok_or(invalid)?.to_string();
+                let count = words
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .ok_or(invalid)?;
+                elements.push(Element {
+                    name,
+                    count,
+                    properties: vec![],
+                });
+            }
+            Some("property") => {
+                let element = elements.last_mut().ok_or(invalid)?;
+                let property = match words.next().ok_or(invalid)? {
+                    "list" => {
+                        let count = Scalar::from_name(words.next().ok_or(invalid)?)?;
+                        let item = Scalar::from_name(words.next().ok_or(invalid)?)?;
+                        Property::List(words.next().ok_or(invalid)?.to_string(), count, item)
+                    }
+                    scalar => Property::Scalar(
+                        words.next().ok_or(invalid)?.to_string(),
+                        Scalar::from_name(scalar)?,
+                    ),
+                };
+                element.properties.push(property);
+            }
+            // Comments, `obj_info` and blank lines
+            _ => {}
+        }
+    }
+
+    let format = format.ok_or(FormatError::InvalidValue("header"))?;
+    Ok((format, elements, &bytes[body_start..]))
+}
+
+/// Writes the visible or selected meshes into one PLY file
+pub fn export(world: &mut World, options: &MeshExportOptions) -> Vec<u8> {
+    write(&exported_meshes(world, options), options.encoding)
+}
+
+/// Polygons with positions and normals, and sRGB vertex colours if any mesh has colours. The
+/// meshes are merged, PLY has no objects.
+pub fn write(meshes: &[ExportedMesh], encoding: Encoding) -> Vec<u8> {
+    let has_colors = meshes.iter().any(|mesh| mesh.data.colors.is_some());
+
+    let mut offset = 0;
+    let mut faces: Vec<Vec<u32>> = vec![];
+    for mesh in meshes {
+        for corners in &mesh.data.faces {
+            let corners: Vec<u32> = corners.iter().map(|corner| corner + offset).collect();
+            if corners.len() <= MAX_CORNERS {
+                faces.push(corners);
+            } else {
+                faces.extend(
+                    (1..corners.len() - 1).map(|i| vec![corners[0], corners[i], corners[i + 1]]),
+                );
+            }
+        }
+        offset += mesh.data.positions.len() as u32;
+    }
+
+    let vertices = meshes.iter().flat_map(|mesh| {
+        (0..mesh.data.positions.len()).map(move |vertex| {
+            let color = mesh
+                .data
+                .colors
+                .as_ref()
+                .and_then(|colors| colors.get(vertex))
+                .map_or([u8::MAX; 4], |[r, g, b, a]| {
+                    Color::rgba_linear(*r, *g, *b, *a)
+                        .as_rgba_f32()
+                        .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+                });
+            (
+                mesh.data.positions[vertex],
+                mesh.data.normals[vertex],
+                color,
+            )
+        })
+    });
+
+    let mut header = format!(
+        "ply\nformat {} 1.0\ncomment exported by meshup\nelement vertex {offset}\n",
+        match encoding {
+            Encoding::Binary => "binary_little_endian",
+            Encoding::Ascii => "ascii",
+        }
+    );
+    for name in ["x", "y", "z", "nx", "ny", "nz"] {
+        header.push_str(&format!("property float {name}\n"));
+    }
+    if has_colors {
+        for name in COLOR_PROPERTIES {
+            header.push_str(&format!("property uchar {name}\n"));
+        }
+    }
+    header.push_str(&format!(
+        "element face {}\nproperty list uchar uint vertex_indices\nend_header\n",
+        faces.len()
+    ));
+
+    let mut bytes = header.into_bytes();
+    match encoding {
+        Encoding::Binary => {
+            for (position, normal, color) in vertices {
+                for value in position.to_array().into_iter().chain(normal.to_array()) {
+                    bytes.extend_from_slice(&value.to_le_bytes());
+                }
+                if has_colors {
+                    bytes.extend_from_slice(&color);
+                }
+            }
+            for corners in &faces {
+                bytes.push(corners.len() as u8);
+                for corner in corners {
+                    bytes.extend_from_slice(&corner.to_le_bytes());
+                }
+            }
+        }
+        Encoding::Ascii => {
+            let mut text = String::new();
+            for (position, normal, [r, g, b, a]) in vertices {
+                let [x, y, z] = position.to_array();
+                let [nx, ny, nz] = normal.to_array();
+                text.push_str(&format!("{x} {y} {z} {nx} {ny} {nz}"));
+                if has_colors {
+                    text.push_str(&format!(" {r} {g} {b} {a}"));
+                }
+                text.push('\n');
+            }
+            for corners in &faces {
+                text.push_str(&corners.len().to_string());
+                for corner in corners {
+                    text.push_str(&format!(" {corner}"));
+                }
+                text.push('\n');
+            }
+            bytes.extend_from_slice(text.as_bytes());
+        }
+    }
+    bytes
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{read, write};
+    use crate::core::formats::{
+        weld, Encoding, ExportedMesh, FileUnit, FormatError, MeshImportOptions,
+    };
+
+    /// Unit cube of quads, coloured by the position of the corners
+    fn colored_cube() -> Vec<ExportedMesh> {
+        let positions: Vec<Vec3> = (0..8)
+            .map(|i| Vec3::new((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32))
+            .collect();
+        let colors: Vec<[f32; 4]> = positions
+            .iter()
+            .map(|position| position.extend(1.0).to_array())
+            .collect();
+        let faces = [
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+        ];
+
+        vec![ExportedMesh {
+            name: "Cube".to_string(),
+            data: weld(&positions, Some(&colors), &faces).unwrap(),
+        }]
+    }
+
+    fn meters() -> MeshImportOptions {
+        MeshImportOptions {
+            unit: FileUnit::Meters,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_keeps_polygons_and_colors() {
+        let cube = colored_cube();
+
+        for encoding in [Encoding::Binary, Encoding::Ascii] {
+            let data = read(&write(&cube, encoding), &meters()).unwrap();
+
+            assert_eq!(data.positions, cube[0].data.positions);
+            assert_eq!(data.faces, cube[0].data.faces);
+            assert!(data.editable_mesh().unwrap().is_closed());
+
+            // Stored as 8 bit sRGB
+            let colors = data.colors.unwrap();
+            for (color, expected) in colors.iter().zip(cube[0].data.colors.as_ref().unwrap()) {
+                assert!(Vec4::from(*color).abs_diff_eq(Vec4::from(*expected), 0.01));
+            }
+        }
+    }
+
+    #[test]
+    fn test_big_endian_with_other_types_and_elements() {
+        let mut bytes = b"ply\r\n\
+            format binary_big_endian 1.0\r\n\
+            comment written by hand\r\n\
+            element vertex 3\r\n\
+            property double x\r\n\
+            property double y\r\n\
+            property double z\r\n\
+            element face 1\r\n\
+            property list uchar int vertex_index\r\n\
+            property ushort flags\r\n\
+            element edge 1\r\n\
+            property int vertex1\r\n\
+            property int vertex2\r\n\
+            end_header\r\n"
+            .to_vec();
+        for position in [[0.0f64, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 0.0, -2.0]] {
+            for coordinate in position {
+                bytes.extend_from_slice(&coordinate.to_be_bytes());
+            }
+        }
+        bytes.push(3);
+        for corner in [0i32, 1, 2] {
+            bytes.extend_from_slice(&corner.to_be_bytes());
+        }
+        bytes.extend_from_slice(&7u16.to_be_bytes());
+        for vertex in [0i32, 1] {
+            bytes.extend_from_slice(&vertex.to_be_bytes());
+        }
+
+        let data = read(&bytes, &meters()).unwrap();
+        assert_eq!(
+            data.positions,
+            vec![
+                Vec3::ZERO,
+                Vec3::new(2.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, -2.0)
+            ]
+        );
+        assert_eq!(data.faces, vec![vec![0, 1, 2]]);
+        assert_eq!(data.face_normals, vec![Vec3::Y]);
+
+        bytes.truncate(bytes.len() - 1);
+        assert_eq!(
+            read(&bytes, &meters()).err(),
+            Some(FormatError::UnexpectedEnd)
+        );
+        assert_eq!(
+            read(b"solid cube\nendsolid cube\n", &meters()).err(),
+            Some(FormatError::InvalidValue("header"))
+        );
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::prelude::*;
+
+use crate::core::project::MeshData;
+
+use super::{
+    exported_meshes, weld, Encoding, ExportedMesh, FormatError, MeshExportOptions,
+    MeshImportOptions,
+};
+
+/// Binary files start with a header nobody reads, then the number of triangles
+const HEADER_SIZE: usize = 80;
+/// Normal, three corners and the unused attribute byte count
+const TRIANGLE_SIZE: usize = 50;
+
+/// Welded mesh of the triangles of a binary or ASCII STL file
+pub fn read(bytes: &[u8], options: &MeshImportOptions) -> Result<MeshData, FormatError> {
+    let triangles = match is_ascii(bytes) {
+        true => read_ascii(bytes)?,
+        false => read_binary(bytes)?,
+    };
+
+    let positions: Vec<Vec3> = triangles
+        .iter()
+        .flatten()
+        .map(|position| options.to_world(*position))
+        .collect();
+    let faces: Vec<Vec<u32>> = (0..triangles.len() as u32)
+        .map(|triangle| vec![triangle * 3, triangle * 3 + 1, triangle * 3 + 2])
+        .collect();
+
+    weld(&positions, None, &faces)
+}
+
+/// Writes the visible or selected meshes into one STL file
+pub fn export(world: &mut World, options: &MeshExportOptions) -> Vec<u8> {
+    write(&exported_meshes(world, options), options.encoding)
+}
+
+/// Triangulated meshes, one solid per mesh in ASCII files. The binary format has no names.
+pub fn write(meshes: &[ExportedMesh], encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Binary => write_binary(meshes),
+        Encoding::Ascii => write_ascii(meshes).into_bytes(),
+    }
+}
+
+fn triangle_count(bytes: &[u8]) -> Option<usize> {
+    let count = bytes.get(HEADER_SIZE..HEADER_SIZE + 4)?;
+    Some(u32::from_le_bytes([count[0], count[1], count[2], count[3]]) as usize)
+}
+
+/// Binary headers may start with `solid` too, so the size decides
+fn is_ascii(bytes: &[u8]) -> bool {
+    let binary_size =
+        triangle_count(bytes).map(|count| HEADER_SIZE + 4 + count.saturating_mul(TRIANGLE_SIZE));
+
+    bytes.starts_with(b"solid") && binary_size != Some(bytes.len())
+}
+
+fn read_binary(bytes: &[u8]) -> Result<Vec<[Vec3; 3]>, FormatError> {
+    let count = triangle_count(bytes).ok_or(FormatError::UnexpectedEnd)?;
+    let records = bytes[HEADER_SIZE + 4..].chunks_exact(TRIANGLE_SIZE);
+    if records.len() < count {
+        return Err(FormatError::UnexpectedEnd);
+    }
+
+    let f32_at = |record: &[u8], offset: usize| {
+        f32::from_le_bytes([
+            record[offset],
+            record[offset + 1],
+            record[offset + 2],
+            record[offset + 3],
+        ])
+    };
+    // The normal comes first, it is recomputed from the winding
+    let corner = |record: &[u8], corner: usize| {
+        let offset = 12 + corner * 12;
+        Vec3::new(
+            f32_at(record, offset),
+            f32_at(record, offset + 4),
+            f32_at(record, offset + 8),
+        )
+    };
+
+    Ok(records
+        .take(count)
+        .map(|record| [corner(record, 0), corner(record, 1), corner(record, 2)])
+        .collect())
+}
+
+fn read_ascii(bytes: &[u8]) -> Result<Vec<[Vec3; 3]>, FormatError> {
+    let text = std::str::from_utf8(bytes).map_err(|_| FormatError::InvalidValue("text"))?;
+    let mut tokens = text.split_ascii_whitespace();
+
+    let mut triangles = vec![];
+    let mut corners = vec![];
+    while let Some(token) = tokens.next() {
+        match token {
+            "vertex" => {
+                let mut coordinate = || {
+                    tokens
+                        .next()
+                        .ok_or(FormatError::UnexpectedEnd)?
+                        .parse::<f32>()
+                        .map_err(|_| FormatError::InvalidValue("vertex"))
+                };
+                corners.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            "endloop" => {
+                let triangle: [Vec3; 3] = std::mem::take(&mut corners)
+                    .try_into()
+                    .map_err(|_| FormatError::Unsupported("facet that is not a triangle"))?;
+                triangles.push(triangle);
+            }
+            _ => {}
+        }
+    }
+
+    if !corners.is_empty() {
+        return Err(FormatError::UnexpectedEnd);
+    }
+    Ok(triangles)
+}
+
+fn triangles(data: &MeshData) -> Vec<[Vec3; 3]> {
+    data.triangles()
+        .chunks_exact(3)
+        .map(|corners| [0, 1, 2].map(|i| data.positions[corners[i] as usize]))
+        .collect()
+}
+
+/// Normal from the counter-clockwise winding, zero for degenerate triangles
+fn normal([a, b, c]: &[Vec3; 3]) -> Vec3 {
+    (*b - *a).cross(*c - *a).normalize_or_zero()
+}
+
+fn write_binary(meshes: &[ExportedMesh]) -> Vec<u8> {
+    let triangles: Vec<[Vec3; 3]> = meshes
+        .iter()
+        .flat_map(|mesh| triangles(&mesh.data))
+        .collect();
+
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + 4 + triangles.len() * TRIANGLE_SIZE);
+    bytes.extend_from_slice(b"meshup binary STL");
+    bytes.resize(HEADER_SIZE, 0);
+    bytes.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+
+    for triangle in &triangles {
+        for vector in [normal(triangle), triangle[0], triangle[1], triangle[2]] {
+            for coordinate in vector.to_array() {
+                bytes.extend_from_slice(&coordinate.to_le_bytes());
+            }
+        }
+        bytes.extend_from_slice(&0u16.to_le_bytes());
+    }
+    bytes
+}
+
+fn write_ascii(meshes: &[ExportedMesh]) -> String {
+    let mut text = String::new();
+
+    for mesh in meshes {
+        // The name ends at the first whitespace
+        let name = mesh.name.replace(char::is_whitespace, "_");
+        text.push_str(&format!("solid {name}\n"));
+
+        for triangle in triangles(&mesh.data) {
+            let [x, y, z] = normal(&triangle).to_array();
+            text.push_str(&format!("facet normal {x} {y} {z}\n"));
+            text.push_str("  outer loop\n");
+            for [x, y, z] in triangle.map(|corner| corner.to_array()) {
+                text.push_str(&format!("    vertex {x} {y} {z}\n"));
+            }
+            text.push_str("  endloop\n");
+            text.push_str("endfacet\n");
+        }
+
+        text.push_str(&format!("endsolid {name}\n"));
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{read, write};
+    use crate::core::{
+        editable_mesh::EditableMesh,
+        formats::{Encoding, ExportedMesh, FileUnit, FormatError, MeshImportOptions},
+        project::MeshData,
+    };
+
+    fn cuboid() -> Vec<ExportedMesh> {
+        let mesh: EditableMesh = (&Cuboid::new(1.0, 2.0, 3.0).mesh()).into();
+
+        vec![ExportedMesh {
+            name: "Cuboid 1".to_string(),
+            data: MeshData::new(&mesh, None),
+        }]
+    }
+
+    fn meters() -> MeshImportOptions {
+        MeshImportOptions {
+            unit: FileUnit::Meters,
+            ..default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_welds_a_closed_mesh() {
+        for encoding in [Encoding::Binary, Encoding::Ascii] {
+            let bytes = write(&cuboid(), encoding);
+            let data = read(&bytes, &meters()).unwrap();
+
+            // The 24 vertices split along the edges of the cuboid are welded into its corners
+            assert_eq!(data.positions.len(), 8);
+            assert_eq!(data.faces.len(), 12);
+            assert!(data
+                .positions
+                .iter()
+                .all(|position| position.abs() == Vec3::new(0.5, 1.0, 1.5)));
+
+            let mesh = data.editable_mesh().unwrap();
+            assert!(mesh.is_closed());
+            let volume = mesh.volume(&GlobalTransform::IDENTITY).unwrap();
+            assert!((volume - 6.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_binary_files_are_told_apart_by_their_size() {
+        let mut bytes = write(&cuboid(), Encoding::Binary);
+        bytes.truncate(bytes.len() - 1);
+        assert_eq!(
+            read(&bytes, &meters()).err(),
+            Some(FormatError::UnexpectedEnd)
+        );
+
+        // Headers starting like ASCII files
+        let mut bytes = write(&cuboid(), Encoding::Binary);
+        bytes[..5].copy_from_slice(b"solid");
+        assert_eq!(read(&bytes, &meters()).unwrap().faces.len(), 12);
+    }
+
+    #[test]
+    fn test_millimeter_files_are_scaled_down() {
+        let bytes = write(&cuboid(), Encoding::Ascii);
+        let data = read(
+            &bytes,
+            &MeshImportOptions {
+                unit: FileUnit::Millimeters,
+                ..default()
+            },
+        )
+        .unwrap();
+
+        assert!(data.positions.iter().all(|position| position
+            .abs()
+            .abs_diff_eq(Vec3::new(0.0005, 0.001, 0.0015), 1e-9)));
+    }
+}
@@ -2,6 +2,9 @@ use bevy::{
     diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
 };
+
+use super::keymap::{EditorAction, EditorActionEvent};
+
 pub struct FpsPlugin;
 
 impl Plugin for FpsPlugin {
@@ -130,12 +133,15 @@ impl FpsPlugin {
         }
     }
 
-    /// Toggle the FPS counter when pressing F12
+    /// Toggle the FPS counter on [`EditorAction::ToggleFps`] (F12 by default)
     fn fps_counter_showhide(
         mut q: Query<&mut Visibility, With<FpsRoot>>,
-        kbd: Res<ButtonInput<KeyCode>>,
+        mut actions: EventReader<EditorActionEvent>,
     ) {
-        if kbd.just_pressed(KeyCode::F12) {
+        for _ in actions
+            .read()
+            .filter(|action| action.0 == EditorAction::ToggleFps)
+        {
             let mut vis = q.single_mut();
             *vis = match *vis {
                 Visibility::Hidden => Visibility::Visible,
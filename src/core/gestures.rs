@@ -0,0 +1,325 @@
+use bevy::{input::InputSystem, prelude::*, window::PrimaryWindow};
+
+/// Unifies mouse and touch input: a [`Pointer`] for clicking and dragging handles, and
+/// [`TouchNavigation`] recognised from one and two finger gestures for the camera
+pub struct GesturePlugin;
+
+/// Distance in logical pixels a press may move and still count as a tap
+pub const TAP_SLOP: f32 = 8.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PointerSource {
+    #[default]
+    Mouse,
+    Touch(u64),
+}
+
+/// The primary pointer: the mouse with its left button, or a single finger on a touch screen
+#[derive(Resource, Clone, Debug, Default)]
+pub struct Pointer {
+    pub source: PointerSource,
+    /// Logical window position, if the pointer is over the window
+    pub position: Option<Vec2>,
+    pub pressed: bool,
+    pub just_pressed: bool,
+    pub just_released: bool,
+    /// Released this frame without moving further than [`TAP_SLOP`]
+    pub tapped: bool,
+    /// Set by tools when the press grabbed a handle, so the drag does not also move the camera.
+    /// Cleared on the next press.
+    pub captured: bool,
+    /// Set by the active tool while the pointer is over one of its handles, so pressing there
+    /// grabs the handle instead of selecting what is behind it
+    pub over_handle: bool,
+    press_position: Option<Vec2>,
+    dragged: bool,
+    /// A touch press turned into a multi-finger gesture, ignored until every finger is lifted
+    cancelled: bool,
+}
+
+/// Camera motion recognised from the touches of this frame
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct TouchNavigation {
+    /// Logical pixels moved by a single finger
+    pub orbit: Vec2,
+    /// Logical pixels moved by the midpoint of two fingers
+    pub pan: Vec2,
+    /// Log of how much two fingers spread apart, positive when zooming in like `TouchpadMagnify`
+    pub pinch: f32,
+}
+
+/// Turns touch positions into [`TouchNavigation`]. Only works on positions, so it can be fed by
+/// hand without a window.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct TouchGestureRecognizer {
+    /// Touches of the previous frame, sorted by id
+    previous: Vec<(u64, Vec2)>,
+    /// Where the single finger went down
+    start: Option<Vec2>,
+    /// The single finger moved past [`TAP_SLOP`] and orbits
+    dragging: bool,
+}
+
+impl Pointer {
+    /// Advances the pointer by a frame
+    pub fn update(&mut self, source: PointerSource, position: Option<Vec2>, pressed: bool) {
+        let was_pressed = self.pressed;
+
+        self.just_pressed = pressed && !was_pressed;
+        self.just_released = !pressed && was_pressed;
+
+        if self.just_pressed {
+            self.press_position = position;
+            self.dragged = false;
+            self.captured = false;
+        }
+
+        if pressed {
+            if let (Some(start), Some(position)) = (self.press_position, position) {
+                self.dragged |= start.distance(position) > TAP_SLOP;
+            }
+        }
+
+        self.tapped = self.just_released && !self.dragged;
+        self.source = source;
+        self.position = position;
+        self.pressed = pressed;
+    }
+
+    /// Releases the pointer without a tap
+    pub fn cancel(&mut self) {
+        self.just_pressed = false;
+        self.just_released = self.pressed;
+        self.tapped = false;
+        self.pressed = false;
+        self.dragged = true;
+        self.cancelled = true;
+    }
+
+    /// Takes the current press, e.g. a click confirming a modal operation, so that neither
+    /// selection nor the tools react to it
+    pub fn consume(&mut self) {
+        self.just_pressed = false;
+        self.tapped = false;
+        self.dragged = true;
+        self.captured = true;
+    }
+
+    /// Whether this frame should select what is under the pointer. Mouse presses select right
+    /// away, while on touch screens dragging navigates so only taps select. Never over a handle.
+    pub fn select_triggered(&self) -> bool {
+        if self.over_handle {
+            return false;
+        }
+
+        match self.source {
+            PointerSource::Mouse => self.just_pressed,
+            PointerSource::Touch(_) => self.tapped,
+        }
+    }
+}
+
+impl TouchGestureRecognizer {
+    /// Recognises the gesture between the previous touches and `touches`. When fingers are added
+    /// or lifted, the gesture restarts from the new positions so the camera does not jump.
+    pub fn update(&mut self, touches: &[(u64, Vec2)]) -> TouchNavigation {
+        let mut touches = touches.to_vec();
+        touches.sort_by_key(|(id, _)| *id);
+
+        let same_fingers = touches.len() == self.previous.len()
+            && touches
+                .iter()
+                .zip(&self.previous)
+                .all(|((id, _), (previous_id, _))| id == previous_id);
+
+        let mut navigation = TouchNavigation::default();
+
+        if !same_fingers {
+            self.start = match touches.as_slice() {
+                [(_, position)] => Some(*position),
+                _ => None,
+            };
+            self.dragging = false;
+        } else {
+            match (touches.as_slice(), self.previous.as_slice()) {
+                ([(_, position)], [(_, previous)]) => {
+                    if let Some(start) = self.start {
+                        self.dragging |= start.distance(*position) > TAP_SLOP;
+                    }
+                    if self.dragging {
+                        navigation.orbit = *position - *previous;
+                    }
+                }
+                ([(_, a), (_, b)], [(_, previous_a), (_, previous_b)]) => {
+                    navigation.pan = (*a + *b) / 2.0 - (*previous_a + *previous_b) / 2.0;
+
+                    let spread = a.distance(*b);
+                    let previous_spread = previous_a.distance(*previous_b);
+                    if spread > f32::EPSILON && previous_spread > f32::EPSILON {
+                        navigation.pinch = (spread / previous_spread).ln();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.previous = touches;
+
+        navigation
+    }
+}
+
+impl Plugin for GesturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pointer>()
+            .init_resource::<TouchNavigation>()
+            .init_resource::<TouchGestureRecognizer>()
+            .add_systems(
+                PreUpdate,
+                (Self::update_pointer, Self::recognize_touch_gestures).after(InputSystem),
+            );
+    }
+}
+
+impl GesturePlugin {
+    fn update_pointer(
+        mut pointer: ResMut<Pointer>,
+        mouse: Res<ButtonInput<MouseButton>>,
+        touches: Res<Touches>,
+        window: Query<&Window, With<PrimaryWindow>>,
+    ) {
+        let mut pressed_touches = touches.iter();
+
+        match (pressed_touches.next(), pressed_touches.next()) {
+            // Several fingers navigate
+            (Some(_), Some(_)) => pointer.cancel(),
+            (Some(_), None) if pointer.cancelled => pointer.cancel(),
+            (Some(touch), None) => {
+                pointer.update(
+                    PointerSource::Touch(touch.id()),
+                    Some(touch.position()),
+                    true,
+                );
+            }
+            (None, _) => {
+                pointer.cancelled = false;
+
+                match pointer.source {
+                    // The finger was just lifted
+                    PointerSource::Touch(id) if pointer.pressed => {
+                        let position = touches
+                            .get_released(id)
+                            .map(|touch| touch.position())
+                            .or(pointer.position);
+                        pointer.update(PointerSource::Touch(id), position, false);
+                    }
+                    _ => {
+                        let position = window
+                            .get_single()
+                            .ok()
+                            .and_then(|window| window.cursor_position());
+                        pointer.update(
+                            PointerSource::Mouse,
+                            position,
+                            mouse.pressed(MouseButton::Left),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn recognize_touch_gestures(
+        mut recognizer: ResMut<TouchGestureRecognizer>,
+        mut navigation: ResMut<TouchNavigation>,
+        touches: Res<Touches>,
+    ) {
+        let touches: Vec<(u64, Vec2)> = touches
+            .iter()
+            .map(|touch| (touch.id(), touch.position()))
+            .collect();
+
+        *navigation = recognizer.update(&touches);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{Pointer, PointerSource, TouchGestureRecognizer, TouchNavigation, TAP_SLOP};
+
+    #[test]
+    fn test_single_finger_orbits_after_slop() {
+        let mut recognizer = TouchGestureRecognizer::default();
+
+        assert_eq!(
+            recognizer.update(&[(0, Vec2::ZERO)]),
+            TouchNavigation::default()
+        );
+        // Small jitter is not a drag
+        let jitter = recognizer.update(&[(0, Vec2::new(TAP_SLOP / 2.0, 0.0))]);
+        assert_eq!(jitter.orbit, Vec2::ZERO);
+
+        let drag = recognizer.update(&[(0, Vec2::new(TAP_SLOP * 2.0, 0.0))]);
+        assert_eq!(drag.orbit, Vec2::new(TAP_SLOP * 1.5, 0.0));
+        assert_eq!(drag.pan, Vec2::ZERO);
+    }
+
+    #[test]
+    fn test_two_fingers_pan_and_pinch() {
+        let mut recognizer = TouchGestureRecognizer::default();
+
+        recognizer.update(&[(1, Vec2::new(0.0, 0.0)), (2, Vec2::new(100.0, 0.0))]);
+        // Order of the touches does not matter
+        let navigation =
+            recognizer.update(&[(2, Vec2::new(160.0, 10.0)), (1, Vec2::new(-40.0, 10.0))]);
+
+        assert_eq!(navigation.orbit, Vec2::ZERO);
+        assert!((navigation.pan - Vec2::new(10.0, 10.0)).length() < 1e-4);
+        assert!((navigation.pinch - 2f32.ln()).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_changing_fingers_does_not_jump() {
+        let mut recognizer = TouchGestureRecognizer::default();
+
+        recognizer.update(&[(1, Vec2::ZERO), (2, Vec2::new(100.0, 0.0))]);
+        // Lifting a finger leaves one far from where it went down
+        let lifted = recognizer.update(&[(2, Vec2::new(100.0, 0.0))]);
+        assert_eq!(lifted, TouchNavigation::default());
+
+        let navigation = recognizer.update(&[(2, Vec2::new(102.0, 0.0))]);
+        assert_eq!(navigation, TouchNavigation::default());
+    }
+
+    #[test]
+    fn test_pointer_tap_and_drag() {
+        let mut pointer = Pointer::default();
+        let touch = PointerSource::Touch(0);
+
+        pointer.update(touch, Some(Vec2::ZERO), true);
+        assert!(pointer.just_pressed);
+        assert!(!pointer.select_triggered());
+
+        pointer.update(touch, Some(Vec2::ONE), false);
+        assert!(pointer.tapped);
+        assert!(pointer.select_triggered());
+
+        pointer.update(touch, Some(Vec2::ZERO), true);
+        pointer.update(touch, Some(Vec2::new(TAP_SLOP * 2.0, 0.0)), true);
+        pointer.update(touch, Some(Vec2::new(TAP_SLOP * 2.0, 0.0)), false);
+        assert!(pointer.just_released);
+        assert!(!pointer.tapped);
+
+        // The mouse selects on press
+        pointer.update(PointerSource::Mouse, Some(Vec2::ZERO), true);
+        assert!(pointer.select_triggered());
+
+        // Unless the press grabs a tool handle
+        pointer.update(PointerSource::Mouse, Some(Vec2::ZERO), false);
+        pointer.over_handle = true;
+        pointer.update(PointerSource::Mouse, Some(Vec2::ZERO), true);
+        assert!(!pointer.select_triggered());
+    }
+}
@@ -4,10 +4,7 @@ use bevy::{
         query::QueryItem,
         system::lifetimeless::{Read, SRes},
     },
-    math::{
-        bounding::{Aabb3d, Bounded3d, BoundingVolume},
-        Vec3A,
-    },
+    math::Vec3A,
     pbr::{
         MeshPipeline, MeshPipelineKey, RenderMeshInstances, SetMeshBindGroup, SetMeshViewBindGroup,
     },
@@ -34,8 +31,8 @@ use bevy::{
     },
 };
 use bytemuck::{Pod, Zeroable};
-
-use crate::utils;
+use std::f32::consts::FRAC_PI_2;
+use wasm_bindgen::prelude::*;
 
 use super::pan_orbit_camera::{PanOrbitCameraUpdate, PanOrbitState, PrimaryCamera};
 
@@ -44,6 +41,9 @@ use super::pan_orbit_camera::{PanOrbitCameraUpdate, PanOrbitState, PrimaryCamera
 struct GridInstanceData {
     pub position: Vec3,
     pub scale: f32,
+    /// Distance from the camera at which the lines have faded out
+    pub fade_distance: f32,
+    _padding: Vec3,
 }
 
 #[derive(Component, Deref, DerefMut, Default)]
@@ -70,6 +70,175 @@ pub struct Grid3d;
 
 pub struct GridPlugin;
 
+/// Unit system the grid spacing is given in. World units are meters.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridUnits {
+    #[default]
+    Metric,
+    /// Spacing in feet
+    Imperial,
+}
+
+impl GridUnits {
+    /// World units per unit of the grid spacing
+    pub fn unit_length(self) -> f32 {
+        match self {
+            GridUnits::Metric => 1.0,
+            GridUnits::Imperial => 0.3048,
+        }
+    }
+
+    /// Abbreviation shown after lengths in these units
+    pub fn suffix(self) -> &'static str {
+        match self {
+            GridUnits::Metric => "m",
+            GridUnits::Imperial => "ft",
+        }
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GridAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// Plane the grid is drawn on. Only orthographic views along an axis leave the floor.
+#[wasm_bindgen]
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GridPlane {
+    #[default]
+    XZ,
+    XY,
+    YZ,
+}
+
+impl GridPlane {
+    /// Rotation from the XZ plane the grid is built in
+    pub fn rotation(self) -> Quat {
+        match self {
+            GridPlane::XZ => Quat::IDENTITY,
+            GridPlane::XY => Quat::from_rotation_x(FRAC_PI_2),
+            GridPlane::YZ => Quat::from_rotation_z(-FRAC_PI_2),
+        }
+    }
+
+    /// Plane facing a camera looking along `forward`, if it looks along an axis
+    pub fn facing(forward: Vec3) -> Option<GridPlane> {
+        const ALIGNED: f32 = 0.999;
+
+        let forward = forward.abs();
+        if forward.y > ALIGNED {
+            Some(GridPlane::XZ)
+        } else if forward.z > ALIGNED {
+            Some(GridPlane::XY)
+        } else if forward.x > ALIGNED {
+            Some(GridPlane::YZ)
+        } else {
+            None
+        }
+    }
+
+    /// World axes lying in the plane
+    pub fn axes(self) -> [GridAxis; 2] {
+        match self {
+            GridPlane::XZ => [GridAxis::X, GridAxis::Z],
+            GridPlane::XY => [GridAxis::X, GridAxis::Y],
+            GridPlane::YZ => [GridAxis::Y, GridAxis::Z],
+        }
+    }
+}
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct GridSettings {
+    pub units: GridUnits,
+    /// Distance between the finest lines at the closest level of detail, in `units`
+    pub spacing: f32,
+    /// Cells between two major lines, also the factor between levels of detail
+    pub subdivisions: u32,
+    /// World distance from the camera at which the grid has faded out, at the base spacing. It
+    /// grows with the level of detail.
+    pub fade_distance: f32,
+    pub color: Color,
+    /// Lines along the world axes through the origin
+    pub axis_lines: bool,
+    /// Indexed by [`GridAxis`]
+    pub axis_colors: [Color; 3],
+    /// Coarser lines when zooming out and finer ones when zooming in, so the grid keeps the same
+    /// density on screen
+    pub adaptive: bool,
+    /// Orthographic views along the X or Z axis show the grid on the plane facing the camera
+    pub side_view_grids: bool,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            units: GridUnits::Metric,
+            spacing: 1.0,
+            subdivisions: 10,
+            fade_distance: 100.0,
+            color: Color::rgb(0.35, 0.35, 0.35),
+            axis_lines: true,
+            axis_colors: [
+                Color::rgb_u8(200, 70, 70),
+                Color::rgb_u8(100, 190, 70),
+                Color::rgb_u8(70, 120, 210),
+            ],
+            adaptive: true,
+            side_view_grids: true,
+        }
+    }
+}
+
+impl GridSettings {
+    pub const MAX_SUBDIVISIONS: u32 = 100;
+    const MIN_LEVEL: i32 = -3;
+    const MAX_LEVEL: i32 = 6;
+    /// Finest lines across the distance from the camera to the orbit center, below which the
+    /// next coarser level is used
+    const LINES_PER_DISTANCE: f32 = 20.0;
+
+    /// Distance between the finest lines at the closest level of detail, in world units
+    pub fn base_spacing(&self) -> f32 {
+        self.spacing * self.units.unit_length()
+    }
+
+    /// How many times the base spacing is multiplied by the subdivisions, for a camera
+    /// `distance` away from the orbit center
+    pub fn level(&self, distance: f32) -> i32 {
+        if !self.adaptive || self.subdivisions < 2 || self.base_spacing() <= 0.0 {
+            return 0;
+        }
+
+        let lines = distance / (self.base_spacing() * Self::LINES_PER_DISTANCE);
+        let level = (lines.ln() / (self.subdivisions as f32).ln()).floor();
+        if level.is_nan() {
+            return 0;
+        }
+
+        (level as i32).clamp(Self::MIN_LEVEL, Self::MAX_LEVEL)
+    }
+
+    /// Spacing of the finest visible lines in world units, for a camera `distance` away from
+    /// the orbit center
+    pub fn spacing_at(&self, distance: f32) -> f32 {
+        self.base_spacing() * (self.subdivisions.max(1) as f32).powi(self.level(distance))
+    }
+
+    /// Fade distance scaled along with the spacing, so zooming out does not fade the grid away
+    pub fn fade_distance_at(&self, distance: f32) -> f32 {
+        self.fade_distance * (self.subdivisions.max(1) as f32).powi(self.level(distance))
+    }
+
+    pub fn axis_color(&self, axis: GridAxis) -> Color {
+        self.axis_colors[axis as usize]
+    }
+}
+
 impl ExtractComponent for PrimaryCamera {
     type QueryData = ();
     type QueryFilter = With<Self>;
@@ -84,7 +253,13 @@ impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(ExtractComponentPlugin::<GridInstances>::default())
             .add_plugins(ExtractComponentPlugin::<PrimaryCamera>::default())
-            .add_systems(Update, update_grid.after(PanOrbitCameraUpdate))
+            .init_resource::<GridSettings>()
+            .add_systems(
+                Update,
+                (update_grid_mesh, update_grid, draw_axis_lines)
+                    .chain()
+                    .after(PanOrbitCameraUpdate),
+            )
             .add_systems(Startup, setup_grid)
             .sub_app_mut(RenderApp)
             .add_render_command::<Transparent3d, DrawGrid>()
@@ -181,11 +356,16 @@ fn queue_grid(
     }
 }
 
-fn setup_grid(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+fn setup_grid(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<GridSettings>,
+) {
     commands.spawn((
-        meshes.add(create_grid_base(10, 1.0)),
+        meshes.add(create_grid_base(settings.subdivisions, settings.color)),
         SpatialBundle::default(),
         GridInstances::default(),
+        GridPlane::default(),
         NoFrustumCulling,
         Grid3d,
     ));
@@ -231,153 +411,173 @@ fn line_intersects_plane_at(line: &(Vec3, Vec3), plane: &Plane3d) -> Option<Vec3
     }
 }
 
+/// Tiles drawn at most on each side of the orbit center, for planes seen almost edge-on
+const MAX_TILES_FROM_CENTER: f32 = 32.0;
+/// Opacity of the lines between the major ones, relative to the grid color
+const MINOR_LINE_OPACITY: f32 = 0.5;
+
+fn update_grid_mesh(
+    settings: Res<GridSettings>,
+    grid: Query<&Handle<Mesh>, With<Grid3d>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for handle in grid.iter() {
+        if let Some(mesh) = meshes.get_mut(handle) {
+            *mesh = create_grid_base(settings.subdivisions, settings.color);
+        }
+    }
+}
+
 fn update_grid(
-    mut grid: Query<(&mut GridInstances, &Visibility), With<Grid3d>>,
-    camera: Query<(&Projection, &PanOrbitState, Ref<Transform>), With<PrimaryCamera>>,
+    settings: Res<GridSettings>,
+    mut grid: Query<
+        (
+            &mut GridInstances,
+            &mut Transform,
+            &mut GridPlane,
+            Ref<Visibility>,
+        ),
+        (With<Grid3d>, Without<PrimaryCamera>),
+    >,
+    camera: Query<(Ref<Projection>, &PanOrbitState, Ref<Transform>), With<PrimaryCamera>>,
 ) {
-    let Ok((mut grid, visibility)) = grid.get_single_mut() else {
+    let Ok((mut grid, mut grid_transform, mut grid_plane, visibility)) = grid.get_single_mut()
+    else {
         return;
     };
 
-    if visibility == Visibility::Hidden {
+    if *visibility == Visibility::Hidden {
         return;
     }
 
-    let (projection, orbit_state, camera_transform) = camera.single();
+    let Ok((projection, orbit_state, camera_transform)) = camera.get_single() else {
+        return;
+    };
 
-    if !(camera_transform.is_added() || camera_transform.is_changed()) {
+    if !(camera_transform.is_changed()
+        || projection.is_changed()
+        || visibility.is_changed()
+        || settings.is_changed())
+    {
         return;
     }
 
-    let camera_orientation = Quat::from_rotation_arc(Vec3::Z, camera_transform.forward().into());
+    let plane = match &*projection {
+        Projection::Orthographic(_) if settings.side_view_grids => {
+            GridPlane::facing(camera_transform.forward().into()).unwrap_or_default()
+        }
+        _ => GridPlane::XZ,
+    };
+    if *grid_plane != plane {
+        *grid_plane = plane;
+        grid_transform.rotation = plane.rotation();
+    }
 
-    let plane = Plane3d::new(Direction3d::Y.into());
+    let spacing = settings.spacing_at(orbit_state.radius);
+    let fade_distance = settings.fade_distance_at(orbit_state.radius);
 
-    let frustum_corners = match projection {
-        Projection::Perspective(projection) => projection.get_frustum_corners(
-            projection.near,
-            plane.normal.dot(camera_transform.forward().into()).abs() * projection.far,
-        ),
+    // Nothing is visible past the fade distance
+    let frustum_corners = match &*projection {
+        Projection::Perspective(projection) => {
+            projection.get_frustum_corners(projection.near, projection.far.min(fade_distance))
+        }
         Projection::Orthographic(projection) => {
             projection.get_frustum_corners(projection.near, projection.far)
         }
     };
 
-    let lines = frustum_corners_to_lines(&frustum_corners);
+    // The tiles are laid out in the XZ plane of the grid, before its rotation
+    let to_grid = plane.rotation().inverse();
+    let to_grid_space =
+        |point: Vec3A| -> Vec3 { to_grid * camera_transform.transform_point(point.into()) };
+    let floor = Plane3d::new(Vec3::Y);
 
-    let transform_point = |point: Vec3A| -> Vec3 {
-        let point = camera_orientation * Vec3::from(point) + camera_transform.translation;
-        point
-    };
-
-    let aabb = lines
+    let bounds = frustum_corners_to_lines(&frustum_corners)
         .iter()
-        .map(|line| (transform_point(line.0), transform_point(line.1)))
-        .fold(None, |bounds, line| {
-            let intersection = line_intersects_plane_at(&line, &plane);
-
-            match intersection {
-                Some(intersection) => match bounds {
-                    Some((min, max)) => {
-                        let min = intersection.min(min);
-                        let max = intersection.max(max);
-                        Some((min, max))
-                    }
-                    None => Some((intersection, intersection)),
-                },
-                None => bounds,
-            }
+        .filter_map(|line| {
+            line_intersects_plane_at(&(to_grid_space(line.0), to_grid_space(line.1)), &floor)
         })
-        .map_or(None, |(min, max)| Some(Aabb3d { min, max }));
+        .fold(None, |bounds: Option<(Vec3, Vec3)>, point| match bounds {
+            Some((min, max)) => Some((point.min(min), point.max(max))),
+            None => Some((point, point)),
+        });
 
     grid.clear();
 
-    let Some(mut aabb) = aabb else {
-        info!("Plane is not visible");
+    let Some((min, max)) = bounds else {
+        // The plane is out of view
         return;
     };
 
-    let area = aabb.visible_area();
-    info!("{}", area * 0.5);
-
-    let distance = orbit_state.radius;
-
-    let size = if distance > 12000.0 {
-        10000.0f32
-    } else if distance > 800.0 {
-        1000.0f32
-    } else if distance > 200.0 {
-        100.0f32
-    } else {
-        10.0f32
+    let tile = spacing * settings.subdivisions.max(1) as f32;
+    let center = (to_grid * orbit_state.center / tile).round();
+    let tiles = |min: f32, max: f32, center: f32| {
+        let first = (min / tile).floor().max(center - MAX_TILES_FROM_CENTER);
+        let last = (max / tile).ceil().min(center + MAX_TILES_FROM_CENTER);
+        first as i32..last as i32
     };
 
-    let half_size = size * 0.5;
-    let padding = 10.0;
-
-    aabb.min -= padding;
-    aabb.max += padding;
+    for x in tiles(min.x, max.x, center.x) {
+        for z in tiles(min.z, max.z, center.z) {
+            grid.push(GridInstanceData {
+                position: Vec3::new(x as f32 + 0.5, 0.0, z as f32 + 0.5) * tile,
+                scale: tile,
+                fade_distance,
+                _padding: Vec3::ZERO,
+            });
+        }
+    }
+}
 
-    let compute_starting_min = |value: Vec3| -> Vec3 {
-        Vec3::new(
-            utils::multiples::largest_multiple_less_than_or_equal_to(value.x, size),
-            0.0,
-            utils::multiples::largest_multiple_less_than_or_equal_to(value.z, size),
-        )
-    };
+/// Lines along the world axes lying in the grid plane, as far as the grid reaches
+fn draw_axis_lines(
+    settings: Res<GridSettings>,
+    grid: Query<(&GridInstances, &GridPlane, &Visibility), With<Grid3d>>,
+    mut gizmos: Gizmos,
+) {
+    if !settings.axis_lines {
+        return;
+    }
 
-    let compute_starting_max = |value: Vec3| -> Vec3 {
-        Vec3::new(
-            utils::multiples::smallest_multiple_greater_than_or_equal_to(value.x, size),
-            0.0,
-            utils::multiples::smallest_multiple_greater_than_or_equal_to(value.z, size),
-        )
+    let Ok((instances, plane, visibility)) = grid.get_single() else {
+        return;
     };
 
-    // let (min, max) = (
-    //     compute_starting_min(aabb.min),
-    //     compute_starting_max(aabb.max),
-    // );
-
-    // for x in (min.x as i32..=max.x as i32).step_by(size as usize) {
-    //     for z in (min.z as i32..=max.z as i32).step_by(size as usize) {
-    //         grid.push(GridInstanceData {
-    //             position: Vec3::new(x as f32, 0.0, z as f32),
-    //             scale: size / 10.0,
-    //         });
-    //     }
-    // }
-
-    info!("Grid size: {}", grid.len());
-
-    let distance = orbit_state.radius;
-
-    let (base_size, scale) = if distance > 12000.0 {
-        (10000, 1000.0)
-    } else if distance > 800.0 {
-        (1000, 100.0)
-    } else if distance > 200.0 {
-        (100, 10.0)
-    } else {
-        (10, 1.0)
-    };
+    if *visibility == Visibility::Hidden {
+        return;
+    }
 
-    let grid_size = 30;
-    let half_size = (grid_size * base_size) as i32 / 2;
+    let extent = instances
+        .iter()
+        .map(|instance| instance.position.abs().max_element() + instance.scale * 0.5)
+        .fold(0.0, f32::max);
+    if extent <= 0.0 {
+        return;
+    }
 
-    for i in 0..grid_size * grid_size {
-        let x = (i as usize % grid_size as usize) as i32 * base_size - half_size + base_size / 2;
-        let z = (i as usize / grid_size as usize) as i32 * base_size - half_size + base_size / 2;
+    for axis in plane.axes() {
+        let direction = match axis {
+            GridAxis::X => Vec3::X,
+            GridAxis::Y => Vec3::Y,
+            GridAxis::Z => Vec3::Z,
+        };
 
-        grid.push(GridInstanceData {
-            position: Vec3::new(x as f32, 0.0, z as f32),
-            scale,
-        });
+        gizmos.line(
+            -direction * extent,
+            direction * extent,
+            settings.axis_color(axis),
+        );
     }
 }
 
-fn create_grid_base(size: u32, spacing: f32) -> Mesh {
-    let half_size = (size as f32 * 0.5) as i32;
+/// A square tile of unit size on the XZ plane, with `subdivisions` cells along each side. Its
+/// border makes the major lines, the lines inside are drawn fainter.
+fn create_grid_base(subdivisions: u32, color: Color) -> Mesh {
+    let subdivisions = subdivisions.clamp(1, GridSettings::MAX_SUBDIVISIONS);
     let mut positions = Vec::new();
     let mut indices = Vec::new();
     let mut colors = Vec::new();
@@ -387,45 +587,35 @@ fn create_grid_base(size: u32, spacing: f32) -> Mesh {
         RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
     );
 
-    let mut index: u16 = 0;
-
-    let line_color = [0.1, 0.1, 0.1, 1.0f32];
-    let line_edge_color = [0.12, 0.12, 0.12, 1.0f32];
+    let major_color = color.as_linear_rgba_f32();
+    let minor_color = color
+        .with_a(color.a() * MINOR_LINE_OPACITY)
+        .as_linear_rgba_f32();
 
-    for i in -half_size..=half_size {
-        let x = i as f32 * spacing;
-        positions.push(Vec3::new(x, 0.0, -half_size as f32 * spacing));
-        positions.push(Vec3::new(x, 0.0, half_size as f32 * spacing));
-        indices.push(index);
-        indices.push(index + 1);
+    let mut index: u16 = 0;
 
-        if i == -half_size || i == half_size {
-            colors.push(line_edge_color);
-            colors.push(line_edge_color);
+    for i in 0..=subdivisions {
+        let offset = i as f32 / subdivisions as f32 - 0.5;
+        let line_color = if i == 0 || i == subdivisions {
+            major_color
         } else {
-            colors.push(line_color.clone());
-            colors.push(line_color.clone());
-        }
-        index += 2
-    }
-
-    for i in -half_size..=half_size {
-        let z = i as f32 * spacing;
-        positions.push(Vec3::new(-half_size as f32 * spacing, 0.0, z));
-        positions.push(Vec3::new(half_size as f32 * spacing, 0.0, z));
-        indices.push(index);
-        indices.push(index + 1);
+            minor_color
+        };
 
-        if i == -half_size || i == half_size {
-            colors.push(line_edge_color);
-            colors.push(line_edge_color);
-        } else {
+        for (start, end) in [
+            (Vec3::new(offset, 0.0, -0.5), Vec3::new(offset, 0.0, 0.5)),
+            (Vec3::new(-0.5, 0.0, offset), Vec3::new(0.5, 0.0, offset)),
+        ] {
+            positions.push(start);
+            positions.push(end);
+            indices.push(index);
+            indices.push(index + 1);
             colors.push(line_color);
             colors.push(line_color);
+            index += 2;
         }
-
-        index += 2
     }
+
     let normals = vec![[0.0, 1.0, 0.0f32]; positions.len()];
     mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
@@ -505,11 +695,18 @@ impl SpecializedMeshPipeline for GridPipeline {
         descriptor.vertex.buffers.push(VertexBufferLayout {
             array_stride: std::mem::size_of::<GridInstanceData>() as u64,
             step_mode: VertexStepMode::Instance,
-            attributes: vec![VertexAttribute {
-                format: VertexFormat::Float32x4,
-                offset: 0,
-                shader_location: 3,
-            }],
+            attributes: vec![
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 0,
+                    shader_location: 3,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 16,
+                    shader_location: 4,
+                },
+            ],
         });
         descriptor.fragment.as_mut().unwrap().shader = self.shader.clone();
 
@@ -599,3 +796,48 @@ impl<P: PhaseItem> RenderCommand<P> for DrawMeshInstanced {
         RenderCommandResult::Success
     }
 }
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{create_grid_base, GridPlane, GridSettings, GridUnits};
+
+    #[test]
+    fn test_level_of_detail() {
+        let mut settings = GridSettings::default();
+        assert_eq!(settings.spacing_at(20.0), 1.0);
+        assert_eq!(settings.spacing_at(250.0), 10.0);
+        assert!((settings.spacing_at(5.0) - 0.1).abs() < 1e-6);
+        assert_eq!(settings.fade_distance_at(250.0), 1000.0);
+
+        settings.units = GridUnits::Imperial;
+        assert!((settings.spacing_at(20.0) - 0.3048).abs() < 1e-6);
+
+        // A fixed grid keeps the base spacing at any distance
+        settings.adaptive = false;
+        assert!((settings.spacing_at(5000.0) - 0.3048).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_plane_facing_side_views() {
+        assert_eq!(GridPlane::facing(Vec3::NEG_Y), Some(GridPlane::XZ));
+        assert_eq!(GridPlane::facing(Vec3::Z), Some(GridPlane::XY));
+        assert_eq!(GridPlane::facing(Vec3::NEG_X), Some(GridPlane::YZ));
+        assert_eq!(
+            GridPlane::facing(Vec3::new(1.0, -1.0, 0.0).normalize()),
+            None
+        );
+
+        // The grid is built on XZ, so Y must turn into the normal of the plane
+        assert!((GridPlane::XY.rotation() * Vec3::Y - Vec3::Z).length() < 1e-6);
+        assert!((GridPlane::YZ.rotation() * Vec3::Y - Vec3::X).length() < 1e-6);
+    }
+
+    #[test]
+    fn test_grid_base_lines() {
+        let mesh = create_grid_base(4, Color::WHITE);
+        // Five lines along each direction, two vertices each
+        assert_eq!(mesh.count_vertices(), 20);
+    }
+}
@@ -9,12 +9,15 @@ struct Vertex {
     @location(5) color: vec4<f32>,
     @builtin(instance_index) instance_index: u32,
     @location(3) i_pos_scale: vec4<f32>,
+    @location(4) i_fade: vec4<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
     @location(1) position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
+    @location(3) fade_distance: f32,
 };
 
 struct GridData {
@@ -27,14 +30,14 @@ struct GridData {
 
 @vertex
 fn vertex(vertex: Vertex) -> VertexOutput {
+    let model = get_model_matrix(grid_data.model_index);
     let position = vertex.position * vertex.i_pos_scale.w + vertex.i_pos_scale.xyz;
     var out: VertexOutput;
-    out.clip_position = mesh_position_local_to_clip(
-        get_model_matrix(grid_data.model_index),
-        vec4<f32>(position, 1.0)
-    );
-    out.position = position.xyz;
+    out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(position, 1.0));
+    out.position = (model * vec4<f32>(position, 1.0)).xyz;
+    out.normal = normalize((model * vec4<f32>(vertex.normal, 0.0)).xyz);
     out.color = vertex.color;
+    out.fade_distance = vertex.i_fade.x;
     return out;
 }
 
@@ -42,7 +45,9 @@ fn vertex(vertex: Vertex) -> VertexOutput {
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     let camera_vector = view.world_position - in.position;
     let len = length(camera_vector);
-    let camera_vector_n = normalize(camera_vector);
-    let alpha = pow(abs(dot(camera_vector_n, vec3<f32>(0.0, 1.0, 0.0))), 1.0) * min(pow((50.0 / len), 2.0), 1.0);
-    return vec4<f32>(in.color.xyz, 1.0);
+    let camera_vector_n = camera_vector / len;
+    // Fade out with the distance, and when the plane is seen edge-on
+    let distance_fade = 1.0 - smoothstep(0.5 * in.fade_distance, in.fade_distance, len);
+    let angle_fade = min(abs(dot(camera_vector_n, in.normal)) * 4.0, 1.0);
+    return vec4<f32>(in.color.xyz, in.color.w * distance_fade * angle_fade);
 }
@@ -1,233 +1,943 @@
-use std::{fmt::Debug, marker::PhantomData};
-
 use bevy::{
-    core_pipeline::core_3d::Transparent3d,
-    pbr::{
-        DrawMesh, ExtendedMaterial, MaterialPipeline, MaterialPipelineKey, MeshPipeline,
-        MeshPipelineKey, SetMaterialBindGroup, SetMeshBindGroup, SetMeshViewBindGroup,
-        StandardMaterialUniform,
+    core_pipeline::{
+        core_3d::{
+            graph::{Core3d, Node3d},
+            Camera3dDepthTextureUsage,
+        },
+        fullscreen_vertex_shader::fullscreen_shader_vertex_state,
+        tonemapping::Tonemapping,
     },
+    ecs::query::QueryItem,
     prelude::*,
     render::{
-        extract_component::{ComponentUniforms, ExtractComponent, ExtractComponentPlugin},
-        render_phase::{DrawFunctionId, PhaseItem, RenderCommand, RenderPhase, SetItemPipeline},
+        camera::{ExtractedCamera, RenderTarget},
+        extract_component::{
+            ComponentUniforms, DynamicUniformIndex, ExtractComponent, ExtractComponentPlugin,
+            UniformComponentPlugin,
+        },
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::RenderAssets,
+        render_graph::{
+            NodeRunError, RenderGraphApp, RenderGraphContext, RenderLabel, ViewNode, ViewNodeRunner,
+        },
         render_resource::{
-            CachedRenderPipelineId, PipelineCache, SpecializedMeshPipeline,
-            SpecializedMeshPipelines,
+            binding_types::{
+                sampler, texture_2d, texture_depth_2d, texture_depth_2d_multisampled,
+                uniform_buffer,
+            },
+            BindGroup, BindGroupEntries, BindGroupLayout, BindGroupLayoutEntries,
+            CachedRenderPipelineId, ColorTargetState, ColorWrites, DynamicUniformBuffer, Extent3d,
+            FragmentState, MultisampleState, Operations, PipelineCache, PrimitiveState,
+            RenderPassColorAttachment, RenderPassDescriptor, RenderPipeline,
+            RenderPipelineDescriptor, Sampler, SamplerBindingType, SamplerDescriptor, ShaderStages,
+            ShaderType, SpecializedRenderPipeline, SpecializedRenderPipelines, TextureDescriptor,
+            TextureDimension, TextureFormat, TextureSampleType, TextureUsages, TextureView,
         },
-        view::ExtractedView,
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::{BevyDefault, CachedTexture, TextureCache},
+        view::{NoFrustumCulling, RenderLayers, ViewDepthTexture, ViewTarget, VisibilitySystems},
         Render, RenderApp, RenderSet,
     },
+    transform::TransformSystem,
+    window::{PrimaryWindow, WindowResized},
 };
 
-use super::pan_orbit_camera::PrimaryCamera;
+use super::{
+    editor::Focused,
+    interaction::Hovered,
+    pan_orbit_camera::{PanOrbitCameraUpdate, PrimaryCamera},
+};
 
-#[derive(Default)]
-pub struct HighlightPlugin<T: Material>(pub PhantomData<T>);
+/// Draws a screen-space outline around selected, active and hovered entities.
+///
+/// Highlighted meshes are drawn a second time by an offscreen mask camera, each kind in its own colour channel.
+/// A post-processing pass on the primary camera then depth tests the mask against the scene and
+/// grows the visible part into an outline with a jump flood, so the cost only grows with the
+/// logarithm of the outline width.
+pub struct HighlightPlugin;
 
+/// Marks an entity as selected
 #[derive(Component)]
 pub struct Highlight;
 
-#[derive(Component, Deref, DerefMut, Clone, Debug)]
-pub struct HighlightTarget<T: Material + Debug>(pub Handle<T>);
+/// Render layer only seen by the mask camera
+const HIGHLIGHT_MASK_LAYER: u8 = 2;
 
-impl<T: Material + Debug> ExtractComponent for HighlightTarget<T> {
-    type Out = Self;
-    type QueryData = &'static Self;
-    type QueryFilter = ();
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HighlightKind {
+    Selected,
+    Active,
+    Hovered,
+}
 
-    fn extract_component(item: &Self) -> Option<Self::Out> {
-        Some(item.clone())
+impl HighlightKind {
+    /// Colour written to the mask, each kind has its own channel
+    fn mask_color(&self) -> Color {
+        match self {
+            HighlightKind::Selected => Color::rgb(1.0, 0.0, 0.0),
+            HighlightKind::Active => Color::rgb(0.0, 1.0, 0.0),
+            HighlightKind::Hovered => Color::rgb(0.0, 0.0, 1.0),
+        }
     }
 }
 
-impl<T: Material + Debug> Plugin for HighlightPlugin<T> {
+/// Outline settings, put on the camera whose output gets outlined
+#[derive(Component, Clone, Copy, ExtractComponent, ShaderType)]
+pub struct HighlightSettings {
+    /// Linear RGBA, alpha is the outline opacity
+    pub selected: Vec4,
+    pub active: Vec4,
+    pub hovered: Vec4,
+    /// Outline width in physical pixels
+    pub width: f32,
+}
+
+impl Default for HighlightSettings {
+    fn default() -> Self {
+        Self {
+            selected: Vec4::from(Color::rgb_u8(241, 88, 0).as_linear_rgba_f32()),
+            active: Vec4::from(Color::rgb_u8(255, 170, 64).as_linear_rgba_f32()),
+            hovered: Vec4::from(Color::rgba_u8(110, 180, 255, 200).as_linear_rgba_f32()),
+            width: 3.0,
+        }
+    }
+}
+
+/// Points to the mask proxy drawn for a highlighted entity
+#[derive(Component)]
+pub struct HighlightMask(pub Entity);
+
+/// Copy of a highlighted mesh living on the mask layer
+#[derive(Component)]
+pub struct HighlightMaskProxy {
+    pub source: Entity,
+}
+
+#[derive(Component)]
+struct HighlightMaskCamera;
+
+#[derive(Resource, Clone, ExtractResource)]
+struct HighlightMaskImage(Handle<Image>);
+
+/// Mask camera entity, its depth texture is compared with the scene depth in the render world
+#[derive(Resource, Clone, ExtractResource)]
+struct HighlightMaskView(Entity);
+
+#[derive(Resource)]
+struct HighlightMaskMaterials {
+    selected: Handle<StandardMaterial>,
+    active: Handle<StandardMaterial>,
+    hovered: Handle<StandardMaterial>,
+}
+
+impl HighlightMaskMaterials {
+    fn get(&self, kind: HighlightKind) -> &Handle<StandardMaterial> {
+        match kind {
+            HighlightKind::Selected => &self.selected,
+            HighlightKind::Active => &self.active,
+            HighlightKind::Hovered => &self.hovered,
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct HighlightLabel;
+
+impl Plugin for HighlightPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(ExtractComponentPlugin::<HighlightTarget<T>>::extract_visible())
+        app.add_plugins((
+            ExtractComponentPlugin::<HighlightSettings>::default(),
+            UniformComponentPlugin::<HighlightSettings>::default(),
+            ExtractResourcePlugin::<HighlightMaskImage>::default(),
+            ExtractResourcePlugin::<HighlightMaskView>::default(),
+        ))
+        .add_systems(Startup, Self::setup_mask_camera)
+        .add_systems(
+            Update,
+            (
+                Self::insert_highlight_settings,
+                Self::resize_mask_image,
+                Self::sync_mask_camera.after(PanOrbitCameraUpdate),
+                Self::update_highlight_masks,
+            ),
+        )
+        .add_systems(
+            PostUpdate,
+            Self::sync_mask_proxies
+                .after(TransformSystem::TransformPropagate)
+                .after(VisibilitySystems::VisibilityPropagate)
+                .before(VisibilitySystems::CheckVisibility),
+        );
+
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app
+            .init_resource::<JumpFloodUniforms>()
+            .init_resource::<SpecializedRenderPipelines<HighlightPipeline>>()
             .add_systems(
-                Update,
+                Render,
                 (
-                    Self::extract_highlighted_entities,
-                    Self::handle_removed_highlight,
+                    prepare_highlight_uniforms.in_set(RenderSet::PrepareResources),
+                    prepare_highlight_textures.in_set(RenderSet::PrepareResources),
+                    prepare_highlight_pipelines.in_set(RenderSet::Prepare),
                 ),
             )
-            .sub_app_mut(RenderApp)
-            .add_systems(
-                Render,
+            .add_render_graph_node::<ViewNodeRunner<HighlightNode>>(Core3d, HighlightLabel)
+            .add_render_graph_edges(
+                Core3d,
                 (
-                    Self::prepare_pipeline.in_set(RenderSet::Prepare),
-                    Self::queue_highlighted_meshes.in_set(RenderSet::QueueMeshes),
+                    Node3d::Tonemapping,
+                    HighlightLabel,
+                    Node3d::EndMainPassPostProcessing,
                 ),
             );
     }
+
+    fn finish(&self, app: &mut App) {
+        let Ok(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.init_resource::<HighlightPipeline>();
+    }
 }
-impl<T: Material + Debug> HighlightPlugin<T> {
-    pub fn extract_highlighted_entities(
+
+impl HighlightPlugin {
+    fn setup_mask_camera(
         mut commands: Commands,
-        query: Query<(Entity, &Handle<T>), With<Highlight>>,
+        mut images: ResMut<Assets<Image>>,
+        mut materials: ResMut<Assets<StandardMaterial>>,
+        window: Query<&Window, With<PrimaryWindow>>,
     ) {
-        for (entity, handle) in query.iter() {
-            commands
-                .entity(entity)
-                .insert(HighlightTarget(handle.clone()))
-                .remove::<Handle<T>>();
-        }
+        let size = window
+            .get_single()
+            .map(|window| Extent3d {
+                width: window.physical_width().max(1),
+                height: window.physical_height().max(1),
+                depth_or_array_layers: 1,
+            })
+            .unwrap_or_default();
+
+        let mut image = Image {
+            texture_descriptor: TextureDescriptor {
+                label: Some("highlight_mask_texture"),
+                size,
+                dimension: TextureDimension::D2,
+                format: TextureFormat::bevy_default(),
+                mip_level_count: 1,
+                sample_count: 1,
+                usage: TextureUsages::TEXTURE_BINDING
+                    | TextureUsages::COPY_DST
+                    | TextureUsages::RENDER_ATTACHMENT,
+                view_formats: &[],
+            },
+            ..default()
+        };
+        image.resize(size);
+
+        let image = images.add(image);
+
+        let mask_camera = commands
+            .spawn((
+                Camera3dBundle {
+                    camera: Camera {
+                        // Renders before the primary camera so the mask is ready for its post-processing
+                        order: -1,
+                        target: RenderTarget::Image(image.clone()),
+                        clear_color: ClearColorConfig::Custom(Color::NONE),
+                        ..default()
+                    },
+                    camera_3d: Camera3d {
+                        depth_texture_usages: depth_texture_binding(),
+                        ..default()
+                    },
+                    tonemapping: Tonemapping::None,
+                    ..default()
+                },
+                HighlightMaskCamera,
+                RenderLayers::layer(HIGHLIGHT_MASK_LAYER),
+            ))
+            .id();
+
+        let mut mask_material = |kind: HighlightKind| {
+            materials.add(StandardMaterial {
+                base_color: kind.mask_color(),
+                unlit: true,
+                cull_mode: None,
+                double_sided: true,
+                ..default()
+            })
+        };
+
+        commands.insert_resource(HighlightMaskMaterials {
+            selected: mask_material(HighlightKind::Selected),
+            active: mask_material(HighlightKind::Active),
+            hovered: mask_material(HighlightKind::Hovered),
+        });
+        commands.insert_resource(HighlightMaskImage(image));
+        commands.insert_resource(HighlightMaskView(mask_camera));
     }
 
-    pub fn handle_removed_highlight(
+    /// Puts the outline settings on the primary camera and lets its depth be read by the outline
+    /// pass
+    fn insert_highlight_settings(
         mut commands: Commands,
-        query: Query<(Entity, &HighlightTarget<T>), Without<Highlight>>,
+        mut camera: Query<(Entity, &mut Camera3d, Has<HighlightSettings>), With<PrimaryCamera>>,
     ) {
-        for (entity, highlight_target) in query.iter() {
-            commands
-                .entity(entity)
-                .insert((**highlight_target).clone())
-                .remove::<HighlightTarget<T>>();
+        for (entity, mut camera_3d, has_settings) in camera.iter_mut() {
+            if has_settings {
+                continue;
+            }
+
+            camera_3d.depth_texture_usages = depth_texture_binding();
+            commands.entity(entity).insert(HighlightSettings::default());
         }
     }
 
-    pub fn queue_highlighted_meshes(
-        mut pipelines: ResMut<SpecializedMeshPipelines<PreHighlightPipeline<T>>>,
-        pipeline_cache: Res<PipelineCache>,
-        highlight_pipeline: Res<PreHighlightPipeline<T>>,
-        highlighted: Query<(Entity, &HighlightTarget<T>)>,
-        views: Query<(&ExtractedView, &mut RenderPhase<Transparent3d>), With<PrimaryCamera>>,
+    fn resize_mask_image(
+        mut resize_events: EventReader<WindowResized>,
+        window: Query<&Window, With<PrimaryWindow>>,
+        mask_image: Option<Res<HighlightMaskImage>>,
+        mut images: ResMut<Assets<Image>>,
     ) {
-        for (view, mut transparent_phase) in views.iter() {
-            for (entity, highlight_target) in highlighted.iter() {
-                // queue mesh
-
-                // transparent_phase.add(Transparent3d {
-                //     batch_range: 0..1,
-                //     distance: 0.0,
-                //     draw_function: DrawFunctionId::,
-                //     dynamic_offset: None,
-                //     entity,
-                //     pipeline: CachedRenderPipelineId::INVALID,
-                // });
+        if resize_events.read().count() == 0 {
+            return;
+        }
+
+        let (Ok(window), Some(mask_image)) = (window.get_single(), mask_image) else {
+            return;
+        };
+
+        let Some(image) = images.get_mut(&mask_image.0) else {
+            return;
+        };
+
+        image.resize(Extent3d {
+            width: window.physical_width().max(1),
+            height: window.physical_height().max(1),
+            depth_or_array_layers: 1,
+        });
+    }
+
+    fn sync_mask_camera(
+        q_main_camera: Query<(&Transform, &Projection), With<PrimaryCamera>>,
+        mut q_mask_camera: Query<
+            (&mut Transform, &mut Projection),
+            (With<HighlightMaskCamera>, Without<PrimaryCamera>),
+        >,
+    ) {
+        let (Ok((main_transform, main_projection)), Ok((mut transform, mut projection))) =
+            (q_main_camera.get_single(), q_mask_camera.get_single_mut())
+        else {
+            return;
+        };
+
+        *transform = *main_transform;
+        *projection = main_projection.clone();
+    }
+
+    /// Spawns, updates and despawns the mask proxies following the highlight state of every mesh
+    fn update_highlight_masks(
+        mut commands: Commands,
+        materials: Option<Res<HighlightMaskMaterials>>,
+        sources: Query<
+            (
+                Entity,
+                &Handle<Mesh>,
+                Option<&HighlightMask>,
+                Has<Highlight>,
+                Has<Focused>,
+                Has<Hovered>,
+            ),
+            Without<HighlightMaskProxy>,
+        >,
+        mut proxies: Query<(
+            Entity,
+            &HighlightMaskProxy,
+            &mut Handle<Mesh>,
+            &mut Handle<StandardMaterial>,
+        )>,
+    ) {
+        let Some(materials) = materials else {
+            return;
+        };
+
+        for (entity, mesh, mask, selected, active, hovered) in sources.iter() {
+            let kind = if active {
+                Some(HighlightKind::Active)
+            } else if selected {
+                Some(HighlightKind::Selected)
+            } else if hovered {
+                Some(HighlightKind::Hovered)
+            } else {
+                None
+            };
+
+            match (kind, mask) {
+                (None, None) => {}
+                (None, Some(mask)) => {
+                    commands.entity(mask.0).despawn();
+                    commands.entity(entity).remove::<HighlightMask>();
+                }
+                (Some(kind), None) => {
+                    let proxy = commands
+                        .spawn((
+                            mesh.clone(),
+                            materials.get(kind).clone(),
+                            SpatialBundle::default(),
+                            NoFrustumCulling,
+                            RenderLayers::layer(HIGHLIGHT_MASK_LAYER),
+                            HighlightMaskProxy { source: entity },
+                        ))
+                        .id();
+                    commands.entity(entity).insert(HighlightMask(proxy));
+                }
+                (Some(kind), Some(mask)) => {
+                    let Ok((_, _, mut proxy_mesh, mut proxy_material)) = proxies.get_mut(mask.0)
+                    else {
+                        continue;
+                    };
+
+                    if *proxy_mesh != *mesh {
+                        *proxy_mesh = mesh.clone();
+                    }
+                    if *proxy_material != *materials.get(kind) {
+                        *proxy_material = materials.get(kind).clone();
+                    }
+                }
+            }
+        }
+
+        // Sources despawned while highlighted
+        for (proxy_entity, proxy, _, _) in proxies.iter() {
+            if !sources.contains(proxy.source) {
+                commands.entity(proxy_entity).despawn();
             }
         }
     }
 
-    pub fn prepare_pipeline(query: Query<(Entity, &HighlightTarget<T>)>) {
-        for (_, highlight_target) in query.iter() {
-            // prepare pipeline
+    /// Places the mask proxies where their sources are, after transforms are propagated
+    fn sync_mask_proxies(
+        sources: Query<(&GlobalTransform, &InheritedVisibility), Without<HighlightMaskProxy>>,
+        mut proxies: Query<(&HighlightMaskProxy, &mut GlobalTransform, &mut Visibility)>,
+    ) {
+        for (proxy, mut global_transform, mut visibility) in proxies.iter_mut() {
+            let Ok((source_transform, source_visibility)) = sources.get(proxy.source) else {
+                continue;
+            };
+
+            *global_transform = *source_transform;
+
+            let target_visibility = if source_visibility.get() {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            if *visibility != target_visibility {
+                *visibility = target_visibility;
+            }
         }
     }
 }
 
-#[derive(Resource)]
-struct PreHighlightPipeline<T: Material> {
-    material_pipeline: MaterialPipeline<T>,
+/// Depth texture usages of the cameras whose depth is read by the seed pass
+fn depth_texture_binding() -> Camera3dDepthTextureUsage {
+    (TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING).into()
 }
 
-impl<T: Material> FromWorld for PreHighlightPipeline<T> {
-    fn from_world(world: &mut World) -> Self {
-        unimplemented!()
+/// Step of one jump flood pass, in pixels
+#[derive(Clone, Copy, ShaderType)]
+struct JumpFloodStep {
+    step: f32,
+    // WebGL2 needs uniform buffers padded to 16 bytes
+    _padding: Vec3,
+}
+
+#[derive(Resource, Default)]
+struct JumpFloodUniforms(DynamicUniformBuffer<JumpFloodStep>);
+
+/// Dynamic offsets of the jump flood passes of a view, largest step first
+#[derive(Component)]
+struct JumpFloodOffsets(Vec<u32>);
+
+/// Ping-pong targets of the jump flood, each texel holds the offset to its closest seed in `xy`,
+/// the seed's [`HighlightKind`] index in `z` and whether a seed was found in `w`
+#[derive(Component)]
+struct HighlightTextures([CachedTexture; 2]);
+
+#[derive(Component)]
+struct HighlightSeedPipeline {
+    id: CachedRenderPipelineId,
+    key: HighlightPipelineKey,
+}
+
+/// Offsets to the closest seed are small, half floats hold them exactly
+const JUMP_FLOOD_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+/// Step sizes of the jump flood for an outline `width` pixels wide. Seeds farther than the width
+/// are never drawn, so the flood starts at the width instead of the screen size, and ends with an
+/// extra single pixel pass (JFA+1) to fix the errors left by the larger steps.
+fn jump_flood_steps(width: f32) -> Vec<u32> {
+    let mut step = (width.ceil().max(1.0) as u32).next_power_of_two();
+    let mut steps = Vec::new();
+
+    while step > 1 {
+        steps.push(step);
+        step /= 2;
     }
+    steps.extend([1, 1]);
+
+    steps
 }
 
-impl<T: Material> SpecializedMeshPipeline for PreHighlightPipeline<T> {
-    type Key = MeshPipelineKey;
+fn prepare_highlight_uniforms(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    render_queue: Res<RenderQueue>,
+    mut uniforms: ResMut<JumpFloodUniforms>,
+    views: Query<(Entity, &HighlightSettings)>,
+) {
+    uniforms.0.clear();
 
-    fn specialize(
-        &self,
-        _key: Self::Key,
-        _layout: &bevy::render::mesh::MeshVertexBufferLayout,
-    ) -> Result<
-        bevy::render::render_resource::RenderPipelineDescriptor,
-        bevy::render::render_resource::SpecializedMeshPipelineError,
-    > {
-        unimplemented!()
+    for (entity, settings) in views.iter() {
+        let offsets = jump_flood_steps(settings.width)
+            .into_iter()
+            .map(|step| {
+                uniforms.0.push(&JumpFloodStep {
+                    step: step as f32,
+                    _padding: Vec3::ZERO,
+                })
+            })
+            .collect();
+
+        commands.entity(entity).insert(JumpFloodOffsets(offsets));
     }
+
+    uniforms.0.write_buffer(&render_device, &render_queue);
+}
+
+fn prepare_highlight_textures(
+    mut commands: Commands,
+    render_device: Res<RenderDevice>,
+    mut texture_cache: ResMut<TextureCache>,
+    views: Query<(Entity, &ExtractedCamera), With<HighlightSettings>>,
+) {
+    for (entity, camera) in views.iter() {
+        let Some(size) = camera.physical_target_size else {
+            continue;
+        };
+
+        let mut texture = |label| {
+            texture_cache.get(
+                &render_device,
+                TextureDescriptor {
+                    label: Some(label),
+                    size: Extent3d {
+                        width: size.x,
+                        height: size.y,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: JUMP_FLOOD_FORMAT,
+                    usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+            )
+        };
+
+        let textures = [
+            texture("highlight_jump_flood_texture_a"),
+            texture("highlight_jump_flood_texture_b"),
+        ];
+
+        commands.entity(entity).insert(HighlightTextures(textures));
+    }
+}
+
+fn prepare_highlight_pipelines(
+    mut commands: Commands,
+    pipeline_cache: Res<PipelineCache>,
+    mut pipelines: ResMut<SpecializedRenderPipelines<HighlightPipeline>>,
+    highlight_pipeline: Res<HighlightPipeline>,
+    msaa: Res<Msaa>,
+    views: Query<Entity, With<HighlightSettings>>,
+) {
+    let key = HighlightPipelineKey {
+        multisampled: msaa.samples() > 1,
+        // WebGL2 can not read multisampled depth textures, the outline then shows through
+        // occluders
+        depth_test: !(cfg!(target_arch = "wasm32") && msaa.samples() > 1),
+    };
+
+    for entity in views.iter() {
+        let id = pipelines.specialize(&pipeline_cache, &highlight_pipeline, key);
+
+        commands
+            .entity(entity)
+            .insert(HighlightSeedPipeline { id, key });
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct HighlightPipelineKey {
+    /// Whether the depth textures read by the seed pass are multisampled
+    multisampled: bool,
+    /// Whether mask pixels hidden behind other geometry are dropped
+    depth_test: bool,
 }
 
 #[derive(Resource)]
-struct PostHighlightPipeline {
-    material_pipeline: MeshPipeline,
+struct HighlightPipeline {
+    seed_layout: BindGroupLayout,
+    seed_layout_depth: BindGroupLayout,
+    seed_layout_depth_multisampled: BindGroupLayout,
+    flood_layout: BindGroupLayout,
+    outline_layout: BindGroupLayout,
+    sampler: Sampler,
+    shader: Handle<Shader>,
+    flood_pipeline_id: CachedRenderPipelineId,
+    outline_pipeline_id: CachedRenderPipelineId,
 }
 
-impl FromWorld for PostHighlightPipeline {
+impl FromWorld for HighlightPipeline {
     fn from_world(world: &mut World) -> Self {
-        unimplemented!()
+        let render_device = world.resource::<RenderDevice>();
+
+        let seed_layout = render_device.create_bind_group_layout(
+            "Highlight Seed Bind Group Layout",
+            &BindGroupLayoutEntries::single(
+                ShaderStages::FRAGMENT,
+                texture_2d(TextureSampleType::Float { filterable: false }),
+            ),
+        );
+
+        let seed_layout_depth = render_device.create_bind_group_layout(
+            "Highlight Seed Depth Bind Group Layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_depth_2d(),
+                    texture_depth_2d(),
+                ),
+            ),
+        );
+
+        let seed_layout_depth_multisampled = render_device.create_bind_group_layout(
+            "Highlight Seed Depth Multisampled Bind Group Layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    texture_depth_2d_multisampled(),
+                    texture_depth_2d_multisampled(),
+                ),
+            ),
+        );
+
+        let flood_layout = render_device.create_bind_group_layout(
+            "Highlight Flood Bind Group Layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<JumpFloodStep>(true),
+                ),
+            ),
+        );
+
+        let outline_layout = render_device.create_bind_group_layout(
+            "Highlight Bind Group Layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
+                    texture_2d(TextureSampleType::Float { filterable: false }),
+                    uniform_buffer::<HighlightSettings>(true),
+                ),
+            ),
+        );
+
+        let sampler = render_device.create_sampler(&SamplerDescriptor::default());
+
+        let asset_server = world.resource::<AssetServer>();
+        let shader = asset_server.add(Shader::from_wgsl(
+            include_str!("highlight.wgsl"),
+            "highlight.wgsl",
+        ));
+
+        let fullscreen_pipeline = |label: &'static str,
+                                   layout: &BindGroupLayout,
+                                   entry_point: &'static str,
+                                   format: TextureFormat| {
+            RenderPipelineDescriptor {
+                label: Some(label.into()),
+                layout: vec![layout.clone()],
+                vertex: fullscreen_shader_vertex_state(),
+                fragment: Some(FragmentState {
+                    shader: shader.clone(),
+                    shader_defs: vec![],
+                    entry_point: entry_point.into(),
+                    targets: vec![Some(ColorTargetState {
+                        format,
+                        blend: None,
+                        write_mask: ColorWrites::ALL,
+                    })],
+                }),
+                primitive: PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: MultisampleState::default(),
+                push_constant_ranges: vec![],
+            }
+        };
+
+        let flood_descriptor = fullscreen_pipeline(
+            "highlight_flood_pipeline",
+            &flood_layout,
+            "flood",
+            JUMP_FLOOD_FORMAT,
+        );
+        let outline_descriptor = fullscreen_pipeline(
+            "highlight_pipeline",
+            &outline_layout,
+            "outline",
+            TextureFormat::bevy_default(),
+        );
+
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let flood_pipeline_id = pipeline_cache.queue_render_pipeline(flood_descriptor);
+        let outline_pipeline_id = pipeline_cache.queue_render_pipeline(outline_descriptor);
+
+        Self {
+            seed_layout,
+            seed_layout_depth,
+            seed_layout_depth_multisampled,
+            flood_layout,
+            outline_layout,
+            sampler,
+            shader,
+            flood_pipeline_id,
+            outline_pipeline_id,
+        }
     }
 }
 
-impl SpecializedMeshPipeline for PostHighlightPipeline {
-    type Key = MeshPipelineKey;
+impl HighlightPipeline {
+    fn seed_layout(&self, key: HighlightPipelineKey) -> &BindGroupLayout {
+        match (key.depth_test, key.multisampled) {
+            (false, _) => &self.seed_layout,
+            (true, false) => &self.seed_layout_depth,
+            (true, true) => &self.seed_layout_depth_multisampled,
+        }
+    }
+}
+
+impl SpecializedRenderPipeline for HighlightPipeline {
+    type Key = HighlightPipelineKey;
+
+    fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
+        let mut shader_defs = Vec::new();
+        if key.depth_test {
+            shader_defs.push("DEPTH_TEST".into());
+            if key.multisampled {
+                shader_defs.push("MULTISAMPLED".into());
+            }
+        }
+
+        RenderPipelineDescriptor {
+            label: Some("highlight_seed_pipeline".into()),
+            layout: vec![self.seed_layout(key).clone()],
+            vertex: fullscreen_shader_vertex_state(),
+            fragment: Some(FragmentState {
+                shader: self.shader.clone(),
+                shader_defs,
+                entry_point: "seed".into(),
+                targets: vec![Some(ColorTargetState {
+                    format: JUMP_FLOOD_FORMAT,
+                    blend: None,
+                    write_mask: ColorWrites::ALL,
+                })],
+            }),
+            primitive: PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: MultisampleState::default(),
+            push_constant_ranges: vec![],
+        }
+    }
+}
+
+/// Outlines the highlighted entities in three steps: a seed pass keeps the mask pixels that are
+/// not hidden behind other geometry, jump flood passes spread the closest seed to every pixel
+/// within the outline width, and the outline pass colours the pixels close to a seed.
+#[derive(Default)]
+struct HighlightNode;
 
-    fn specialize(
+impl ViewNode for HighlightNode {
+    type ViewQuery = (
+        &'static ViewTarget,
+        &'static ViewDepthTexture,
+        &'static HighlightTextures,
+        &'static HighlightSeedPipeline,
+        &'static JumpFloodOffsets,
+        &'static DynamicUniformIndex<HighlightSettings>,
+    );
+
+    fn run(
         &self,
-        _key: Self::Key,
-        _layout: &bevy::render::mesh::MeshVertexBufferLayout,
-    ) -> Result<
-        bevy::render::render_resource::RenderPipelineDescriptor,
-        bevy::render::render_resource::SpecializedMeshPipelineError,
-    > {
-        unimplemented!()
-    }
-}
-
-type DrawHighlightedMesh<T> = (
-    SetPreHighlightPipeline,
-    SetMeshViewBindGroup<0>,
-    SetMeshBindGroup<1>,
-    SetMaterialBindGroup<T, 2>,
-    DrawMesh,
-    SetPostHighlightPipeline,
-    DrawHighlight,
-);
-
-struct SetPreHighlightPipeline;
-
-impl<T: PhaseItem> RenderCommand<T> for SetPreHighlightPipeline {
-    type ItemQuery = ();
-    type Param = ();
-    type ViewQuery = ();
-
-    fn render<'w>(
-        item: &T,
-        view: bevy::ecs::query::ROQueryItem<'w, Self::ViewQuery>,
-        entity: Option<bevy::ecs::query::ROQueryItem<'w, Self::ItemQuery>>,
-        param: bevy::ecs::system::SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut bevy::render::render_phase::TrackedRenderPass<'w>,
-    ) -> bevy::render::render_phase::RenderCommandResult {
-        unimplemented!()
-    }
-}
-
-struct SetPostHighlightPipeline;
-
-impl<T: PhaseItem> RenderCommand<T> for SetPostHighlightPipeline {
-    type ItemQuery = ();
-    type Param = ();
-    type ViewQuery = ();
-
-    fn render<'w>(
-        item: &T,
-        view: bevy::ecs::query::ROQueryItem<'w, Self::ViewQuery>,
-        entity: Option<bevy::ecs::query::ROQueryItem<'w, Self::ItemQuery>>,
-        param: bevy::ecs::system::SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut bevy::render::render_phase::TrackedRenderPass<'w>,
-    ) -> bevy::render::render_phase::RenderCommandResult {
-        unimplemented!()
-    }
-}
-
-struct DrawHighlight;
-
-impl<T: PhaseItem> RenderCommand<T> for DrawHighlight {
-    type ItemQuery = ();
-    type Param = ();
-    type ViewQuery = ();
-
-    fn render<'w>(
-        item: &T,
-        view: bevy::ecs::query::ROQueryItem<'w, Self::ViewQuery>,
-        entity: Option<bevy::ecs::query::ROQueryItem<'w, Self::ItemQuery>>,
-        param: bevy::ecs::system::SystemParamItem<'w, '_, Self::Param>,
-        pass: &mut bevy::render::render_phase::TrackedRenderPass<'w>,
-    ) -> bevy::render::render_phase::RenderCommandResult {
-        unimplemented!()
+        _graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        (view_target, view_depth, textures, seed_pipeline, flood_offsets, settings_index): QueryItem<
+            Self::ViewQuery,
+        >,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let highlight_pipeline = world.resource::<HighlightPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        let (Some(seed_render_pipeline), Some(flood_pipeline), Some(outline_pipeline)) = (
+            pipeline_cache.get_render_pipeline(seed_pipeline.id),
+            pipeline_cache.get_render_pipeline(highlight_pipeline.flood_pipeline_id),
+            pipeline_cache.get_render_pipeline(highlight_pipeline.outline_pipeline_id),
+        ) else {
+            return Ok(());
+        };
+
+        let (Some(settings_binding), Some(flood_binding)) = (
+            world
+                .resource::<ComponentUniforms<HighlightSettings>>()
+                .uniforms()
+                .binding(),
+            world.resource::<JumpFloodUniforms>().0.binding(),
+        ) else {
+            return Ok(());
+        };
+
+        let Some(mask_image) = world
+            .get_resource::<HighlightMaskImage>()
+            .and_then(|mask_image| world.resource::<RenderAssets<Image>>().get(&mask_image.0))
+        else {
+            return Ok(());
+        };
+
+        let Some(mask_depth) = world
+            .get_resource::<HighlightMaskView>()
+            .and_then(|mask_view| world.get::<ViewDepthTexture>(mask_view.0))
+        else {
+            return Ok(());
+        };
+
+        // The mask image lags one frame behind a window resize
+        if mask_depth.texture.size() != view_depth.texture.size() {
+            return Ok(());
+        }
+
+        let render_device = render_context.render_device().clone();
+
+        let seed_layout = highlight_pipeline.seed_layout(seed_pipeline.key);
+        let seed_bind_group = if seed_pipeline.key.depth_test {
+            render_device.create_bind_group(
+                "highlight_seed_bind_group",
+                seed_layout,
+                &BindGroupEntries::sequential((
+                    &mask_image.texture_view,
+                    mask_depth.view(),
+                    view_depth.view(),
+                )),
+            )
+        } else {
+            render_device.create_bind_group(
+                "highlight_seed_bind_group",
+                seed_layout,
+                &BindGroupEntries::single(&mask_image.texture_view),
+            )
+        };
+
+        Self::fullscreen_pass(
+            render_context,
+            "highlight_seed_pass",
+            &textures.0[0].default_view,
+            seed_render_pipeline,
+            &seed_bind_group,
+            &[],
+        );
+
+        let mut current = 0;
+        for offset in flood_offsets.0.iter() {
+            let flood_bind_group = render_device.create_bind_group(
+                "highlight_flood_bind_group",
+                &highlight_pipeline.flood_layout,
+                &BindGroupEntries::sequential((
+                    &textures.0[current].default_view,
+                    flood_binding.clone(),
+                )),
+            );
+
+            Self::fullscreen_pass(
+                render_context,
+                "highlight_flood_pass",
+                &textures.0[1 - current].default_view,
+                flood_pipeline,
+                &flood_bind_group,
+                &[*offset],
+            );
+
+            current = 1 - current;
+        }
+
+        let post_process = view_target.post_process_write();
+
+        let outline_bind_group = render_device.create_bind_group(
+            "highlight_bind_group",
+            &highlight_pipeline.outline_layout,
+            &BindGroupEntries::sequential((
+                post_process.source,
+                &highlight_pipeline.sampler,
+                &textures.0[current].default_view,
+                settings_binding.clone(),
+            )),
+        );
+
+        Self::fullscreen_pass(
+            render_context,
+            "highlight_pass",
+            post_process.destination,
+            outline_pipeline,
+            &outline_bind_group,
+            &[settings_index.index()],
+        );
+
+        Ok(())
+    }
+}
+
+impl HighlightNode {
+    fn fullscreen_pass(
+        render_context: &mut RenderContext,
+        label: &'static str,
+        destination: &TextureView,
+        pipeline: &RenderPipeline,
+        bind_group: &BindGroup,
+        dynamic_offsets: &[u32],
+    ) {
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(RenderPassColorAttachment {
+                view: destination,
+                resolve_target: None,
+                ops: Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, bind_group, dynamic_offsets);
+        render_pass.draw(0..3, 0..1);
     }
 }
@@ -0,0 +1,134 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+struct HighlightSettings {
+    // `active` is a reserved word in WGSL
+    selected_color: vec4<f32>,
+    active_color: vec4<f32>,
+    hovered_color: vec4<f32>,
+    width: f32,
+}
+
+struct JumpFloodStep {
+    step: f32,
+    _padding: vec3<f32>,
+}
+
+// Seed pass
+
+// Selected, active and hovered entities are drawn in the red, green and blue channels
+@group(0) @binding(0) var mask_texture: texture_2d<f32>;
+#ifdef DEPTH_TEST
+#ifdef MULTISAMPLED
+@group(0) @binding(1) var mask_depth: texture_depth_multisampled_2d;
+@group(0) @binding(2) var scene_depth: texture_depth_multisampled_2d;
+#else
+@group(0) @binding(1) var mask_depth: texture_depth_2d;
+@group(0) @binding(2) var scene_depth: texture_depth_2d;
+#endif
+#endif
+
+// Highlighted meshes are drawn with the same transforms in both passes, so a visible mask pixel
+// has the scene depth up to rounding
+const DEPTH_EPSILON: f32 = 1e-4;
+
+const NO_SEED: vec4<f32> = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+
+@fragment
+fn seed(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let mask = textureLoad(mask_texture, pixel, 0).rgb;
+
+    // Active wins over selected, selected over hovered
+    var kind = 0.0;
+    if mask.g > 0.5 {
+        kind = 1.0;
+    } else if mask.r > 0.5 {
+        kind = 0.0;
+    } else if mask.b > 0.5 {
+        kind = 2.0;
+    } else {
+        return NO_SEED;
+    }
+
+#ifdef DEPTH_TEST
+    // Reversed depth, greater is closer. Pixels hidden behind other geometry get no outline
+    let depth = textureLoad(mask_depth, pixel, 0);
+    if depth < textureLoad(scene_depth, pixel, 0) * (1.0 - DEPTH_EPSILON) {
+        return NO_SEED;
+    }
+#endif
+
+    return vec4<f32>(0.0, 0.0, kind, 1.0);
+}
+
+// Jump flood pass
+
+@group(0) @binding(0) var flood_texture: texture_2d<f32>;
+@group(0) @binding(1) var<uniform> jump: JumpFloodStep;
+
+@fragment
+fn flood(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let size = vec2<i32>(textureDimensions(flood_texture));
+    let step = i32(jump.step);
+
+    var closest = textureLoad(flood_texture, pixel, 0);
+    var closest_distance = select(1e20, dot(closest.xy, closest.xy), closest.w > 0.5);
+
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let offset = vec2<i32>(x, y) * step;
+            let neighbour = pixel + offset;
+            if (x == 0 && y == 0) || any(neighbour < vec2<i32>(0)) || any(neighbour >= size) {
+                continue;
+            }
+
+            let candidate = textureLoad(flood_texture, neighbour, 0);
+            if candidate.w < 0.5 {
+                continue;
+            }
+
+            // The neighbour stores the offset from itself to its seed
+            let seed_offset = candidate.xy + vec2<f32>(offset);
+            let distance = dot(seed_offset, seed_offset);
+            if distance < closest_distance {
+                closest = vec4<f32>(seed_offset, candidate.z, 1.0);
+                closest_distance = distance;
+            }
+        }
+    }
+
+    return closest;
+}
+
+// Outline pass
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+@group(0) @binding(2) var seed_texture: texture_2d<f32>;
+@group(0) @binding(3) var<uniform> settings: HighlightSettings;
+
+@fragment
+fn outline(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    let scene = textureSample(screen_texture, texture_sampler, in.uv);
+    let closest = textureLoad(seed_texture, vec2<i32>(in.position.xy), 0);
+
+    let distance = length(closest.xy);
+
+    // No seed nearby, or the pixel is a seed itself
+    if closest.w < 0.5 || distance == 0.0 {
+        return scene;
+    }
+
+    var outline = settings.selected_color;
+    if closest.z > 1.5 {
+        outline = settings.hovered_color;
+    } else if closest.z > 0.5 {
+        outline = settings.active_color;
+    }
+
+    // One pixel of smoothing on the outer edge
+    let coverage = clamp(settings.width + 0.5 - distance, 0.0, 1.0);
+
+    return vec4<f32>(mix(scene.rgb, outline.rgb, coverage * outline.a), scene.a);
+}
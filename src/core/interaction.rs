@@ -1,13 +1,17 @@
 use bevy::{
-    ecs::schedule::common_conditions, math::bounding::RayCast3d, prelude::*, window::PrimaryWindow,
+    ecs::schedule::common_conditions, math::bounding::RayCast3d, prelude::*,
+    transform::TransformSystem,
 };
 use lox::FaceHandle;
 
 use super::{
     editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh, SelectMode},
-    editor::Focused,
+    editor::{Focused, UserSpace},
+    gestures::Pointer,
     pan_orbit_camera::{PanOrbitCameraUpdate, PrimaryCamera},
+    scene_bvh::{self, SceneBoundingVolumeHierarchy},
 };
+use crate::utils;
 
 use wasm_bindgen::prelude::*;
 pub struct InteractionPlugin;
@@ -25,6 +29,10 @@ pub enum InteractionSet {
 #[derive(Component, Deref, DerefMut, Default)]
 pub struct InteractionCache(pub Option<(FaceHandle, Vec3)>);
 
+/// Marks the entity under the cursor in object mode
+#[derive(Component)]
+pub struct Hovered;
+
 #[wasm_bindgen]
 #[derive(Resource, Clone, Debug, PartialEq, Eq, Copy)]
 pub enum InteractionMode {
@@ -35,12 +43,18 @@ pub enum InteractionMode {
 
 impl Plugin for InteractionPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(InteractionMode::Object).add_systems(
-            Update,
-            Self::multi_object_mode_interaction
-                .in_set(InteractionSet::IntersectionTest)
-                .after(PanOrbitCameraUpdate),
-        );
+        app.insert_resource(InteractionMode::Object)
+            .init_resource::<SceneBoundingVolumeHierarchy>()
+            .add_systems(
+                Update,
+                (Self::multi_object_mode_interaction, Self::hover_interaction)
+                    .in_set(InteractionSet::IntersectionTest)
+                    .after(PanOrbitCameraUpdate),
+            )
+            .add_systems(
+                PostUpdate,
+                Self::update_scene_bvh.after(TransformSystem::TransformPropagate),
+            );
     }
 }
 
@@ -51,7 +65,7 @@ impl InteractionPlugin {
         focused: Query<
             (
                 &BoundingVolumeHierarchy,
-                &Transform,
+                &GlobalTransform,
                 &Name,
                 &EditableMesh,
                 Entity,
@@ -61,24 +75,22 @@ impl InteractionPlugin {
         query: Query<
             (
                 &BoundingVolumeHierarchy,
-                &Transform,
+                &GlobalTransform,
                 &Name,
                 &EditableMesh,
                 Entity,
             ),
             Without<Focused>,
         >,
-        mouse: Res<ButtonInput<MouseButton>>,
-        window: Query<&Window, With<PrimaryWindow>>,
+        scene_bvh: Res<SceneBoundingVolumeHierarchy>,
+        pointer: Res<Pointer>,
         camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
     ) {
-        if !mouse.just_pressed(MouseButton::Left) {
+        if !pointer.select_triggered() {
             return;
         }
 
-        let window = window.single();
-
-        let Some(cursor_position) = window.cursor_position() else {
+        let Some(cursor_position) = pointer.position else {
             return;
         };
 
@@ -94,17 +106,14 @@ impl InteractionPlugin {
 
         match *interaction_mode {
             InteractionMode::Object => {
-                // In object mode, exact face and point is not needed, so we use the fast intersection test which is a rough approximation
-                let (mut closest_t, mut closest_entity) = (f32::MAX, None);
-
-                for (bvh, transform, _, _, entity) in query.iter().chain(focused.iter()) {
-                    if let Some(t) = bvh.intersects_ray_at_fast(&ray_cast, transform) {
-                        if t < closest_t {
-                            closest_t = t;
-                            closest_entity = Some(entity);
-                        }
-                    }
-                }
+                let closest_entity = Self::pick_entity(&scene_bvh, &ray_cast, |entity| {
+                    query
+                        .get(entity)
+                        .or_else(|_| focused.get(entity))
+                        .ok()
+                        .map(|(bvh, transform, _, mesh, _)| (bvh, transform, mesh))
+                });
+
                 if let Some(entity) = closest_entity {
                     commands.entity(entity).insert(Focused);
                 }
@@ -134,4 +143,164 @@ impl InteractionPlugin {
             }
         };
     }
+
+    /// Finds the entity hit first by a world-space ray. Candidates come from the scene BVH in the order the
+    /// ray enters their bounds, and each one is confirmed with the exact test against its own BVH.
+    pub fn pick_entity<'a>(
+        scene_bvh: &SceneBoundingVolumeHierarchy,
+        ray: &RayCast3d,
+        mut get: impl FnMut(
+            Entity,
+        ) -> Option<(
+            &'a BoundingVolumeHierarchy,
+            &'a GlobalTransform,
+            &'a EditableMesh,
+        )>,
+    ) -> Option<Entity> {
+        scene_bvh
+            .cast_ray(ray, |entity| {
+                let (bvh, transform, mesh) = get(entity)?;
+                bvh.intersects_ray_at(ray, transform, mesh)
+            })
+            .map(|(entity, _, _)| entity)
+    }
+
+    /// Keeps the scene BVH in sync with the world bounds of every visible user-space mesh, so
+    /// hidden entities can not be picked
+    fn update_scene_bvh(
+        mut scene_bvh: ResMut<SceneBoundingVolumeHierarchy>,
+        changed: Query<
+            (
+                Entity,
+                &BoundingVolumeHierarchy,
+                &GlobalTransform,
+                &InheritedVisibility,
+            ),
+            (
+                With<UserSpace>,
+                Or<(
+                    Changed<GlobalTransform>,
+                    Changed<BoundingVolumeHierarchy>,
+                    Changed<InheritedVisibility>,
+                )>,
+            ),
+        >,
+        mut removed: RemovedComponents<BoundingVolumeHierarchy>,
+    ) {
+        for entity in removed.read() {
+            scene_bvh.remove(entity);
+        }
+
+        for (entity, bvh, transform, visibility) in changed.iter() {
+            match bvh.nodes.first() {
+                Some(root) if visibility.get() => {
+                    scene_bvh
+                        .insert_or_update(entity, scene_bvh::world_aabb(&root.aabb(), transform));
+                }
+                _ => scene_bvh.remove(entity),
+            }
+        }
+    }
+
+    /// Tracks the entity under the cursor in object mode and marks it with [`Hovered`]
+    fn hover_interaction(
+        interaction_mode: Res<InteractionMode>,
+        mut commands: Commands,
+        scene_bvh: Res<SceneBoundingVolumeHierarchy>,
+        query: Query<(&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh)>,
+        hovered: Query<Entity, With<Hovered>>,
+        pointer: Res<Pointer>,
+        mut last_position: Local<Option<Vec2>>,
+        camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+    ) {
+        let moved = pointer.position != *last_position;
+        *last_position = pointer.position;
+
+        let hovered_entity = if *interaction_mode != InteractionMode::Object {
+            None
+        } else if !moved && !scene_bvh.is_changed() {
+            return;
+        } else {
+            let (camera, camera_transform) = camera.single();
+
+            pointer
+                .position
+                .and_then(|position| camera.viewport_to_world(camera_transform, position))
+                .and_then(|ray| {
+                    Self::pick_entity(&scene_bvh, &RayCast3d::from_ray(ray, 1000.0), |entity| {
+                        query.get(entity).ok()
+                    })
+                })
+        };
+
+        for entity in hovered.iter() {
+            if Some(entity) != hovered_entity {
+                commands.entity(entity).remove::<Hovered>();
+            }
+        }
+
+        if let Some(entity) = hovered_entity {
+            if !hovered.contains(entity) {
+                commands.entity(entity).insert(Hovered);
+            }
+        }
+    }
+
+    /// Switches the interaction mode. Modes other than [`InteractionMode::Object`] work on the focused
+    /// entity, so they are refused when there is none. Returns whether the mode was set.
+    pub fn set_interaction_mode(world: &mut World, mode: InteractionMode) -> bool {
+        let mut focused_entity = world.query_filtered::<(), With<Focused>>();
+
+        match &mode {
+            InteractionMode::Object => {}
+            _ => {
+                if focused_entity.get_single(world).is_err() {
+                    return false;
+                }
+            }
+        };
+
+        *world.resource_mut::<InteractionMode>() = mode;
+
+        true
+    }
+
+    /// Collects the user-space entities with at least one vertex inside a screen rectangle of the primary camera.
+    /// `min` and `max` are viewport coordinates.
+    pub fn entities_in_screen_rect(world: &mut World, min: Vec2, max: Vec2) -> Vec<Entity> {
+        let mut camera = world.query_filtered::<(&Camera, &GlobalTransform), With<PrimaryCamera>>();
+        let mut query =
+            world.query::<(&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh)>();
+
+        let Ok((camera, camera_transform)) = camera.get_single(world) else {
+            return vec![];
+        };
+
+        let Some(frustum) = utils::projection::screen_rect_frustum(
+            camera,
+            camera_transform,
+            1000.0,
+            min.min(max),
+            min.max(max),
+        ) else {
+            return vec![];
+        };
+
+        let Some(scene_bvh) = world.get_resource::<SceneBoundingVolumeHierarchy>() else {
+            return vec![];
+        };
+
+        scene_bvh
+            .query_frustum(&frustum)
+            .into_iter()
+            .filter(|entity| {
+                query
+                    .get(world, *entity)
+                    .is_ok_and(|(bvh, transform, mesh)| {
+                        !bvh.vertices_in_frustum(&frustum, transform, mesh)
+                            .is_empty()
+                    })
+            })
+            .collect()
+    }
 }
@@ -0,0 +1,946 @@
+use std::fmt;
+
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+use wasm_bindgen::prelude::*;
+
+use super::{
+    editable_mesh::SelectMode,
+    editor::EditorPlugin,
+    fly_camera::FlyCameraPlugin,
+    interaction::{InteractionMode, InteractionPlugin},
+    pan_orbit_camera::{PanOrbitCameraPlugin, PanOrbitCameraUpdate, ViewAxis},
+    snapping::SnapSettings,
+    tools::{
+        modal::{ModalTransformKind, ModalTransformPlugin},
+        ToolType,
+    },
+};
+
+/// Maps keys, mouse buttons, modifiers and trackpad gestures to editor actions
+pub struct KeymapPlugin;
+
+/// Everything the keymap can trigger. Camera navigation actions are continuous and read by the
+/// camera controller every frame, the others are sent once as [`EditorActionEvent`] when triggered.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum EditorAction {
+    Orbit,
+    Pan,
+    Zoom,
+    MoveTool,
+    RotateTool,
+    ScaleTool,
+    CursorTool,
+    MeasureTool,
+    ClearTool,
+    ModalMove,
+    ModalRotate,
+    ModalScale,
+    ObjectMode,
+    EditMode,
+    SculptMode,
+    ToggleEditMode,
+    SelectVertices,
+    SelectEdges,
+    SelectFaces,
+    FrameSelected,
+    FrameAll,
+    Delete,
+    ToggleProjection,
+    ViewFront,
+    ViewBack,
+    ViewLeft,
+    ViewRight,
+    ViewTop,
+    ViewBottom,
+    ToggleFly,
+    ToggleSnapping,
+    ToggleFps,
+    ToggleStats,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeymapPreset {
+    Default,
+    Blender,
+    Maya,
+}
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+/// Trackpad gestures
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Gesture {
+    /// Two finger scroll, or the mouse wheel
+    Scroll,
+    Pinch,
+    /// Two fingers turning around each other
+    Rotate,
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum InputTrigger {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Gesture(Gesture),
+}
+
+/// A trigger with the exact set of modifiers that must be held with it
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct InputBinding {
+    pub trigger: InputTrigger,
+    pub modifiers: Modifiers,
+}
+
+/// Sent once each time a discrete action is triggered
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct EditorActionEvent(pub EditorAction);
+
+/// The active keymap. A binding maps to a single action, an action may have several bindings.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Keymap {
+    pub bindings: Vec<(InputBinding, EditorAction)>,
+}
+
+/// Present while a modal mode, like fly navigation, reads the keyboard itself.
+/// Only the `passthrough` actions are still triggered.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct InputCapture {
+    pub passthrough: Vec<EditorAction>,
+    /// The mode reads the mouse wheel too, so it does not zoom the camera
+    pub scroll: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum KeymapParseError {
+    /// The line has no `=` between the action and the binding
+    MissingSeparator {
+        line: usize,
+    },
+    UnknownAction {
+        line: usize,
+        name: String,
+    },
+    UnknownInput {
+        line: usize,
+        name: String,
+    },
+}
+
+impl fmt::Display for KeymapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapParseError::MissingSeparator { line } => {
+                write!(f, "line {line}: expected `action = binding`")
+            }
+            KeymapParseError::UnknownAction { line, name } => {
+                write!(f, "line {line}: unknown action `{name}`")
+            }
+            KeymapParseError::UnknownInput { line, name } => {
+                write!(f, "line {line}: unknown input `{name}`")
+            }
+        }
+    }
+}
+
+impl std::error::Error for KeymapParseError {}
+
+const ACTION_NAMES: &[(EditorAction, &str)] = &[
+    (EditorAction::Orbit, "orbit"),
+    (EditorAction::Pan, "pan"),
+    (EditorAction::Zoom, "zoom"),
+    (EditorAction::MoveTool, "move_tool"),
+    (EditorAction::RotateTool, "rotate_tool"),
+    (EditorAction::ScaleTool, "scale_tool"),
+    (EditorAction::CursorTool, "cursor_tool"),
+    (EditorAction::MeasureTool, "measure_tool"),
+    (EditorAction::ClearTool, "clear_tool"),
+    (EditorAction::ModalMove, "modal_move"),
+    (EditorAction::ModalRotate, "modal_rotate"),
+    (EditorAction::ModalScale, "modal_scale"),
+    (EditorAction::ObjectMode, "object_mode"),
+    (EditorAction::EditMode, "edit_mode"),
+    (EditorAction::SculptMode, "sculpt_mode"),
+    (EditorAction::ToggleEditMode, "toggle_edit_mode"),
+    (EditorAction::SelectVertices, "select_vertices"),
+    (EditorAction::SelectEdges, "select_edges"),
+    (EditorAction::SelectFaces, "select_faces"),
+    (EditorAction::FrameSelected, "frame_selected"),
+    (EditorAction::FrameAll, "frame_all"),
+    (EditorAction::Delete, "delete"),
+    (EditorAction::ToggleProjection, "toggle_projection"),
+    (EditorAction::ViewFront, "view_front"),
+    (EditorAction::ViewBack, "view_back"),
+    (EditorAction::ViewLeft, "view_left"),
+    (EditorAction::ViewRight, "view_right"),
+    (EditorAction::ViewTop, "view_top"),
+    (EditorAction::ViewBottom, "view_bottom"),
+    (EditorAction::ToggleFly, "toggle_fly"),
+    (EditorAction::ToggleSnapping, "toggle_snapping"),
+    (EditorAction::ToggleFps, "toggle_fps"),
+    (EditorAction::ToggleStats, "toggle_stats"),
+];
+
+/// Keys that can appear in a serialised keymap, named after their `KeyCode` variant
+const KEY_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::KeyA, "KeyA"),
+    (KeyCode::KeyB, "KeyB"),
+    (KeyCode::KeyC, "KeyC"),
+    (KeyCode::KeyD, "KeyD"),
+    (KeyCode::KeyE, "KeyE"),
+    (KeyCode::KeyF, "KeyF"),
+    (KeyCode::KeyG, "KeyG"),
+    (KeyCode::KeyH, "KeyH"),
+    (KeyCode::KeyI, "KeyI"),
+    (KeyCode::KeyJ, "KeyJ"),
+    (KeyCode::KeyK, "KeyK"),
+    (KeyCode::KeyL, "KeyL"),
+    (KeyCode::KeyM, "KeyM"),
+    (KeyCode::KeyN, "KeyN"),
+    (KeyCode::KeyO, "KeyO"),
+    (KeyCode::KeyP, "KeyP"),
+    (KeyCode::KeyQ, "KeyQ"),
+    (KeyCode::KeyR, "KeyR"),
+    (KeyCode::KeyS, "KeyS"),
+    (KeyCode::KeyT, "KeyT"),
+    (KeyCode::KeyU, "KeyU"),
+    (KeyCode::KeyV, "KeyV"),
+    (KeyCode::KeyW, "KeyW"),
+    (KeyCode::KeyX, "KeyX"),
+    (KeyCode::KeyY, "KeyY"),
+    (KeyCode::KeyZ, "KeyZ"),
+    (KeyCode::Digit0, "Digit0"),
+    (KeyCode::Digit1, "Digit1"),
+    (KeyCode::Digit2, "Digit2"),
+    (KeyCode::Digit3, "Digit3"),
+    (KeyCode::Digit4, "Digit4"),
+    (KeyCode::Digit5, "Digit5"),
+    (KeyCode::Digit6, "Digit6"),
+    (KeyCode::Digit7, "Digit7"),
+    (KeyCode::Digit8, "Digit8"),
+    (KeyCode::Digit9, "Digit9"),
+    (KeyCode::Numpad0, "Numpad0"),
+    (KeyCode::Numpad1, "Numpad1"),
+    (KeyCode::Numpad2, "Numpad2"),
+    (KeyCode::Numpad3, "Numpad3"),
+    (KeyCode::Numpad4, "Numpad4"),
+    (KeyCode::Numpad5, "Numpad5"),
+    (KeyCode::Numpad6, "Numpad6"),
+    (KeyCode::Numpad7, "Numpad7"),
+    (KeyCode::Numpad8, "Numpad8"),
+    (KeyCode::Numpad9, "Numpad9"),
+    (KeyCode::NumpadDecimal, "NumpadDecimal"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::ArrowUp, "ArrowUp"),
+    (KeyCode::ArrowDown, "ArrowDown"),
+    (KeyCode::ArrowLeft, "ArrowLeft"),
+    (KeyCode::ArrowRight, "ArrowRight"),
+    (KeyCode::Period, "Period"),
+    (KeyCode::Comma, "Comma"),
+    (KeyCode::Backquote, "Backquote"),
+];
+
+const MOUSE_NAMES: &[(MouseButton, &str)] = &[
+    (MouseButton::Left, "MouseLeft"),
+    (MouseButton::Right, "MouseRight"),
+    (MouseButton::Middle, "MouseMiddle"),
+    (MouseButton::Back, "MouseBack"),
+    (MouseButton::Forward, "MouseForward"),
+];
+
+const GESTURE_NAMES: &[(Gesture, &str)] = &[
+    (Gesture::Scroll, "Scroll"),
+    (Gesture::Pinch, "Pinch"),
+    (Gesture::Rotate, "Rotate"),
+];
+
+impl EditorAction {
+    /// Actions held down over several frames rather than triggered once
+    pub fn is_continuous(&self) -> bool {
+        matches!(
+            self,
+            EditorAction::Orbit | EditorAction::Pan | EditorAction::Zoom
+        )
+    }
+
+    pub fn name(&self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(action, _)| action == self)
+            .map(|(_, name)| *name)
+            .unwrap()
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTION_NAMES
+            .iter()
+            .find(|(_, action_name)| *action_name == name)
+            .map(|(action, _)| *action)
+    }
+}
+
+impl Modifiers {
+    pub const NONE: Self = Self {
+        ctrl: false,
+        shift: false,
+        alt: false,
+        meta: false,
+    };
+
+    pub const CTRL: Self = Self {
+        ctrl: true,
+        ..Self::NONE
+    };
+
+    pub const SHIFT: Self = Self {
+        shift: true,
+        ..Self::NONE
+    };
+
+    pub const ALT: Self = Self {
+        alt: true,
+        ..Self::NONE
+    };
+
+    /// Modifiers currently held
+    pub fn from_keys(keys: &ButtonInput<KeyCode>) -> Self {
+        Self {
+            ctrl: keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]),
+            shift: keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            alt: keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]),
+            meta: keys.any_pressed([KeyCode::SuperLeft, KeyCode::SuperRight]),
+        }
+    }
+}
+
+impl InputBinding {
+    pub fn new(trigger: InputTrigger, modifiers: Modifiers) -> Self {
+        Self { trigger, modifiers }
+    }
+
+    pub fn key(key: KeyCode) -> Self {
+        Self::new(InputTrigger::Key(key), Modifiers::NONE)
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self::new(InputTrigger::Mouse(button), Modifiers::NONE)
+    }
+
+    pub fn gesture(gesture: Gesture) -> Self {
+        Self::new(InputTrigger::Gesture(gesture), Modifiers::NONE)
+    }
+
+    pub fn with(self, modifiers: Modifiers) -> Self {
+        Self { modifiers, ..self }
+    }
+
+    /// Whether this is a plain left click, which also selects in the viewport
+    pub fn is_selection_button(&self) -> bool {
+        self.trigger == InputTrigger::Mouse(MouseButton::Left) && self.modifiers == Modifiers::NONE
+    }
+
+    /// Held down with exactly its modifiers. Gestures are never held.
+    pub fn pressed(
+        &self,
+        modifiers: Modifiers,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> bool {
+        self.modifiers == modifiers
+            && match self.trigger {
+                InputTrigger::Key(key) => keys.pressed(key),
+                InputTrigger::Mouse(button) => mouse.pressed(button),
+                InputTrigger::Gesture(_) => false,
+            }
+    }
+
+    /// Pressed this frame with exactly its modifiers
+    pub fn just_pressed(
+        &self,
+        modifiers: Modifiers,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> bool {
+        self.modifiers == modifiers
+            && match self.trigger {
+                InputTrigger::Key(key) => keys.just_pressed(key),
+                InputTrigger::Mouse(button) => mouse.just_pressed(button),
+                InputTrigger::Gesture(_) => false,
+            }
+    }
+
+    /// Parses `Ctrl+Shift+KeyA` style bindings
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut modifiers = Modifiers::NONE;
+        let mut trigger = None;
+
+        for part in text.split('+').map(str::trim) {
+            match part {
+                "Ctrl" => modifiers.ctrl = true,
+                "Shift" => modifiers.shift = true,
+                "Alt" => modifiers.alt = true,
+                "Meta" => modifiers.meta = true,
+                name if trigger.is_none() => trigger = Some(parse_trigger(name)?),
+                _ => return None,
+            }
+        }
+
+        Some(Self::new(trigger?, modifiers))
+    }
+}
+
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl+"),
+            (self.modifiers.shift, "Shift+"),
+            (self.modifiers.alt, "Alt+"),
+            (self.modifiers.meta, "Meta+"),
+        ];
+        for (held, name) in modifiers {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+
+        let trigger = match self.trigger {
+            InputTrigger::Key(key) => lookup_name(KEY_NAMES, &key),
+            InputTrigger::Mouse(button) => lookup_name(MOUSE_NAMES, &button),
+            InputTrigger::Gesture(gesture) => lookup_name(GESTURE_NAMES, &gesture),
+        };
+
+        match trigger {
+            Some(name) => f.write_str(name),
+            None => write!(f, "{:?}", self.trigger),
+        }
+    }
+}
+
+fn lookup_name<T: PartialEq>(table: &[(T, &'static str)], value: &T) -> Option<&'static str> {
+    table
+        .iter()
+        .find(|(entry, _)| entry == value)
+        .map(|(_, name)| *name)
+}
+
+fn lookup_value<T: Copy>(table: &[(T, &'static str)], name: &str) -> Option<T> {
+    table
+        .iter()
+        .find(|(_, entry)| *entry == name)
+        .map(|(value, _)| *value)
+}
+
+fn parse_trigger(name: &str) -> Option<InputTrigger> {
+    lookup_value(KEY_NAMES, name)
+        .map(InputTrigger::Key)
+        .or_else(|| lookup_value(MOUSE_NAMES, name).map(InputTrigger::Mouse))
+        .or_else(|| lookup_value(GESTURE_NAMES, name).map(InputTrigger::Gesture))
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::preset(KeymapPreset::Default)
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: KeymapPreset) -> Self {
+        use EditorAction as A;
+        use InputBinding as B;
+
+        // Bound the same way in every preset
+        let shared = [
+            (B::key(KeyCode::Numpad1), A::ViewFront),
+            (B::key(KeyCode::Numpad1).with(Modifiers::CTRL), A::ViewBack),
+            (B::key(KeyCode::Numpad3), A::ViewRight),
+            (B::key(KeyCode::Numpad3).with(Modifiers::CTRL), A::ViewLeft),
+            (B::key(KeyCode::Numpad7), A::ViewTop),
+            (
+                B::key(KeyCode::Numpad7).with(Modifiers::CTRL),
+                A::ViewBottom,
+            ),
+            (B::key(KeyCode::Numpad5), A::ToggleProjection),
+            (B::gesture(Gesture::Rotate), A::Orbit),
+            (
+                B::key(KeyCode::Tab).with(Modifiers::SHIFT),
+                A::ToggleSnapping,
+            ),
+            (
+                B::key(KeyCode::Backquote).with(Modifiers::SHIFT),
+                A::ToggleFly,
+            ),
+        ];
+
+        let bindings = match preset {
+            KeymapPreset::Default => vec![
+                // Left drags only orbit from the navigation zone, see `PanOrbitCameraPlugin`
+                (B::mouse(MouseButton::Left), A::Orbit),
+                (B::mouse(MouseButton::Right), A::Pan),
+                (B::gesture(Gesture::Scroll), A::Zoom),
+                (B::gesture(Gesture::Pinch), A::Zoom),
+                (B::key(KeyCode::KeyW), A::MoveTool),
+                (B::key(KeyCode::KeyE), A::RotateTool),
+                (B::key(KeyCode::KeyR), A::ScaleTool),
+                (B::key(KeyCode::KeyC), A::CursorTool),
+                (B::key(KeyCode::KeyM), A::MeasureTool),
+                (B::key(KeyCode::KeyQ), A::ClearTool),
+                (B::key(KeyCode::KeyG), A::ModalMove),
+                (B::key(KeyCode::KeyE).with(Modifiers::SHIFT), A::ModalRotate),
+                (B::key(KeyCode::KeyR).with(Modifiers::SHIFT), A::ModalScale),
+                (B::key(KeyCode::Tab), A::ToggleEditMode),
+                (B::key(KeyCode::Digit1), A::SelectVertices),
+                (B::key(KeyCode::Digit2), A::SelectEdges),
+                (B::key(KeyCode::Digit3), A::SelectFaces),
+                (B::key(KeyCode::KeyF), A::FrameSelected),
+                (B::key(KeyCode::Home), A::FrameAll),
+                (B::key(KeyCode::Delete), A::Delete),
+                (B::key(KeyCode::Backspace), A::Delete),
+                (B::key(KeyCode::F12), A::ToggleFps),
+                (B::key(KeyCode::F12).with(Modifiers::SHIFT), A::ToggleStats),
+            ],
+            KeymapPreset::Blender => vec![
+                (B::mouse(MouseButton::Middle), A::Orbit),
+                (B::mouse(MouseButton::Middle).with(Modifiers::SHIFT), A::Pan),
+                (B::mouse(MouseButton::Middle).with(Modifiers::CTRL), A::Zoom),
+                (B::gesture(Gesture::Scroll), A::Zoom),
+                (B::gesture(Gesture::Pinch), A::Zoom),
+                (B::key(KeyCode::KeyG), A::ModalMove),
+                (B::key(KeyCode::KeyR), A::ModalRotate),
+                (B::key(KeyCode::KeyS), A::ModalScale),
+                (B::key(KeyCode::KeyG).with(Modifiers::SHIFT), A::MoveTool),
+                (B::key(KeyCode::KeyR).with(Modifiers::SHIFT), A::RotateTool),
+                (B::key(KeyCode::KeyS).with(Modifiers::SHIFT), A::ScaleTool),
+                (
+                    B::mouse(MouseButton::Right).with(Modifiers::SHIFT),
+                    A::CursorTool,
+                ),
+                (B::key(KeyCode::KeyM).with(Modifiers::SHIFT), A::MeasureTool),
+                (B::key(KeyCode::Escape), A::ClearTool),
+                (B::key(KeyCode::Tab), A::ToggleEditMode),
+                (B::key(KeyCode::Digit1), A::SelectVertices),
+                (B::key(KeyCode::Digit2), A::SelectEdges),
+                (B::key(KeyCode::Digit3), A::SelectFaces),
+                (B::key(KeyCode::NumpadDecimal), A::FrameSelected),
+                (B::key(KeyCode::Home), A::FrameAll),
+                (B::key(KeyCode::KeyX), A::Delete),
+                (B::key(KeyCode::Delete), A::Delete),
+                (B::key(KeyCode::F12), A::ToggleFps),
+                (B::key(KeyCode::F12).with(Modifiers::SHIFT), A::ToggleStats),
+            ],
+            KeymapPreset::Maya => vec![
+                (B::mouse(MouseButton::Left).with(Modifiers::ALT), A::Orbit),
+                (B::mouse(MouseButton::Middle).with(Modifiers::ALT), A::Pan),
+                (B::mouse(MouseButton::Right).with(Modifiers::ALT), A::Zoom),
+                (B::gesture(Gesture::Scroll), A::Zoom),
+                (B::gesture(Gesture::Pinch), A::Zoom),
+                (B::key(KeyCode::KeyW), A::MoveTool),
+                (B::key(KeyCode::KeyE), A::RotateTool),
+                (B::key(KeyCode::KeyR), A::ScaleTool),
+                (B::key(KeyCode::KeyD), A::CursorTool),
+                (B::key(KeyCode::KeyM), A::MeasureTool),
+                (B::key(KeyCode::KeyQ), A::ClearTool),
+                (B::key(KeyCode::KeyW).with(Modifiers::SHIFT), A::ModalMove),
+                (B::key(KeyCode::KeyE).with(Modifiers::SHIFT), A::ModalRotate),
+                (B::key(KeyCode::KeyR).with(Modifiers::SHIFT), A::ModalScale),
+                (B::key(KeyCode::F8), A::ToggleEditMode),
+                (B::key(KeyCode::F9), A::SelectVertices),
+                (B::key(KeyCode::F10), A::SelectEdges),
+                (B::key(KeyCode::F11), A::SelectFaces),
+                (B::key(KeyCode::KeyF), A::FrameSelected),
+                (B::key(KeyCode::KeyA), A::FrameAll),
+                (B::key(KeyCode::Delete), A::Delete),
+                (B::key(KeyCode::Backspace), A::Delete),
+                (B::key(KeyCode::F12), A::ToggleFps),
+                (B::key(KeyCode::F12).with(Modifiers::SHIFT), A::ToggleStats),
+            ],
+        };
+
+        let mut keymap = Self { bindings: vec![] };
+        for (binding, action) in bindings.into_iter().chain(shared) {
+            keymap.bind(binding, action);
+        }
+        keymap
+    }
+
+    /// Binds an input to an action, taking it away from any other action it was bound to
+    pub fn bind(&mut self, binding: InputBinding, action: EditorAction) {
+        self.bindings.retain(|(existing, _)| *existing != binding);
+        self.bindings.push((binding, action));
+    }
+
+    /// Replaces all bindings of an action
+    pub fn rebind(
+        &mut self,
+        action: EditorAction,
+        bindings: impl IntoIterator<Item = InputBinding>,
+    ) {
+        self.unbind_action(action);
+        for binding in bindings {
+            self.bind(binding, action);
+        }
+    }
+
+    pub fn unbind_action(&mut self, action: EditorAction) {
+        self.bindings.retain(|(_, existing)| *existing != action);
+    }
+
+    pub fn bindings_for(&self, action: EditorAction) -> impl Iterator<Item = &InputBinding> {
+        self.bindings
+            .iter()
+            .filter(move |(_, existing)| *existing == action)
+            .map(|(binding, _)| binding)
+    }
+
+    pub fn action_for(&self, binding: &InputBinding) -> Option<EditorAction> {
+        self.bindings
+            .iter()
+            .find(|(existing, _)| existing == binding)
+            .map(|(_, action)| *action)
+    }
+
+    /// The binding of a continuous action currently held down, if any
+    pub fn held_binding(
+        &self,
+        action: EditorAction,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> Option<InputBinding> {
+        let modifiers = Modifiers::from_keys(keys);
+        self.bindings_for(action)
+            .find(|binding| binding.pressed(modifiers, keys, mouse))
+            .copied()
+    }
+
+    /// Whether a binding of the action was pressed this frame
+    pub fn just_pressed(
+        &self,
+        action: EditorAction,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> bool {
+        let modifiers = Modifiers::from_keys(keys);
+        self.bindings_for(action)
+            .any(|binding| binding.just_pressed(modifiers, keys, mouse))
+    }
+
+    /// Action the gesture drives with the modifiers currently held
+    pub fn gesture_action(&self, gesture: Gesture, modifiers: Modifiers) -> Option<EditorAction> {
+        self.action_for(&InputBinding::new(
+            InputTrigger::Gesture(gesture),
+            modifiers,
+        ))
+    }
+
+    /// Discrete actions triggered this frame
+    pub fn triggered_actions(
+        &self,
+        keys: &ButtonInput<KeyCode>,
+        mouse: &ButtonInput<MouseButton>,
+    ) -> Vec<EditorAction> {
+        let modifiers = Modifiers::from_keys(keys);
+        self.bindings
+            .iter()
+            .filter(|(binding, action)| {
+                !action.is_continuous() && binding.just_pressed(modifiers, keys, mouse)
+            })
+            .map(|(_, action)| *action)
+            .collect()
+    }
+
+    /// One `action = binding` line per binding
+    pub fn to_text(&self) -> String {
+        self.bindings
+            .iter()
+            .map(|(binding, action)| format!("{} = {binding}\n", action.name()))
+            .collect()
+    }
+
+    /// Reads the format written by [`Self::to_text`]. Blank lines and lines starting with `#` are skipped.
+    pub fn from_text(text: &str) -> Result<Self, KeymapParseError> {
+        let mut keymap = Self { bindings: vec![] };
+
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let Some((action, binding)) = line.split_once('=') else {
+                return Err(KeymapParseError::MissingSeparator { line: line_number });
+            };
+
+            let action = EditorAction::from_name(action.trim()).ok_or_else(|| {
+                KeymapParseError::UnknownAction {
+                    line: line_number,
+                    name: action.trim().to_string(),
+                }
+            })?;
+            let binding =
+                InputBinding::parse(binding).ok_or_else(|| KeymapParseError::UnknownInput {
+                    line: line_number,
+                    name: binding.trim().to_string(),
+                })?;
+
+            keymap.bind(binding, action);
+        }
+
+        Ok(keymap)
+    }
+}
+
+#[derive(SystemSet, Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct KeymapUpdate;
+
+impl Plugin for KeymapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Keymap>()
+            .add_event::<EditorActionEvent>()
+            .add_systems(
+                Update,
+                (Self::dispatch_actions, Self::apply_actions)
+                    .chain()
+                    .in_set(KeymapUpdate)
+                    .before(PanOrbitCameraUpdate),
+            );
+    }
+}
+
+impl KeymapPlugin {
+    fn dispatch_actions(
+        keymap: Res<Keymap>,
+        capture: Option<Res<InputCapture>>,
+        keys: Res<ButtonInput<KeyCode>>,
+        mouse: Res<ButtonInput<MouseButton>>,
+        mut actions: EventWriter<EditorActionEvent>,
+    ) {
+        actions.send_batch(
+            keymap
+                .triggered_actions(&keys, &mouse)
+                .into_iter()
+                .filter(|action| {
+                    capture
+                        .as_ref()
+                        .map_or(true, |capture| capture.passthrough.contains(action))
+                })
+                .map(EditorActionEvent),
+        );
+    }
+
+    /// Runs the actions working on the whole world. Others, like [`EditorAction::ToggleFps`],
+    /// are handled by their plugin reading [`EditorActionEvent`].
+    fn apply_actions(world: &mut World, mut reader: Local<ManualEventReader<EditorActionEvent>>) {
+        let actions: Vec<EditorAction> = reader
+            .read(world.resource::<Events<EditorActionEvent>>())
+            .map(|event| event.0)
+            .collect();
+
+        for action in actions {
+            Self::apply_action(world, action);
+        }
+    }
+
+    pub fn apply_action(world: &mut World, action: EditorAction) {
+        match action {
+            EditorAction::MoveTool => EditorPlugin::set_active_tool(world, ToolType::Move),
+            EditorAction::RotateTool => EditorPlugin::set_active_tool(world, ToolType::Rotate),
+            EditorAction::ScaleTool => EditorPlugin::set_active_tool(world, ToolType::Scale),
+            EditorAction::CursorTool => EditorPlugin::set_active_tool(world, ToolType::Cursor),
+            EditorAction::MeasureTool => EditorPlugin::set_active_tool(world, ToolType::Measure),
+            EditorAction::ClearTool => EditorPlugin::unset_active_tool(world),
+            EditorAction::ModalMove => {
+                ModalTransformPlugin::begin(world, ModalTransformKind::Move);
+            }
+            EditorAction::ModalRotate => {
+                ModalTransformPlugin::begin(world, ModalTransformKind::Rotate);
+            }
+            EditorAction::ModalScale => {
+                ModalTransformPlugin::begin(world, ModalTransformKind::Scale);
+            }
+            EditorAction::ObjectMode => {
+                InteractionPlugin::set_interaction_mode(world, InteractionMode::Object);
+            }
+            EditorAction::EditMode => {
+                InteractionPlugin::set_interaction_mode(world, InteractionMode::Edit);
+            }
+            EditorAction::SculptMode => {
+                InteractionPlugin::set_interaction_mode(world, InteractionMode::Sculpt);
+            }
+            EditorAction::ToggleEditMode => {
+                let mode = match *world.resource::<InteractionMode>() {
+                    InteractionMode::Object => InteractionMode::Edit,
+                    _ => InteractionMode::Object,
+                };
+                InteractionPlugin::set_interaction_mode(world, mode);
+            }
+            EditorAction::SelectVertices => {
+                *world.resource_mut::<SelectMode>() = SelectMode::Vertices
+            }
+            EditorAction::SelectEdges => *world.resource_mut::<SelectMode>() = SelectMode::Edges,
+            EditorAction::SelectFaces => *world.resource_mut::<SelectMode>() = SelectMode::Faces,
+            EditorAction::FrameSelected => EditorPlugin::frame_selected(world),
+            EditorAction::FrameAll => EditorPlugin::frame_all(world),
+            EditorAction::Delete => EditorPlugin::delete_selected(world),
+            EditorAction::ToggleProjection => PanOrbitCameraPlugin::toggle_projection(world),
+            EditorAction::ViewFront => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Front),
+            EditorAction::ViewBack => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Back),
+            EditorAction::ViewLeft => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Left),
+            EditorAction::ViewRight => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Right),
+            EditorAction::ViewTop => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Top),
+            EditorAction::ViewBottom => PanOrbitCameraPlugin::snap_to_axis(world, ViewAxis::Bottom),
+            EditorAction::ToggleFly => FlyCameraPlugin::toggle(world),
+            EditorAction::ToggleSnapping => {
+                let mut settings = world.resource_mut::<SnapSettings>();
+                settings.enabled = !settings.enabled;
+            }
+            EditorAction::Orbit
+            | EditorAction::Pan
+            | EditorAction::Zoom
+            | EditorAction::ToggleFps
+            | EditorAction::ToggleStats => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{
+        EditorAction, Gesture, InputBinding, Keymap, KeymapParseError, KeymapPreset, Modifiers,
+    };
+
+    #[test]
+    fn test_presets_round_trip_through_text() {
+        for preset in [
+            KeymapPreset::Default,
+            KeymapPreset::Blender,
+            KeymapPreset::Maya,
+        ] {
+            let keymap = Keymap::preset(preset);
+            let parsed = Keymap::from_text(&keymap.to_text()).unwrap();
+
+            assert_eq!(parsed, keymap, "{preset:?}");
+        }
+    }
+
+    #[test]
+    fn test_binding_belongs_to_one_action() {
+        let mut keymap = Keymap::preset(KeymapPreset::Default);
+        let key_w = InputBinding::key(KeyCode::KeyW);
+
+        assert_eq!(keymap.action_for(&key_w), Some(EditorAction::MoveTool));
+
+        keymap.bind(key_w, EditorAction::FrameAll);
+        assert_eq!(keymap.action_for(&key_w), Some(EditorAction::FrameAll));
+        assert_eq!(keymap.bindings_for(EditorAction::MoveTool).count(), 0);
+
+        keymap.rebind(
+            EditorAction::Delete,
+            [InputBinding::key(KeyCode::KeyX).with(Modifiers::CTRL)],
+        );
+        let delete: Vec<_> = keymap.bindings_for(EditorAction::Delete).collect();
+        assert_eq!(delete.len(), 1);
+        assert_eq!(delete[0].to_string(), "Ctrl+KeyX");
+    }
+
+    #[test]
+    fn test_modifiers_must_match_exactly() {
+        let keymap = Keymap::preset(KeymapPreset::Blender);
+
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mut mouse = ButtonInput::<MouseButton>::default();
+        mouse.press(MouseButton::Middle);
+
+        assert!(keymap
+            .held_binding(EditorAction::Orbit, &keys, &mouse)
+            .is_some());
+        assert!(keymap
+            .held_binding(EditorAction::Pan, &keys, &mouse)
+            .is_none());
+
+        keys.press(KeyCode::ShiftLeft);
+        assert!(keymap
+            .held_binding(EditorAction::Orbit, &keys, &mouse)
+            .is_none());
+        assert!(keymap
+            .held_binding(EditorAction::Pan, &keys, &mouse)
+            .is_some());
+
+        assert_eq!(
+            keymap.gesture_action(Gesture::Scroll, Modifiers::NONE),
+            Some(EditorAction::Zoom)
+        );
+        assert_eq!(
+            keymap.gesture_action(Gesture::Scroll, Modifiers::SHIFT),
+            None
+        );
+    }
+
+    #[test]
+    fn test_triggered_actions() {
+        let keymap = Keymap::preset(KeymapPreset::Default);
+
+        let mut keys = ButtonInput::<KeyCode>::default();
+        let mouse = ButtonInput::<MouseButton>::default();
+
+        keys.press(KeyCode::ControlLeft);
+        keys.press(KeyCode::Numpad1);
+
+        assert_eq!(
+            keymap.triggered_actions(&keys, &mouse),
+            vec![EditorAction::ViewBack]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Keymap::from_text("# comment\n\nmove_tool KeyW"),
+            Err(KeymapParseError::MissingSeparator { line: 3 })
+        );
+        assert_eq!(
+            Keymap::from_text("teleport = KeyT"),
+            Err(KeymapParseError::UnknownAction {
+                line: 1,
+                name: "teleport".to_string()
+            })
+        );
+        assert_eq!(
+            Keymap::from_text("move_tool = Ctrl+Hyper"),
+            Err(KeymapParseError::UnknownInput {
+                line: 1,
+                name: "Ctrl+Hyper".to_string()
+            })
+        );
+        assert!(InputBinding::parse("Ctrl+KeyA+KeyB").is_none());
+    }
+}
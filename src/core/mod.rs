@@ -1,11 +1,23 @@
+pub mod camera_bookmarks;
+pub mod edit_overlay;
 pub mod editable_mesh;
 pub mod editor;
+pub mod fly_camera;
+pub mod formats;
 mod fps;
+pub mod gestures;
 mod gizmos;
+mod navigation_gizmo;
 pub mod grid;
-mod pan_orbit_camera;
+pub mod pan_orbit_camera;
+pub mod project;
 
 mod dim3;
 pub mod interaction;
+pub mod keymap;
 pub mod tools;
-pub mod highlight;
\ No newline at end of file
+pub mod highlight;
+pub mod scene_bvh;
+pub mod shading;
+pub mod snapping;
+pub mod stats;
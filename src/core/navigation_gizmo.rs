@@ -0,0 +1,177 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use super::{
+    gizmos::{
+        CustomGizmo, GizmoCamera, GizmoColors, GizmoPlaneDistance, GizmoScaleToViewportRatio,
+    },
+    pan_orbit_camera::{
+        PanOrbitCameraPlugin, PanOrbitCameraUpdate, ViewAxis, NAVIGATION_ZONE_SIZE,
+    },
+};
+
+/// Axis widget drawn in the orbit zone of the viewport. Clicking one of its handles snaps the camera to that side.
+pub struct NavigationGizmoPlugin;
+
+/// Length of the axes in pixels
+const AXIS_LENGTH: f32 = 50.0;
+/// Radius of the clickable handles in pixels
+const HANDLE_RADIUS: f32 = 9.0;
+/// How far the cursor may move between press and release for it to still count as a click
+const CLICK_TOLERANCE: f32 = 4.0;
+
+impl Plugin for NavigationGizmoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (Self::draw_navigation_gizmo, Self::handle_navigation_click)
+                .after(PanOrbitCameraUpdate),
+        );
+    }
+}
+
+impl NavigationGizmoPlugin {
+    /// World-space center of the widget and the position of each handle.
+    /// The widget is placed on the gizmo plane, where one pixel is `scale` world units.
+    fn handles(
+        window: &Window,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        plane_distance: f32,
+        scale: f32,
+    ) -> Option<(Vec3, [(ViewAxis, Vec3); 6])> {
+        let center = Vec2::new(
+            window.width() - NAVIGATION_ZONE_SIZE / 2.0,
+            NAVIGATION_ZONE_SIZE / 2.0,
+        );
+        let ray = camera.viewport_to_world(camera_transform, center)?;
+
+        let depth = plane_distance / ray.direction.dot(camera_transform.forward());
+        let center = ray.get_point(depth);
+
+        Some((
+            center,
+            ViewAxis::ALL.map(|axis| (axis, center + axis.direction() * AXIS_LENGTH * scale)),
+        ))
+    }
+
+    fn axis_color(axis: ViewAxis, colors: &GizmoColors) -> Color {
+        match axis {
+            ViewAxis::Right => colors.red,
+            ViewAxis::Left => colors.dark_red,
+            ViewAxis::Top => colors.green,
+            ViewAxis::Bottom => colors.dark_green,
+            ViewAxis::Front => colors.blue,
+            ViewAxis::Back => colors.dark_blue,
+        }
+    }
+
+    /// Handle under a viewport position, if any
+    fn handle_at(
+        position: Vec2,
+        handles: &[(ViewAxis, Vec3)],
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+    ) -> Option<ViewAxis> {
+        handles
+            .iter()
+            .filter_map(|(axis, handle)| {
+                let handle = camera.world_to_viewport(camera_transform, *handle)?;
+                let distance = handle.distance(position);
+                (distance <= HANDLE_RADIUS).then_some((*axis, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(axis, _)| axis)
+    }
+
+    fn draw_navigation_gizmo(
+        mut gizmos: Gizmos<CustomGizmo>,
+        window: Query<&Window, With<PrimaryWindow>>,
+        camera: Query<(&Camera, &GlobalTransform), With<GizmoCamera>>,
+        plane_distance: Res<GizmoPlaneDistance>,
+        scale: Res<GizmoScaleToViewportRatio>,
+        colors: Res<GizmoColors>,
+    ) {
+        let (Ok(window), Ok((camera, camera_transform))) =
+            (window.get_single(), camera.get_single())
+        else {
+            return;
+        };
+
+        let Some((center, handles)) =
+            Self::handles(window, camera, camera_transform, plane_distance.0, scale.0)
+        else {
+            return;
+        };
+
+        let hovered = window
+            .cursor_position()
+            .and_then(|cursor| Self::handle_at(cursor, &handles, camera, camera_transform));
+
+        let Ok(facing) = Direction3d::new(camera_transform.back()) else {
+            return;
+        };
+
+        for (axis, position) in handles {
+            let color = Self::axis_color(axis, &colors);
+            let radius = HANDLE_RADIUS * scale.0;
+
+            // Only positive axes get a line, negative ones are just a handle
+            if matches!(axis, ViewAxis::Right | ViewAxis::Top | ViewAxis::Front) {
+                gizmos.line(center, position, color);
+                gizmos.circle(position, facing, radius, color);
+            } else {
+                gizmos.circle(position, facing, radius * 0.75, color);
+            }
+
+            if hovered == Some(axis) {
+                gizmos.circle(position, facing, radius * 1.25, Color::WHITE);
+            }
+        }
+    }
+
+    fn handle_navigation_click(
+        mut commands: Commands,
+        mut press_position: Local<Option<Vec2>>,
+        mouse: Res<ButtonInput<MouseButton>>,
+        window: Query<&Window, With<PrimaryWindow>>,
+        camera: Query<(&Camera, &GlobalTransform), With<GizmoCamera>>,
+        plane_distance: Res<GizmoPlaneDistance>,
+        scale: Res<GizmoScaleToViewportRatio>,
+    ) {
+        let (Ok(window), Ok((camera, camera_transform))) =
+            (window.get_single(), camera.get_single())
+        else {
+            return;
+        };
+
+        let Some(cursor) = window.cursor_position() else {
+            return;
+        };
+
+        if mouse.just_pressed(MouseButton::Left) {
+            *press_position = Some(cursor);
+        }
+
+        if !mouse.just_released(MouseButton::Left) {
+            return;
+        }
+
+        // A drag orbits the camera, only a click snaps it
+        let Some(press) = press_position.take() else {
+            return;
+        };
+        if press.distance(cursor) > CLICK_TOLERANCE {
+            return;
+        }
+
+        let Some((_, handles)) =
+            Self::handles(window, camera, camera_transform, plane_distance.0, scale.0)
+        else {
+            return;
+        };
+
+        if let Some(axis) = Self::handle_at(cursor, &handles, camera, camera_transform) {
+            commands.add(move |world: &mut World| PanOrbitCameraPlugin::snap_to_axis(world, axis));
+        }
+    }
+}
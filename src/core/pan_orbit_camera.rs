@@ -1,9 +1,21 @@
 use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
 use bevy::{
-    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    input::{
+        mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+        touchpad::{TouchpadMagnify, TouchpadRotate},
+    },
+    math::bounding::{Aabb3d, BoundingVolume},
     prelude::*,
-    window::{CursorGrabMode, PrimaryWindow},
+    render::camera::ScalingMode,
+    window::{CursorGrabMode, PrimaryWindow, RequestRedraw},
+};
+use wasm_bindgen::prelude::*;
+
+use super::{
+    fly_camera::FlyCamera,
+    gestures::{Pointer, TouchNavigation},
+    keymap::{EditorAction, Gesture, InputCapture, Keymap, Modifiers},
 };
 
 // Bundle to spawn our custom camera easily
@@ -15,7 +27,7 @@ pub struct PanOrbitCameraBundle {
 }
 
 // The internal state of the pan-orbit controller
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
 pub struct PanOrbitState {
     pub center: Vec3,
     pub radius: f32,
@@ -24,6 +36,46 @@ pub struct PanOrbitState {
     pub yaw: f32,
     pub min_radius: f32,
     pub max_radius: f32,
+    /// Vertical field of view used in perspective, and to size the orthographic view so both frame the same
+    pub fov: f32,
+}
+
+/// The part of [`PanOrbitState`] that places the camera
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PanOrbitView {
+    pub center: Vec3,
+    pub radius: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+/// Smoothly moves a pan-orbit camera to another view. Removed once done, or as soon as the user moves the camera.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PanOrbitTransition {
+    pub from: PanOrbitView,
+    pub to: PanOrbitView,
+    /// Seconds
+    pub duration: f32,
+    pub elapsed: f32,
+}
+
+/// Axis-aligned views the camera can snap to, named after the side the camera looks from
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ViewAxis {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CameraProjectionKind {
+    Perspective,
+    Orthographic,
 }
 
 /// The configuration of the pan-orbit controller
@@ -35,12 +87,6 @@ pub struct PanOrbitSettings {
     pub orbit_sensitivity: f32,
     /// Exponent per pixel of mouse motion
     pub zoom_sensitivity: f32,
-    /// Key to hold for panning
-    pub pan_key: Option<MouseButton>,
-    /// Key to hold for orbiting
-    pub orbit_key: Option<MouseButton>,
-    /// What action is bound to the scroll wheel?
-    pub scroll_action: Option<PanOrbitAction>,
     /// For devices with a notched scroll wheel, like desktop mice
     pub scroll_line_sensitivity: f32,
     /// For devices with smooth scrolling, like touchpads
@@ -57,45 +103,198 @@ impl Default for PanOrbitState {
             upside_down: false,
             pitch: -0.55196005,
             yaw: -0.4406954,
+            fov: PerspectiveProjection::default().fov,
+        }
+    }
+}
+
+impl PanOrbitState {
+    pub fn view(&self) -> PanOrbitView {
+        PanOrbitView {
+            center: self.center,
+            radius: self.radius,
+            yaw: self.yaw,
+            pitch: self.pitch,
+        }
+    }
+
+    pub fn set_view(&mut self, view: PanOrbitView) {
+        self.center = view.center;
+        self.radius = view.radius.clamp(self.min_radius, self.max_radius);
+        self.yaw = view.yaw;
+        self.pitch = view.pitch;
+    }
+
+    /// Places the camera on its orbit
+    pub fn apply_to(&self, transform: &mut Transform) {
+        // YXZ Euler Rotation performs yaw/pitch/roll.
+        transform.rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+        // To position the camera, get the backward direction vector
+        // and place the camera at the desired radius from the center.
+        transform.translation = self.center + transform.back() * self.radius;
+    }
+
+    /// Orthographic projection showing the same area as the perspective one at the orbit center
+    pub fn orthographic_projection(&self) -> OrthographicProjection {
+        OrthographicProjection {
+            // The camera sits `radius` away from the center, so nothing behind it should be clipped
+            near: -self.max_radius,
+            far: self.max_radius,
+            scaling_mode: ScalingMode::FixedVertical(self.orthographic_height()),
+            ..default()
+        }
+    }
+
+    /// Height of the view at the orbit center for the current radius and field of view
+    pub fn orthographic_height(&self) -> f32 {
+        2.0 * self.radius * (self.fov / 2.0).tan()
+    }
+
+    /// Orbit radius at which a sphere around the center fits the view, with a little margin.
+    /// `fov` is vertical and `aspect_ratio` is width over height.
+    pub fn framing_radius(
+        sphere_radius: f32,
+        fov: f32,
+        aspect_ratio: f32,
+        orthographic: bool,
+    ) -> f32 {
+        const MARGIN: f32 = 1.1;
+
+        let half_vertical = fov / 2.0;
+        let half_horizontal = (half_vertical.tan() * aspect_ratio).atan();
+        let half_angle = half_vertical.min(half_horizontal);
+
+        if orthographic {
+            // Only the view size depends on the radius, see `orthographic_height`
+            sphere_radius / half_angle.tan() * MARGIN
+        } else {
+            sphere_radius / half_angle.sin() * MARGIN
+        }
+    }
+}
+
+impl PanOrbitTransition {
+    pub const DEFAULT_DURATION: f32 = 0.3;
+
+    pub fn new(from: PanOrbitView, to: PanOrbitView) -> Self {
+        Self {
+            from,
+            to,
+            duration: Self::DEFAULT_DURATION,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Eased view after `elapsed` seconds. Yaw turns the short way around.
+    pub fn sample(&self) -> PanOrbitView {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        // Smoothstep
+        let t = t * t * (3.0 - 2.0 * t);
+
+        let yaw_delta = wrap_angle(self.to.yaw - self.from.yaw);
+
+        PanOrbitView {
+            center: self.from.center.lerp(self.to.center, t),
+            // Interpolating in log space keeps the zoom speed even
+            radius: (self.from.radius.ln() + (self.to.radius.ln() - self.from.radius.ln()) * t)
+                .exp(),
+            yaw: wrap_angle(self.from.yaw + yaw_delta * t),
+            pitch: self.from.pitch + (self.to.pitch - self.from.pitch) * t,
         }
     }
 }
 
+impl ViewAxis {
+    pub const ALL: [ViewAxis; 6] = [
+        ViewAxis::Right,
+        ViewAxis::Left,
+        ViewAxis::Top,
+        ViewAxis::Bottom,
+        ViewAxis::Front,
+        ViewAxis::Back,
+    ];
+
+    /// Yaw and pitch of a camera looking at the center from this side
+    pub fn yaw_pitch(&self) -> (f32, f32) {
+        match self {
+            ViewAxis::Front => (0.0, 0.0),
+            ViewAxis::Back => (PI, 0.0),
+            ViewAxis::Right => (FRAC_PI_2, 0.0),
+            ViewAxis::Left => (-FRAC_PI_2, 0.0),
+            ViewAxis::Top => (0.0, -FRAC_PI_2),
+            ViewAxis::Bottom => (0.0, FRAC_PI_2),
+        }
+    }
+
+    /// Direction from the center towards the camera
+    pub fn direction(&self) -> Vec3 {
+        match self {
+            ViewAxis::Front => Vec3::Z,
+            ViewAxis::Back => Vec3::NEG_Z,
+            ViewAxis::Right => Vec3::X,
+            ViewAxis::Left => Vec3::NEG_X,
+            ViewAxis::Top => Vec3::Y,
+            ViewAxis::Bottom => Vec3::NEG_Y,
+        }
+    }
+}
+
+/// Wraps an angle to stay between +- 180 degrees
+pub fn wrap_angle(angle: f32) -> f32 {
+    (angle + PI).rem_euclid(TAU) - PI
+}
+
 impl Default for PanOrbitSettings {
     fn default() -> Self {
         PanOrbitSettings {
             pan_sensitivity: 0.001,                 // 1000 pixels per world unit
             orbit_sensitivity: 0.1f32.to_radians(), // 0.1 degree per pixel
             zoom_sensitivity: 0.01,
-            pan_key: Some(MouseButton::Right),
-            orbit_key: Some(MouseButton::Left),
-            scroll_action: Some(PanOrbitAction::Zoom),
             scroll_line_sensitivity: 1.0, // 1 "line" == 16 "pixels of motion"
             scroll_pixel_sensitivity: 1.0 / 16.0,
         }
     }
 }
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum PanOrbitAction {
-    Orbit,
-    Zoom,
-}
-
 #[derive(Component)]
 pub struct PrimaryCamera;
 
+/// Side of the square in the top-right corner of the viewport where dragging orbits the camera
+pub const NAVIGATION_ZONE_SIZE: f32 = 200.0;
+
 pub struct PanOrbitCameraPlugin;
 
 impl PanOrbitCameraPlugin {
     fn pan_orbit_camera_controller(
+        mut commands: Commands,
         mut allow_orbit: Local<bool>,
+        keymap: Res<Keymap>,
+        keys: Res<ButtonInput<KeyCode>>,
         mouse: Res<ButtonInput<MouseButton>>,
         mut evr_motion: EventReader<MouseMotion>,
         mut evr_scroll: EventReader<MouseWheel>,
+        mut evr_magnify: EventReader<TouchpadMagnify>,
+        mut evr_rotate: EventReader<TouchpadRotate>,
+        touch: Res<TouchNavigation>,
+        pointer: Res<Pointer>,
+        capture: Option<Res<InputCapture>>,
         mut window: Query<&mut Window, With<PrimaryWindow>>,
         mut q_camera: Query<
-            (&PanOrbitSettings, &mut PanOrbitState, &mut Transform),
-            With<Camera3d>,
+            (
+                Entity,
+                &PanOrbitSettings,
+                &mut PanOrbitState,
+                &mut Transform,
+                Has<PanOrbitTransition>,
+            ),
+            (With<Camera3d>, Without<FlyCamera>),
         >,
     ) {
         // First, accumulate the total amount of
@@ -121,11 +320,26 @@ impl PanOrbitCameraPlugin {
             }
         }
 
+        if capture.is_some_and(|capture| capture.scroll) {
+            total_scroll_lines = Vec2::ZERO;
+            total_scroll_pixels = Vec2::ZERO;
+        }
+
+        // Positive when the fingers move apart
+        let total_magnify: f32 = evr_magnify.read().map(|ev| ev.0).sum();
+        // Degrees, counter-clockwise
+        let total_rotate: f32 = evr_rotate.read().map(|ev| ev.0).sum();
+
+        let modifiers = Modifiers::from_keys(&keys);
+        let scroll_action = keymap.gesture_action(Gesture::Scroll, modifiers);
+        let pinch_action = keymap.gesture_action(Gesture::Pinch, modifiers);
+        let rotate_action = keymap.gesture_action(Gesture::Rotate, modifiers);
+
         let mut window = window.single_mut();
 
         if mouse.just_pressed(MouseButton::Left) {
             if let Some(cursor_position) = window.cursor_position() {
-                let threshold = 200.0;
+                let threshold = NAVIGATION_ZONE_SIZE;
                 let (x, y) = (cursor_position.x, cursor_position.y);
                 if x > window.width() - threshold && y < threshold {
                     *allow_orbit = true;
@@ -134,59 +348,77 @@ impl PanOrbitCameraPlugin {
             }
         }
 
-        for (settings, mut state, mut transform) in &mut q_camera {
+        for (entity, settings, mut state, mut transform, in_transition) in &mut q_camera {
             // Check how much of each thing we need to apply.
             // Accumulate values from motion and scroll,
             // based on our configuration settings.
             let mut total_pan = Vec2::ZERO;
-            if settings
-                .pan_key
-                .map(|key| mouse.pressed(key))
-                .unwrap_or(false)
+            if keymap
+                .held_binding(EditorAction::Pan, &keys, &mouse)
+                .is_some()
             {
                 total_pan -= total_motion * settings.pan_sensitivity;
             }
 
             let mut total_orbit = Vec2::ZERO;
 
-            if settings
-                .orbit_key
-                .map(|key| mouse.pressed(key))
-                .unwrap_or(false)
-                && *allow_orbit
-            {
+            // Plain left drags select, so they only orbit when started in the navigation zone
+            let orbiting = match keymap.held_binding(EditorAction::Orbit, &keys, &mouse) {
+                Some(binding) if binding.is_selection_button() => *allow_orbit,
+                Some(_) => true,
+                None => false,
+            };
+
+            if orbiting {
                 total_orbit -= (total_motion * Vec2::new(1.0, -1.0)) * settings.orbit_sensitivity;
             } else {
                 *allow_orbit = false;
                 window.cursor.grab_mode = CursorGrabMode::None;
             }
 
-            if settings.scroll_action == Some(PanOrbitAction::Orbit) {
-                total_orbit -= total_scroll_lines
-                    * settings.scroll_line_sensitivity
-                    * settings.orbit_sensitivity;
-                total_orbit -= total_scroll_pixels
-                    * settings.scroll_pixel_sensitivity
-                    * settings.orbit_sensitivity;
+            let total_scroll = total_scroll_lines * settings.scroll_line_sensitivity
+                + total_scroll_pixels * settings.scroll_pixel_sensitivity;
+
+            if scroll_action == Some(EditorAction::Orbit) {
+                total_orbit -= total_scroll * settings.orbit_sensitivity;
+            }
+
+            if scroll_action == Some(EditorAction::Pan) {
+                // `total_scroll` is in lines, pan sensitivity is per pixel
+                total_pan -= total_scroll * settings.pan_sensitivity * 16.0;
             }
 
             let mut total_zoom = Vec2::ZERO;
-            if settings.scroll_action == Some(PanOrbitAction::Zoom) {
-                total_zoom -= total_scroll_lines
-                    * settings.scroll_line_sensitivity
-                    * settings.zoom_sensitivity;
-                total_zoom -= total_scroll_pixels
-                    * settings.scroll_pixel_sensitivity
-                    * settings.zoom_sensitivity;
+            if scroll_action == Some(EditorAction::Zoom) {
+                total_zoom -= total_scroll * settings.zoom_sensitivity;
+            }
+
+            if pinch_action == Some(EditorAction::Zoom) {
+                total_zoom.y += total_magnify;
+            }
+
+            if rotate_action == Some(EditorAction::Orbit) {
+                total_orbit.x -= total_rotate.to_radians();
+            }
+
+            // Touch screens: one finger orbits unless it grabbed a tool handle, two fingers pan and pinch
+            if !pointer.captured {
+                total_orbit -= touch.orbit * settings.orbit_sensitivity;
+            }
+            total_pan -= Vec2::new(touch.pan.x, -touch.pan.y) * settings.pan_sensitivity;
+            total_zoom.y += touch.pinch;
+
+            // Dragging with a zoom binding held zooms in when moving up
+            if keymap
+                .held_binding(EditorAction::Zoom, &keys, &mouse)
+                .is_some()
+            {
+                total_zoom.y += total_motion.y * settings.zoom_sensitivity;
             }
 
             // Upon starting a new orbit maneuver (key is just pressed),
             // check if we are starting it upside-down
-            if settings
-                .orbit_key
-                .map(|key| mouse.just_pressed(key))
-                .unwrap_or(false)
-            {
+            if keymap.just_pressed(EditorAction::Orbit, &keys, &mouse) {
                 state.upside_down = state.pitch < -FRAC_PI_2 || state.pitch > FRAC_PI_2;
             }
 
@@ -250,11 +482,12 @@ impl PanOrbitCameraPlugin {
             // controller was just added and thus we are running
             // for the first time and need to initialize)
             if any || state.is_added() {
-                // YXZ Euler Rotation performs yaw/pitch/roll.
-                transform.rotation = Quat::from_euler(EulerRot::YXZ, state.yaw, state.pitch, 0.0);
-                // To position the camera, get the backward direction vector
-                // and place the camera at the desired radius from the center.
-                transform.translation = state.center + transform.back() * state.radius;
+                state.apply_to(&mut transform);
+            }
+
+            // User input takes over any ongoing transition
+            if any && in_transition {
+                commands.entity(entity).remove::<PanOrbitTransition>();
             }
         }
     }
@@ -263,6 +496,150 @@ impl PanOrbitCameraPlugin {
 #[derive(SystemSet, Hash, Debug, Eq, Clone, PartialEq)]
 pub struct PanOrbitCameraUpdate;
 
+impl PanOrbitCameraPlugin {
+    fn animate_transition(
+        mut commands: Commands,
+        time: Res<Time>,
+        mut q_camera: Query<(
+            Entity,
+            &mut PanOrbitState,
+            &mut Transform,
+            &mut PanOrbitTransition,
+        )>,
+        mut redraw: EventWriter<RequestRedraw>,
+    ) {
+        for (entity, mut state, mut transform, mut transition) in q_camera.iter_mut() {
+            transition.elapsed += time.delta_seconds();
+
+            state.set_view(transition.sample());
+            state.apply_to(&mut transform);
+
+            if transition.is_finished() {
+                commands.entity(entity).remove::<PanOrbitTransition>();
+            } else {
+                // The app only updates on input, keep it running until the transition is over
+                redraw.send(RequestRedraw);
+            }
+        }
+    }
+
+    /// Keeps the orthographic view size matching the orbit radius, so zooming works in both projections
+    fn sync_orthographic_scale(
+        mut q_camera: Query<(&PanOrbitState, &mut Projection), Changed<PanOrbitState>>,
+    ) {
+        for (state, mut projection) in q_camera.iter_mut() {
+            if let Projection::Orthographic(orthographic) = projection.as_mut() {
+                orthographic.scaling_mode = ScalingMode::FixedVertical(state.orthographic_height());
+            }
+        }
+    }
+
+    /// Starts a transition of the primary camera to `to`
+    pub fn transition_to(world: &mut World, to: PanOrbitView) {
+        let mut q_camera = world.query_filtered::<(Entity, &PanOrbitState), With<PrimaryCamera>>();
+        let Ok((entity, state)) = q_camera.get_single(world) else {
+            return;
+        };
+
+        let transition = PanOrbitTransition::new(state.view(), to);
+        world.entity_mut(entity).insert(transition);
+    }
+
+    /// Smoothly turns the primary camera to look from one side, keeping the center and distance
+    pub fn snap_to_axis(world: &mut World, axis: ViewAxis) {
+        let mut q_camera = world.query_filtered::<&PanOrbitState, With<PrimaryCamera>>();
+        let Ok(state) = q_camera.get_single(world) else {
+            return;
+        };
+
+        let (yaw, pitch) = axis.yaw_pitch();
+        let to = PanOrbitView {
+            yaw,
+            pitch,
+            ..state.view()
+        };
+
+        Self::transition_to(world, to);
+    }
+
+    /// Smoothly moves the primary camera so a world-space box fills the view, keeping the orientation
+    pub fn frame_aabb(world: &mut World, aabb: Aabb3d) {
+        let mut q_camera =
+            world.query_filtered::<(&Camera, &Projection, &PanOrbitState), With<PrimaryCamera>>();
+        let Ok((camera, projection, state)) = q_camera.get_single(world) else {
+            return;
+        };
+
+        let aspect_ratio = camera
+            .logical_viewport_size()
+            .map_or(1.0, |size| size.x / size.y.max(1.0));
+
+        let (fov, orthographic) = match projection {
+            Projection::Perspective(perspective) => (perspective.fov, false),
+            Projection::Orthographic(_) => (state.fov, true),
+        };
+
+        let sphere_radius = aabb.half_size().length();
+        // A single point has no size to fit, only recenter on it
+        let radius = if sphere_radius > f32::EPSILON {
+            PanOrbitState::framing_radius(sphere_radius, fov, aspect_ratio, orthographic)
+        } else {
+            state.radius
+        };
+
+        let to = PanOrbitView {
+            center: aabb.center(),
+            radius,
+            ..state.view()
+        };
+
+        Self::transition_to(world, to);
+    }
+
+    pub fn projection_kind(world: &mut World) -> Option<CameraProjectionKind> {
+        let mut q_camera = world.query_filtered::<&Projection, With<PrimaryCamera>>();
+
+        match q_camera.get_single(world).ok()? {
+            Projection::Perspective(_) => Some(CameraProjectionKind::Perspective),
+            Projection::Orthographic(_) => Some(CameraProjectionKind::Orthographic),
+        }
+    }
+
+    /// Switches the primary camera projection. The orthographic view shows what the perspective one
+    /// shows at the orbit center, so the framing does not jump.
+    pub fn set_projection(world: &mut World, kind: CameraProjectionKind) {
+        let mut q_camera =
+            world.query_filtered::<(&mut PanOrbitState, &mut Projection), With<PrimaryCamera>>();
+        let Ok((mut state, mut projection)) = q_camera.get_single_mut(world) else {
+            return;
+        };
+
+        match (kind, projection.as_ref()) {
+            (CameraProjectionKind::Orthographic, Projection::Perspective(perspective)) => {
+                state.fov = perspective.fov;
+                *projection = Projection::Orthographic(state.orthographic_projection());
+            }
+            (CameraProjectionKind::Perspective, Projection::Orthographic(_)) => {
+                *projection = Projection::Perspective(PerspectiveProjection {
+                    fov: state.fov,
+                    ..default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    pub fn toggle_projection(world: &mut World) {
+        let kind = match Self::projection_kind(world) {
+            Some(CameraProjectionKind::Perspective) => CameraProjectionKind::Orthographic,
+            Some(CameraProjectionKind::Orthographic) => CameraProjectionKind::Perspective,
+            None => return,
+        };
+
+        Self::set_projection(world, kind);
+    }
+}
+
 impl Plugin for PanOrbitCameraPlugin {
     fn build(&self, app: &mut App) {
         app.world
@@ -270,9 +647,109 @@ impl Plugin for PanOrbitCameraPlugin {
 
         app.add_systems(
             Update,
-            Self::pan_orbit_camera_controller
+            (
+                Self::pan_orbit_camera_controller,
+                Self::animate_transition,
+                Self::sync_orthographic_scale,
+            )
+                .chain()
                 .run_if(any_with_component::<PanOrbitState>)
                 .in_set(PanOrbitCameraUpdate),
         );
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::f32::consts::{FRAC_PI_2, PI};
+
+    use bevy::prelude::*;
+
+    use super::{PanOrbitState, PanOrbitTransition, PanOrbitView, ViewAxis};
+
+    #[test]
+    fn test_transition_turns_the_short_way() {
+        let from = PanOrbitView {
+            center: Vec3::ZERO,
+            radius: 1.0,
+            yaw: PI - 0.1,
+            pitch: 0.0,
+        };
+        let to = PanOrbitView {
+            yaw: -PI + 0.1,
+            radius: 4.0,
+            center: Vec3::X,
+            ..from
+        };
+
+        let mut transition = PanOrbitTransition::new(from, to);
+        transition.elapsed = transition.duration / 2.0;
+        let half_way = transition.sample();
+
+        // Crosses +-180 degrees instead of going through 0
+        assert!(half_way.yaw.abs() > PI - 0.1 - 1e-5);
+        assert!((half_way.radius - 2.0).abs() < 1e-5);
+        assert!((half_way.center - Vec3::new(0.5, 0.0, 0.0)).length() < 1e-5);
+
+        transition.elapsed = transition.duration;
+        assert!(transition.is_finished());
+        let end = transition.sample();
+        assert!((end.yaw - to.yaw).abs() < 1e-5);
+        assert!((end.radius - 4.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_view_axis_looks_at_center() {
+        for axis in ViewAxis::ALL {
+            let (yaw, pitch) = axis.yaw_pitch();
+            let mut state = PanOrbitState {
+                yaw,
+                pitch,
+                radius: 5.0,
+                ..default()
+            };
+            state.center = Vec3::new(1.0, 2.0, 3.0);
+
+            let mut transform = Transform::default();
+            state.apply_to(&mut transform);
+
+            let offset = transform.translation - state.center;
+            assert!(
+                (offset - axis.direction() * 5.0).length() < 1e-4,
+                "{axis:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_framing_radius_fits_sphere() {
+        let fov = FRAC_PI_2;
+
+        // Square view, the sphere touches the sides of a 90 degree cone
+        let radius = PanOrbitState::framing_radius(1.0, fov, 1.0, false);
+        assert!((radius - 2f32.sqrt() * 1.1).abs() < 1e-4);
+
+        // A narrow view is limited by its width
+        assert!(PanOrbitState::framing_radius(1.0, fov, 0.5, false) > radius);
+
+        // Orthographic framing shows the whole diameter
+        let state = PanOrbitState {
+            radius: PanOrbitState::framing_radius(1.0, fov, 1.0, true),
+            fov,
+            ..default()
+        };
+        assert!(state.orthographic_height() >= 2.0);
+    }
+
+    #[test]
+    fn test_orthographic_height_matches_perspective_framing() {
+        let state = PanOrbitState {
+            radius: 10.0,
+            fov: FRAC_PI_2,
+            ..default()
+        };
+
+        // A 90 degree view 10 units away is 20 units tall
+        assert!((state.orthographic_height() - 20.0).abs() < 1e-4);
+    }
+}
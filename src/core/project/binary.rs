@@ -0,0 +1,195 @@
+use bevy::prelude::*;
+
+use super::ProjectError;
+
+/// Four character code naming a chunk
+pub type ChunkTag = [u8; 4];
+
+/// Tagged block of the file, skipped whole by readers that do not know its tag
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    pub tag: ChunkTag,
+    pub payload: Vec<u8>,
+}
+
+/// Appends little endian values. Lengths and counts are `u32`.
+#[derive(Default)]
+pub struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+/// Reads what [`ByteWriter`] wrote, failing with [`ProjectError::UnexpectedEnd`] instead of
+/// reading past the end
+pub struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl ByteWriter {
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.bytes.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// Number of elements that follow
+    pub fn count(&mut self, count: usize) {
+        self.u32(count as u32);
+    }
+
+    pub fn f32s(&mut self, values: &[f32]) {
+        for value in values {
+            self.f32(*value);
+        }
+    }
+
+    pub fn vec2(&mut self, value: Vec2) {
+        self.f32s(&value.to_array());
+    }
+
+    pub fn vec3(&mut self, value: Vec3) {
+        self.f32s(&value.to_array());
+    }
+
+    pub fn quat(&mut self, value: Quat) {
+        self.f32s(&value.to_array());
+    }
+
+    /// sRGB components and alpha
+    pub fn color(&mut self, value: Color) {
+        self.f32s(&value.as_rgba_f32());
+    }
+
+    /// UTF-8 bytes after their length
+    pub fn str(&mut self, value: &str) {
+        self.count(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    pub fn chunk(&mut self, chunk: &Chunk) {
+        self.bytes(&chunk.tag);
+        self.count(chunk.payload.len());
+        self.bytes(&chunk.payload);
+    }
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.position == self.bytes.len()
+    }
+
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.position
+    }
+
+    pub fn bytes(&mut self, length: usize) -> Result<&'a [u8], ProjectError> {
+        if length > self.remaining() {
+            return Err(ProjectError::UnexpectedEnd);
+        }
+
+        let bytes = &self.bytes[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ProjectError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn u8(&mut self) -> Result<u8, ProjectError> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool, ProjectError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ProjectError::InvalidValue("boolean")),
+        }
+    }
+
+    pub fn u32(&mut self) -> Result<u32, ProjectError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32, ProjectError> {
+        Ok(f32::from_le_bytes(self.array()?))
+    }
+
+    /// Number of elements that follow, each at least `element_size` bytes long. Checked against
+    /// what is left, so a corrupted count cannot allocate more than the file holds.
+    pub fn count(&mut self, element_size: usize) -> Result<usize, ProjectError> {
+        let count = self.u32()? as usize;
+
+        if count.saturating_mul(element_size) > self.remaining() {
+            return Err(ProjectError::UnexpectedEnd);
+        }
+        Ok(count)
+    }
+
+    pub fn f32s<const N: usize>(&mut self) -> Result<[f32; N], ProjectError> {
+        let mut values = [0.0; N];
+        for value in &mut values {
+            *value = self.f32()?;
+        }
+        Ok(values)
+    }
+
+    pub fn vec2(&mut self) -> Result<Vec2, ProjectError> {
+        Ok(Vec2::from_array(self.f32s()?))
+    }
+
+    pub fn vec3(&mut self) -> Result<Vec3, ProjectError> {
+        Ok(Vec3::from_array(self.f32s()?))
+    }
+
+    pub fn quat(&mut self) -> Result<Quat, ProjectError> {
+        Ok(Quat::from_array(self.f32s()?))
+    }
+
+    pub fn color(&mut self) -> Result<Color, ProjectError> {
+        let [r, g, b, a] = self.f32s()?;
+        Ok(Color::rgba(r, g, b, a))
+    }
+
+    pub fn str(&mut self) -> Result<String, ProjectError> {
+        let length = self.count(1)?;
+
+        String::from_utf8(self.bytes(length)?.to_vec())
+            .map_err(|_| ProjectError::InvalidValue("text"))
+    }
+
+    pub fn chunk(&mut self) -> Result<Chunk, ProjectError> {
+        let tag = self.array()?;
+        let length = self.count(1)?;
+
+        Ok(Chunk {
+            tag,
+            payload: self.bytes(length)?.to_vec(),
+        })
+    }
+}
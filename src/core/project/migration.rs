@@ -0,0 +1,83 @@
+use super::{binary::Chunk, ProjectError};
+
+/// Rewrites the chunks of a file from one version into the next one
+pub type Migration = fn(&mut Vec<Chunk>) -> Result<(), ProjectError>;
+
+/// `MIGRATIONS[n]` upgrades version `n + 1` to version `n + 2`. Changing the layout of a chunk
+/// means appending the migration that rewrites the old layout, which also bumps
+/// [`CURRENT_VERSION`]. Chunks that are only added need no migration when readers fall back to
+/// defaults without them.
+const MIGRATIONS: &[Migration] = &[];
+
+/// Version written by [`ProjectFile::to_bytes`](super::ProjectFile::to_bytes)
+pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32 + 1;
+
+/// Brings the chunks of a `version` file up to [`CURRENT_VERSION`]
+pub fn upgrade(version: u32, chunks: &mut Vec<Chunk>) -> Result<(), ProjectError> {
+    upgrade_with(MIGRATIONS, version, chunks)
+}
+
+fn upgrade_with(
+    migrations: &[Migration],
+    version: u32,
+    chunks: &mut Vec<Chunk>,
+) -> Result<(), ProjectError> {
+    let current = migrations.len() as u32 + 1;
+
+    if version == 0 {
+        return Err(ProjectError::InvalidValue("version"));
+    }
+    if version > current {
+        return Err(ProjectError::NewerVersion(version));
+    }
+
+    for migration in &migrations[version as usize - 1..] {
+        migration(chunks)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{upgrade_with, Migration};
+    use crate::core::project::{binary::Chunk, ProjectError};
+
+    #[test]
+    fn test_migrations_run_in_order_from_the_file_version() {
+        let migrations: &[Migration] = &[
+            |chunks| {
+                chunks.push(Chunk {
+                    tag: *b"V2  ",
+                    payload: vec![],
+                });
+                Ok(())
+            },
+            |chunks| {
+                // Only runs after the first one
+                if chunks.last().map(|chunk| &chunk.tag) != Some(b"V2  ") {
+                    return Err(ProjectError::MissingChunk(*b"V2  "));
+                }
+                chunks.push(Chunk {
+                    tag: *b"V3  ",
+                    payload: vec![],
+                });
+                Ok(())
+            },
+        ];
+
+        let mut chunks = vec![];
+        upgrade_with(migrations, 1, &mut chunks).unwrap();
+        let tags: Vec<_> = chunks.iter().map(|chunk| chunk.tag).collect();
+        assert_eq!(tags, vec![*b"V2  ", *b"V3  "]);
+
+        // Files of the current version are left alone
+        let mut chunks = vec![];
+        upgrade_with(migrations, 3, &mut chunks).unwrap();
+        assert!(chunks.is_empty());
+
+        assert_eq!(
+            upgrade_with(migrations, 4, &mut vec![]),
+            Err(ProjectError::NewerVersion(4))
+        );
+    }
+}
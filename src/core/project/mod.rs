@@ -0,0 +1,906 @@
+pub mod binary;
+pub mod migration;
+
+use std::fmt;
+
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology, VertexAttributeValues},
+        render_asset::RenderAssetUsages,
+    },
+    utils::HashMap,
+};
+use lox::{
+    core::{Mesh as LoxMesh, MeshMut},
+    map::PropStoreMut,
+    Handle as LoxHandle, VertexHandle,
+};
+
+use binary::{ByteReader, ByteWriter, Chunk, ChunkTag};
+use migration::CURRENT_VERSION;
+
+use super::{
+    camera_bookmarks::{CameraBookmark, CameraBookmarks},
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh, EditableMeshBundle},
+    editor::{Cursor3d, Focused, UserSpace, ViewportMaterial},
+    fly_camera::FlyCameraPlugin,
+    grid::{GridSettings, GridUnits},
+    interaction::{InteractionMode, InteractionPlugin},
+    pan_orbit_camera::{
+        CameraProjectionKind, PanOrbitCameraPlugin, PanOrbitState, PanOrbitTransition,
+        PanOrbitView, PrimaryCamera,
+    },
+    tools::modal::{ModalTransform, ModalTransformPlugin},
+};
+
+/// First bytes of every project file
+const MAGIC: &[u8; 4] = b"MSHP";
+
+const EDITOR_CHUNK: ChunkTag = *b"EDIT";
+const ENTITIES_CHUNK: ChunkTag = *b"ENTS";
+
+/// A saved scene: every [`UserSpace`] entity and the editor state around them.
+///
+/// The file is [`MAGIC`], the `u32` format version and a list of [`Chunk`]s, all little endian.
+/// Files of older versions are brought up to date by the [`migration`]s before decoding.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProjectFile {
+    pub editor: EditorState,
+    /// Parents come before their children
+    pub entities: Vec<SceneEntity>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EditorState {
+    pub grid: GridSettings,
+    pub camera: PanOrbitState,
+    pub projection: CameraProjectionKind,
+    pub cursor: Cursor3d,
+    /// Restored once the entities are back, falls back to object mode without a focused entity
+    pub interaction_mode: InteractionMode,
+    pub bookmarks: Vec<CameraBookmark>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SceneEntity {
+    pub name: String,
+    /// Index of the parent in [`ProjectFile::entities`]
+    pub parent: Option<u32>,
+    pub transform: Transform,
+    pub visibility: Visibility,
+    pub focused: bool,
+    pub mesh: Option<MeshData>,
+}
+
+/// An [`EditableMesh`] with the render attributes it does not hold. The half-edge structure is
+/// rebuilt from the faces, whose corners are in winding order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    /// Corners of every face, as indices into the vertices
+    pub faces: Vec<Vec<u32>>,
+    pub face_normals: Vec<Vec3>,
+    pub uvs: Option<Vec<Vec2>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectError {
+    /// The bytes do not start with [`MAGIC`]
+    NotAProject,
+    /// Saved by a newer editor, which may have changed the layout
+    NewerVersion(u32),
+    UnexpectedEnd,
+    MissingChunk(ChunkTag),
+    /// A value out of its range, named by the argument
+    InvalidValue(&'static str),
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectError::NotAProject => write!(f, "not a project file"),
+            ProjectError::NewerVersion(version) => write!(
+                f,
+                "project version {version} is newer than the supported version {CURRENT_VERSION}"
+            ),
+            ProjectError::UnexpectedEnd => write!(f, "project file is truncated"),
+            ProjectError::MissingChunk(tag) => {
+                write!(f, "project file has no `{}` chunk", tag.escape_ascii())
+            }
+            ProjectError::InvalidValue(name) => write!(f, "invalid {name} in project file"),
+        }
+    }
+}
+
+impl std::error::Error for ProjectError {}
+
+impl ProjectFile {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut editor = ByteWriter::default();
+        self.editor.write(&mut editor);
+
+        let mut entities = ByteWriter::default();
+        entities.count(self.entities.len());
+        for entity in &self.entities {
+            entity.write(&mut entities);
+        }
+
+        let mut file = ByteWriter::default();
+        file.bytes(MAGIC);
+        file.u32(CURRENT_VERSION);
+        for (tag, writer) in [(EDITOR_CHUNK, editor), (ENTITIES_CHUNK, entities)] {
+            file.chunk(&Chunk {
+                tag,
+                payload: writer.into_bytes(),
+            });
+        }
+        file.into_bytes()
+    }
+
+    /// Decodes a file of this or an older version. Unknown chunks are skipped.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProjectError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+            return Err(ProjectError::NotAProject);
+        }
+        let version = reader.u32()?;
+
+        let mut chunks = vec![];
+        while !reader.is_empty() {
+            chunks.push(reader.chunk()?);
+        }
+        migration::upgrade(version, &mut chunks)?;
+
+        let chunk = |tag: ChunkTag| {
+            chunks
+                .iter()
+                .find(|chunk| chunk.tag == tag)
+                .map(|chunk| ByteReader::new(&chunk.payload))
+                .ok_or(ProjectError::MissingChunk(tag))
+        };
+
+        let editor = EditorState::read(&mut chunk(EDITOR_CHUNK)?)?;
+
+        let mut reader = chunk(ENTITIES_CHUNK)?;
+        let count = reader.count(1)?;
+        let entities = (0..count)
+            .map(|index| SceneEntity::read(&mut reader, index))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { editor, entities })
+    }
+
+    /// Collects every [`UserSpace`] entity and the editor state
+    pub fn capture(world: &mut World) -> Self {
+        let editor = EditorState::capture(world);
+
+        let mut q_entities = world.query_filtered::<(
+            Entity,
+            Option<&Name>,
+            &Transform,
+            Option<&Visibility>,
+            Option<&Parent>,
+            Has<Focused>,
+            Option<&EditableMesh>,
+            Option<&Handle<Mesh>>,
+        ), With<UserSpace>>();
+        let mut q_children = world.query_filtered::<&Children, With<UserSpace>>();
+        let meshes = world.resource::<Assets<Mesh>>();
+
+        let parents: HashMap<Entity, Option<Entity>> = q_entities
+            .iter(world)
+            .map(|(entity, _, _, _, parent, ..)| (entity, parent.map(Parent::get)))
+            .collect();
+
+        // Depth first from the roots, so parents are written before their children. Entities
+        // under something outside of the user space are roots too.
+        let mut roots: Vec<Entity> = parents
+            .iter()
+            .filter(|(_, parent)| parent.map_or(true, |parent| !parents.contains_key(&parent)))
+            .map(|(entity, _)| *entity)
+            .collect();
+        roots.sort_by(|a, b| b.cmp(a));
+
+        let mut stack: Vec<(Entity, Option<u32>)> =
+            roots.into_iter().map(|entity| (entity, None)).collect();
+        let mut entities = vec![];
+
+        while let Some((entity, parent)) = stack.pop() {
+            let Ok((_, name, transform, visibility, _, focused, mesh, render_mesh)) =
+                q_entities.get(world, entity)
+            else {
+                continue;
+            };
+
+            let index = entities.len() as u32;
+            entities.push(SceneEntity {
+                name: name.map_or_else(String::new, |name| name.to_string()),
+                parent,
+                transform: *transform,
+                visibility: visibility.copied().unwrap_or_default(),
+                focused,
+                mesh: mesh.map(|mesh| {
+                    MeshData::new(mesh, render_mesh.and_then(|handle| meshes.get(handle)))
+                }),
+            });
+
+            if let Ok(children) = q_children.get(world, entity) {
+                stack.extend(children.iter().rev().map(|child| (*child, Some(index))));
+            }
+        }
+
+        Self { editor, entities }
+    }
+
+    /// Replaces the [`UserSpace`] entities and the editor state. The meshes are checked first, so
+    /// the scene is left untouched on error.
+    pub fn restore(&self, world: &mut World) -> Result<(), ProjectError> {
+        let editable_meshes = self
+            .entities
+            .iter()
+            .map(|entity| {
+                entity
+                    .mesh
+                    .as_ref()
+                    .map(MeshData::editable_mesh)
+                    .transpose()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if world.contains_resource::<ModalTransform>() {
+            ModalTransformPlugin::finish(world, true);
+        }
+        // Leaves edit mode before its entity goes away, the saved mode is set back by
+        // `EditorState::restore`
+        InteractionPlugin::set_interaction_mode(world, InteractionMode::Object);
+
+        let mut q_roots = world.query_filtered::<Entity, (With<UserSpace>, Without<Parent>)>();
+        let roots: Vec<Entity> = q_roots.iter(world).collect();
+        for root in roots {
+            world.entity_mut(root).despawn_recursive();
+        }
+
+        let material = world.resource::<ViewportMaterial>().0.clone();
+        let mut spawned: Vec<Entity> = Vec::with_capacity(self.entities.len());
+
+        for (entity, editable_mesh) in self.entities.iter().zip(editable_meshes) {
+            let mut entity_mut = match (editable_mesh, &entity.mesh) {
+                (Some(editable_mesh), Some(data)) => {
+                    let bvh = BoundingVolumeHierarchy::from(&editable_mesh);
+                    let mesh = world.resource_mut::<Assets<Mesh>>().add(data.render_mesh());
+
+                    world.spawn(EditableMeshBundle {
+                        editable_mesh,
+                        bvh,
+                        mesh,
+                        material: material.clone(),
+                        transform: entity.transform,
+                        visibility: entity.visibility,
+                        ..default()
+                    })
+                }
+                _ => world.spawn(SpatialBundle {
+                    transform: entity.transform,
+                    visibility: entity.visibility,
+                    ..default()
+                }),
+            };
+
+            entity_mut.insert((Name::from(entity.name.as_str()), UserSpace));
+            if entity.focused {
+                entity_mut.insert(Focused);
+            }
+            // Parents come first, which reading checked
+            if let Some(parent) = entity
+                .parent
+                .and_then(|parent| spawned.get(parent as usize))
+            {
+                entity_mut.set_parent(*parent);
+            }
+            spawned.push(entity_mut.id());
+        }
+
+        self.editor.restore(world);
+        Ok(())
+    }
+}
+
+impl EditorState {
+    fn capture(world: &mut World) -> Self {
+        let mut q_camera = world.query_filtered::<&PanOrbitState, With<PrimaryCamera>>();
+        let camera = q_camera.get_single(world).copied().unwrap_or_default();
+
+        Self {
+            grid: world.resource::<GridSettings>().clone(),
+            camera,
+            projection: PanOrbitCameraPlugin::projection_kind(world)
+                .unwrap_or(CameraProjectionKind::Perspective),
+            cursor: *world.resource::<Cursor3d>(),
+            interaction_mode: *world.resource::<InteractionMode>(),
+            bookmarks: world
+                .get_resource::<CameraBookmarks>()
+                .map(|bookmarks| bookmarks.0.clone())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Applies the state once the entities are restored, as the interaction mode needs the focused
+    /// one
+    fn restore(&self, world: &mut World) {
+        world.insert_resource(self.grid.clone());
+        world.insert_resource(self.cursor);
+        world.insert_resource(CameraBookmarks(self.bookmarks.clone()));
+
+        if FlyCameraPlugin::is_active(world) {
+            FlyCameraPlugin::exit(world, false);
+        }
+        // Switching projections keeps the field of view, so the saved one is written after
+        PanOrbitCameraPlugin::set_projection(world, self.projection);
+
+        let mut q_camera = world
+            .query_filtered::<(Entity, &mut PanOrbitState, &mut Transform), With<PrimaryCamera>>();
+        if let Ok((entity, mut state, mut transform)) = q_camera.get_single_mut(world) {
+            *state = self.camera;
+            state.apply_to(&mut transform);
+            world.entity_mut(entity).remove::<PanOrbitTransition>();
+        }
+
+        InteractionPlugin::set_interaction_mode(world, self.interaction_mode);
+    }
+
+    fn write(&self, writer: &mut ByteWriter) {
+        let grid = &self.grid;
+        writer.u8(match grid.units {
+            GridUnits::Metric => 0,
+            GridUnits::Imperial => 1,
+        });
+        writer.f32(grid.spacing);
+        writer.u32(grid.subdivisions);
+        writer.f32(grid.fade_distance);
+        writer.color(grid.color);
+        writer.bool(grid.axis_lines);
+        for color in grid.axis_colors {
+            writer.color(color);
+        }
+        writer.bool(grid.adaptive);
+        writer.bool(grid.side_view_grids);
+
+        let camera = &self.camera;
+        writer.vec3(camera.center);
+        writer.f32(camera.radius);
+        writer.bool(camera.upside_down);
+        writer.f32(camera.pitch);
+        writer.f32(camera.yaw);
+        writer.f32(camera.min_radius);
+        writer.f32(camera.max_radius);
+        writer.f32(camera.fov);
+        Self::write_projection(writer, self.projection);
+
+        writer.vec3(self.cursor.position);
+        writer.quat(self.cursor.orientation);
+        writer.u8(match self.interaction_mode {
+            InteractionMode::Object => 0,
+            InteractionMode::Edit => 1,
+            InteractionMode::Sculpt => 2,
+        });
+
+        writer.count(self.bookmarks.len());
+        for bookmark in &self.bookmarks {
+            writer.str(&bookmark.name);
+            writer.vec3(bookmark.view.center);
+            writer.f32(bookmark.view.radius);
+            writer.f32(bookmark.view.yaw);
+            writer.f32(bookmark.view.pitch);
+            Self::write_projection(writer, bookmark.projection);
+        }
+    }
+
+    fn write_projection(writer: &mut ByteWriter, projection: CameraProjectionKind) {
+        writer.u8(match projection {
+            CameraProjectionKind::Perspective => 0,
+            CameraProjectionKind::Orthographic => 1,
+        });
+    }
+
+    fn read_projection(reader: &mut ByteReader) -> Result<CameraProjectionKind, ProjectError> {
+        match reader.u8()? {
+            0 => Ok(CameraProjectionKind::Perspective),
+            1 => Ok(CameraProjectionKind::Orthographic),
+            _ => Err(ProjectError::InvalidValue("camera projection")),
+        }
+    }
+
+    fn read(reader: &mut ByteReader) -> Result<Self, ProjectError> {
+        let grid = GridSettings {
+            units: match reader.u8()? {
+                0 => GridUnits::Metric,
+                1 => GridUnits::Imperial,
+                _ => return Err(ProjectError::InvalidValue("grid units")),
+            },
+            spacing: reader.f32()?,
+            subdivisions: reader.u32()?,
+            fade_distance: reader.f32()?,
+            color: reader.color()?,
+            axis_lines: reader.bool()?,
+            axis_colors: [reader.color()?, reader.color()?, reader.color()?],
+            adaptive: reader.bool()?,
+            side_view_grids: reader.bool()?,
+        };
+
+        let camera = PanOrbitState {
+            center: reader.vec3()?,
+            radius: reader.f32()?,
+            upside_down: reader.bool()?,
+            pitch: reader.f32()?,
+            yaw: reader.f32()?,
+            min_radius: reader.f32()?,
+            max_radius: reader.f32()?,
+            fov: reader.f32()?,
+        };
+        let projection = Self::read_projection(reader)?;
+
+        let cursor = Cursor3d {
+            position: reader.vec3()?,
+            orientation: reader.quat()?,
+        };
+        let interaction_mode = match reader.u8()? {
+            0 => InteractionMode::Object,
+            1 => InteractionMode::Edit,
+            2 => InteractionMode::Sculpt,
+            _ => return Err(ProjectError::InvalidValue("interaction mode")),
+        };
+
+        // Name length, six floats of view and the projection
+        let count = reader.count(4 + 24 + 1)?;
+        let bookmarks = (0..count)
+            .map(|_| {
+                Ok(CameraBookmark {
+                    name: reader.str()?,
+                    view: PanOrbitView {
+                        center: reader.vec3()?,
+                        radius: reader.f32()?,
+                        yaw: reader.f32()?,
+                        pitch: reader.f32()?,
+                    },
+                    projection: Self::read_projection(reader)?,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            grid,
+            camera,
+            projection,
+            cursor,
+            interaction_mode,
+            bookmarks,
+        })
+    }
+}
+
+impl SceneEntity {
+    fn write(&self, writer: &mut ByteWriter) {
+        writer.str(&self.name);
+        // Entities cannot be their own parent, so their index means none
+        writer.u32(self.parent.unwrap_or(u32::MAX));
+        writer.vec3(self.transform.translation);
+        writer.quat(self.transform.rotation);
+        writer.vec3(self.transform.scale);
+        writer.u8(match self.visibility {
+            Visibility::Inherited => 0,
+            Visibility::Hidden => 1,
+            Visibility::Visible => 2,
+        });
+        writer.bool(self.focused);
+
+        writer.bool(self.mesh.is_some());
+        if let Some(mesh) = &self.mesh {
+            mesh.write(writer);
+        }
+    }
+
+    /// Reads the entity at `index` of the list
+    fn read(reader: &mut ByteReader, index: usize) -> Result<Self, ProjectError> {
+        let name = reader.str()?;
+        let parent = match reader.u32()? {
+            u32::MAX => None,
+            parent if (parent as usize) < index => Some(parent),
+            _ => return Err(ProjectError::InvalidValue("parent")),
+        };
+        let transform = Transform {
+            translation: reader.vec3()?,
+            rotation: reader.quat()?,
+            scale: reader.vec3()?,
+        };
+        let visibility = match reader.u8()? {
+            0 => Visibility::Inherited,
+            1 => Visibility::Hidden,
+            2 => Visibility::Visible,
+            _ => return Err(ProjectError::InvalidValue("visibility")),
+        };
+        let focused = reader.bool()?;
+
+        let mesh = match reader.bool()? {
+            true => Some(MeshData::read(reader)?),
+            false => None,
+        };
+
+        Ok(Self {
+            name,
+            parent,
+            transform,
+            visibility,
+            focused,
+            mesh,
+        })
+    }
+}
+
+impl MeshData {
+    /// Takes the UVs and colours from the render mesh, whose attributes are indexed by vertex
+    pub fn new(mesh: &EditableMesh, render_mesh: Option<&Mesh>) -> Self {
+        let vertices: Vec<VertexHandle> = mesh.structure.vertex_handles().collect();
+        let indices: HashMap<VertexHandle, u32> = vertices
+            .iter()
+            .enumerate()
+            .map(|(index, vertex)| (*vertex, index as u32))
+            .collect();
+
+        let mut data = Self {
+            positions: vertices
+                .iter()
+                .map(|vertex| mesh.vertex_positions[*vertex])
+                .collect(),
+            normals: vertices
+                .iter()
+                .map(|vertex| mesh.vertex_normals[*vertex])
+                .collect(),
+            ..default()
+        };
+
+        for face_handle in mesh.structure.face_handles() {
+            data.faces.push(
+                mesh.structure
+                    .get_ref(face_handle)
+                    .adjacent_vertices()
+                    .map(|vertex| indices[&vertex.handle()])
+                    .collect(),
+            );
+            data.face_normals.push(mesh.face_normals[face_handle]);
+        }
+
+        let attribute = |id| render_mesh.and_then(|render_mesh| render_mesh.attribute(id));
+        let at = |vertex: &VertexHandle| vertex.idx() as usize;
+
+        if let Some(VertexAttributeValues::Float32x2(uvs)) = attribute(Mesh::ATTRIBUTE_UV_0.id) {
+            data.uvs = Some(
+                vertices
+                    .iter()
+                    .map(|vertex| uvs.get(at(vertex)).map_or(Vec2::ZERO, |uv| Vec2::from(*uv)))
+                    .collect(),
+            );
+        }
+        if let Some(VertexAttributeValues::Float32x4(colors)) = attribute(Mesh::ATTRIBUTE_COLOR.id)
+        {
+            data.colors = Some(
+                vertices
+                    .iter()
+                    .map(|vertex| colors.get(at(vertex)).copied().unwrap_or([1.0; 4]))
+                    .collect(),
+            );
+        }
+
+        data
+    }
+
+    /// Rebuilds the half-edge structure. Fails if a face is not a polygon of known vertices.
+    pub fn editable_mesh(&self) -> Result<EditableMesh, ProjectError> {
+        let mut mesh = EditableMesh::default();
+
+        let vertices: Vec<VertexHandle> = self
+            .positions
+            .iter()
+            .zip(&self.normals)
+            .map(|(position, normal)| {
+                let vertex = mesh.structure.add_vertex();
+                mesh.vertex_positions.insert(vertex, *position);
+                mesh.vertex_normals.insert(vertex, *normal);
+                vertex
+            })
+            .collect();
+
+        for (corners, normal) in self.faces.iter().zip(&self.face_normals) {
+            let handles = corners
+                .iter()
+                .map(|corner| vertices.get(*corner as usize).copied())
+                .collect::<Option<Vec<_>>>()
+                .filter(|handles| handles.len() >= 3)
+                .ok_or(ProjectError::InvalidValue("face"))?;
+
+            let face = mesh.structure.add_face(&handles);
+            mesh.face_normals.insert(face, *normal);
+        }
+
+        Ok(mesh)
+    }
+
+    /// Vertex indices of the faces fanned out into triangles, three per triangle
+    pub fn triangles(&self) -> Vec<u32> {
+        self.faces
+            .iter()
+            .flat_map(|corners| {
+                (1..corners.len().saturating_sub(1))
+                    .flat_map(move |i| [corners[0], corners[i], corners[i + 1]])
+            })
+            .collect()
+    }
+
+    /// Triangle list with an attribute value per vertex of [`MeshData::editable_mesh`], whose
+    /// handles are numbered in the same order
+    pub fn render_mesh(&self) -> Mesh {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_POSITION,
+            self.positions
+                .iter()
+                .map(Vec3::to_array)
+                .collect::<Vec<_>>(),
+        )
+        .with_inserted_attribute(
+            Mesh::ATTRIBUTE_NORMAL,
+            self.normals.iter().map(Vec3::to_array).collect::<Vec<_>>(),
+        )
+        .with_inserted_indices(Indices::U32(self.triangles()));
+
+        if let Some(uvs) = &self.uvs {
+            mesh.insert_attribute(
+                Mesh::ATTRIBUTE_UV_0,
+                uvs.iter().map(|uv| uv.to_array()).collect::<Vec<_>>(),
+            );
+        }
+        if let Some(colors) = &self.colors {
+            mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.clone());
+        }
+
+        mesh
+    }
+
+    fn write(&self, writer: &mut ByteWriter) {
+        writer.count(self.positions.len());
+        for (position, normal) in self.positions.iter().zip(&self.normals) {
+            writer.vec3(*position);
+            writer.vec3(*normal);
+        }
+
+        writer.count(self.faces.len());
+        for (corners, normal) in self.faces.iter().zip(&self.face_normals) {
+            writer.count(corners.len());
+            for corner in corners {
+                writer.u32(*corner);
+            }
+            writer.vec3(*normal);
+        }
+
+        writer.bool(self.uvs.is_some());
+        for uv in self.uvs.iter().flatten() {
+            writer.vec2(*uv);
+        }
+        writer.bool(self.colors.is_some());
+        for color in self.colors.iter().flatten() {
+            writer.f32s(color);
+        }
+    }
+
+    fn read(reader: &mut ByteReader) -> Result<Self, ProjectError> {
+        let mut data = Self::default();
+
+        let vertex_count = reader.count(24)?;
+        for _ in 0..vertex_count {
+            data.positions.push(reader.vec3()?);
+            data.normals.push(reader.vec3()?);
+        }
+
+        let face_count = reader.count(16)?;
+        for _ in 0..face_count {
+            let corner_count = reader.count(4)?;
+            let corners = (0..corner_count)
+                .map(|_| reader.u32())
+                .collect::<Result<_, _>>()?;
+            data.faces.push(corners);
+            data.face_normals.push(reader.vec3()?);
+        }
+
+        if reader.bool()? {
+            data.uvs = Some(
+                (0..vertex_count)
+                    .map(|_| reader.vec2())
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+        if reader.bool()? {
+            data.colors = Some(
+                (0..vertex_count)
+                    .map(|_| reader.f32s())
+                    .collect::<Result<_, _>>()?,
+            );
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use lox::core::Mesh as LoxMesh;
+
+    use super::{
+        binary::{ByteWriter, Chunk},
+        EditorState, MeshData, ProjectError, ProjectFile, SceneEntity, MAGIC,
+    };
+    use crate::core::{
+        camera_bookmarks::{CameraBookmark, CameraBookmarks},
+        editable_mesh::EditableMesh,
+        editor::{Cursor3d, Focused, ViewportMaterial},
+        grid::GridSettings,
+        interaction::InteractionMode,
+        pan_orbit_camera::{CameraProjectionKind, PanOrbitState, PanOrbitView},
+    };
+
+    fn cuboid_project() -> ProjectFile {
+        let render_mesh = Cuboid::new(1.0, 2.0, 3.0).mesh();
+        let mesh: EditableMesh = (&render_mesh).into();
+
+        ProjectFile {
+            editor: EditorState {
+                grid: GridSettings {
+                    spacing: 0.25,
+                    ..default()
+                },
+                camera: PanOrbitState {
+                    radius: 7.5,
+                    ..default()
+                },
+                projection: CameraProjectionKind::Orthographic,
+                cursor: Cursor3d {
+                    position: Vec3::new(1.0, 2.0, 3.0),
+                    orientation: Quat::from_rotation_y(0.5),
+                },
+                interaction_mode: InteractionMode::Edit,
+                bookmarks: vec![CameraBookmark {
+                    name: "Front".to_string(),
+                    view: PanOrbitView {
+                        center: Vec3::Y,
+                        radius: 4.0,
+                        yaw: 0.25,
+                        pitch: -0.5,
+                    },
+                    projection: CameraProjectionKind::Orthographic,
+                }],
+            },
+            entities: vec![
+                SceneEntity {
+                    name: "Group".to_string(),
+                    parent: None,
+                    transform: Transform::from_xyz(0.0, 1.0, 0.0),
+                    visibility: Visibility::Hidden,
+                    focused: false,
+                    mesh: None,
+                },
+                SceneEntity {
+                    name: "Cuboid".to_string(),
+                    parent: Some(0),
+                    transform: Transform::from_scale(Vec3::splat(2.0)),
+                    visibility: Visibility::Inherited,
+                    focused: true,
+                    mesh: Some(MeshData::new(&mesh, Some(&render_mesh))),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        let project = cuboid_project();
+        let bytes = project.to_bytes();
+        assert_eq!(&bytes[..4], MAGIC);
+
+        let loaded = ProjectFile::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded, project);
+
+        // The rebuilt structure has the same elements and connectivity
+        let data = loaded.entities[1].mesh.as_ref().unwrap();
+        assert!(data.uvs.is_some());
+        let mesh = data.editable_mesh().unwrap();
+        assert_eq!(mesh.structure.num_vertices(), 24);
+        assert_eq!(mesh.structure.num_faces(), 12);
+        let rebuilt = MeshData::new(&mesh, Some(&data.render_mesh()));
+        assert_eq!(rebuilt.positions, data.positions);
+        assert_eq!(rebuilt.uvs, data.uvs);
+        for (rebuilt, saved) in rebuilt.faces.iter().zip(&data.faces) {
+            // Same winding, maybe starting from another corner
+            assert!((0..saved.len()).any(|shift| {
+                saved
+                    .iter()
+                    .cycle()
+                    .skip(shift)
+                    .take(saved.len())
+                    .eq(rebuilt.iter())
+            }));
+        }
+    }
+
+    #[test]
+    fn test_restore_sets_editor_state() {
+        let mut world = World::new();
+        world.insert_resource(InteractionMode::Object);
+        world.init_resource::<ViewportMaterial>();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Cursor3d>();
+        world.init_resource::<CameraBookmarks>();
+
+        let project = cuboid_project();
+        project.restore(&mut world).unwrap();
+
+        // Edit mode comes back as the focused entity was restored first
+        assert_eq!(*world.resource::<InteractionMode>(), InteractionMode::Edit);
+        let mut q_focused = world.query_filtered::<&Name, With<Focused>>();
+        assert_eq!(q_focused.single(&world).as_str(), "Cuboid");
+
+        assert_eq!(
+            world.resource::<CameraBookmarks>().0,
+            project.editor.bookmarks
+        );
+        assert_eq!(
+            ProjectFile::capture(&mut world).editor.bookmarks,
+            project.editor.bookmarks
+        );
+    }
+
+    #[test]
+    fn test_unknown_chunks_are_skipped() {
+        let mut bytes = cuboid_project().to_bytes();
+
+        let mut extra = ByteWriter::default();
+        extra.chunk(&Chunk {
+            tag: *b"NEW ",
+            payload: vec![1, 2, 3],
+        });
+        bytes.extend(extra.into_bytes());
+
+        assert_eq!(ProjectFile::from_bytes(&bytes).unwrap(), cuboid_project());
+    }
+
+    #[test]
+    fn test_invalid_files_are_refused() {
+        let bytes = cuboid_project().to_bytes();
+
+        assert_eq!(
+            ProjectFile::from_bytes(b"glTF"),
+            Err(ProjectError::NotAProject)
+        );
+        assert_eq!(
+            ProjectFile::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ProjectError::UnexpectedEnd)
+        );
+
+        let mut newer = bytes.clone();
+        newer[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(
+            ProjectFile::from_bytes(&newer),
+            Err(ProjectError::NewerVersion(u32::MAX))
+        );
+
+        // A face pointing past the vertices
+        let mut project = cuboid_project();
+        let mesh = project.entities[1].mesh.as_mut().unwrap();
+        mesh.faces[0][0] = mesh.positions.len() as u32;
+        assert!(mesh.editable_mesh().is_err());
+    }
+}
@@ -0,0 +1,487 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::{
+    math::{
+        bounding::{Aabb3d, BoundingVolume, RayCast3d},
+        Affine3A,
+    },
+    prelude::*,
+    render::primitives::{Aabb, Frustum},
+    utils::HashMap,
+};
+
+use super::dim3::transform_aabb;
+
+/// Top-level acceleration structure over the world-space bounds of every pickable entity.
+/// It is a dynamic AABB tree: leaves are stored with some slack so small movements do not
+/// touch the tree, and entities are inserted and removed individually instead of rebuilding.
+#[derive(Resource, Default)]
+pub struct SceneBoundingVolumeHierarchy {
+    nodes: Vec<SceneNode>,
+    root: Option<u32>,
+    free_nodes: Vec<u32>,
+    leaves: HashMap<Entity, u32>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum SceneNodeKind {
+    Leaf(Entity),
+    Branch([u32; 2]),
+    Free,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct SceneNode {
+    /// Fattened bounds for leaves, union of the children for branches.
+    aabb: Aabb3d,
+    parent: Option<u32>,
+    kind: SceneNodeKind,
+}
+
+/// Candidate waiting in a best-first ray traversal, ordered so the nearest entry point is popped first.
+#[derive(Clone, Copy, PartialEq)]
+struct RayCandidate {
+    t: f32,
+    node: u32,
+}
+
+impl Eq for RayCandidate {}
+
+impl Ord for RayCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.t.total_cmp(&self.t)
+    }
+}
+
+impl PartialOrd for RayCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl SceneBoundingVolumeHierarchy {
+    /// Fraction of a leaf's size added on every side when it is (re)inserted.
+    const FAT_MARGIN_RATIO: f32 = 0.1;
+    const MINIMUM_FAT_MARGIN: f32 = 0.01;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.leaves.contains_key(&entity)
+    }
+
+    /// World bounds of the whole scene, if it has any entity.
+    pub fn root_aabb(&self) -> Option<Aabb3d> {
+        self.root.map(|root| self.nodes[root as usize].aabb)
+    }
+
+    /// Inserts an entity, or updates it if it is already in the tree. Returns whether the tree changed.
+    pub fn insert_or_update(&mut self, entity: Entity, aabb: Aabb3d) -> bool {
+        if let Some(&leaf) = self.leaves.get(&entity) {
+            // Still inside the slack, nothing to do
+            if self.nodes[leaf as usize].aabb.contains(&aabb) {
+                return false;
+            }
+            self.remove(entity);
+        }
+
+        self.insert(entity, aabb);
+        true
+    }
+
+    /// Inserts an entity with its tight world-space bounds.
+    pub fn insert(&mut self, entity: Entity, aabb: Aabb3d) {
+        let margin = (aabb.half_size() * 2.0 * Self::FAT_MARGIN_RATIO)
+            .max(Vec3::splat(Self::MINIMUM_FAT_MARGIN));
+        let fat_aabb = aabb.grow(margin);
+
+        let leaf = self.allocate_node(SceneNode {
+            aabb: fat_aabb,
+            parent: None,
+            kind: SceneNodeKind::Leaf(entity),
+        });
+        self.leaves.insert(entity, leaf);
+
+        let Some(root) = self.root else {
+            self.root = Some(leaf);
+            return;
+        };
+
+        let sibling = self.find_best_sibling(&fat_aabb, root);
+        let old_parent = self.nodes[sibling as usize].parent;
+
+        let branch = self.allocate_node(SceneNode {
+            aabb: fat_aabb.merge(&self.nodes[sibling as usize].aabb),
+            parent: old_parent,
+            kind: SceneNodeKind::Branch([sibling, leaf]),
+        });
+
+        self.nodes[sibling as usize].parent = Some(branch);
+        self.nodes[leaf as usize].parent = Some(branch);
+
+        match old_parent {
+            Some(old_parent) => {
+                self.replace_child(old_parent, sibling, branch);
+                self.refit_ancestors(old_parent);
+            }
+            None => self.root = Some(branch),
+        }
+    }
+
+    /// Removes an entity from the tree. Does nothing if it is not in it.
+    pub fn remove(&mut self, entity: Entity) {
+        let Some(leaf) = self.leaves.remove(&entity) else {
+            return;
+        };
+
+        let parent = self.nodes[leaf as usize].parent;
+        self.free_node(leaf);
+
+        let Some(parent) = parent else {
+            self.root = None;
+            return;
+        };
+
+        let SceneNodeKind::Branch(children) = self.nodes[parent as usize].kind else {
+            unreachable!("Parent of a scene BVH node must be a branch");
+        };
+        let sibling = if children[0] == leaf {
+            children[1]
+        } else {
+            children[0]
+        };
+
+        // The sibling takes the parent's place
+        let grandparent = self.nodes[parent as usize].parent;
+        self.nodes[sibling as usize].parent = grandparent;
+        self.free_node(parent);
+
+        match grandparent {
+            Some(grandparent) => {
+                self.replace_child(grandparent, parent, sibling);
+                self.refit_ancestors(grandparent);
+            }
+            None => self.root = Some(sibling),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Casts a world-space ray through the scene. Entities are visited in the order the ray enters
+    /// their bounds, and `hit_test` runs the exact test on each of them, returning the hit data and
+    /// its distance along the ray. Traversal stops as soon as no remaining bounds can beat the closest hit.
+    pub fn cast_ray<T>(
+        &self,
+        ray: &RayCast3d,
+        mut hit_test: impl FnMut(Entity) -> Option<(T, f32)>,
+    ) -> Option<(Entity, T, f32)> {
+        let root = self.root?;
+        let root_t = ray.aabb_intersection_at(&self.nodes[root as usize].aabb)?;
+
+        let mut queue = BinaryHeap::new();
+        queue.push(RayCandidate {
+            t: root_t,
+            node: root,
+        });
+
+        let mut closest = Option::<(Entity, T, f32)>::None;
+
+        while let Some(RayCandidate { t, node }) = queue.pop() {
+            if let Some((_, _, closest_t)) = &closest {
+                if t >= *closest_t {
+                    break;
+                }
+            }
+
+            match self.nodes[node as usize].kind {
+                SceneNodeKind::Leaf(entity) => {
+                    let Some((data, hit_t)) = hit_test(entity) else {
+                        continue;
+                    };
+
+                    if closest
+                        .as_ref()
+                        .map_or(true, |(_, _, closest_t)| hit_t < *closest_t)
+                    {
+                        closest = Some((entity, data, hit_t));
+                    }
+                }
+                SceneNodeKind::Branch(children) => {
+                    for child in children {
+                        if let Some(t) = ray.aabb_intersection_at(&self.nodes[child as usize].aabb)
+                        {
+                            queue.push(RayCandidate { t, node: child });
+                        }
+                    }
+                }
+                SceneNodeKind::Free => {}
+            }
+        }
+
+        closest
+    }
+
+    /// Collects the entities whose bounds intersect a world-space frustum.
+    pub fn query_frustum(&self, frustum: &Frustum) -> Vec<Entity> {
+        self.query(|aabb| {
+            frustum.intersects_obb(
+                &Aabb::from_min_max(aabb.min, aabb.max),
+                &Affine3A::IDENTITY,
+                true,
+                true,
+            )
+        })
+    }
+
+    /// Collects the entities whose bounds intersect a world-space box.
+    pub fn query_aabb(&self, aabb: &Aabb3d) -> Vec<Entity> {
+        self.query(|node_aabb| {
+            node_aabb.min.cmple(aabb.max).all() && node_aabb.max.cmpge(aabb.min).all()
+        })
+    }
+
+    fn query(&self, mut overlaps: impl FnMut(&Aabb3d) -> bool) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        let mut stack: Vec<u32> = self.root.into_iter().collect();
+
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node as usize];
+
+            if !overlaps(&node.aabb) {
+                continue;
+            }
+
+            match node.kind {
+                SceneNodeKind::Leaf(entity) => entities.push(entity),
+                SceneNodeKind::Branch(children) => stack.extend(children),
+                SceneNodeKind::Free => {}
+            }
+        }
+
+        entities
+    }
+
+    /// Descends from `root` towards the node that grows the least when merged with `aabb`.
+    fn find_best_sibling(&self, aabb: &Aabb3d, root: u32) -> u32 {
+        let mut current = root;
+
+        while let SceneNodeKind::Branch([left, right]) = self.nodes[current as usize].kind {
+            let node_aabb = self.nodes[current as usize].aabb;
+            let merged_area = node_aabb.merge(aabb).visible_area();
+
+            // Cost of making a new parent for `aabb` and this node right here
+            let cost_here = merged_area;
+            // Every ancestor of the descended path grows by this much anyway
+            let inherited_cost = merged_area - node_aabb.visible_area();
+
+            let child_cost = |child: u32| {
+                let child_aabb = self.nodes[child as usize].aabb;
+                let merged = child_aabb.merge(aabb).visible_area();
+                match self.nodes[child as usize].kind {
+                    SceneNodeKind::Leaf(_) => merged + inherited_cost,
+                    _ => merged - child_aabb.visible_area() + inherited_cost,
+                }
+            };
+
+            let (left_cost, right_cost) = (child_cost(left), child_cost(right));
+
+            if cost_here < left_cost && cost_here < right_cost {
+                break;
+            }
+
+            current = if left_cost <= right_cost { left } else { right };
+        }
+
+        current
+    }
+
+    fn replace_child(&mut self, parent: u32, old_child: u32, new_child: u32) {
+        if let SceneNodeKind::Branch(children) = &mut self.nodes[parent as usize].kind {
+            for child in children.iter_mut() {
+                if *child == old_child {
+                    *child = new_child;
+                }
+            }
+        }
+    }
+
+    fn refit_ancestors(&mut self, node: u32) {
+        let mut current = Some(node);
+
+        while let Some(index) = current {
+            if let SceneNodeKind::Branch([left, right]) = self.nodes[index as usize].kind {
+                self.nodes[index as usize].aabb = self.nodes[left as usize]
+                    .aabb
+                    .merge(&self.nodes[right as usize].aabb);
+            }
+            current = self.nodes[index as usize].parent;
+        }
+    }
+
+    fn allocate_node(&mut self, node: SceneNode) -> u32 {
+        match self.free_nodes.pop() {
+            Some(index) => {
+                self.nodes[index as usize] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() as u32 - 1
+            }
+        }
+    }
+
+    fn free_node(&mut self, index: u32) {
+        self.nodes[index as usize].kind = SceneNodeKind::Free;
+        self.nodes[index as usize].parent = None;
+        self.free_nodes.push(index);
+    }
+}
+
+/// World-space bounds of a mesh whose local bounds are `local_aabb`.
+pub fn world_aabb(local_aabb: &Aabb3d, transform: &GlobalTransform) -> Aabb3d {
+    transform_aabb(local_aabb, &transform.affine())
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{
+        math::bounding::{Aabb3d, BoundingVolume, RayCast3d},
+        prelude::*,
+    };
+
+    use super::{SceneBoundingVolumeHierarchy, SceneNodeKind};
+
+    fn unit_box_at(center: Vec3) -> Aabb3d {
+        Aabb3d::new(center, Vec3::splat(0.5))
+    }
+
+    /// Checks parent links, that branches enclose their children and that every entity has one leaf.
+    fn assert_consistent(tree: &SceneBoundingVolumeHierarchy) {
+        let mut leaf_count = 0;
+        let mut stack: Vec<u32> = tree.root.into_iter().collect();
+
+        if let Some(root) = tree.root {
+            assert!(tree.nodes[root as usize].parent.is_none());
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &tree.nodes[index as usize];
+            match node.kind {
+                SceneNodeKind::Leaf(entity) => {
+                    assert_eq!(tree.leaves[&entity], index);
+                    leaf_count += 1;
+                }
+                SceneNodeKind::Branch(children) => {
+                    for child in children {
+                        assert_eq!(tree.nodes[child as usize].parent, Some(index));
+                        assert!(node.aabb.contains(&tree.nodes[child as usize].aabb));
+                        stack.push(child);
+                    }
+                }
+                SceneNodeKind::Free => panic!("Free node reachable from the root"),
+            }
+        }
+
+        assert_eq!(leaf_count, tree.len());
+    }
+
+    #[test]
+    fn test_insert_update_remove() {
+        let mut tree = SceneBoundingVolumeHierarchy::new();
+
+        for i in 0..64 {
+            tree.insert(
+                Entity::from_raw(i),
+                unit_box_at(Vec3::new(i as f32 * 2.0, (i % 7) as f32, 0.0)),
+            );
+        }
+        assert_consistent(&tree);
+        assert_eq!(tree.len(), 64);
+
+        // A small nudge stays within the slack
+        assert!(!tree.insert_or_update(Entity::from_raw(3), unit_box_at(Vec3::new(6.01, 3.0, 0.0))));
+        // A big move reinserts
+        assert!(tree.insert_or_update(Entity::from_raw(3), unit_box_at(Vec3::new(-50.0, 0.0, 0.0))));
+        assert_consistent(&tree);
+        assert!(tree.root_aabb().unwrap().min.x <= -50.5);
+
+        for i in (0..64).step_by(2) {
+            tree.remove(Entity::from_raw(i));
+        }
+        assert_consistent(&tree);
+        assert_eq!(tree.len(), 32);
+
+        for i in (1..64).step_by(2) {
+            tree.remove(Entity::from_raw(i));
+        }
+        assert!(tree.is_empty());
+        assert!(tree.root_aabb().is_none());
+    }
+
+    #[test]
+    fn test_cast_ray_returns_closest_exact_hit() {
+        let mut tree = SceneBoundingVolumeHierarchy::new();
+
+        let centers = [
+            Vec3::new(0.0, 0.0, -10.0),
+            Vec3::new(0.0, 0.0, -5.0),
+            Vec3::new(0.0, 0.0, -20.0),
+            Vec3::new(5.0, 0.0, -2.0),
+        ];
+        for (i, center) in centers.iter().enumerate() {
+            tree.insert(Entity::from_raw(i as u32), unit_box_at(*center));
+        }
+
+        let ray = RayCast3d::from_ray(Ray3d::new(Vec3::ZERO, Vec3::NEG_Z), 1000.0);
+
+        // Exact test against unit spheres of radius 0.5 at the box centres
+        let mut tested = vec![];
+        let hit = tree.cast_ray(&ray, |entity| {
+            tested.push(entity);
+            let center = centers[entity.index() as usize];
+            (center.x.abs() < 0.5).then(|| ((), -center.z - 0.5))
+        });
+
+        let (entity, _, t) = hit.unwrap();
+        assert_eq!(entity, Entity::from_raw(1));
+        assert!((t - 4.5).abs() < 1e-6);
+        // The farthest boxes are never handed to the exact test
+        assert!(!tested.contains(&Entity::from_raw(2)));
+
+        // A miss on the exact test falls through to the next entity along the ray
+        let hit = tree.cast_ray(&ray, |entity| {
+            let center = centers[entity.index() as usize];
+            (entity.index() != 1 && center.x.abs() < 0.5).then(|| ((), -center.z - 0.5))
+        });
+        assert_eq!(hit.unwrap().0, Entity::from_raw(0));
+    }
+
+    #[test]
+    fn test_query_aabb() {
+        let mut tree = SceneBoundingVolumeHierarchy::new();
+        for i in 0..10 {
+            tree.insert(
+                Entity::from_raw(i),
+                unit_box_at(Vec3::new(i as f32 * 3.0, 0.0, 0.0)),
+            );
+        }
+
+        let mut found = tree.query_aabb(&Aabb3d::new(Vec3::new(4.5, 0.0, 0.0), Vec3::splat(2.0)));
+        found.sort();
+
+        assert_eq!(found, vec![Entity::from_raw(1), Entity::from_raw(2)]);
+    }
+}
@@ -0,0 +1,699 @@
+use bevy::{
+    asset::load_internal_asset,
+    pbr::{MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    render::{
+        mesh::{MeshVertexAttribute, MeshVertexBufferLayout, VertexAttributeValues},
+        render_resource::{
+            AsBindGroup, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError, VertexFormat,
+        },
+        view::{NoFrustumCulling, VisibilitySystems},
+    },
+    transform::TransformSystem,
+    utils::{HashMap, HashSet},
+};
+use lox::{
+    core::Mesh as LoxMesh,
+    FaceHandle, Handle as LoxHandle, VertexHandle,
+};
+use wasm_bindgen::prelude::*;
+
+use super::{
+    edit_overlay::{create_overlay_base, EditOverlay, OverlayInstance},
+    editable_mesh::EditableMesh,
+    editor::{Focused, UserSpace, ViewportMaterial},
+    tools::cursor::world_normal,
+};
+
+/// How the user meshes are drawn in the viewport, independently of the scene lights
+pub struct ShadingPlugin;
+
+const SHADING_SHADER_HANDLE: Handle<Shader> =
+    Handle::weak_from_u128(0x5d1c_93a7_2be4_4f0e_8a61_0c7f_3e92_b4d8);
+
+/// Curvature of the surface around each vertex, see [`vertex_cavity`]
+pub const ATTRIBUTE_CAVITY: MeshVertexAttribute =
+    MeshVertexAttribute::new("Vertex_Cavity", 2_840_918_375, VertexFormat::Float32);
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShadingMode {
+    /// Only the edges, surfaces are not drawn
+    Wireframe,
+    /// Studio lighting from the camera, optionally with cavity
+    #[default]
+    Solid,
+    /// Solid, but see-through
+    XRay,
+    /// Lit by a material capture, following the camera
+    Matcap,
+    /// Front faces in blue, back faces in red
+    FaceOrientation,
+}
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ViewportShading {
+    pub mode: ShadingMode,
+    pub color: Color,
+    /// Darkens concave areas and lightens convex edges in solid and x-ray
+    pub cavity: bool,
+    pub cavity_strength: f32,
+    /// Opacity of the surfaces in x-ray
+    pub xray_alpha: f32,
+    /// Lines along the face normals
+    pub normals_overlay: bool,
+    /// World length of the normal lines
+    pub normal_length: f32,
+    /// Edges of the focused meshes drawn over their surfaces
+    pub focused_wireframe: bool,
+}
+
+impl Default for ViewportShading {
+    fn default() -> Self {
+        Self {
+            mode: ShadingMode::Solid,
+            color: Color::rgb_linear(0.35, 0.35, 0.35),
+            cavity: false,
+            cavity_strength: 1.0,
+            xray_alpha: 0.5,
+            normals_overlay: false,
+            normal_length: 0.1,
+            focused_wireframe: false,
+        }
+    }
+}
+
+/// Marks the [`EditOverlay`] that draws the edges of the wireframe mode and overlay, and the
+/// normal lines. Its instances are rebuilt only when a user mesh or the shading changes.
+#[derive(Component)]
+pub struct WireframeOverlay;
+
+/// Width of the edges and normal lines, in pixels
+const WIRE_WIDTH: f32 = 1.0;
+const WIRE_COLOR: Color = Color::rgb(0.1, 0.1, 0.1);
+const WIREFRAME_MODE_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+const FOCUSED_WIRE_COLOR: Color = Color::rgb(1.0, 0.67, 0.25);
+const NORMAL_COLOR: Color = Color::rgb(0.35, 0.85, 0.95);
+
+#[derive(Clone, Copy, Debug, Default, ShaderType)]
+pub struct ShadingUniform {
+    /// Linear RGB, alpha is the opacity of the surfaces
+    pub color: Vec4,
+    /// [`ShadingMode`] as its discriminant
+    pub mode: u32,
+    /// Zero when cavity is off
+    pub cavity_strength: f32,
+}
+
+/// Material of every user mesh, shared and updated from [`ViewportShading`]
+#[derive(Asset, TypePath, AsBindGroup, Clone, Debug)]
+pub struct ViewportShadingMaterial {
+    #[uniform(0)]
+    pub settings: ShadingUniform,
+    pub alpha_mode: AlphaMode,
+}
+
+impl From<&ViewportShading> for ViewportShadingMaterial {
+    fn from(shading: &ViewportShading) -> Self {
+        let see_through = shading.mode == ShadingMode::XRay;
+        let alpha = if see_through { shading.xray_alpha } else { 1.0 };
+
+        Self {
+            settings: ShadingUniform {
+                color: Vec4::from(shading.color.with_a(alpha).as_linear_rgba_f32()),
+                mode: shading.mode as u32,
+                cavity_strength: if shading.cavity {
+                    shading.cavity_strength
+                } else {
+                    0.0
+                },
+            },
+            alpha_mode: if see_through {
+                AlphaMode::Blend
+            } else {
+                AlphaMode::Opaque
+            },
+        }
+    }
+}
+
+impl Material for ViewportShadingMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADING_SHADER_HANDLE.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADING_SHADER_HANDLE.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayout,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        let vertex_layout = layout.get_layout(&[
+            Mesh::ATTRIBUTE_POSITION.at_shader_location(0),
+            Mesh::ATTRIBUTE_NORMAL.at_shader_location(1),
+            ATTRIBUTE_CAVITY.at_shader_location(2),
+        ])?;
+        descriptor.vertex.buffers = vec![vertex_layout];
+        // Back faces are shaded too, and shown in red by the face orientation mode
+        descriptor.primitive.cull_mode = None;
+
+        Ok(())
+    }
+}
+
+const WELD_DISTANCE: f32 = 1e-4;
+
+fn weld_key(position: Vec3) -> IVec3 {
+    (position / WELD_DISTANCE).round().as_ivec3()
+}
+
+/// Curvature around every vertex, about -1 in concave corners to 1 on convex edges. Vertices
+/// at the same position count as one, since meshes are imported with vertices duplicated per
+/// face.
+pub fn vertex_cavity(mesh: &EditableMesh) -> HashMap<VertexHandle, f32> {
+    let cavity = VertexCavity::new(mesh);
+
+    mesh.structure
+        .vertex_handles()
+        .map(|vertex| (vertex, cavity.get(vertex)))
+        .collect()
+}
+
+/// The [`vertex_cavity`] of a mesh, kept with the faces and vertices at every welded position
+/// so that it is only recomputed around the vertices that moved
+#[derive(Component, Default)]
+pub struct VertexCavity {
+    /// By vertex index
+    values: Vec<f32>,
+    /// Positions the values were computed with, by vertex index
+    positions: Vec<Vec3>,
+    vertex_count: usize,
+    face_count: usize,
+    faces_at: HashMap<IVec3, Vec<FaceHandle>>,
+    vertices_at: HashMap<IVec3, Vec<VertexHandle>>,
+}
+
+impl VertexCavity {
+    pub fn new(mesh: &EditableMesh) -> Self {
+        let length = mesh
+            .structure
+            .vertex_handles()
+            .map(|vertex| vertex.idx() as usize + 1)
+            .max()
+            .unwrap_or(0);
+
+        let mut cavity = Self {
+            values: vec![0.0; length],
+            positions: vec![Vec3::ZERO; length],
+            vertex_count: mesh.structure.num_vertices() as usize,
+            face_count: mesh.structure.num_faces() as usize,
+            ..default()
+        };
+
+        for face in mesh.structure.face_handles() {
+            for vertex in mesh.structure.get_ref(face).adjacent_vertices() {
+                cavity
+                    .faces_at
+                    .entry(weld_key(mesh.vertex_positions[vertex.handle()]))
+                    .or_default()
+                    .push(face);
+            }
+        }
+        for vertex in mesh.structure.vertex_handles() {
+            let position = mesh.vertex_positions[vertex];
+            cavity.positions[vertex.idx() as usize] = position;
+            cavity
+                .vertices_at
+                .entry(weld_key(position))
+                .or_default()
+                .push(vertex);
+        }
+
+        let keys: Vec<IVec3> = cavity.vertices_at.keys().copied().collect();
+        cavity.recompute(mesh, keys);
+
+        cavity
+    }
+
+    pub fn get(&self, vertex: VertexHandle) -> f32 {
+        self.values
+            .get(vertex.idx() as usize)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    /// Recomputes the cavity around the vertices that moved since the last update, and returns
+    /// the vertices whose cavity was recomputed. Everything is when faces or vertices were added
+    /// or removed.
+    pub fn update(&mut self, mesh: &EditableMesh) -> Vec<VertexHandle> {
+        if self.vertex_count != mesh.structure.num_vertices() as usize
+            || self.face_count != mesh.structure.num_faces() as usize
+        {
+            *self = Self::new(mesh);
+            return mesh.structure.vertex_handles().collect();
+        }
+
+        let moved: Vec<VertexHandle> = mesh
+            .structure
+            .vertex_handles()
+            .filter(|vertex| {
+                self.positions.get(vertex.idx() as usize) != Some(&mesh.vertex_positions[*vertex])
+            })
+            .collect();
+        if moved.is_empty() {
+            return vec![];
+        }
+
+        let mut keys: HashSet<IVec3> = HashSet::new();
+        let mut moved_faces: HashSet<FaceHandle> = HashSet::new();
+        for vertex in moved.iter().copied() {
+            let Some(start) = self.positions.get_mut(vertex.idx() as usize) else {
+                *self = Self::new(mesh);
+                return mesh.structure.vertex_handles().collect();
+            };
+            let (from, to) = (weld_key(*start), weld_key(mesh.vertex_positions[vertex]));
+            *start = mesh.vertex_positions[vertex];
+            keys.insert(from);
+            if from == to {
+                continue;
+            }
+            keys.insert(to);
+
+            if let Some(vertices) = self.vertices_at.get_mut(&from) {
+                vertices.retain(|other| *other != vertex);
+            }
+            self.vertices_at.entry(to).or_default().push(vertex);
+
+            for face in mesh.structure.get_ref(vertex).adjacent_faces() {
+                if let Some(faces) = self.faces_at.get_mut(&from) {
+                    if let Some(index) = faces.iter().position(|other| *other == face.handle()) {
+                        faces.swap_remove(index);
+                    }
+                }
+                self.faces_at.entry(to).or_default().push(face.handle());
+            }
+        }
+
+        // Faces around a moved vertex changed their centre and normal, which every one of their
+        // corners sees
+        for vertex in moved.iter().copied() {
+            moved_faces.extend(
+                mesh.structure
+                    .get_ref(vertex)
+                    .adjacent_faces()
+                    .map(|face| face.handle()),
+            );
+        }
+        for face in moved_faces {
+            keys.extend(
+                mesh.structure
+                    .get_ref(face)
+                    .adjacent_vertices()
+                    .map(|vertex| weld_key(mesh.vertex_positions[vertex.handle()])),
+            );
+        }
+
+        self.faces_at.retain(|_, faces| !faces.is_empty());
+        self.vertices_at.retain(|_, vertices| !vertices.is_empty());
+
+        self.recompute(mesh, keys)
+    }
+
+    /// Cavity of the vertices at the given welded positions, which are returned
+    fn recompute(
+        &mut self,
+        mesh: &EditableMesh,
+        keys: impl IntoIterator<Item = IVec3>,
+    ) -> Vec<VertexHandle> {
+        let center = |face: FaceHandle| {
+            let corners: Vec<Vec3> = mesh
+                .structure
+                .get_ref(face)
+                .adjacent_vertices()
+                .map(|vertex| mesh.vertex_positions[vertex.handle()])
+                .collect();
+            corners.iter().sum::<Vec3>() / corners.len().max(1) as f32
+        };
+
+        let mut recomputed = vec![];
+        for key in keys {
+            let Some(vertices) = self.vertices_at.get(&key) else {
+                continue;
+            };
+            let faces = self.faces_at.get(&key).map_or(&[][..], Vec::as_slice);
+
+            let position = key.as_vec3() * WELD_DISTANCE;
+            let normal = faces
+                .iter()
+                .map(|face| mesh.face_normals[*face])
+                .sum::<Vec3>()
+                .normalize_or_zero();
+
+            // Around a convex point the faces bend away from the normal, in a concave one
+            // towards it
+            let bend = faces
+                .iter()
+                .filter_map(|face| (center(*face) - position).try_normalize())
+                .map(|direction| -normal.dot(direction))
+                .sum::<f32>()
+                / faces.len().max(1) as f32;
+
+            for vertex in vertices {
+                self.values[vertex.idx() as usize] = bend.clamp(-1.0, 1.0);
+            }
+            recomputed.extend(vertices.iter().copied());
+        }
+
+        recomputed
+    }
+}
+
+impl ViewportShading {
+    /// Edges of a mesh in wireframe mode or with the focused wireframe overlay, and its normal
+    /// lines with the normals overlay
+    pub fn mesh_instances(
+        &self,
+        mesh: &EditableMesh,
+        transform: &GlobalTransform,
+        focused: bool,
+    ) -> Vec<OverlayInstance> {
+        let wireframe_mode = self.mode == ShadingMode::Wireframe;
+        let position =
+            |vertex: VertexHandle| transform.transform_point(mesh.vertex_positions[vertex]);
+
+        let mut instances = vec![];
+
+        if wireframe_mode || (self.focused_wireframe && focused) {
+            let color = match (focused, wireframe_mode) {
+                (true, _) => FOCUSED_WIRE_COLOR,
+                (false, true) => WIREFRAME_MODE_COLOR,
+                (false, false) => WIRE_COLOR,
+            };
+
+            for edge in mesh.structure.edges() {
+                let [start, end] = edge.endpoints().map(|vertex| position(vertex.handle()));
+                instances.push(OverlayInstance::line(start, end, WIRE_WIDTH, color));
+            }
+        }
+
+        if self.normals_overlay {
+            for face in mesh.structure.face_handles() {
+                let Some(normal) = world_normal(transform, mesh.face_normals[face]) else {
+                    continue;
+                };
+                let corners: Vec<Vec3> = mesh
+                    .structure
+                    .get_ref(face)
+                    .adjacent_vertices()
+                    .map(|vertex| position(vertex.handle()))
+                    .collect();
+                let center = corners.iter().sum::<Vec3>() / corners.len().max(1) as f32;
+
+                instances.push(OverlayInstance::line(
+                    center,
+                    center + normal * self.normal_length,
+                    WIRE_WIDTH,
+                    NORMAL_COLOR,
+                ));
+            }
+        }
+
+        instances
+    }
+}
+
+impl Plugin for ShadingPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(
+            app,
+            SHADING_SHADER_HANDLE,
+            "shading.wgsl",
+            Shader::from_wgsl
+        );
+
+        app.add_plugins(MaterialPlugin::<ViewportShadingMaterial>::default())
+            .init_resource::<ViewportShading>()
+            .add_systems(Startup, Self::setup_wireframe_overlay)
+            .add_systems(Update, (Self::update_material, Self::update_cavity))
+            .add_systems(
+                PostUpdate,
+                Self::update_wireframe_overlay
+                    .after(TransformSystem::TransformPropagate)
+                    .after(VisibilitySystems::VisibilityPropagate),
+            );
+    }
+}
+
+impl ShadingPlugin {
+    fn setup_wireframe_overlay(mut commands: Commands, mut meshes: ResMut<Assets<Mesh>>) {
+        commands.spawn((
+            meshes.add(create_overlay_base()),
+            SpatialBundle::default(),
+            EditOverlay::default(),
+            NoFrustumCulling,
+            WireframeOverlay,
+        ));
+    }
+
+    fn update_material(
+        shading: Res<ViewportShading>,
+        viewport_material: Res<ViewportMaterial>,
+        mut materials: ResMut<Assets<ViewportShadingMaterial>>,
+    ) {
+        if !shading.is_changed() {
+            return;
+        }
+
+        if let Some(material) = materials.get_mut(&viewport_material.0) {
+            *material = ViewportShadingMaterial::from(shading.as_ref());
+        }
+    }
+
+    /// Gives every render mesh the cavity attribute the material needs, zero while cavity is
+    /// off. While it is on, edits only recompute it around the vertices that moved.
+    fn update_cavity(
+        mut commands: Commands,
+        shading: Res<ViewportShading>,
+        mut cavity_was_on: Local<bool>,
+        mut render_meshes: ResMut<Assets<Mesh>>,
+        mut meshes: Query<(
+            Entity,
+            Ref<EditableMesh>,
+            &Handle<Mesh>,
+            Option<&mut VertexCavity>,
+        )>,
+    ) {
+        let turned_off = !shading.cavity && *cavity_was_on;
+        *cavity_was_on = shading.cavity;
+
+        for (entity, mesh, handle, cavity) in meshes.iter_mut() {
+            let Some(render_mesh) = render_meshes.get(handle) else {
+                continue;
+            };
+            let vertex_count = render_mesh.count_vertices();
+            let complete = render_mesh
+                .attribute(ATTRIBUTE_CAVITY)
+                .is_some_and(|values| values.len() == vertex_count);
+
+            if !shading.cavity {
+                if cavity.is_some() {
+                    commands.entity(entity).remove::<VertexCavity>();
+                }
+                if !complete || turned_off {
+                    if let Some(render_mesh) = render_meshes.get_mut(handle) {
+                        render_mesh.insert_attribute(
+                            ATTRIBUTE_CAVITY,
+                            VertexAttributeValues::Float32(vec![0.0; vertex_count]),
+                        );
+                    }
+                }
+                continue;
+            }
+
+            match cavity {
+                Some(mut cavity) if complete && !mesh.is_added() => {
+                    if !mesh.is_changed() {
+                        continue;
+                    }
+                    let recomputed = cavity.update(&mesh);
+                    if recomputed.is_empty() {
+                        continue;
+                    }
+
+                    let Some(VertexAttributeValues::Float32(values)) = render_meshes
+                        .get_mut(handle)
+                        .and_then(|render_mesh| render_mesh.attribute_mut(ATTRIBUTE_CAVITY))
+                    else {
+                        continue;
+                    };
+                    for vertex in recomputed {
+                        if let Some(value) = values.get_mut(vertex.idx() as usize) {
+                            *value = cavity.get(vertex);
+                        }
+                    }
+                }
+                _ => {
+                    let cavity = VertexCavity::new(&mesh);
+                    let mut values = vec![0.0; vertex_count];
+                    for vertex in mesh.structure.vertex_handles() {
+                        if let Some(value) = values.get_mut(vertex.idx() as usize) {
+                            *value = cavity.get(vertex);
+                        }
+                    }
+
+                    if let Some(render_mesh) = render_meshes.get_mut(handle) {
+                        render_mesh.insert_attribute(
+                            ATTRIBUTE_CAVITY,
+                            VertexAttributeValues::Float32(values),
+                        );
+                    }
+                    commands.entity(entity).insert(cavity);
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the edges and normal lines when the shading or a user mesh changed
+    fn update_wireframe_overlay(
+        shading: Res<ViewportShading>,
+        mut unfocused: RemovedComponents<Focused>,
+        mut removed: RemovedComponents<EditableMesh>,
+        meshes: Query<
+            (
+                Ref<EditableMesh>,
+                Ref<GlobalTransform>,
+                Ref<InheritedVisibility>,
+                Has<Focused>,
+            ),
+            With<UserSpace>,
+        >,
+        focused: Query<(), (Added<Focused>, With<EditableMesh>)>,
+        mut overlay: Query<&mut EditOverlay, With<WireframeOverlay>>,
+    ) {
+        let Ok(mut overlay) = overlay.get_single_mut() else {
+            return;
+        };
+
+        let focus_changed = unfocused.read().count() > 0 || !focused.is_empty();
+        let meshes_removed = removed.read().count() > 0;
+        let meshes_changed = meshes.iter().any(|(mesh, transform, visibility, _)| {
+            mesh.is_changed() || transform.is_changed() || visibility.is_changed()
+        });
+        if !shading.is_changed() && !focus_changed && !meshes_removed && !meshes_changed {
+            return;
+        }
+
+        let mut instances = vec![];
+        for (mesh, transform, visibility, focused) in meshes.iter() {
+            if visibility.get() {
+                instances.extend(shading.mesh_instances(&mesh, &transform, focused));
+            }
+        }
+
+        overlay.instances = instances;
+        // Surfaces are see-through in x-ray, so is everything behind them
+        overlay.hide_occluded = shading.mode != ShadingMode::XRay;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use lox::{core::Mesh as LoxMesh, map::PropStoreMut};
+
+    use super::{
+        vertex_cavity, ShadingMode, VertexCavity, ViewportShading, ViewportShadingMaterial,
+    };
+    use crate::core::editable_mesh::EditableMesh;
+
+    #[test]
+    fn test_cavity_of_convex_corners() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let cavity = vertex_cavity(&mesh);
+
+        // Every vertex of a box is on a convex corner
+        assert_eq!(cavity.len(), mesh.structure.num_vertices() as usize);
+        assert!(cavity.values().all(|value| *value > 0.0));
+
+        // Seen from the inside, the same corners are concave
+        let mut inverted = mesh;
+        let faces: Vec<_> = inverted.structure.face_handles().collect();
+        for face in faces {
+            let normal = inverted.face_normals[face];
+            inverted.face_normals.insert(face, -normal);
+        }
+        assert!(vertex_cavity(&inverted).values().all(|value| *value < 0.0));
+    }
+
+    #[test]
+    fn test_material_follows_mode() {
+        let mut shading = ViewportShading::default();
+        let material = ViewportShadingMaterial::from(&shading);
+        assert_eq!(material.alpha_mode, AlphaMode::Opaque);
+        assert_eq!(material.settings.color.w, 1.0);
+        assert_eq!(material.settings.cavity_strength, 0.0);
+
+        shading.mode = ShadingMode::XRay;
+        shading.xray_alpha = 0.3;
+        shading.cavity = true;
+        let material = ViewportShadingMaterial::from(&shading);
+        assert_eq!(material.alpha_mode, AlphaMode::Blend);
+        assert_eq!(material.settings.color.w, 0.3);
+        assert_eq!(material.settings.mode, ShadingMode::XRay as u32);
+        assert_eq!(material.settings.cavity_strength, 1.0);
+    }
+
+    #[test]
+    fn test_cavity_updates_around_moved_vertices() {
+        let mut mesh: EditableMesh = (&Sphere::new(1.0).mesh().uv(16, 8)).into();
+        let mut cavity = VertexCavity::new(&mesh);
+
+        // Push one vertex in, to make a dent
+        let dented = mesh.structure.vertex_handles().nth(40).unwrap();
+        let position = mesh.vertex_positions[dented];
+        mesh.vertex_positions.insert(dented, position * 0.5);
+        mesh.update_normals([dented]);
+
+        let recomputed = cavity.update(&mesh);
+        assert!(recomputed.contains(&dented));
+        assert!(recomputed.len() < mesh.structure.num_vertices() as usize);
+        assert!(cavity.get(dented) < 0.0);
+
+        let expected = vertex_cavity(&mesh);
+        for vertex in mesh.structure.vertex_handles() {
+            assert!((cavity.get(vertex) - expected[&vertex]).abs() < 1e-5);
+        }
+        assert!(cavity.update(&mesh).is_empty());
+    }
+
+    #[test]
+    fn test_wireframe_and_normal_lines() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let transform = GlobalTransform::IDENTITY;
+        let edges = mesh.structure.num_edges() as usize;
+        let faces = mesh.structure.num_faces() as usize;
+
+        let mut shading = ViewportShading::default();
+        assert!(shading.mesh_instances(&mesh, &transform, true).is_empty());
+
+        shading.focused_wireframe = true;
+        assert!(shading.mesh_instances(&mesh, &transform, false).is_empty());
+        assert_eq!(shading.mesh_instances(&mesh, &transform, true).len(), edges);
+
+        shading.mode = ShadingMode::Wireframe;
+        shading.normals_overlay = true;
+        assert_eq!(
+            shading.mesh_instances(&mesh, &transform, false).len(),
+            edges + faces
+        );
+    }
+}
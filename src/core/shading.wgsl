@@ -0,0 +1,95 @@
+#import bevy_pbr::{
+    mesh_functions::{get_model_matrix, mesh_normal_local_to_world, mesh_position_local_to_clip},
+    mesh_view_bindings::view,
+}
+
+// Same order as `ShadingMode`
+const MODE_WIREFRAME: u32 = 0u;
+const MODE_SOLID: u32 = 1u;
+const MODE_XRAY: u32 = 2u;
+const MODE_MATCAP: u32 = 3u;
+const MODE_FACE_ORIENTATION: u32 = 4u;
+
+struct ShadingSettings {
+    color: vec4<f32>,
+    mode: u32,
+    cavity_strength: f32,
+}
+
+@group(2) @binding(0) var<uniform> settings: ShadingSettings;
+
+struct Vertex {
+    @builtin(instance_index) instance_index: u32,
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) cavity: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) cavity: f32,
+};
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    let model = get_model_matrix(vertex.instance_index);
+    var out: VertexOutput;
+    out.clip_position = mesh_position_local_to_clip(model, vec4<f32>(vertex.position, 1.0));
+    out.world_position = (model * vec4<f32>(vertex.position, 1.0)).xyz;
+    out.world_normal = mesh_normal_local_to_world(vertex.normal, vertex.instance_index);
+    out.cavity = vertex.cavity;
+    return out;
+}
+
+// A key light from the camera and a soft fill from above, whatever lights the scene has
+fn studio(normal: vec3<f32>, view_direction: vec3<f32>) -> vec3<f32> {
+    let key = max(dot(normal, view_direction), 0.0);
+    let sky = 0.5 + 0.5 * normal.y;
+    return settings.color.rgb * (0.25 + 0.6 * key + 0.25 * sky);
+}
+
+// A clay sphere lit from the top left, looked up with the normal in view space
+fn matcap(normal: vec3<f32>) -> vec3<f32> {
+    let n = normalize((view.inverse_view * vec4<f32>(normal, 0.0)).xyz);
+    let light = normalize(vec3<f32>(-0.4, 0.6, 0.7));
+    let diffuse = max(dot(n, light), 0.0);
+    let specular = pow(max(reflect(-light, n).z, 0.0), 24.0);
+    let rim = pow(1.0 - max(n.z, 0.0), 3.0);
+    return vec3<f32>(0.55, 0.45, 0.4) * (0.2 + 0.8 * diffuse)
+        + vec3<f32>(0.35) * specular
+        + vec3<f32>(0.15, 0.17, 0.2) * rim;
+}
+
+@fragment
+fn fragment(in: VertexOutput, @builtin(front_facing) is_front: bool) -> @location(0) vec4<f32> {
+    // The edges are drawn as lines
+    if settings.mode == MODE_WIREFRAME {
+        discard;
+    }
+
+    if settings.mode == MODE_FACE_ORIENTATION {
+        if is_front {
+            return vec4<f32>(0.05, 0.15, 0.8, 1.0);
+        }
+        return vec4<f32>(0.8, 0.05, 0.05, 1.0);
+    }
+
+    var normal = normalize(in.world_normal);
+    if !is_front {
+        normal = -normal;
+    }
+
+    var color: vec3<f32>;
+    if settings.mode == MODE_MATCAP {
+        color = matcap(normal);
+    } else {
+        color = studio(normal, normalize(view.world_position - in.world_position));
+    }
+
+    // Concave areas darken, convex edges lighten
+    color *= max(1.0 + in.cavity * settings.cavity_strength, 0.0);
+
+    return vec4<f32>(color, settings.color.a);
+}
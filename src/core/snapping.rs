@@ -0,0 +1,458 @@
+use std::f32::consts::PI;
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::{bounding::RayCast3d, BVec3},
+    prelude::*,
+    utils::HashSet,
+};
+use lox::{core::Mesh as LoxMesh, VertexHandle};
+use wasm_bindgen::prelude::*;
+
+use crate::utils;
+
+use super::{
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh},
+    editor::{Cursor3d, UserSpace},
+    gestures::Pointer,
+    gizmos::{GizmoPlaneDistance, GizmoScaleToViewportRatio},
+    grid::GridSettings,
+    pan_orbit_camera::{PanOrbitCameraUpdate, PanOrbitState, PrimaryCamera},
+    scene_bvh::SceneBoundingVolumeHierarchy,
+};
+
+/// Snapping for the transform tools: increments for moving, scaling and rotating, and snapping
+/// to the grid, to the elements of other meshes and to the 3D cursor
+pub struct SnappingPlugin;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum SnapTarget {
+    /// Increments of the grid spacing
+    Grid,
+    Vertex,
+    /// Closest point on an edge
+    Edge,
+    /// Point under the pointer on a face
+    Face,
+    FaceCenter,
+    Cursor,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapIncrementMode {
+    /// Values land on multiples of the increment
+    Absolute,
+    /// Changes are multiples of the increment, from where the drag started
+    Relative,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct SnapSettings {
+    pub enabled: bool,
+    pub targets: HashSet<SnapTarget>,
+    pub increment_mode: SnapIncrementMode,
+    /// World units. Follows the spacing of the visible grid when `None`.
+    pub translation_increment: Option<f32>,
+    pub scale_increment: f32,
+    /// Radians
+    pub rotation_increment: f32,
+    /// Logical pixels the pointer may be away from a vertex, edge, face center or the cursor to snap to it
+    pub snap_distance: f32,
+}
+
+/// A snapped position and what it snapped to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SnapPoint {
+    pub position: Vec3,
+    pub target: SnapTarget,
+}
+
+/// Where the current drag snapped, drawn as a marker until the pointer is released
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SnapIndicator(pub Option<SnapPoint>);
+
+impl Default for SnapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            targets: HashSet::from_iter([SnapTarget::Grid]),
+            increment_mode: SnapIncrementMode::Absolute,
+            translation_increment: None,
+            scale_increment: 0.1,
+            rotation_increment: 15f32.to_radians(),
+            snap_distance: 12.0,
+        }
+    }
+}
+
+/// Rounds `value` to the closest multiple of `increment`. Increments of zero or less leave it as is.
+pub fn snap_to_increment(value: f32, increment: f32) -> f32 {
+    if increment <= 0.0 {
+        return value;
+    }
+
+    (value / increment).round() * increment
+}
+
+fn snap_axes(value: Vec3, increment: f32, axes: BVec3) -> Vec3 {
+    let snapped = Vec3::new(
+        snap_to_increment(value.x, increment),
+        snap_to_increment(value.y, increment),
+        snap_to_increment(value.z, increment),
+    );
+
+    Vec3::select(axes, snapped, value)
+}
+
+impl SnapSettings {
+    /// Snapping applies while enabled, and can be flipped for a single drag by holding Ctrl
+    pub fn is_active(&self, keys: &ButtonInput<KeyCode>) -> bool {
+        self.enabled != keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    }
+
+    fn snap_increment(&self, start: Vec3, free: Vec3, increment: f32, axes: BVec3) -> Vec3 {
+        match self.increment_mode {
+            SnapIncrementMode::Absolute => snap_axes(free, increment, axes),
+            SnapIncrementMode::Relative => start + snap_axes(free - start, increment, axes),
+        }
+    }
+
+    /// Snaps a position moved from `start` to `free` along `axes` to translation increments
+    pub fn snap_translation(
+        &self,
+        start: Vec3,
+        free: Vec3,
+        axes: BVec3,
+        grid_spacing: f32,
+    ) -> Vec3 {
+        let increment = self.translation_increment.unwrap_or(grid_spacing);
+        self.snap_increment(start, free, increment, axes)
+    }
+
+    pub fn snap_scale(&self, start: Vec3, free: Vec3, axes: BVec3) -> Vec3 {
+        self.snap_increment(start, free, self.scale_increment, axes)
+    }
+
+    /// Angles are always snapped relative to where the rotation started
+    pub fn snap_angle(&self, angle: f32) -> f32 {
+        snap_to_increment(angle, self.rotation_increment)
+    }
+}
+
+/// Closest point to `point` on the segment between `a` and `b`, as a parameter between 0 and 1
+fn closest_on_segment(a: Vec2, b: Vec2, point: Vec2) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared <= f32::EPSILON {
+        return 0.0;
+    }
+
+    ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
+
+/// Everything the transform tools need to snap, as a single system parameter
+#[derive(SystemParam)]
+pub struct Snapper<'w, 's> {
+    pub settings: Res<'w, SnapSettings>,
+    pub indicator: ResMut<'w, SnapIndicator>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    cursor: Res<'w, Cursor3d>,
+    grid: Res<'w, GridSettings>,
+    scene_bvh: Res<'w, SceneBoundingVolumeHierarchy>,
+    meshes: Query<
+        'w,
+        's,
+        (
+            &'static BoundingVolumeHierarchy,
+            &'static GlobalTransform,
+            &'static EditableMesh,
+        ),
+        With<UserSpace>,
+    >,
+    camera: Query<
+        'w,
+        's,
+        (
+            &'static Camera,
+            &'static GlobalTransform,
+            &'static PanOrbitState,
+        ),
+        With<PrimaryCamera>,
+    >,
+}
+
+impl<'w, 's> Snapper<'w, 's> {
+    pub fn is_active(&self) -> bool {
+        self.settings.is_active(&self.keys)
+    }
+
+    /// Snaps a position the tools moved from `start` to `free`, changing only `axes` of the
+    /// frame rotated by `orientation`. Elements under the pointer win over the grid. `exclude`
+    /// is the entity being moved.
+    pub fn snap_translation(
+        &mut self,
+        start: Vec3,
+        free: Vec3,
+        axes: BVec3,
+        orientation: Quat,
+        pointer: Option<Vec2>,
+        exclude: Entity,
+    ) -> Vec3 {
+        if !self.is_active() {
+            self.indicator.0 = None;
+            return free;
+        }
+
+        let inverse = orientation.inverse();
+
+        if let Some(point) = pointer.and_then(|pointer| self.find_element(pointer, exclude)) {
+            self.indicator.0 = Some(point);
+            return orientation * Vec3::select(axes, inverse * point.position, inverse * free);
+        }
+
+        if !self.settings.targets.contains(&SnapTarget::Grid) {
+            self.indicator.0 = None;
+            return free;
+        }
+
+        let grid_spacing = self
+            .camera
+            .get_single()
+            .map_or(self.grid.base_spacing(), |(_, _, state)| {
+                self.grid.spacing_at(state.radius)
+            });
+
+        let snapped = orientation
+            * self
+                .settings
+                .snap_translation(inverse * start, inverse * free, axes, grid_spacing);
+        self.indicator.0 = Some(SnapPoint {
+            position: snapped,
+            target: SnapTarget::Grid,
+        });
+
+        snapped
+    }
+
+    pub fn snap_scale(&self, start: Vec3, free: Vec3, axes: BVec3) -> Vec3 {
+        if !self.is_active() {
+            return free;
+        }
+
+        self.settings.snap_scale(start, free, axes)
+    }
+
+    pub fn snap_angle(&self, angle: f32) -> f32 {
+        if !self.is_active() {
+            return angle;
+        }
+
+        self.settings.snap_angle(angle)
+    }
+
+    /// Closest enabled element of another mesh, or the 3D cursor, under a viewport position
+    pub fn find_element(&self, pointer: Vec2, exclude: Entity) -> Option<SnapPoint> {
+        let (camera, camera_transform, _) = self.camera.get_single().ok()?;
+        let targets = &self.settings.targets;
+        let snap_distance = self.settings.snap_distance;
+
+        let to_screen = |position: Vec3| camera.world_to_viewport(camera_transform, position);
+
+        let mut best: Option<(f32, SnapPoint)> = None;
+        let mut consider = |position: Vec3, target: SnapTarget| {
+            if !targets.contains(&target) {
+                return;
+            }
+            let Some(screen) = to_screen(position) else {
+                return;
+            };
+            let distance = screen.distance(pointer);
+            if distance <= snap_distance && best.map_or(true, |(best, _)| distance < best) {
+                best = Some((distance, SnapPoint { position, target }));
+            }
+        };
+
+        consider(self.cursor.position, SnapTarget::Cursor);
+
+        let Some(ray) = camera.viewport_to_world(camera_transform, pointer) else {
+            return best.map(|(_, point)| point);
+        };
+        let ray = RayCast3d::from_ray(ray, 1000.0);
+
+        let hit = self.scene_bvh.cast_ray(&ray, |entity| {
+            if entity == exclude {
+                return None;
+            }
+            let (bvh, transform, mesh) = self.meshes.get(entity).ok()?;
+            bvh.intersects_ray_at(&ray, transform, mesh)
+        });
+
+        let mut face_point = None;
+
+        if let Some((entity, face_handle, t)) = hit {
+            let Ok((_, transform, mesh)) = self.meshes.get(entity) else {
+                return best.map(|(_, point)| point);
+            };
+
+            let vertices: Vec<Vec3> = mesh
+                .structure
+                .get_ref(face_handle)
+                .adjacent_vertices()
+                .map(|vertex| vertex.handle())
+                .map(|vertex_handle: VertexHandle| {
+                    transform.transform_point(mesh.vertex_positions[vertex_handle])
+                })
+                .collect();
+
+            for position in vertices.iter() {
+                consider(*position, SnapTarget::Vertex);
+            }
+
+            if !vertices.is_empty() {
+                let center = vertices.iter().sum::<Vec3>() / vertices.len() as f32;
+                consider(center, SnapTarget::FaceCenter);
+            }
+
+            // Edges are matched in screen space, so the point is the one that looks closest
+            for (index, a) in vertices.iter().enumerate() {
+                let b = vertices[(index + 1) % vertices.len()];
+                let (Some(screen_a), Some(screen_b)) = (to_screen(*a), to_screen(b)) else {
+                    continue;
+                };
+                let s = closest_on_segment(screen_a, screen_b, pointer);
+                consider(a.lerp(b, s), SnapTarget::Edge);
+            }
+
+            if targets.contains(&SnapTarget::Face) {
+                face_point = Some(SnapPoint {
+                    position: ray.ray.get_point(t),
+                    target: SnapTarget::Face,
+                });
+            }
+        }
+
+        best.map(|(_, point)| point).or(face_point)
+    }
+}
+
+impl Plugin for SnappingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SnapSettings>()
+            .init_resource::<SnapIndicator>()
+            .add_systems(
+                Update,
+                (Self::clear_indicator, Self::draw_indicator)
+                    .chain()
+                    .after(PanOrbitCameraUpdate),
+            );
+    }
+}
+
+impl SnappingPlugin {
+    fn clear_indicator(pointer: Res<Pointer>, mut indicator: ResMut<SnapIndicator>) {
+        if !pointer.pressed && indicator.0.is_some() {
+            indicator.0 = None;
+        }
+    }
+
+    /// Marks the snap target: a circle for vertices, a diamond for edges, a square for face
+    /// centers, a cross for faces and the grid, and a double circle for the cursor
+    fn draw_indicator(
+        indicator: Res<SnapIndicator>,
+        mut gizmos: Gizmos,
+        camera: Query<&Transform, With<PrimaryCamera>>,
+        pixel_scale: Res<GizmoScaleToViewportRatio>,
+        plane_distance: Res<GizmoPlaneDistance>,
+    ) {
+        let Some(point) = indicator.0 else {
+            return;
+        };
+        let Ok(camera) = camera.get_single() else {
+            return;
+        };
+
+        let forward: Vec3 = camera.forward().into();
+        let position = utils::projection::project_to_plane(
+            camera.translation,
+            forward,
+            point.position,
+            plane_distance.0,
+        );
+
+        let size = 6.0 * pixel_scale.0;
+        let color = Color::rgb(1.0, 0.6, 0.0);
+        let right: Vec3 = camera.right().into();
+        let up: Vec3 = camera.up().into();
+
+        let polygon = |corners: usize, angle: f32| -> Vec<Vec3> {
+            (0..=corners)
+                .map(|i| {
+                    let a = angle + i as f32 * 2.0 * PI / corners as f32;
+                    position + (right * a.cos() + up * a.sin()) * size
+                })
+                .collect()
+        };
+
+        match point.target {
+            SnapTarget::Vertex => {
+                gizmos.circle(position, camera.forward(), size, color);
+            }
+            SnapTarget::Edge => gizmos.linestrip(polygon(4, 0.0), color),
+            SnapTarget::FaceCenter => gizmos.linestrip(polygon(4, PI / 4.0), color),
+            SnapTarget::Face | SnapTarget::Grid => {
+                gizmos.line(position - right * size, position + right * size, color);
+                gizmos.line(position - up * size, position + up * size, color);
+            }
+            SnapTarget::Cursor => {
+                gizmos.circle(position, camera.forward(), size, color);
+                gizmos.circle(position, camera.forward(), size * 1.5, color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{math::BVec3, prelude::*};
+
+    use super::{snap_to_increment, SnapIncrementMode, SnapSettings};
+
+    #[test]
+    fn test_snap_to_increment() {
+        assert_eq!(snap_to_increment(0.74, 0.5), 0.5);
+        assert_eq!(snap_to_increment(-0.76, 0.5), -1.0);
+        assert_eq!(snap_to_increment(0.3, 0.0), 0.3);
+    }
+
+    #[test]
+    fn test_absolute_and_relative_translation() {
+        let mut settings = SnapSettings::default();
+        let start = Vec3::new(0.3, 0.0, 0.0);
+        let free = Vec3::new(1.6, 0.2, 0.0);
+        let x_only = BVec3::new(true, false, false);
+
+        settings.increment_mode = SnapIncrementMode::Absolute;
+        assert_eq!(
+            settings.snap_translation(start, free, x_only, 1.0),
+            Vec3::new(2.0, 0.2, 0.0)
+        );
+
+        settings.increment_mode = SnapIncrementMode::Relative;
+        let relative = settings.snap_translation(start, free, x_only, 1.0);
+        assert!((relative - Vec3::new(1.3, 0.2, 0.0)).length() < 1e-5);
+
+        // An explicit increment overrides the grid spacing
+        settings.translation_increment = Some(0.25);
+        let relative = settings.snap_translation(start, free, BVec3::TRUE, 1.0);
+        assert!((relative - Vec3::new(1.55, 0.25, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_snap_angle() {
+        let settings = SnapSettings::default();
+        let angle = settings.snap_angle(20f32.to_radians());
+        assert!((angle - 15f32.to_radians()).abs() < 1e-5);
+    }
+}
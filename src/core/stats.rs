@@ -0,0 +1,500 @@
+use std::ops::AddAssign;
+
+use bevy::{
+    diagnostic::{
+        Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin,
+        RegisterDiagnostic,
+    },
+    prelude::*,
+    utils::Instant,
+};
+use lox::{
+    core::Mesh as LoxMesh,
+    FaceHandle,
+};
+
+use super::{
+    edit_overlay::OverlaySelection,
+    editable_mesh::{
+        bvh::BoundingVolumeHierarchy, ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh,
+    },
+    editor::{Focused, UserSpace},
+    highlight::Highlight,
+    interaction::InteractionMode,
+    keymap::{EditorAction, EditorActionEvent},
+};
+
+/// Scene and selection statistics, BVH and memory figures and frame timings, shown in an
+/// overlay toggled with [`EditorAction::ToggleStats`] (Shift+F12 by default)
+pub struct StatsPlugin;
+
+/// Number of elements of the user meshes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ElementCounts {
+    pub objects: usize,
+    pub vertices: usize,
+    pub edges: usize,
+    pub faces: usize,
+    /// Faces once triangulated, a face with n corners makes n - 2 triangles
+    pub triangles: usize,
+}
+
+impl ElementCounts {
+    pub fn of_mesh(mesh: &EditableMesh) -> Self {
+        Self {
+            objects: 1,
+            vertices: mesh.structure.num_vertices() as usize,
+            edges: mesh.structure.num_edges() as usize,
+            faces: mesh.structure.num_faces() as usize,
+            triangles: mesh
+                .structure
+                .face_handles()
+                .map(|face| triangle_count(mesh, face))
+                .sum(),
+        }
+    }
+
+    /// Elements selected in edit mode, counted like they are drawn by the edit overlay
+    pub fn of_selection(mesh: &EditableMesh, selection: &OverlaySelection) -> Self {
+        Self {
+            objects: 1,
+            vertices: selection.vertices.len(),
+            edges: selection.edges.len(),
+            faces: selection.faces.len(),
+            triangles: selection
+                .faces
+                .iter()
+                .map(|face| triangle_count(mesh, *face))
+                .sum(),
+        }
+    }
+}
+
+impl AddAssign for ElementCounts {
+    fn add_assign(&mut self, other: Self) {
+        self.objects += other.objects;
+        self.vertices += other.vertices;
+        self.edges += other.edges;
+        self.faces += other.faces;
+        self.triangles += other.triangles;
+    }
+}
+
+fn triangle_count(mesh: &EditableMesh, face: FaceHandle) -> usize {
+    mesh.structure
+        .get_ref(face)
+        .adjacent_vertices()
+        .count()
+        .saturating_sub(2)
+}
+
+/// Bounding volume hierarchies of the user meshes, added together
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BvhStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    /// Deepest of the trees
+    pub depth: u32,
+    /// Approximate, in bytes
+    pub memory: usize,
+}
+
+impl BvhStats {
+    pub fn of_tree(bvh: &BoundingVolumeHierarchy) -> Self {
+        Self {
+            nodes: bvh.node_count(),
+            leaves: bvh.leaves().count(),
+            depth: bvh.depth(),
+            memory: bvh.memory_usage(),
+        }
+    }
+}
+
+impl AddAssign for BvhStats {
+    fn add_assign(&mut self, other: Self) {
+        self.nodes += other.nodes;
+        self.leaves += other.leaves;
+        self.depth = self.depth.max(other.depth);
+        self.memory += other.memory;
+    }
+}
+
+/// Where the time of a frame goes, in milliseconds
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    pub fps: f64,
+    pub frame_time: f64,
+    /// Spent running the main world schedules
+    pub update_time: f64,
+    /// The rest of the frame: extraction, rendering and waiting for the display
+    pub render_time: f64,
+    /// Shortest and longest frames of the diagnostic history
+    pub min_frame_time: f64,
+    pub max_frame_time: f64,
+}
+
+impl FrameTiming {
+    pub fn from_diagnostics(diagnostics: &DiagnosticsStore) -> Self {
+        let smoothed = |path: &DiagnosticPath| {
+            diagnostics
+                .get(path)
+                .and_then(|diagnostic| diagnostic.smoothed())
+                .unwrap_or(0.0)
+        };
+        let frame_times: Vec<f64> = diagnostics
+            .get(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+            .map(|diagnostic| diagnostic.values().copied().collect())
+            .unwrap_or_default();
+
+        let frame_time = smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME);
+        let update_time = smoothed(&StatsPlugin::UPDATE_TIME);
+
+        Self {
+            fps: smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+            frame_time,
+            update_time,
+            render_time: (frame_time - update_time).max(0.0),
+            min_frame_time: frame_times.iter().copied().reduce(f64::min).unwrap_or(0.0),
+            max_frame_time: frame_times.iter().copied().reduce(f64::max).unwrap_or(0.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SceneStats {
+    /// Every user mesh
+    pub scene: ElementCounts,
+    /// The selected objects in object mode, the selected elements of the focused mesh in edit
+    /// mode
+    pub selection: ElementCounts,
+    pub bvh: BvhStats,
+    /// Approximate memory held by the editable meshes, without their BVH, in bytes
+    pub mesh_memory: usize,
+    pub timing: FrameTiming,
+}
+
+/// Approximate heap memory of an editable mesh, in bytes. The half-edge structure keeps a
+/// handle per vertex and face and four per half-edge, next to the positions and normals.
+pub fn mesh_memory(mesh: &EditableMesh) -> usize {
+    const HANDLE: usize = std::mem::size_of::<u32>();
+    const VEC3: usize = std::mem::size_of::<Vec3>();
+
+    let vertices = mesh.structure.num_vertices() as usize;
+    let half_edges = 2 * mesh.structure.num_edges() as usize;
+    let faces = mesh.structure.num_faces() as usize;
+
+    vertices * (HANDLE + 2 * VEC3) + half_edges * 4 * HANDLE + faces * (HANDLE + VEC3)
+}
+
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+impl SceneStats {
+    /// Lines shown by the overlay, selection next to the scene totals
+    pub fn to_text(&self) -> String {
+        let counts = [
+            ("Objects", self.selection.objects, self.scene.objects),
+            ("Vertices", self.selection.vertices, self.scene.vertices),
+            ("Edges", self.selection.edges, self.scene.edges),
+            ("Faces", self.selection.faces, self.scene.faces),
+            ("Triangles", self.selection.triangles, self.scene.triangles),
+        ];
+
+        let mut lines: Vec<String> = counts
+            .iter()
+            .map(|(name, selected, total)| format!("{name:<10} {selected} / {total}"))
+            .collect();
+
+        lines.push(format!(
+            "{:<10} {} nodes, {} leaves, depth {}",
+            "BVH", self.bvh.nodes, self.bvh.leaves, self.bvh.depth
+        ));
+        lines.push(format!(
+            "{:<10} {} meshes, {} BVH",
+            "Memory",
+            format_bytes(self.mesh_memory),
+            format_bytes(self.bvh.memory)
+        ));
+
+        let timing = &self.timing;
+        lines.push(format!(
+            "{:<10} {:.1} ms ({:.0} fps)",
+            "Frame", timing.frame_time, timing.fps
+        ));
+        lines.push(format!("{:<10} {:.1} ms", "  Update", timing.update_time));
+        lines.push(format!("{:<10} {:.1} ms", "  Render", timing.render_time));
+        lines.push(format!(
+            "{:<10} {:.1} / {:.1} ms",
+            "  Min/max", timing.min_frame_time, timing.max_frame_time
+        ));
+
+        lines.join("\n")
+    }
+}
+
+/// Marker to find the container entity so we can show/hide the statistics
+#[derive(Component)]
+struct StatsRoot;
+
+/// Marker to find the text entity so we can update it
+#[derive(Component)]
+struct StatsText;
+
+/// When the main world schedules of the current frame started
+#[derive(Resource)]
+struct UpdateStart(Instant);
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_diagnostic(Diagnostic::new(Self::UPDATE_TIME).with_suffix("ms"))
+            .insert_resource(UpdateStart(Instant::now()))
+            .add_systems(First, Self::begin_update)
+            .add_systems(Last, Self::end_update)
+            .add_systems(Startup, Self::setup_stats_overlay)
+            .add_systems(
+                Update,
+                (Self::stats_showhide, Self::stats_text_update_system),
+            );
+    }
+}
+
+impl StatsPlugin {
+    pub const UPDATE_TIME: DiagnosticPath = DiagnosticPath::const_new("editor/update_time");
+
+    /// Seconds between two updates of the overlay, so the numbers stay readable
+    const REFRESH_INTERVAL: f32 = 0.25;
+
+    fn begin_update(mut start: ResMut<UpdateStart>) {
+        start.0 = Instant::now();
+    }
+
+    fn end_update(start: Res<UpdateStart>, mut diagnostics: Diagnostics) {
+        diagnostics.add_measurement(&Self::UPDATE_TIME, || {
+            start.0.elapsed().as_secs_f64() * 1000.0
+        });
+    }
+
+    /// Statistics of the current state of the world
+    pub fn collect(world: &mut World) -> SceneStats {
+        let edit_mode = world
+            .get_resource::<InteractionMode>()
+            .is_some_and(|mode| *mode == InteractionMode::Edit);
+
+        let mut stats = SceneStats::default();
+
+        let mut query = world.query_filtered::<(
+            &EditableMesh,
+            &BoundingVolumeHierarchy,
+            &ActiveVertices,
+            &ActiveEdges,
+            &ActiveFaces,
+            Has<Focused>,
+            Has<Highlight>,
+        ), With<UserSpace>>();
+
+        for (mesh, bvh, vertices, edges, faces, focused, highlighted) in query.iter(world) {
+            let counts = ElementCounts::of_mesh(mesh);
+            stats.scene += counts;
+
+            if edit_mode {
+                if focused {
+                    let selection = OverlaySelection::new(mesh, vertices, edges, faces);
+                    stats.selection += ElementCounts::of_selection(mesh, &selection);
+                }
+            } else if focused || highlighted {
+                stats.selection += counts;
+            }
+
+            stats.bvh += BvhStats::of_tree(bvh);
+            stats.mesh_memory += mesh_memory(mesh);
+        }
+
+        if let Some(diagnostics) = world.get_resource::<DiagnosticsStore>() {
+            stats.timing = FrameTiming::from_diagnostics(diagnostics);
+        }
+
+        stats
+    }
+
+    pub fn is_visible(world: &mut World) -> bool {
+        world
+            .query_filtered::<&Visibility, With<StatsRoot>>()
+            .get_single(world)
+            .is_ok_and(|visibility| *visibility != Visibility::Hidden)
+    }
+
+    pub fn set_visible(world: &mut World, visible: bool) {
+        let mut query = world.query_filtered::<&mut Visibility, With<StatsRoot>>();
+        if let Ok(mut visibility) = query.get_single_mut(world) {
+            *visibility = if visible {
+                Visibility::Visible
+            } else {
+                Visibility::Hidden
+            };
+        }
+    }
+
+    fn setup_stats_overlay(mut commands: Commands) {
+        let root = commands
+            .spawn((
+                StatsRoot,
+                NodeBundle {
+                    background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
+                    z_index: ZIndex::Global(i32::MAX),
+                    // Hidden until toggled
+                    visibility: Visibility::Hidden,
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        // Bottom-left corner, clear of the FPS counter and the modal header
+                        left: Val::Percent(1.),
+                        bottom: Val::Percent(1.),
+                        right: Val::Auto,
+                        top: Val::Auto,
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let text = commands
+            .spawn((
+                StatsText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 14.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ))
+            .id();
+        commands.entity(root).push_children(&[text]);
+    }
+
+    /// Refreshes the text while the overlay is visible
+    fn stats_text_update_system(world: &mut World, mut since_refresh: Local<Option<f32>>) {
+        if !Self::is_visible(world) {
+            *since_refresh = None;
+            return;
+        }
+
+        let delta = world.resource::<Time>().delta_seconds();
+        if let Some(elapsed) = since_refresh.as_mut() {
+            *elapsed += delta;
+            if *elapsed < Self::REFRESH_INTERVAL {
+                return;
+            }
+        }
+        *since_refresh = Some(0.0);
+
+        let text = Self::collect(world).to_text();
+
+        let mut query = world.query_filtered::<&mut Text, With<StatsText>>();
+        for mut stats_text in query.iter_mut(world) {
+            stats_text.sections[0].value = text.clone();
+        }
+    }
+
+    /// Toggle the statistics on [`EditorAction::ToggleStats`]
+    fn stats_showhide(
+        mut q: Query<&mut Visibility, With<StatsRoot>>,
+        mut actions: EventReader<EditorActionEvent>,
+    ) {
+        for _ in actions
+            .read()
+            .filter(|action| action.0 == EditorAction::ToggleStats)
+        {
+            let mut vis = q.single_mut();
+            *vis = match *vis {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+    use lox::{core::Mesh as LoxMesh, Handle as LoxHandle};
+
+    use super::{format_bytes, BvhStats, ElementCounts, SceneStats};
+    use crate::core::{
+        edit_overlay::OverlaySelection,
+        editable_mesh::{
+            bvh::BoundingVolumeHierarchy, ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh,
+        },
+    };
+
+    #[test]
+    fn test_counts_of_mesh_and_selection() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+
+        let counts = ElementCounts::of_mesh(&mesh);
+        assert_eq!(counts.objects, 1);
+        assert_eq!(counts.vertices, 24);
+        assert_eq!(counts.faces, 12);
+        assert_eq!(counts.triangles, 12);
+
+        let face = mesh.structure.face_handles().next().unwrap();
+        let selection = OverlaySelection::new(
+            &mesh,
+            &ActiveVertices::default(),
+            &ActiveEdges::default(),
+            &ActiveFaces([face.idx()].into_iter().collect()),
+        );
+        let selected = ElementCounts::of_selection(&mesh, &selection);
+        assert_eq!(
+            selected,
+            ElementCounts {
+                objects: 1,
+                vertices: 3,
+                edges: 3,
+                faces: 1,
+                triangles: 1,
+            }
+        );
+
+        let mut total = counts;
+        total += counts;
+        assert_eq!(total.triangles, 24);
+        assert_eq!(total.objects, 2);
+    }
+
+    #[test]
+    fn test_overlay_text() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let bvh = BoundingVolumeHierarchy::from(&mesh);
+
+        let stats = SceneStats {
+            scene: ElementCounts::of_mesh(&mesh),
+            bvh: BvhStats::of_tree(&bvh),
+            mesh_memory: 3 * 1024 * 1024 / 2,
+            ..default()
+        };
+        let text = stats.to_text();
+
+        assert!(text.contains("Vertices   0 / 24"));
+        assert!(text.contains(&format!("depth {}", bvh.depth())));
+        assert!(text.contains("1.5 MB meshes"));
+
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+    }
+}
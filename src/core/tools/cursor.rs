@@ -0,0 +1,147 @@
+use bevy::{
+    math::{bounding::RayCast3d, BVec3},
+    prelude::*,
+};
+
+use crate::core::{
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh},
+    editor::{Cursor3d, UserSpace},
+    gestures::Pointer,
+    pan_orbit_camera::PrimaryCamera,
+    scene_bvh::SceneBoundingVolumeHierarchy,
+    snapping::Snapper,
+    tools::transform_space::TransformSpacePlugin,
+};
+
+/// Places the [`Cursor3d`] on the surface under the pointer, with Y along the face normal
+pub struct CursorTool;
+
+/// Normal of a mesh space face in world space, correct under non-uniform scale
+pub fn world_normal(transform: &GlobalTransform, normal: Vec3) -> Option<Vec3> {
+    let matrix = Mat3::from_mat4(transform.compute_matrix());
+
+    (matrix.inverse().transpose() * normal).try_normalize()
+}
+
+impl CursorTool {
+    pub fn update_system(
+        mut commands: Commands,
+        pointer: Res<Pointer>,
+        cursor: Res<Cursor3d>,
+        scene_bvh: Res<SceneBoundingVolumeHierarchy>,
+        q_meshes: Query<
+            (&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh),
+            With<UserSpace>,
+        >,
+        q_camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+        mut snapper: Snapper,
+    ) {
+        if !pointer.select_triggered() || pointer.captured {
+            return;
+        }
+        let Some(pointer_position) = pointer.position else {
+            return;
+        };
+        let Ok((camera, camera_transform)) = q_camera.get_single() else {
+            return;
+        };
+        let Some(ray) = camera.viewport_to_world(camera_transform, pointer_position) else {
+            return;
+        };
+        let ray_cast = RayCast3d::from_ray(ray, 1000.0);
+
+        let hit = scene_bvh.cast_ray(&ray_cast, |entity| {
+            let (bvh, transform, mesh) = q_meshes.get(entity).ok()?;
+            bvh.intersects_ray_at(&ray_cast, transform, mesh)
+        });
+
+        let (point, orientation) = match hit {
+            Some((entity, face, t)) => {
+                let orientation = q_meshes
+                    .get(entity)
+                    .ok()
+                    .and_then(|(_, transform, mesh)| {
+                        world_normal(transform, mesh.face_normals[face])
+                    })
+                    // Back faces are hit from behind, so the cursor faces the camera
+                    .map(|normal| {
+                        if normal.dot(*ray.direction) > 0.0 {
+                            -normal
+                        } else {
+                            normal
+                        }
+                    })
+                    .map_or(cursor.orientation, |normal| {
+                        Quat::from_rotation_arc(Vec3::Y, normal)
+                    });
+
+                (ray.get_point(t), orientation)
+            }
+            // Off the meshes the cursor moves in the plane facing the camera at its current depth
+            None => {
+                let plane = Plane3d::new(camera_transform.back());
+                let Some(distance) = ray.intersect_plane(cursor.position, plane) else {
+                    return;
+                };
+
+                (ray.get_point(distance), cursor.orientation)
+            }
+        };
+
+        let position = snapper.snap_translation(
+            Vec3::ZERO,
+            point,
+            BVec3::TRUE,
+            Quat::IDENTITY,
+            Some(pointer_position),
+            Entity::PLACEHOLDER,
+        );
+
+        // The snapper already reads the cursor, so it is written once the system is done
+        commands.add(move |world: &mut World| {
+            let mut cursor = world.resource_mut::<Cursor3d>();
+            cursor.position = position;
+            cursor.orientation = orientation;
+        });
+    }
+
+    /// Moves the cursor back to the origin with the world orientation
+    pub fn reset(world: &mut World) {
+        *world.resource_mut::<Cursor3d>() = Cursor3d::default();
+    }
+
+    /// Moves the cursor to the median of the selection, keeping its orientation. Returns false if
+    /// nothing is selected.
+    pub fn snap_to_selection(world: &mut World) -> bool {
+        let Some(position) = TransformSpacePlugin::selection_median(world) else {
+            return false;
+        };
+
+        world.resource_mut::<Cursor3d>().position = position;
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::world_normal;
+
+    #[test]
+    fn test_world_normal_under_non_uniform_scale() {
+        // A 45° slope in mesh space gets steeper when stretched along Y
+        let transform = GlobalTransform::from(Transform::from_scale(Vec3::new(1.0, 2.0, 1.0)));
+        let normal = world_normal(&transform, Vec3::new(-1.0, 1.0, 0.0).normalize()).unwrap();
+
+        // The stretched surface runs along (1, 2, 0), so the normal must stay perpendicular
+        assert!(normal.dot(Vec3::new(1.0, 2.0, 0.0)).abs() < 1e-5);
+        assert!((normal.length() - 1.0).abs() < 1e-5);
+
+        let rotated = GlobalTransform::from(Transform::from_rotation(Quat::from_rotation_x(
+            std::f32::consts::FRAC_PI_2,
+        )));
+        let normal = world_normal(&rotated, Vec3::Y).unwrap();
+        assert!((normal - Vec3::Z).length() < 1e-5);
+    }
+}
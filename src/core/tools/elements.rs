@@ -0,0 +1,383 @@
+use bevy::{
+    ecs::system::SystemParam,
+    math::Affine3A,
+    prelude::*,
+    render::primitives::Aabb,
+    utils::{HashMap, HashSet},
+};
+use lox::{core::Mesh as LoxMesh, VertexHandle};
+
+use crate::core::{
+    editable_mesh::{
+        bvh::BoundingVolumeHierarchy, update_moved_vertices, ActiveEdges, ActiveFaces,
+        ActiveVertices, EditableMesh,
+    },
+    editor::Focused,
+    interaction::InteractionMode,
+    tools::{proportional::ProportionalEditing, transform_space::median_point},
+};
+
+/// A transform in world space around a pivot, applied to vertices by weight
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ElementDelta {
+    pub pivot: Vec3,
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+    /// Frame the scale is along
+    pub scale_orientation: Quat,
+}
+
+impl Default for ElementDelta {
+    fn default() -> Self {
+        Self {
+            pivot: Vec3::ZERO,
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            scale: Vec3::ONE,
+            scale_orientation: Quat::IDENTITY,
+        }
+    }
+}
+
+impl ElementDelta {
+    /// The change from `start` to `current`, around the translation of `start` and scaling along
+    /// its rotation
+    pub fn between(start: &Transform, current: &Transform) -> Self {
+        Self {
+            pivot: start.translation,
+            translation: current.translation - start.translation,
+            rotation: current.rotation * start.rotation.inverse(),
+            scale: Vec3::select(
+                start.scale.cmpeq(Vec3::ZERO),
+                Vec3::ONE,
+                current.scale / start.scale,
+            ),
+            scale_orientation: start.rotation,
+        }
+    }
+
+    /// Where `point` goes with `weight` of the delta applied
+    pub fn apply(&self, point: Vec3, weight: f32) -> Vec3 {
+        let offset = point - self.pivot;
+
+        let scale = Vec3::ONE.lerp(self.scale, weight);
+        let scaled = self.scale_orientation * (scale * (self.scale_orientation.inverse() * offset));
+        let rotated = Quat::IDENTITY.slerp(self.rotation, weight) * scaled;
+
+        self.pivot + rotated + self.translation * weight
+    }
+}
+
+/// Selected vertices of a mesh being transformed in edit mode, together with the unselected
+/// ones that follow through proportional editing
+#[derive(Clone, Debug)]
+pub struct ElementTransform {
+    pub entity: Entity,
+    pub selected: HashSet<VertexHandle>,
+    /// Mesh to world space of the entity when the transform started
+    to_world: Affine3A,
+    /// Mesh space positions when the transform started
+    start_positions: HashMap<VertexHandle, Vec3>,
+    weights: Vec<(VertexHandle, f32)>,
+    /// Proportional radius the weights were computed with
+    radius: Option<f32>,
+    /// Vertices moved by the last [`ElementTransform::apply`]
+    moved: Vec<VertexHandle>,
+}
+
+impl ElementTransform {
+    pub fn new(
+        entity: Entity,
+        transform: &GlobalTransform,
+        mesh: &EditableMesh,
+        selected: HashSet<VertexHandle>,
+        proportional: &ProportionalEditing,
+    ) -> Self {
+        let mut elements = Self {
+            entity,
+            selected,
+            to_world: transform.affine(),
+            start_positions: mesh
+                .structure
+                .vertex_handles()
+                .map(|vertex| (vertex, mesh.vertex_positions[vertex]))
+                .collect(),
+            weights: vec![],
+            radius: None,
+            moved: vec![],
+        };
+        elements.update_weights(mesh, proportional);
+
+        elements
+    }
+
+    /// World space position of a vertex when the transform started
+    pub fn start_position(&self, vertex: VertexHandle) -> Option<Vec3> {
+        self.start_positions
+            .get(&vertex)
+            .map(|position| self.to_world.transform_point3(*position))
+    }
+
+    /// Median of the selected vertices in world space
+    pub fn median(&self) -> Option<Vec3> {
+        let positions: Vec<Vec3> = self
+            .selected
+            .iter()
+            .filter_map(|vertex| self.start_position(*vertex))
+            .collect();
+
+        median_point(&positions)
+    }
+
+    pub fn weights(&self) -> &[(VertexHandle, f32)] {
+        &self.weights
+    }
+
+    /// Radius of the proportional falloff, if it is used
+    pub fn radius(&self) -> Option<f32> {
+        self.radius
+    }
+
+    /// Recomputes which vertices follow the selection, e.g. after the radius changed
+    pub fn update_weights(&mut self, mesh: &EditableMesh, proportional: &ProportionalEditing) {
+        self.weights = proportional.weights(
+            mesh,
+            |vertex| self.start_position(vertex).unwrap_or(Vec3::ZERO),
+            &self.selected,
+        );
+        self.radius = proportional.enabled.then_some(proportional.radius);
+    }
+
+    /// Moves the vertices from their start positions by `delta`, and returns every vertex that
+    /// changed position, including those no longer reached by the falloff
+    pub fn apply(&mut self, mesh: &mut EditableMesh, delta: &ElementDelta) -> Vec<VertexHandle> {
+        let mut touched: HashSet<VertexHandle> = self.restore_positions(mesh).into_iter().collect();
+        let to_mesh = self.to_world.inverse();
+
+        for (vertex, weight) in self.weights.iter().copied() {
+            let Some(start) = self.start_position(vertex) else {
+                continue;
+            };
+            mesh.vertex_positions[vertex] = to_mesh.transform_point3(delta.apply(start, weight));
+            touched.insert(vertex);
+        }
+
+        self.moved = self.weights.iter().map(|(vertex, _)| *vertex).collect();
+
+        touched.into_iter().collect()
+    }
+
+    /// Puts the moved vertices back where they started, and brings the mesh up to date
+    pub fn restore(
+        &mut self,
+        mesh: &mut EditableMesh,
+        bvh: &mut BoundingVolumeHierarchy,
+        render_mesh: Option<&mut Mesh>,
+    ) {
+        let restored = self.restore_positions(mesh);
+        update_moved_vertices(mesh, bvh, render_mesh, &restored);
+    }
+
+    fn restore_positions(&mut self, mesh: &mut EditableMesh) -> Vec<VertexHandle> {
+        let moved = std::mem::take(&mut self.moved);
+
+        for vertex in moved.iter() {
+            if let Some(start) = self.start_positions.get(vertex) {
+                mesh.vertex_positions[*vertex] = *start;
+            }
+        }
+
+        moved
+    }
+}
+
+/// Everything the transform tools need to move the selected vertices of the focused mesh in
+/// edit mode, as a single system parameter
+#[derive(SystemParam)]
+pub struct ElementEditor<'w, 's> {
+    commands: Commands<'w, 's>,
+    mode: Res<'w, InteractionMode>,
+    proportional: Res<'w, ProportionalEditing>,
+    render_meshes: ResMut<'w, Assets<Mesh>>,
+    meshes: Query<
+        'w,
+        's,
+        (
+            Entity,
+            &'static mut EditableMesh,
+            &'static mut BoundingVolumeHierarchy,
+            &'static Handle<Mesh>,
+            &'static ActiveVertices,
+            &'static ActiveEdges,
+            &'static ActiveFaces,
+        ),
+        With<Focused>,
+    >,
+}
+
+impl<'w, 's> ElementEditor<'w, 's> {
+    pub fn is_editing(&self) -> bool {
+        *self.mode == InteractionMode::Edit
+    }
+
+    /// Whether anything is selected on the focused mesh
+    pub fn has_selection(&self) -> bool {
+        self.meshes.iter().any(|(.., vertices, edges, faces)| {
+            !vertices.is_empty() || !edges.is_empty() || !faces.is_empty()
+        })
+    }
+
+    /// Starts transforming the selected vertices of the focused mesh, placed in the world by
+    /// `transform`. None outside of edit mode or without a selection.
+    pub fn begin(&self, transform: &GlobalTransform) -> Option<ElementTransform> {
+        if !self.is_editing() {
+            return None;
+        }
+
+        let (entity, mesh, _, _, vertices, edges, faces) = self.meshes.iter().next()?;
+        let selected = mesh.selected_vertices(vertices, edges, faces);
+        if selected.is_empty() {
+            return None;
+        }
+
+        Some(ElementTransform::new(
+            entity,
+            transform,
+            mesh,
+            selected,
+            &self.proportional,
+        ))
+    }
+
+    /// Moves the vertices by `delta`, and brings the normals, BVH and render mesh up to date
+    pub fn apply(&mut self, elements: &mut ElementTransform, delta: &ElementDelta) {
+        let Ok((entity, mut mesh, mut bvh, handle, ..)) = self.meshes.get_mut(elements.entity)
+        else {
+            return;
+        };
+
+        let moved = elements.apply(&mut mesh, delta);
+        update_moved_vertices(
+            &mut mesh,
+            &mut bvh,
+            self.render_meshes.get_mut(handle),
+            &moved,
+        );
+        // Recomputed by Bevy for frustum culling
+        self.commands.entity(entity).remove::<Aabb>();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{prelude::*, utils::HashSet};
+    use lox::{core::Mesh as LoxMesh, VertexHandle};
+
+    use super::{ElementDelta, ElementTransform};
+    use crate::core::{
+        editable_mesh::EditableMesh,
+        tools::proportional::{ProportionalEditing, ProportionalFalloff},
+    };
+
+    #[test]
+    fn test_delta_with_weight() {
+        let start = Transform::from_xyz(1.0, 0.0, 0.0);
+        let current = Transform::from_xyz(1.0, 2.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+            .with_scale(Vec3::splat(3.0));
+        let delta = ElementDelta::between(&start, &current);
+
+        let point = Vec3::new(2.0, 0.0, 0.0);
+        assert!((delta.apply(point, 0.0) - point).length() < 1e-5);
+        // Scaled to 3 along X, turned onto Y, then moved up by 2
+        assert!((delta.apply(point, 1.0) - Vec3::new(1.0, 5.0, 0.0)).length() < 1e-5);
+    }
+
+    #[test]
+    fn test_falloff_moves_neighbors_partially() {
+        let mut mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let transform = GlobalTransform::from_xyz(10.0, 0.0, 0.0);
+
+        let top: Vec<VertexHandle> = mesh
+            .structure
+            .vertex_handles()
+            .filter(|vertex| mesh.vertex_normals[*vertex].y > 0.5)
+            .collect();
+        let selected: HashSet<VertexHandle> = top
+            .iter()
+            .copied()
+            .filter(|vertex| mesh.vertex_positions[*vertex].x > 0.0)
+            .collect();
+
+        let proportional = ProportionalEditing {
+            enabled: true,
+            falloff: ProportionalFalloff::Linear,
+            radius: 2.0,
+            connected_only: true,
+        };
+        let mut elements = ElementTransform::new(
+            Entity::PLACEHOLDER,
+            &transform,
+            &mesh,
+            selected.clone(),
+            &proportional,
+        );
+        assert_eq!(elements.median(), Some(Vec3::new(10.5, 0.5, 0.0)));
+
+        let start = Transform::from_translation(elements.median().unwrap());
+        let delta =
+            ElementDelta::between(&start, &start.with_translation(Vec3::new(10.5, 1.5, 0.0)));
+        let moved = elements.apply(&mut mesh, &delta);
+        assert_eq!(moved.len(), 4);
+
+        for vertex in top.iter() {
+            let y = mesh.vertex_positions[*vertex].y;
+            if selected.contains(vertex) {
+                assert!((y - 1.5).abs() < 1e-5);
+            } else {
+                // One edge away from the selection, half way to the radius
+                assert!((y - 1.0).abs() < 1e-5);
+            }
+        }
+
+        let restored = elements.restore_positions(&mut mesh);
+        assert_eq!(restored.len(), 4);
+        assert!(top
+            .iter()
+            .all(|vertex| (mesh.vertex_positions[*vertex].y - 0.5).abs() < 1e-5));
+    }
+
+    #[test]
+    fn test_elements_under_parent_use_global_transform() {
+        let mut mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let parent =
+            GlobalTransform::from(Transform::from_xyz(0.0, 5.0, 0.0).with_scale(Vec3::splat(2.0)));
+        let transform = parent.mul_transform(Transform::from_xyz(1.0, 0.0, 0.0));
+
+        let selected: HashSet<VertexHandle> = mesh
+            .structure
+            .vertex_handles()
+            .filter(|vertex| mesh.vertex_positions[*vertex].y > 0.0)
+            .collect();
+        let mut elements = ElementTransform::new(
+            Entity::PLACEHOLDER,
+            &transform,
+            &mesh,
+            selected.clone(),
+            &ProportionalEditing::default(),
+        );
+        // Local offset of 1 and half extent of 0.5, both scaled by the parent
+        assert_eq!(elements.median(), Some(Vec3::new(2.0, 6.0, 0.0)));
+
+        let start = Transform::from_translation(elements.median().unwrap());
+        let delta =
+            ElementDelta::between(&start, &start.with_translation(Vec3::new(2.0, 8.0, 0.0)));
+        elements.apply(&mut mesh, &delta);
+
+        // Two world units up is one unit in mesh space
+        assert!(selected
+            .iter()
+            .all(|vertex| (mesh.vertex_positions[*vertex].y - 1.5).abs() < 1e-5));
+    }
+}
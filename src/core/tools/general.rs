@@ -1,27 +1,35 @@
 use core::f32;
 
 use bevy::{
-    math::bounding::{Aabb3d, BoundingVolume, RayCast3d},
+    math::{
+        bounding::{Aabb3d, RayCast3d},
+        BVec3,
+    },
     prelude::*,
-    window::PrimaryWindow,
 };
 
 use crate::{
     core::{
         dim3::Torus,
         editor::Focused,
+        gestures::Pointer,
         gizmos::{
             CustomGizmo, GizmoColors, GizmoDataHandles, GizmoPlaneDistance,
             GizmoScaleToViewportRatio, RotationGizmo, ScaleGizmo, TranslationGizmo,
         },
         pan_orbit_camera::PrimaryCamera,
+        snapping::Snapper,
+        tools::{
+            elements::{ElementDelta, ElementEditor, ElementTransform},
+            transform_space::TransformFrame,
+        },
     },
     utils,
 };
 pub struct Translation;
 
-#[derive(Debug, Copy, Clone)]
-enum TranslateAction {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TranslateAction {
     X,
     Y,
     Z,
@@ -30,23 +38,50 @@ enum TranslateAction {
     YZ,
     XYZ,
 }
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Clone)]
 pub struct TranslateToolState {
     active_action: Option<TranslateAction>,
     prev_cursor_position: Option<Vec2>,
     start_position: Option<Vec3>,
+    /// Pivot when the drag started, and where it would be without snapping
+    start_pivot: Option<Vec3>,
+    free_pivot: Option<Vec3>,
+    /// Gizmo orientation, kept for the whole drag
+    orientation: Quat,
+    /// Translations of the focused entities when the drag started
+    start_translations: Vec<(Entity, Vec3)>,
+    /// The selected vertices being moved in edit mode
+    elements: Option<ElementTransform>,
+}
+
+impl TranslateAction {
+    pub fn axes(&self) -> BVec3 {
+        match self {
+            TranslateAction::X => BVec3::new(true, false, false),
+            TranslateAction::Y => BVec3::new(false, true, false),
+            TranslateAction::Z => BVec3::new(false, false, true),
+            TranslateAction::XY => BVec3::new(true, true, false),
+            TranslateAction::XZ => BVec3::new(true, false, true),
+            TranslateAction::YZ => BVec3::new(false, true, true),
+            TranslateAction::XYZ => BVec3::TRUE,
+        }
+    }
 }
 
 impl Translation {
-    pub fn cleanup_system(mut translation_gizmo: Query<&mut Visibility, With<TranslationGizmo>>) {
+    pub fn cleanup_system(
+        mut translation_gizmo: Query<&mut Visibility, With<TranslationGizmo>>,
+        mut pointer: ResMut<Pointer>,
+    ) {
         for mut visibility in translation_gizmo.iter_mut() {
             *visibility = Visibility::Hidden;
         }
+        pointer.over_handle = false;
     }
 
     pub fn update_system(
         mut state: Local<TranslateToolState>,
-        mut focused_entity: Query<&mut Transform, With<Focused>>,
+        mut focused_entities: Query<(Entity, &mut Transform, &GlobalTransform), With<Focused>>,
         mut translate_gizmo: Query<
             (&mut Visibility, &mut Transform),
             (With<TranslationGizmo>, Without<Focused>),
@@ -61,119 +96,99 @@ impl Translation {
         >,
         gizmo_plane_distance: Res<GizmoPlaneDistance>,
         pixel_scale: Res<GizmoScaleToViewportRatio>,
-        mouse: Res<ButtonInput<MouseButton>>,
-        window: Query<&Window, With<PrimaryWindow>>,
+        mut pointer: ResMut<Pointer>,
         mut gizmo: Gizmos<CustomGizmo>,
         colors: Res<GizmoColors>,
+        // The snapper reads every mesh, while edit mode writes the focused one
+        mut editor_or_snapper: ParamSet<(ElementEditor, Snapper)>,
+        frame: Res<TransformFrame>,
     ) {
         let (mut gizmo_visiblity, mut gizmo_transform) = translate_gizmo.single_mut();
         let (camera, camera_transform, camera_global_transform) = q_main_camera.single();
 
-        let Some(mut entity_transform) = focused_entity.iter_mut().nth(0) else {
+        let Some((active_entity, _, active_global_transform)) = focused_entities.iter().nth(0)
+        else {
             *gizmo_visiblity = Visibility::Hidden;
+            pointer.over_handle = false;
             return;
         };
+        let active_global_transform = *active_global_transform;
+
+        // In edit mode there is nothing to move without a selection
+        if state.active_action.is_none() && !Self::has_targets(&editor_or_snapper.p0()) {
+            *gizmo_visiblity = Visibility::Hidden;
+            pointer.over_handle = false;
+            return;
+        }
         *gizmo_visiblity = Visibility::Visible;
 
+        let orientation = match state.active_action {
+            Some(_) => state.orientation,
+            None => frame.orientation,
+        };
+        gizmo_transform.rotation = orientation;
+
         let gizmo_origin = utils::projection::project_to_plane(
             camera_transform.translation,
             camera_transform.forward().into(),
-            entity_transform.translation,
+            frame.pivot,
             gizmo_plane_distance.0,
         );
 
-        if !mouse.pressed(MouseButton::Left) {
+        let hovered = pointer
+            .position
+            .and_then(|position| camera.viewport_to_world(camera_global_transform, position))
+            .and_then(|ray| {
+                Self::handle_at(
+                    Self::gizmo_space_ray(ray, gizmo_origin, orientation),
+                    pixel_scale.0,
+                )
+            });
+
+        if !pointer.pressed {
+            pointer.over_handle = hovered.is_some();
             gizmo_transform.translation = gizmo_origin.clone();
             state.active_action = None;
             state.prev_cursor_position = None;
+            state.start_pivot = None;
+            state.free_pivot = None;
+            state.start_translations.clear();
+            state.elements = None;
             return;
         }
 
-        let window = window.single();
-
-        let Some(cursor_position) = window.cursor_position() else {
+        let Some(cursor_position) = pointer.position else {
             gizmo_transform.translation = gizmo_origin.clone();
             return;
         };
 
-        let mut curr_action: Option<TranslateAction> = None;
-
-        if let Some(prev_action) = &state.active_action {
-            curr_action = Some(prev_action.clone());
+        let action = if let Some(prev_action) = state.active_action {
+            prev_action
+        } else if pointer.captured {
+            // The press was taken by something else, like confirming a modal transform
+            gizmo_transform.translation = gizmo_origin.clone();
+            return;
         } else {
-            let ray = match camera.viewport_to_world(camera_global_transform, cursor_position) {
-                Some(ray) => RayCast3d::from_ray(ray, 1000.),
-                None => {
-                    gizmo_transform.translation = gizmo_origin.clone();
-                    return;
-                }
-            };
-
-            let half_width = 15. * pixel_scale.0 * 0.5;
-            let half_height = 90. * pixel_scale.0 * 0.5;
-            let plane_center = 25. * pixel_scale.0;
-            let half_plane_thickness = 0.05;
-            let half_plane_size = 15. * pixel_scale.0 * 0.5;
-
-            let x_aabb = Aabb3d::new(
-                Vec3::new(half_height, 0.0, 0.) + gizmo_origin,
-                Vec3::new(half_height, half_width, half_width),
-            );
-            let y_aabb = Aabb3d::new(
-                Vec3::new(0.0, half_height, 0.) + gizmo_origin,
-                Vec3::new(half_width, half_height, half_width),
-            );
-            let z_aabb = Aabb3d::new(
-                Vec3::new(0.0, 0.0, half_height) + gizmo_origin,
-                Vec3::new(half_width, half_width, half_height),
-            );
-
-            let xz_aabb = Aabb3d::new(
-                Vec3::new(plane_center, 0., plane_center) + gizmo_origin,
-                Vec3::new(half_plane_size, half_plane_thickness, half_plane_size),
-            );
-
-            let xy_aabb = Aabb3d::new(
-                Vec3::new(plane_center, plane_center, 0.) + gizmo_origin,
-                Vec3::new(half_plane_size, half_plane_size, half_plane_thickness),
-            );
-
-            let yz_aabb = Aabb3d::new(
-                Vec3::new(0., plane_center, plane_center) + gizmo_origin,
-                Vec3::new(half_plane_thickness, half_plane_size, half_plane_size),
-            );
-
-            let mut closest_t_action = None;
-            let mut closest_t = f32::MAX;
-
-            for (aabb, action) in [
-                (x_aabb, TranslateAction::X),
-                (y_aabb, TranslateAction::Y),
-                (z_aabb, TranslateAction::Z),
-                (xy_aabb, TranslateAction::XY),
-                (xz_aabb, TranslateAction::XZ),
-                (yz_aabb, TranslateAction::YZ),
-            ] {
-                let Some(t) = ray.aabb_intersection_at(&aabb) else {
-                    continue;
-                };
-
-                if t < closest_t {
-                    closest_t = t;
-                    closest_t_action = Some(action);
-                }
-            }
-
-            let Some(action) = closest_t_action else {
+            let Some(action) = hovered else {
                 gizmo_transform.translation = gizmo_origin.clone();
                 return;
             };
 
             state.active_action = Some(action);
+            pointer.captured = true;
 
-            curr_action = Some(action);
             state.start_position = Some(gizmo_origin);
-        }
+            state.start_pivot = Some(frame.pivot);
+            state.free_pivot = Some(frame.pivot);
+            state.orientation = orientation;
+            state.start_translations = focused_entities
+                .iter()
+                .map(|(entity, transform, _)| (entity, transform.translation))
+                .collect();
+            state.elements = editor_or_snapper.p0().begin(&active_global_transform);
+
+            action
+        };
 
         if state.prev_cursor_position.is_none() {
             state.prev_cursor_position = Some(cursor_position);
@@ -187,45 +202,150 @@ impl Translation {
 
         let movement: Vec3 = camera_transform.right() * delta.x + camera_transform.up() * delta.y;
 
-        let moves = Vec3::new(
-            movement.dot(Vec3::X),
-            movement.dot(Vec3::Y),
-            movement.dot(Vec3::Z),
-        ) * 0.03;
+        // Movement along the axes of the gizmo
+        let moves = orientation.inverse() * movement * 0.03;
 
         state.prev_cursor_position = Some(cursor_position);
 
-        let action = curr_action.unwrap();
-        let translation = match action {
-            TranslateAction::X => Vec3::new(moves.x, 0.0, 0.0),
-            TranslateAction::Y => Vec3::new(0.0, moves.y, 0.0),
-            TranslateAction::Z => Vec3::new(0.0, 0.0, moves.z),
-            TranslateAction::XY => Vec3::new(moves.x, moves.y, 0.0),
-            TranslateAction::XZ => Vec3::new(moves.x, 0.0, moves.z),
-            TranslateAction::YZ => Vec3::new(0.0, moves.y, moves.z),
-            TranslateAction::XYZ => moves,
-        };
-        entity_transform.translation += translation;
+        let translation = orientation * Vec3::select(action.axes(), moves, Vec3::ZERO);
+
+        let start = state.start_pivot.unwrap_or(frame.pivot);
+        let free = state.free_pivot.unwrap_or(start) + translation;
+        state.free_pivot = Some(free);
+
+        let pivot = editor_or_snapper.p1().snap_translation(
+            start,
+            free,
+            action.axes(),
+            orientation,
+            Some(cursor_position),
+            active_entity,
+        );
+
+        let state = &mut *state;
+        match state.elements.as_mut() {
+            Some(elements) => editor_or_snapper.p0().apply(
+                elements,
+                &ElementDelta {
+                    pivot: start,
+                    translation: pivot - start,
+                    ..default()
+                },
+            ),
+            None => {
+                for (entity, start_translation) in state.start_translations.iter() {
+                    if let Ok((_, mut transform, _)) = focused_entities.get_mut(*entity) {
+                        transform.translation = *start_translation + pivot - start;
+                    }
+                }
+            }
+        }
 
         gizmo_transform.translation = utils::projection::project_to_plane(
             camera_transform.translation,
             camera_transform.forward().into(),
-            entity_transform.translation,
+            pivot,
             gizmo_plane_distance.0,
         );
     }
+
+    /// Whether the tools have something to transform: the focused entities in object mode, or
+    /// the selected elements in edit mode
+    pub fn has_targets(editor: &ElementEditor) -> bool {
+        !editor.is_editing() || editor.has_selection()
+    }
+
+    /// Handle hit by a ray in the space of the gizmo
+    fn handle_at(ray: RayCast3d, pixel_scale: f32) -> Option<TranslateAction> {
+        let half_width = 15. * pixel_scale * 0.5;
+        let half_height = 90. * pixel_scale * 0.5;
+        let plane_center = 25. * pixel_scale;
+        let half_plane_thickness = 0.05;
+        let half_plane_size = 15. * pixel_scale * 0.5;
+
+        // Handles are tested in the space of the gizmo, centered on its origin
+        let x_aabb = Aabb3d::new(
+            Vec3::new(half_height, 0.0, 0.),
+            Vec3::new(half_height, half_width, half_width),
+        );
+        let y_aabb = Aabb3d::new(
+            Vec3::new(0.0, half_height, 0.),
+            Vec3::new(half_width, half_height, half_width),
+        );
+        let z_aabb = Aabb3d::new(
+            Vec3::new(0.0, 0.0, half_height),
+            Vec3::new(half_width, half_width, half_height),
+        );
+
+        let xz_aabb = Aabb3d::new(
+            Vec3::new(plane_center, 0., plane_center),
+            Vec3::new(half_plane_size, half_plane_thickness, half_plane_size),
+        );
+
+        let xy_aabb = Aabb3d::new(
+            Vec3::new(plane_center, plane_center, 0.),
+            Vec3::new(half_plane_size, half_plane_size, half_plane_thickness),
+        );
+
+        let yz_aabb = Aabb3d::new(
+            Vec3::new(0., plane_center, plane_center),
+            Vec3::new(half_plane_thickness, half_plane_size, half_plane_size),
+        );
+
+        let mut closest_t_action = None;
+        let mut closest_t = f32::MAX;
+
+        for (aabb, action) in [
+            (x_aabb, TranslateAction::X),
+            (y_aabb, TranslateAction::Y),
+            (z_aabb, TranslateAction::Z),
+            (xy_aabb, TranslateAction::XY),
+            (xz_aabb, TranslateAction::XZ),
+            (yz_aabb, TranslateAction::YZ),
+        ] {
+            let Some(t) = ray.aabb_intersection_at(&aabb) else {
+                continue;
+            };
+
+            if t < closest_t {
+                closest_t = t;
+                closest_t_action = Some(action);
+            }
+        }
+
+        closest_t_action
+    }
+
+    /// Ray in the space of a gizmo at `origin` rotated by `orientation`, to test its handles
+    fn gizmo_space_ray(ray: Ray3d, origin: Vec3, orientation: Quat) -> RayCast3d {
+        let inverse = orientation.inverse();
+
+        RayCast3d::from_ray(
+            Ray3d::new(inverse * (ray.origin - origin), inverse * *ray.direction),
+            1000.,
+        )
+    }
 }
 
 pub struct Scale;
 
-#[derive(Default, Copy, Clone)]
+#[derive(Default, Clone)]
 pub struct ScaleToolState {
     active_action: Option<ScaleAction>,
     prev_cursor_position: Option<Vec2>,
+    /// Scale of the active entity when the drag started, and what it would be without snapping
+    start_scale: Option<Vec3>,
+    free_scale: Option<Vec3>,
+    /// Transform frame, kept for the whole drag
+    frame: TransformFrame,
+    /// Transforms of the focused entities when the drag started
+    start_transforms: Vec<(Entity, Transform)>,
+    /// The selected vertices being scaled in edit mode
+    elements: Option<ElementTransform>,
 }
 
-#[derive(Debug, Copy, Clone)]
-enum ScaleAction {
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScaleAction {
     X,
     Y,
     Z,
@@ -235,16 +355,48 @@ enum ScaleAction {
     XYZ,
 }
 
+impl ScaleAction {
+    pub fn axes(&self) -> BVec3 {
+        match self {
+            ScaleAction::X => BVec3::new(true, false, false),
+            ScaleAction::Y => BVec3::new(false, true, false),
+            ScaleAction::Z => BVec3::new(false, false, true),
+            ScaleAction::XY => BVec3::new(true, true, false),
+            ScaleAction::XZ => BVec3::new(true, false, true),
+            ScaleAction::YZ => BVec3::new(false, true, true),
+            ScaleAction::XYZ => BVec3::TRUE,
+        }
+    }
+}
+
+impl From<TranslateAction> for ScaleAction {
+    fn from(action: TranslateAction) -> Self {
+        match action {
+            TranslateAction::X => ScaleAction::X,
+            TranslateAction::Y => ScaleAction::Y,
+            TranslateAction::Z => ScaleAction::Z,
+            TranslateAction::XY => ScaleAction::XY,
+            TranslateAction::XZ => ScaleAction::XZ,
+            TranslateAction::YZ => ScaleAction::YZ,
+            TranslateAction::XYZ => ScaleAction::XYZ,
+        }
+    }
+}
+
 impl Scale {
-    pub fn cleanup_system(mut translation_gizmo: Query<&mut Visibility, With<ScaleGizmo>>) {
+    pub fn cleanup_system(
+        mut translation_gizmo: Query<&mut Visibility, With<ScaleGizmo>>,
+        mut pointer: ResMut<Pointer>,
+    ) {
         for mut visibility in translation_gizmo.iter_mut() {
             *visibility = Visibility::Hidden;
         }
+        pointer.over_handle = false;
     }
 
     pub fn update_system(
         mut state: Local<ScaleToolState>,
-        mut focused_entity: Query<&mut Transform, With<Focused>>,
+        mut focused_entities: Query<(Entity, &mut Transform, &GlobalTransform), With<Focused>>,
         mut scale_gizmo: Query<
             (&mut Visibility, &mut Transform),
             (With<ScaleGizmo>, Without<Focused>),
@@ -255,24 +407,49 @@ impl Scale {
         >,
         gizmo_plane_distance: Res<GizmoPlaneDistance>,
         pixel_scale: Res<GizmoScaleToViewportRatio>,
-        mouse: Res<ButtonInput<MouseButton>>,
-        window: Query<&Window, With<PrimaryWindow>>,
+        mut pointer: ResMut<Pointer>,
         mut custom_gizmo: Gizmos<CustomGizmo>,
+        // The snapper reads every mesh, while edit mode writes the focused one
+        mut editor_or_snapper: ParamSet<(ElementEditor, Snapper)>,
+        frame: Res<TransformFrame>,
     ) {
         let (mut gizmo_visiblity, mut gizmo_transform) = scale_gizmo.single_mut();
         let (camera, camera_transform) = q_main_camera.single();
 
-        let Some(mut entity_transform) = focused_entity.iter_mut().nth(0) else {
+        let Some((_, active_transform, active_global_transform)) = focused_entities.iter().nth(0)
+        else {
             *gizmo_visiblity = Visibility::Hidden;
+            pointer.over_handle = false;
             return;
         };
+        let active_transform = *active_transform;
+        let active_global_transform = *active_global_transform;
+
+        let editing = editor_or_snapper.p0().is_editing();
+        if state.active_action.is_none() && !Translation::has_targets(&editor_or_snapper.p0()) {
+            *gizmo_visiblity = Visibility::Hidden;
+            pointer.over_handle = false;
+            return;
+        }
+        // Vertices have no scale of their own, their offsets from the pivot are scaled
+        let active_scale = if editing {
+            Vec3::ONE
+        } else {
+            active_transform.scale
+        };
         *gizmo_visiblity = Visibility::Visible;
 
-        // Since scaling does not change the position of the entity, we can this value can be cached
+        let frame = match state.active_action {
+            Some(_) => state.frame,
+            None => *frame,
+        };
+        gizmo_transform.rotation = frame.orientation;
+
+        // Since scaling does not move the pivot, this value can be cached
         let gizmo_origin = utils::projection::project_to_plane(
             camera_transform.translation,
             camera_transform.forward().into(),
-            entity_transform.translation,
+            frame.pivot,
             gizmo_plane_distance.0,
         );
 
@@ -290,97 +467,63 @@ impl Scale {
             Color::WHITE,
         );
 
-        if !mouse.pressed(MouseButton::Left) {
+        let hovered = pointer
+            .position
+            .and_then(|position| {
+                camera.viewport_to_world(
+                    &(GlobalTransform::IDENTITY.mul_transform(*camera_transform)),
+                    position,
+                )
+            })
+            .and_then(|ray| {
+                Self::handle_at(
+                    Translation::gizmo_space_ray(ray, gizmo_origin, frame.orientation),
+                    pixel_scale.0,
+                )
+            });
+
+        if !pointer.pressed {
+            pointer.over_handle = hovered.is_some();
             gizmo_transform.translation = gizmo_origin.clone();
             state.active_action = None;
             state.prev_cursor_position = None;
+            state.start_scale = None;
+            state.free_scale = None;
+            state.start_transforms.clear();
+            state.elements = None;
             return;
         }
 
-        let Some(cursor_position) = window.single().cursor_position() else {
+        let Some(cursor_position) = pointer.position else {
             gizmo_transform.translation = gizmo_origin.clone();
             return;
         };
 
-        let mut curr_action: Option<ScaleAction> = None;
-
-        if let Some(prev_action) = &state.active_action {
-            curr_action = Some(prev_action.clone());
+        let action = if let Some(prev_action) = state.active_action {
+            prev_action
+        } else if pointer.captured {
+            // The press was taken by something else, like confirming a modal transform
+            gizmo_transform.translation = gizmo_origin.clone();
+            return;
         } else {
-            let ray = match camera.viewport_to_world(
-                &(GlobalTransform::IDENTITY.mul_transform(*camera_transform)),
-                cursor_position,
-            ) {
-                Some(ray) => RayCast3d::from_ray(ray, 1000.),
-                None => {
-                    gizmo_transform.translation = gizmo_origin.clone();
-                    return;
-                }
-            };
-
-            let half_width = 15. * pixel_scale.0 * 0.5;
-            let half_height = 85. * pixel_scale.0 * 0.5;
-            let plane_center = 25. * pixel_scale.0;
-            let half_plane_thickness = 0.05;
-            let half_plane_size = 15. * pixel_scale.0 * 0.5;
-
-            let x_scale_aabb = Aabb3d::new(
-                Vec3::new(half_height, 0.0, 0.) + gizmo_origin,
-                Vec3::new(half_height, half_width, half_width),
-            );
-            let y_scale_aabb = Aabb3d::new(
-                Vec3::new(0.0, half_height, 0.) + gizmo_origin,
-                Vec3::new(half_width, half_height, half_width),
-            );
-            let z_scale_aabb = Aabb3d::new(
-                Vec3::new(0.0, 0.0, half_height) + gizmo_origin,
-                Vec3::new(half_width, half_width, half_height),
-            );
-
-            let xz_scale_aabb = Aabb3d::new(
-                Vec3::new(plane_center, 0., plane_center) + gizmo_origin,
-                Vec3::new(half_plane_size, half_plane_thickness, half_plane_size),
-            );
-
-            let xy_scale_aabb = Aabb3d::new(
-                Vec3::new(plane_center, plane_center, 0.) + gizmo_origin,
-                Vec3::new(half_plane_size, half_plane_size, half_plane_thickness),
-            );
-
-            let yz_scale_aabb = Aabb3d::new(
-                Vec3::new(0., plane_center, plane_center) + gizmo_origin,
-                Vec3::new(half_plane_thickness, half_plane_size, half_plane_size),
-            );
-
-            let mut closest_t_action = None;
-            let mut closest_t = f32::MAX;
-
-            for (aabb, action) in [
-                (x_scale_aabb, ScaleAction::X),
-                (y_scale_aabb, ScaleAction::Y),
-                (z_scale_aabb, ScaleAction::Z),
-                (xy_scale_aabb, ScaleAction::XY),
-                (xz_scale_aabb, ScaleAction::XZ),
-                (yz_scale_aabb, ScaleAction::YZ),
-            ] {
-                let Some(t) = ray.aabb_intersection_at(&aabb) else {
-                    continue;
-                };
-
-                if t < closest_t {
-                    closest_t = t;
-                    closest_t_action = Some(action);
-                }
-            }
-
-            let Some(action) = closest_t_action else {
+            let Some(action) = hovered else {
                 gizmo_transform.translation = gizmo_origin.clone();
                 return;
             };
 
             state.active_action = Some(action);
-            curr_action = Some(action);
-        }
+            pointer.captured = true;
+            state.start_scale = Some(active_scale);
+            state.free_scale = Some(active_scale);
+            state.frame = frame;
+            state.start_transforms = focused_entities
+                .iter()
+                .map(|(entity, transform, _)| (entity, *transform))
+                .collect();
+            state.elements = editor_or_snapper.p0().begin(&active_global_transform);
+
+            action
+        };
 
         if state.prev_cursor_position.is_none() {
             state.prev_cursor_position = Some(cursor_position);
@@ -394,15 +537,11 @@ impl Scale {
 
         let movement: Vec3 = camera_transform.right() * delta.x + camera_transform.up() * delta.y;
 
-        let scales = Vec3::new(
-            movement.dot(Vec3::X),
-            movement.dot(Vec3::Y),
-            movement.dot(Vec3::Z),
-        ) * 0.02;
+        // Movement along the axes of the gizmo
+        let scales = frame.orientation.inverse() * movement * 0.02;
 
         state.prev_cursor_position = Some(cursor_position);
 
-        let action = curr_action.unwrap();
         let scale = match action {
             ScaleAction::X => Vec3::new(scales.x, 0.0, 0.0),
             ScaleAction::Y => Vec3::new(0.0, scales.y, 0.0),
@@ -424,10 +563,108 @@ impl Scale {
                 Vec3::splat(scale)
             }
         };
-        entity_transform.scale += scale;
+        let start = state.start_scale.unwrap_or(active_scale);
+        let free = state.free_scale.unwrap_or(start) + scale;
+        state.free_scale = Some(free);
+
+        // The active entity drives the drag, the others follow by the same factor
+        let snapped = editor_or_snapper
+            .p1()
+            .snap_scale(start, free, action.axes());
+        let factor = Vec3::select(start.cmpeq(Vec3::ZERO), Vec3::ONE, snapped / start);
+
+        let state = &mut *state;
+        if let Some(elements) = state.elements.as_mut() {
+            editor_or_snapper.p0().apply(
+                elements,
+                &ElementDelta {
+                    pivot: frame.pivot,
+                    scale: factor,
+                    scale_orientation: frame.orientation,
+                    ..default()
+                },
+            );
+            gizmo_transform.translation = gizmo_origin.clone();
+            return;
+        }
+
+        for (entity, start_transform) in state.start_transforms.iter() {
+            let Ok((_, mut transform, _)) = focused_entities.get_mut(*entity) else {
+                continue;
+            };
+
+            transform.scale = start_transform.scale * factor;
+            if !frame.individual_origins {
+                // Offsets from the pivot stretch along the axes of the gizmo
+                let offset =
+                    frame.orientation.inverse() * (start_transform.translation - frame.pivot);
+                transform.translation = frame.pivot + frame.orientation * (offset * factor);
+            }
+        }
 
         gizmo_transform.translation = gizmo_origin.clone();
     }
+
+    /// Handle hit by a ray in the space of the gizmo
+    fn handle_at(ray: RayCast3d, pixel_scale: f32) -> Option<ScaleAction> {
+        let half_width = 15. * pixel_scale * 0.5;
+        let half_height = 85. * pixel_scale * 0.5;
+        let plane_center = 25. * pixel_scale;
+        let half_plane_thickness = 0.05;
+        let half_plane_size = 15. * pixel_scale * 0.5;
+
+        // Handles are tested in the space of the gizmo, centered on its origin
+        let x_scale_aabb = Aabb3d::new(
+            Vec3::new(half_height, 0.0, 0.),
+            Vec3::new(half_height, half_width, half_width),
+        );
+        let y_scale_aabb = Aabb3d::new(
+            Vec3::new(0.0, half_height, 0.),
+            Vec3::new(half_width, half_height, half_width),
+        );
+        let z_scale_aabb = Aabb3d::new(
+            Vec3::new(0.0, 0.0, half_height),
+            Vec3::new(half_width, half_width, half_height),
+        );
+
+        let xz_scale_aabb = Aabb3d::new(
+            Vec3::new(plane_center, 0., plane_center),
+            Vec3::new(half_plane_size, half_plane_thickness, half_plane_size),
+        );
+
+        let xy_scale_aabb = Aabb3d::new(
+            Vec3::new(plane_center, plane_center, 0.),
+            Vec3::new(half_plane_size, half_plane_size, half_plane_thickness),
+        );
+
+        let yz_scale_aabb = Aabb3d::new(
+            Vec3::new(0., plane_center, plane_center),
+            Vec3::new(half_plane_thickness, half_plane_size, half_plane_size),
+        );
+
+        let mut closest_t_action = None;
+        let mut closest_t = f32::MAX;
+
+        for (aabb, action) in [
+            (x_scale_aabb, ScaleAction::X),
+            (y_scale_aabb, ScaleAction::Y),
+            (z_scale_aabb, ScaleAction::Z),
+            (xy_scale_aabb, ScaleAction::XY),
+            (xz_scale_aabb, ScaleAction::XZ),
+            (yz_scale_aabb, ScaleAction::YZ),
+        ] {
+            let Some(t) = ray.aabb_intersection_at(&aabb) else {
+                continue;
+            };
+
+            if t < closest_t {
+                closest_t = t;
+                closest_t_action = Some(action);
+            }
+        }
+
+        closest_t_action
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -440,8 +677,27 @@ enum RotateAction {
 
 pub struct Rotation;
 
+#[derive(Default, Clone)]
+pub struct RotateToolState {
+    active_action: Option<RotateAction>,
+    prev_cursor_position: Option<Vec2>,
+    /// Radians turned since the drag started, before snapping
+    free_angle: f32,
+    /// Transform frame, kept for the whole drag
+    frame: TransformFrame,
+    /// Transforms of the focused entities when the drag started
+    start_transforms: Vec<(Entity, Transform)>,
+    /// The selected vertices being rotated in edit mode
+    elements: Option<ElementTransform>,
+}
+
 impl Rotation {
+    pub fn cleanup_system(mut pointer: ResMut<Pointer>) {
+        pointer.over_handle = false;
+    }
+
     pub fn update_system(
+        mut state: Local<RotateToolState>,
         pixel_scale: Res<GizmoScaleToViewportRatio>,
         mut rotation_gizmzo: Gizmos<RotationGizmo>,
         mut custom_gizmo: Gizmos<CustomGizmo>,
@@ -450,21 +706,45 @@ impl Rotation {
             (With<PrimaryCamera>, Without<Focused>),
         >,
         gizmo_plane_distance: Res<GizmoPlaneDistance>,
-        window: Query<&Window, With<PrimaryWindow>>,
-        mut focused_entity: Query<&mut Transform, With<Focused>>,
+        mut focused_entities: Query<(Entity, &mut Transform, &GlobalTransform), With<Focused>>,
         colors: Res<GizmoColors>,
-        mouse: Res<ButtonInput<MouseButton>>,
+        mut pointer: ResMut<Pointer>,
+        // The snapper reads every mesh, while edit mode writes the focused one
+        mut editor_or_snapper: ParamSet<(ElementEditor, Snapper)>,
+        frame: Res<TransformFrame>,
     ) {
-        let Some(mut entity_transform) = focused_entity.iter_mut().nth(0) else {
+        let Some((_, _, active_global_transform)) = focused_entities.iter().nth(0) else {
+            pointer.over_handle = false;
             return;
         };
+        let active_global_transform = *active_global_transform;
+
+        if state.active_action.is_none() && !Translation::has_targets(&editor_or_snapper.p0()) {
+            pointer.over_handle = false;
+            return;
+        }
 
         let (camera, camera_transform, g) = q_main_camera.single();
 
+        if !pointer.pressed {
+            state.active_action = None;
+            state.prev_cursor_position = None;
+            state.free_angle = 0.0;
+            state.start_transforms.clear();
+            state.elements = None;
+            // Set again below when a ring is under the pointer
+            pointer.over_handle = false;
+        }
+
+        let frame = match state.active_action {
+            Some(_) => state.frame,
+            None => *frame,
+        };
+
         let origin = utils::projection::project_to_plane(
             camera_transform.translation,
             camera_transform.forward().into(),
-            entity_transform.translation,
+            frame.pivot,
             gizmo_plane_distance.0,
         );
         let thickness = 3. * pixel_scale.0;
@@ -483,6 +763,7 @@ impl Rotation {
             ),
         ]
         .map(|(rotation, color, action)| {
+            let rotation = frame.orientation * rotation;
             (
                 rotation,
                 color,
@@ -491,7 +772,7 @@ impl Rotation {
             )
         });
 
-        let Some(cursor_position) = window.single().cursor_position() else {
+        let Some(cursor_position) = pointer.position else {
             return;
         };
 
@@ -533,6 +814,74 @@ impl Rotation {
             None => {}
         }
 
+        if !pointer.pressed {
+            pointer.over_handle = closest_t_action.is_some();
+        }
+
+        if pointer.just_pressed && !pointer.captured && state.active_action.is_none() {
+            if let Some(action) = closest_t_action {
+                state.active_action = Some(action);
+                state.frame = frame;
+                state.start_transforms = focused_entities
+                    .iter()
+                    .map(|(entity, transform, _)| (entity, *transform))
+                    .collect();
+                state.elements = editor_or_snapper.p0().begin(&active_global_transform);
+                pointer.captured = true;
+            }
+        }
+
+        // Keep the dragged ring highlighted even when the pointer leaves it
+        if let Some(action) = state.active_action {
+            closest_t_action = Some(action);
+
+            let axis = match action {
+                RotateAction::X => frame.orientation * Vec3::X,
+                RotateAction::Y => frame.orientation * Vec3::Y,
+                RotateAction::Z => frame.orientation * Vec3::Z,
+                RotateAction::CameraFront => camera_transform.back().into(),
+            };
+
+            if let Some(center) = camera.world_to_viewport(g, frame.pivot) {
+                // Counter-clockwise on screen turns around the axis pointing at the camera
+                let facing = axis.dot(camera_transform.back().into()).signum();
+                let axis = axis * facing;
+
+                if let Some(rotation) = Self::drag(
+                    &mut state,
+                    axis,
+                    center,
+                    cursor_position,
+                    &editor_or_snapper.p1(),
+                ) {
+                    let state = &mut *state;
+                    if let Some(elements) = state.elements.as_mut() {
+                        editor_or_snapper.p0().apply(
+                            elements,
+                            &ElementDelta {
+                                pivot: frame.pivot,
+                                rotation,
+                                ..default()
+                            },
+                        );
+                    } else {
+                        for (entity, start_transform) in state.start_transforms.iter() {
+                            let Ok((_, mut transform, _)) = focused_entities.get_mut(*entity)
+                            else {
+                                continue;
+                            };
+
+                            transform.rotation = rotation * start_transform.rotation;
+                            if !frame.individual_origins {
+                                transform.translation = frame.pivot
+                                    + rotation * (start_transform.translation - frame.pivot);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
         custom_gizmo.arc_3d(
             f32::consts::PI * 2.,
             90. * pixel_scale.0,
@@ -566,4 +915,28 @@ impl Rotation {
             );
         }
     }
+
+    /// Rotation around `axis` by the angle the pointer swept around `center` on screen since
+    /// the drag started
+    fn drag(
+        state: &mut RotateToolState,
+        axis: Vec3,
+        center: Vec2,
+        cursor_position: Vec2,
+        snapper: &Snapper,
+    ) -> Option<Quat> {
+        let prev_cursor_position = state.prev_cursor_position.replace(cursor_position)?;
+
+        // Viewport y points down, flip it so counter-clockwise on screen is positive
+        let flip = Vec2::new(1.0, -1.0);
+        let from = (prev_cursor_position - center) * flip;
+        let to = (cursor_position - center) * flip;
+        if from.length_squared() <= f32::EPSILON || to.length_squared() <= f32::EPSILON {
+            return None;
+        }
+        state.free_angle += from.angle_between(to);
+
+        let angle = snapper.snap_angle(state.free_angle);
+        Some(Quat::from_axis_angle(axis, angle))
+    }
 }
@@ -0,0 +1,386 @@
+use bevy::{math::bounding::RayCast3d, prelude::*};
+
+use crate::core::{
+    editable_mesh::{bvh::BoundingVolumeHierarchy, EditableMesh},
+    editor::UserSpace,
+    gestures::Pointer,
+    grid::GridSettings,
+    pan_orbit_camera::{PanOrbitCameraUpdate, PrimaryCamera},
+    scene_bvh::SceneBoundingVolumeHierarchy,
+};
+
+use super::ToolSet;
+
+/// Ruler and protractor. Clicks place points on the meshes, snapping to vertices close to the
+/// pointer: two points measure a distance, a third one the angle at the second point. The next
+/// click starts a new measurement.
+pub struct MeasureTool;
+
+/// Draws the [`Measurement`] with its distance and angle labels
+pub struct MeasurePlugin;
+
+/// How close to a vertex the pointer snaps to it, in logical pixels
+const VERTEX_SNAP_DISTANCE: f32 = 12.0;
+/// Radius of the point markers, in logical pixels
+const POINT_RADIUS: f32 = 4.0;
+const ARC_SEGMENTS: u32 = 16;
+const MEASURE_COLOR: Color = Color::rgb(1.0, 0.8, 0.1);
+
+/// Points of the current measurement, in world space
+#[derive(Resource, Clone, Debug, Default, PartialEq)]
+pub struct Measurement {
+    pub points: Vec<Vec3>,
+    /// Where the next point goes, if the pointer is over a mesh
+    pub hover: Option<Vec3>,
+}
+
+/// Measurement annotations, drawn on top of the meshes
+#[derive(Reflect, Default, GizmoConfigGroup)]
+pub struct MeasureGizmo;
+
+/// Label root of the first segment, the second segment or the angle
+#[derive(Component)]
+struct MeasureLabel(usize);
+
+#[derive(Component)]
+struct MeasureLabelText;
+
+impl Measurement {
+    /// Adds a point, starting over once the angle is complete
+    pub fn push(&mut self, point: Vec3) {
+        if self.points.len() >= 3 {
+            self.points.clear();
+        }
+        self.points.push(point);
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+        self.hover = None;
+    }
+
+    /// The placed points followed by the hovered one while the measurement is not complete
+    pub fn preview(&self) -> Vec<Vec3> {
+        let mut points = self.points.clone();
+        if let (Some(hover), 1..=2) = (self.hover, points.len()) {
+            points.push(hover);
+        }
+        points
+    }
+
+    /// Length between the first two points, in world units
+    pub fn distance(&self) -> Option<f32> {
+        match self.points.as_slice() {
+            [a, b, ..] => Some(a.distance(*b)),
+            _ => None,
+        }
+    }
+
+    /// Angle at the second point between the two segments, in radians
+    pub fn angle(&self) -> Option<f32> {
+        match self.points.as_slice() {
+            [a, b, c, ..] => angle_at(*a, *b, *c),
+            _ => None,
+        }
+    }
+}
+
+/// Angle at `b` between the segments to `a` and `c`, `None` if either has no length
+fn angle_at(a: Vec3, b: Vec3, c: Vec3) -> Option<f32> {
+    let (first, second) = ((a - b).try_normalize()?, (c - b).try_normalize()?);
+
+    Some(first.dot(second).clamp(-1.0, 1.0).acos())
+}
+
+impl MeasureTool {
+    pub fn update_system(
+        pointer: Res<Pointer>,
+        mut measurement: ResMut<Measurement>,
+        scene_bvh: Res<SceneBoundingVolumeHierarchy>,
+        q_meshes: Query<
+            (&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh),
+            With<UserSpace>,
+        >,
+        q_camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+    ) {
+        let hover = match (pointer.position, q_camera.get_single()) {
+            (Some(position), Ok((camera, camera_transform))) if !pointer.captured => {
+                Self::pick(position, camera, camera_transform, &scene_bvh, &q_meshes)
+            }
+            _ => None,
+        };
+
+        if measurement.hover != hover {
+            measurement.hover = hover;
+        }
+
+        if !pointer.select_triggered() || pointer.captured {
+            return;
+        }
+        if let Some(point) = hover {
+            measurement.push(point);
+        }
+    }
+
+    pub fn cleanup_system(mut measurement: ResMut<Measurement>) {
+        measurement.clear();
+    }
+
+    /// Point on the meshes under the pointer, moved to the nearest vertex of the hit mesh when
+    /// that is within [`VERTEX_SNAP_DISTANCE`] on the screen
+    pub fn pick(
+        pointer_position: Vec2,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        scene_bvh: &SceneBoundingVolumeHierarchy,
+        q_meshes: &Query<
+            (&BoundingVolumeHierarchy, &GlobalTransform, &EditableMesh),
+            With<UserSpace>,
+        >,
+    ) -> Option<Vec3> {
+        let ray = camera.viewport_to_world(camera_transform, pointer_position)?;
+        let ray_cast = RayCast3d::from_ray(ray, 1000.0);
+
+        let (entity, _, t) = scene_bvh.cast_ray(&ray_cast, |entity| {
+            let (bvh, transform, mesh) = q_meshes.get(entity).ok()?;
+            bvh.intersects_ray_at(&ray_cast, transform, mesh)
+        })?;
+        let point = ray.get_point(t);
+
+        let (bvh, transform, mesh) = q_meshes.get(entity).ok()?;
+        let vertex = bvh
+            .nearest_vertices(point, 1, transform, mesh)
+            .first()
+            .map(|(vertex, _)| transform.transform_point(mesh.vertex_positions[*vertex]))
+            .filter(|vertex| {
+                camera
+                    .world_to_viewport(camera_transform, *vertex)
+                    .is_some_and(|screen| screen.distance(pointer_position) <= VERTEX_SNAP_DISTANCE)
+            });
+
+        Some(vertex.unwrap_or(point))
+    }
+
+    /// Distance in world units between the first two points, if placed
+    pub fn distance(world: &World) -> Option<f32> {
+        world.resource::<Measurement>().distance()
+    }
+
+    /// Angle in degrees at the second point, once all three are placed
+    pub fn angle(world: &World) -> Option<f32> {
+        world.resource::<Measurement>().angle().map(f32::to_degrees)
+    }
+
+    pub fn clear(world: &mut World) {
+        world.resource_mut::<Measurement>().clear();
+    }
+}
+
+impl Plugin for MeasurePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Measurement>()
+            .init_gizmo_group::<MeasureGizmo>()
+            .add_systems(Startup, (Self::configure_gizmo, Self::setup_labels))
+            .add_systems(
+                Update,
+                (Self::draw_measurement, Self::update_labels)
+                    .after(ToolSet::Update)
+                    .after(PanOrbitCameraUpdate),
+            );
+    }
+}
+
+impl MeasurePlugin {
+    fn configure_gizmo(mut gizmo_config: ResMut<GizmoConfigStore>) {
+        let (config, _) = gizmo_config.config_mut::<MeasureGizmo>();
+        config.line_width = 2.0;
+        // Measured points are often on the far side of the mesh
+        config.depth_bias = -1.0;
+    }
+
+    fn setup_labels(mut commands: Commands) {
+        for index in 0..3 {
+            let root = commands
+                .spawn((
+                    MeasureLabel(index),
+                    NodeBundle {
+                        background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
+                        z_index: ZIndex::Global(i32::MAX),
+                        visibility: Visibility::Hidden,
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            padding: UiRect::all(Val::Px(2.0)),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                ))
+                .id();
+            let text = commands
+                .spawn((
+                    MeasureLabelText,
+                    TextBundle::from_section(
+                        "",
+                        TextStyle {
+                            font_size: 14.0,
+                            color: MEASURE_COLOR,
+                            ..default()
+                        },
+                    ),
+                ))
+                .id();
+            commands.entity(root).push_children(&[text]);
+        }
+    }
+
+    fn draw_measurement(
+        measurement: Res<Measurement>,
+        q_camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+        mut gizmos: Gizmos<MeasureGizmo>,
+    ) {
+        let points = measurement.preview();
+        let Ok((camera, camera_transform)) = q_camera.get_single() else {
+            return;
+        };
+        let Ok(facing) = Direction3d::new(camera_transform.back()) else {
+            return;
+        };
+
+        for point in measurement.points.iter().chain(measurement.hover.iter()) {
+            if let Some(radius) = pixels_to_world(camera, camera_transform, *point, POINT_RADIUS) {
+                gizmos.circle(*point, facing, radius, MEASURE_COLOR);
+            }
+        }
+
+        gizmos.linestrip(points.iter().copied(), MEASURE_COLOR);
+
+        // Arc between the two segments, at a third of the shorter one
+        if let [a, b, c] = points.as_slice() {
+            let (Some(first), Some(second)) =
+                ((*a - *b).try_normalize(), (*c - *b).try_normalize())
+            else {
+                return;
+            };
+            let radius = b.distance(*a).min(b.distance(*c)) / 3.0;
+            let rotation = Quat::from_rotation_arc(first, second);
+
+            gizmos.linestrip(
+                (0..=ARC_SEGMENTS).map(|step| {
+                    let direction =
+                        Quat::IDENTITY.slerp(rotation, step as f32 / ARC_SEGMENTS as f32) * first;
+                    *b + direction * radius
+                }),
+                MEASURE_COLOR.with_a(0.7),
+            );
+        }
+    }
+
+    fn update_labels(
+        measurement: Res<Measurement>,
+        grid: Res<GridSettings>,
+        q_camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+        mut q_labels: Query<(&MeasureLabel, &mut Style, &mut Visibility, &Children)>,
+        mut q_text: Query<&mut Text, With<MeasureLabelText>>,
+    ) {
+        let points = measurement.preview();
+        let camera = q_camera.get_single().ok();
+
+        let labels = [
+            points
+                .get(0..2)
+                .map(|segment| segment_label(segment, &grid)),
+            points
+                .get(1..3)
+                .map(|segment| segment_label(segment, &grid)),
+            match points.as_slice() {
+                [a, b, c] => {
+                    angle_at(*a, *b, *c).map(|angle| (*b, format!("{:.2}°", angle.to_degrees())))
+                }
+                _ => None,
+            },
+        ];
+
+        for (label, mut style, mut visibility, children) in &mut q_labels {
+            let placed = labels[label.0].as_ref().and_then(|(anchor, text)| {
+                let (camera, camera_transform) = camera?;
+                let screen = camera.world_to_viewport(camera_transform, *anchor)?;
+                Some((screen, text))
+            });
+
+            let Some((screen, text)) = placed else {
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            };
+
+            visibility.set_if_neq(Visibility::Visible);
+            // Beside the anchor rather than over the lines
+            style.left = Val::Px(screen.x + 8.0);
+            style.top = Val::Px(screen.y + 8.0);
+
+            for child in children.iter() {
+                if let Ok(mut label_text) = q_text.get_mut(*child) {
+                    if label_text.sections[0].value != *text {
+                        label_text.sections[0].value.clone_from(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Midpoint and length of a segment, in the units of the grid
+fn segment_label(segment: &[Vec3], grid: &GridSettings) -> (Vec3, String) {
+    let (a, b) = (segment[0], segment[1]);
+    let length = a.distance(b) / grid.units.unit_length();
+
+    (
+        (a + b) * 0.5,
+        format!("{length:.4} {}", grid.units.suffix()),
+    )
+}
+
+/// Size in world units of `pixels` on the screen, at the depth of `point`
+fn pixels_to_world(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    point: Vec3,
+    pixels: f32,
+) -> Option<f32> {
+    let screen = camera.world_to_viewport(camera_transform, point)?;
+    let ray = camera.viewport_to_world(camera_transform, screen + Vec2::X * pixels)?;
+    let plane = Plane3d::new(camera_transform.back());
+    let distance = ray.intersect_plane(point, plane)?;
+
+    Some(ray.get_point(distance).distance(point))
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::Measurement;
+
+    #[test]
+    fn test_measurement_distance_and_angle() {
+        let mut measurement = Measurement::default();
+        assert_eq!(measurement.distance(), None);
+
+        measurement.push(Vec3::new(3.0, 0.0, 0.0));
+        measurement.hover = Some(Vec3::ZERO);
+        // The hovered point is only previewed
+        assert_eq!(measurement.distance(), None);
+        assert_eq!(measurement.preview().len(), 2);
+
+        measurement.push(Vec3::ZERO);
+        measurement.push(Vec3::new(0.0, 0.0, 2.0));
+        assert!((measurement.distance().unwrap() - 3.0).abs() < 1e-6);
+        assert!((measurement.angle().unwrap().to_degrees() - 90.0).abs() < 1e-4);
+        // Complete, so nothing is previewed any more
+        assert_eq!(measurement.preview().len(), 3);
+
+        // A fourth point starts over
+        measurement.push(Vec3::ONE);
+        assert_eq!(measurement.points, vec![Vec3::ONE]);
+        assert_eq!(measurement.angle(), None);
+    }
+}
@@ -1,7 +1,13 @@
 use bevy::{ecs::system::SystemId, prelude::SystemSet};
 use wasm_bindgen::prelude::*;
-pub mod general;
 pub mod brush;
+pub mod cursor;
+pub mod elements;
+pub mod general;
+pub mod measure;
+pub mod modal;
+pub mod proportional;
+pub mod transform_space;
 
 pub struct Tool {
     pub startup_system: SystemId,
@@ -23,4 +29,5 @@ pub enum ToolType {
     Rotate,
     Scale,
     Cursor,
+    Measure,
 }
@@ -0,0 +1,779 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    math::BVec3,
+    prelude::*,
+    render::primitives::Aabb,
+};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    core::{
+        editable_mesh::{
+            bvh::BoundingVolumeHierarchy, update_moved_vertices, ActiveEdges, ActiveFaces,
+            ActiveVertices, EditableMesh,
+        },
+        editor::Focused,
+        fly_camera::FlyCameraPlugin,
+        gestures::Pointer,
+        interaction::{InteractionMode, InteractionSet},
+        keymap::{EditorAction, InputCapture},
+        pan_orbit_camera::{PanOrbitCameraUpdate, PrimaryCamera},
+        snapping::{SnapIndicator, Snapper},
+    },
+    utils,
+};
+
+use super::{
+    elements::{ElementDelta, ElementTransform},
+    general::{ScaleAction, TranslateAction},
+    proportional::ProportionalEditing,
+};
+
+/// Keyboard driven Move, Rotate and Scale of the focused entity, e.g. `G X 2.5 Enter`. X, Y and
+/// Z constrain to an axis, with Shift to the plane of the other two, and pressing the same key
+/// again switches to local axes. Typed values override the pointer. Enter or a click confirms,
+/// Escape or a right click restores the original transform.
+///
+/// In edit mode the selected vertices are transformed around their median instead, and with
+/// [`ProportionalEditing`] the wheel or Page Up and Page Down resize the falloff radius.
+pub struct ModalTransformPlugin;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModalTransformKind {
+    Move,
+    Rotate,
+    Scale,
+}
+
+/// Axes a modal transform is limited to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AxisConstraint {
+    pub axes: TranslateAction,
+    /// Follow the rotation the entity had when the transform started, instead of the world axes
+    pub local: bool,
+}
+
+/// Present while a modal transform is running
+#[derive(Resource, Clone, Debug)]
+pub struct ModalTransform {
+    pub kind: ModalTransformKind,
+    pub entity: Entity,
+    /// Restored when cancelling. In edit mode, a transform at the median of the selection with
+    /// the rotation of the entity.
+    pub start: Transform,
+    /// Result of the last update
+    pub current: Transform,
+    /// The vertices being transformed in edit mode
+    pub elements: Option<ElementTransform>,
+    pub constraint: Option<AxisConstraint>,
+    /// Typed value, evaluated as an expression
+    pub input: String,
+    /// Viewport position of the pointer when the transform started
+    start_pointer: Option<Vec2>,
+    prev_pointer: Option<Vec2>,
+    /// Radians swept by the pointer around the entity, before snapping
+    free_angle: f32,
+    /// Radians applied by the last update of a rotation
+    angle: f32,
+}
+
+/// Marker to find the container entity of the header so we can show/hide it
+#[derive(Component)]
+struct ModalTransformHeader;
+
+/// Marker to find the text entity of the header so we can update it
+#[derive(Component)]
+struct ModalTransformHeaderText;
+
+impl AxisConstraint {
+    /// X, Y or Z, or with Shift the plane of the two other axes
+    pub fn axes_for_key(key: KeyCode, shift: bool) -> Option<TranslateAction> {
+        let axes = match (key, shift) {
+            (KeyCode::KeyX, false) => TranslateAction::X,
+            (KeyCode::KeyY, false) => TranslateAction::Y,
+            (KeyCode::KeyZ, false) => TranslateAction::Z,
+            (KeyCode::KeyX, true) => TranslateAction::YZ,
+            (KeyCode::KeyY, true) => TranslateAction::XZ,
+            (KeyCode::KeyZ, true) => TranslateAction::XY,
+            _ => return None,
+        };
+
+        Some(axes)
+    }
+
+    /// Constraint after pressing the key for `axes`. The first press constrains to the world
+    /// axes, the second to the local ones and the third removes the constraint.
+    pub fn toggle(current: Option<Self>, axes: TranslateAction) -> Option<Self> {
+        match current {
+            Some(constraint) if constraint.axes == axes && !constraint.local => {
+                Some(Self { axes, local: true })
+            }
+            Some(constraint) if constraint.axes == axes => None,
+            _ => Some(Self { axes, local: false }),
+        }
+    }
+
+    /// World space directions of the constrained axes, for an entity with `rotation`
+    pub fn directions(&self, rotation: Quat) -> Vec<Vec3> {
+        let axes = self.axes.axes();
+
+        [(axes.x, Vec3::X), (axes.y, Vec3::Y), (axes.z, Vec3::Z)]
+            .into_iter()
+            .filter(|(enabled, _)| *enabled)
+            .map(|(_, axis)| if self.local { rotation * axis } else { axis })
+            .collect()
+    }
+
+    /// Axis to rotate about: the constrained axis, or the normal of the constrained plane
+    pub fn rotation_axis(&self, rotation: Quat) -> Option<Vec3> {
+        let axis = match self.axes {
+            TranslateAction::X | TranslateAction::YZ => Vec3::X,
+            TranslateAction::Y | TranslateAction::XZ => Vec3::Y,
+            TranslateAction::Z | TranslateAction::XY => Vec3::Z,
+            TranslateAction::XYZ => return None,
+        };
+
+        Some(if self.local { rotation * axis } else { axis })
+    }
+}
+
+impl ModalTransform {
+    pub fn new(kind: ModalTransformKind, entity: Entity, start: Transform) -> Self {
+        Self {
+            kind,
+            entity,
+            start,
+            current: start,
+            elements: None,
+            constraint: None,
+            input: String::new(),
+            start_pointer: None,
+            prev_pointer: None,
+            free_angle: 0.0,
+            angle: 0.0,
+        }
+    }
+
+    pub fn typed_value(&self) -> Option<f32> {
+        utils::expression::evaluate(&self.input)
+    }
+
+    /// Transform for a typed value: a distance along the constrained axes (X without a
+    /// constraint), an angle in degrees, or a scale factor. `view_axis` points towards the
+    /// viewer and is rotated about without a constraint.
+    pub fn apply_value(&mut self, value: f32, view_axis: Vec3) -> Transform {
+        let mut transform = self.start;
+
+        match self.kind {
+            ModalTransformKind::Move => {
+                let directions = self.constraint.map_or(vec![Vec3::X], |constraint| {
+                    constraint.directions(self.start.rotation)
+                });
+                transform.translation += directions.into_iter().sum::<Vec3>() * value;
+            }
+            ModalTransformKind::Rotate => {
+                self.angle = value.to_radians();
+                transform.rotation =
+                    Quat::from_axis_angle(self.rotation_axis(view_axis), self.angle)
+                        * self.start.rotation;
+            }
+            ModalTransformKind::Scale => {
+                transform.scale = Vec3::select(
+                    self.scale_axes(),
+                    self.start.scale * value,
+                    self.start.scale,
+                );
+            }
+        }
+
+        transform
+    }
+
+    /// Limits a free movement to the constrained axes
+    pub fn constrain_translation(&self, delta: Vec3) -> Vec3 {
+        match self.constraint {
+            Some(constraint) => constraint
+                .directions(self.start.rotation)
+                .into_iter()
+                .map(|direction| direction * delta.dot(direction))
+                .sum(),
+            None => delta,
+        }
+    }
+
+    fn rotation_axis(&self, view_axis: Vec3) -> Vec3 {
+        self.constraint
+            .and_then(|constraint| constraint.rotation_axis(self.start.rotation))
+            .unwrap_or(view_axis)
+    }
+
+    /// Scale is always along the local axes of the entity
+    fn scale_axes(&self) -> BVec3 {
+        self.constraint.map_or(BVec3::TRUE, |constraint| {
+            ScaleAction::from(constraint.axes).axes()
+        })
+    }
+
+    /// Header text describing the change from the start to `current`
+    pub fn readout(&self, current: &Transform) -> String {
+        let mut text = match self.kind {
+            ModalTransformKind::Move => {
+                let delta = current.translation - self.start.translation;
+                format!(
+                    "Move  X: {:.4}  Y: {:.4}  Z: {:.4}",
+                    delta.x, delta.y, delta.z
+                )
+            }
+            ModalTransformKind::Rotate => format!("Rotate  {:.2}°", self.angle.to_degrees()),
+            ModalTransformKind::Scale => {
+                let ratio = Vec3::select(
+                    self.start.scale.cmpeq(Vec3::ZERO),
+                    Vec3::ONE,
+                    current.scale / self.start.scale,
+                );
+                format!(
+                    "Scale  X: {:.4}  Y: {:.4}  Z: {:.4}",
+                    ratio.x, ratio.y, ratio.z
+                )
+            }
+        };
+
+        if let Some(constraint) = self.constraint {
+            text += &format!("  along {:?}", constraint.axes);
+            if constraint.local {
+                text += " local";
+            }
+        }
+
+        if !self.input.is_empty() {
+            text += &format!("  [{}]", self.input);
+        }
+
+        if let Some(radius) = self
+            .elements
+            .as_ref()
+            .and_then(|elements| elements.radius())
+        {
+            text += &format!("  Proportional size: {radius:.3}");
+        }
+
+        text
+    }
+}
+
+/// Characters that can be typed in a value
+fn is_value_char(c: char) -> bool {
+    c.is_ascii_digit() || "+-*/().".contains(c)
+}
+
+impl Plugin for ModalTransformPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, Self::setup_header).add_systems(
+            Update,
+            (
+                Self::modal_transform.run_if(resource_exists::<ModalTransform>),
+                Self::update_header,
+                Self::draw_proportional_radius.run_if(resource_exists::<ModalTransform>),
+            )
+                .chain()
+                .after(PanOrbitCameraUpdate)
+                .before(InteractionSet::IntersectionTest),
+        );
+    }
+}
+
+impl ModalTransformPlugin {
+    /// Starts a modal transform of the focused entity in object mode, or of its selected
+    /// vertices in edit mode. Not while flying or while another modal transform is running.
+    pub fn begin(world: &mut World, kind: ModalTransformKind) -> bool {
+        if world.contains_resource::<ModalTransform>() || FlyCameraPlugin::is_active(world) {
+            return false;
+        }
+
+        let mode = *world.resource::<InteractionMode>();
+        let proportional = *world.resource::<ProportionalEditing>();
+        let mut q_focused = world.query_filtered::<(
+            Entity,
+            &Transform,
+            &GlobalTransform,
+            Option<&EditableMesh>,
+            Option<&ActiveVertices>,
+            Option<&ActiveEdges>,
+            Option<&ActiveFaces>,
+        ), With<Focused>>();
+        let Some((entity, transform, global_transform, mesh, vertices, edges, faces)) =
+            q_focused.iter(world).next()
+        else {
+            return false;
+        };
+
+        let mut modal = match (mode, mesh, vertices, edges, faces) {
+            (InteractionMode::Object, ..) => ModalTransform::new(kind, entity, *transform),
+            (InteractionMode::Edit, Some(mesh), Some(vertices), Some(edges), Some(faces)) => {
+                let selected = mesh.selected_vertices(vertices, edges, faces);
+                let elements =
+                    ElementTransform::new(entity, global_transform, mesh, selected, &proportional);
+                let Some(median) = elements.median() else {
+                    return false;
+                };
+
+                let (_, rotation, _) = global_transform.to_scale_rotation_translation();
+                let start = Transform::from_translation(median).with_rotation(rotation);
+                let mut modal = ModalTransform::new(kind, entity, start);
+                modal.elements = Some(elements);
+                modal
+            }
+            _ => return false,
+        };
+        modal.start_pointer = world.resource::<Pointer>().position;
+        modal.prev_pointer = modal.start_pointer;
+
+        let scroll = modal.elements.is_some() && proportional.enabled;
+        world.insert_resource(modal);
+        // Axis keys and typed values would otherwise trigger actions
+        world.insert_resource(InputCapture {
+            passthrough: vec![EditorAction::ToggleFps],
+            scroll,
+        });
+
+        true
+    }
+
+    /// Ends the modal transform, restoring the original transform when cancelling
+    pub fn finish(world: &mut World, cancel: bool) {
+        let Some(modal) = world.remove_resource::<ModalTransform>() else {
+            return;
+        };
+
+        world.remove_resource::<InputCapture>();
+        world.resource_mut::<SnapIndicator>().0 = None;
+
+        if !cancel {
+            return;
+        }
+
+        match modal.elements {
+            Some(mut elements) => {
+                world.resource_scope(|world, mut render_meshes: Mut<Assets<Mesh>>| {
+                    let mut q_mesh = world.query::<(
+                        &mut EditableMesh,
+                        &mut BoundingVolumeHierarchy,
+                        &Handle<Mesh>,
+                    )>();
+                    if let Ok((mut mesh, mut bvh, handle)) = q_mesh.get_mut(world, modal.entity) {
+                        elements.restore(&mut mesh, &mut bvh, render_meshes.get_mut(handle));
+                    }
+                });
+                if let Some(mut entity) = world.get_entity_mut(modal.entity) {
+                    entity.remove::<Aabb>();
+                }
+            }
+            None => {
+                if let Some(mut transform) = world.get_mut::<Transform>(modal.entity) {
+                    *transform = modal.start;
+                }
+            }
+        }
+    }
+
+    /// Header text of the running modal transform
+    pub fn readout(world: &World) -> Option<String> {
+        let modal = world.get_resource::<ModalTransform>()?;
+
+        Some(modal.readout(&modal.current))
+    }
+
+    fn modal_transform(
+        mut commands: Commands,
+        mut modal: ResMut<ModalTransform>,
+        keys: Res<ButtonInput<KeyCode>>,
+        mouse: Res<ButtonInput<MouseButton>>,
+        mut evr_chars: EventReader<ReceivedCharacter>,
+        mut evr_scroll: EventReader<MouseWheel>,
+        mut pointer: ResMut<Pointer>,
+        mut proportional: ResMut<ProportionalEditing>,
+        q_camera: Query<(&Camera, &GlobalTransform), With<PrimaryCamera>>,
+        mut q_transform: Query<&mut Transform>,
+        // The snapper reads every mesh, while edit mode writes the focused one
+        mut mesh_or_snapper: ParamSet<(
+            Query<(
+                &mut EditableMesh,
+                &mut BoundingVolumeHierarchy,
+                &Handle<Mesh>,
+            )>,
+            Snapper,
+        )>,
+        mut render_meshes: ResMut<Assets<Mesh>>,
+    ) {
+        let Ok(mut transform) = q_transform.get_mut(modal.entity) else {
+            // The entity was deleted
+            commands.add(|world: &mut World| Self::finish(world, false));
+            return;
+        };
+
+        if keys.just_pressed(KeyCode::Escape) || mouse.just_pressed(MouseButton::Right) {
+            commands.add(|world: &mut World| Self::finish(world, true));
+            return;
+        }
+        if keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) || pointer.just_pressed {
+            pointer.consume();
+            commands.add(|world: &mut World| Self::finish(world, false));
+            return;
+        }
+
+        let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        for key in [KeyCode::KeyX, KeyCode::KeyY, KeyCode::KeyZ] {
+            if !keys.just_pressed(key) {
+                continue;
+            }
+            if let Some(axes) = AxisConstraint::axes_for_key(key, shift) {
+                modal.constraint = AxisConstraint::toggle(modal.constraint, axes);
+            }
+        }
+
+        if keys.just_pressed(KeyCode::Backspace) {
+            modal.input.pop();
+        }
+        for ev in evr_chars.read() {
+            let typed: String = ev.char.chars().filter(|c| is_value_char(*c)).collect();
+            modal.input.push_str(&typed);
+        }
+
+        let entity = modal.entity;
+        let radius_steps = Self::radius_steps(&mut evr_scroll, &keys);
+        if radius_steps != 0.0 && proportional.enabled {
+            let q_mesh = mesh_or_snapper.p0();
+            if let (Some(elements), Ok((mesh, ..))) = (modal.elements.as_mut(), q_mesh.get(entity))
+            {
+                proportional.scale_radius(radius_steps);
+                elements.update_weights(mesh, &proportional);
+            }
+        }
+
+        let Ok((camera, camera_transform)) = q_camera.get_single() else {
+            return;
+        };
+        let view_axis = camera_transform.back();
+
+        let next = match modal.typed_value() {
+            Some(value) => {
+                mesh_or_snapper.p1().indicator.0 = None;
+                modal.apply_value(value, view_axis)
+            }
+            None => {
+                let Some(next) = Self::follow_pointer(
+                    &mut modal,
+                    &pointer,
+                    camera,
+                    camera_transform,
+                    &mut mesh_or_snapper.p1(),
+                ) else {
+                    return;
+                };
+                next
+            }
+        };
+        modal.current = next;
+
+        let start = modal.start;
+        match modal.elements.as_mut() {
+            Some(elements) => {
+                let mut q_mesh = mesh_or_snapper.p0();
+                let Ok((mut mesh, mut bvh, handle)) = q_mesh.get_mut(entity) else {
+                    return;
+                };
+                let moved = elements.apply(&mut mesh, &ElementDelta::between(&start, &next));
+                update_moved_vertices(&mut mesh, &mut bvh, render_meshes.get_mut(handle), &moved);
+                // Recomputed by Bevy for frustum culling
+                commands.entity(entity).remove::<Aabb>();
+            }
+            None => *transform = next,
+        }
+    }
+
+    /// Lines scrolled up, plus Page Up minus Page Down
+    fn radius_steps(evr_scroll: &mut EventReader<MouseWheel>, keys: &ButtonInput<KeyCode>) -> f32 {
+        let mut steps: f32 = evr_scroll
+            .read()
+            .map(|ev| match ev.unit {
+                MouseScrollUnit::Line => ev.y,
+                // 16 pixels to a line, as for the camera
+                MouseScrollUnit::Pixel => ev.y / 16.0,
+            })
+            .sum();
+
+        if keys.just_pressed(KeyCode::PageUp) {
+            steps += 1.0;
+        }
+        if keys.just_pressed(KeyCode::PageDown) {
+            steps -= 1.0;
+        }
+
+        steps
+    }
+
+    /// Transform following the pointer since the start, with constraints and snapping
+    fn follow_pointer(
+        modal: &mut ModalTransform,
+        pointer: &Pointer,
+        camera: &Camera,
+        camera_transform: &GlobalTransform,
+        snapper: &mut Snapper,
+    ) -> Option<Transform> {
+        let view_axis = camera_transform.back();
+
+        let position = pointer.position?;
+        let start_pointer = *modal.start_pointer.get_or_insert(position);
+        let start = modal.start;
+        let center = camera.world_to_viewport(camera_transform, start.translation)?;
+
+        let mut next = start;
+        match modal.kind {
+            ModalTransformKind::Move => {
+                // Pointer positions on the plane facing the camera through the entity
+                let plane = Plane3d::new(view_axis);
+                let on_plane = |viewport_position: Vec2| {
+                    let ray = camera.viewport_to_world(camera_transform, viewport_position)?;
+                    let distance = ray.intersect_plane(start.translation, plane)?;
+                    Some(ray.get_point(distance))
+                };
+                let (from, to) = (on_plane(start_pointer)?, on_plane(position)?);
+
+                let free = start.translation + modal.constrain_translation(to - from);
+                let (axes, orientation) = match modal.constraint {
+                    Some(constraint) if constraint.local => {
+                        (constraint.axes.axes(), start.rotation)
+                    }
+                    Some(constraint) => (constraint.axes.axes(), Quat::IDENTITY),
+                    None => (BVec3::TRUE, Quat::IDENTITY),
+                };
+                next.translation = snapper.snap_translation(
+                    start.translation,
+                    free,
+                    axes,
+                    orientation,
+                    Some(position),
+                    modal.entity,
+                );
+            }
+            ModalTransformKind::Rotate => {
+                if let Some(prev_pointer) = modal.prev_pointer.replace(position) {
+                    // Viewport y points down, flip it so counter-clockwise on screen is positive
+                    let flip = Vec2::new(1.0, -1.0);
+                    let from = (prev_pointer - center) * flip;
+                    let to = (position - center) * flip;
+                    if from.length_squared() > f32::EPSILON && to.length_squared() > f32::EPSILON {
+                        modal.free_angle += from.angle_between(to);
+                    }
+                }
+
+                let axis = modal.rotation_axis(view_axis);
+                // Keep turning the way the pointer does when the axis points away from the viewer
+                let facing = if axis.dot(view_axis) < 0.0 { -1.0 } else { 1.0 };
+                modal.angle = snapper.snap_angle(modal.free_angle) * facing;
+                next.rotation = Quat::from_axis_angle(axis, modal.angle) * start.rotation;
+            }
+            ModalTransformKind::Scale => {
+                let factor = position.distance(center) / start_pointer.distance(center).max(1.0);
+                let axes = modal.scale_axes();
+                let free = Vec3::select(axes, start.scale * factor, start.scale);
+                next.scale = snapper.snap_scale(start.scale, free, axes);
+            }
+        }
+
+        Some(next)
+    }
+
+    fn setup_header(mut commands: Commands) {
+        let root = commands
+            .spawn((
+                ModalTransformHeader,
+                NodeBundle {
+                    background_color: BackgroundColor(Color::BLACK.with_a(0.5)),
+                    z_index: ZIndex::Global(i32::MAX),
+                    visibility: Visibility::Hidden,
+                    style: Style {
+                        position_type: PositionType::Absolute,
+                        // top-left corner, where the FPS counter is not
+                        left: Val::Percent(1.),
+                        top: Val::Percent(1.),
+                        right: Val::Auto,
+                        bottom: Val::Auto,
+                        padding: UiRect::all(Val::Px(4.0)),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+            ))
+            .id();
+        let text = commands
+            .spawn((
+                ModalTransformHeaderText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 16.0,
+                        color: Color::WHITE,
+                        ..default()
+                    },
+                ),
+            ))
+            .id();
+        commands.entity(root).push_children(&[text]);
+    }
+
+    fn update_header(
+        modal: Option<Res<ModalTransform>>,
+        mut q_header: Query<&mut Visibility, With<ModalTransformHeader>>,
+        mut q_text: Query<&mut Text, With<ModalTransformHeaderText>>,
+    ) {
+        let readout = modal.map(|modal| modal.readout(&modal.current));
+
+        for mut visibility in &mut q_header {
+            visibility.set_if_neq(match readout {
+                Some(_) => Visibility::Visible,
+                None => Visibility::Hidden,
+            });
+        }
+
+        let Some(readout) = readout else {
+            return;
+        };
+        for mut text in &mut q_text {
+            text.sections[0].value.clone_from(&readout);
+        }
+    }
+
+    /// Circle around the median showing how far proportional editing reaches
+    fn draw_proportional_radius(
+        modal: Res<ModalTransform>,
+        q_camera: Query<&GlobalTransform, With<PrimaryCamera>>,
+        mut gizmos: Gizmos,
+    ) {
+        let Some(radius) = modal
+            .elements
+            .as_ref()
+            .and_then(|elements| elements.radius())
+        else {
+            return;
+        };
+        let Ok(camera_transform) = q_camera.get_single() else {
+            return;
+        };
+        let Ok(normal) = Direction3d::new(camera_transform.back()) else {
+            return;
+        };
+
+        gizmos.circle(
+            modal.start.translation,
+            normal,
+            radius,
+            Color::WHITE.with_a(0.6),
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{AxisConstraint, ModalTransform, ModalTransformKind};
+    use crate::core::tools::general::TranslateAction;
+
+    #[test]
+    fn test_axis_key_cycles_constraint() {
+        let x = AxisConstraint::axes_for_key(KeyCode::KeyX, false).unwrap();
+        assert_eq!(x, TranslateAction::X);
+        assert_eq!(
+            AxisConstraint::axes_for_key(KeyCode::KeyX, true),
+            Some(TranslateAction::YZ)
+        );
+
+        let global = AxisConstraint::toggle(None, x);
+        assert_eq!(
+            global,
+            Some(AxisConstraint {
+                axes: x,
+                local: false
+            })
+        );
+
+        let local = AxisConstraint::toggle(global, x);
+        assert_eq!(
+            local,
+            Some(AxisConstraint {
+                axes: x,
+                local: true
+            })
+        );
+
+        assert_eq!(AxisConstraint::toggle(local, x), None);
+
+        // Another axis starts over in world space
+        assert_eq!(
+            AxisConstraint::toggle(local, TranslateAction::Y),
+            Some(AxisConstraint {
+                axes: TranslateAction::Y,
+                local: false
+            })
+        );
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let start = Transform::from_xyz(1.0, 2.0, 3.0)
+            .with_rotation(Quat::from_rotation_y(std::f32::consts::FRAC_PI_2));
+        let mut modal = ModalTransform::new(ModalTransformKind::Move, Entity::PLACEHOLDER, start);
+
+        modal.input = "2*1.25".to_string();
+        let value = modal.typed_value().unwrap();
+        assert_eq!(
+            modal.apply_value(value, Vec3::Z).translation,
+            Vec3::new(3.5, 2.0, 3.0)
+        );
+
+        // Local X of an entity turned around Y points along -Z
+        modal.constraint = Some(AxisConstraint {
+            axes: TranslateAction::X,
+            local: true,
+        });
+        let moved = modal.apply_value(value, Vec3::Z).translation;
+        assert!((moved - Vec3::new(1.0, 2.0, 0.5)).length() < 1e-5);
+
+        modal.kind = ModalTransformKind::Scale;
+        modal.constraint = Some(AxisConstraint {
+            axes: TranslateAction::YZ,
+            local: false,
+        });
+        assert_eq!(
+            modal.apply_value(2.0, Vec3::Z).scale,
+            Vec3::new(1.0, 2.0, 2.0)
+        );
+
+        modal.kind = ModalTransformKind::Rotate;
+        modal.constraint = None;
+        modal.input.clear();
+        let rotated = modal.apply_value(90.0, Vec3::Z);
+        assert!(rotated.rotation.abs_diff_eq(
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2) * start.rotation,
+            1e-5
+        ));
+        assert_eq!(modal.readout(&rotated), "Rotate  90.00°");
+    }
+
+    #[test]
+    fn test_constrain_translation_to_plane() {
+        let mut modal = ModalTransform::new(
+            ModalTransformKind::Move,
+            Entity::PLACEHOLDER,
+            Transform::IDENTITY,
+        );
+        modal.constraint = AxisConstraint::toggle(None, TranslateAction::XZ);
+
+        assert_eq!(
+            modal.constrain_translation(Vec3::new(1.0, 2.0, 3.0)),
+            Vec3::new(1.0, 0.0, 3.0)
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use bevy::{
+    math::bounding::{Aabb3d, BoundingVolume},
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use lox::{core::Mesh as LoxMesh, VertexHandle};
+use wasm_bindgen::prelude::*;
+
+use crate::core::editable_mesh::EditableMesh;
+
+/// How the influence of a transform fades from the selection to the edge of the radius
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProportionalFalloff {
+    #[default]
+    Smooth,
+    Sphere,
+    Root,
+    Linear,
+    Sharp,
+    Constant,
+}
+
+/// Proportional (soft) editing: edit-mode transforms also move unselected vertices within
+/// `radius` of the selection, weighted by the falloff
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct ProportionalEditing {
+    pub enabled: bool,
+    pub falloff: ProportionalFalloff,
+    /// World space distance from the selection at which the influence reaches zero
+    pub radius: f32,
+    /// Measure distances along the edges, so parts that are close but not connected stay put
+    pub connected_only: bool,
+}
+
+impl Default for ProportionalEditing {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            falloff: ProportionalFalloff::Smooth,
+            radius: 1.0,
+            connected_only: false,
+        }
+    }
+}
+
+impl ProportionalFalloff {
+    /// Influence at `distance` from the selection: 1 on the selection, 0 at the radius and beyond
+    pub fn weight(self, distance: f32, radius: f32) -> f32 {
+        if distance >= radius {
+            return 0.0;
+        }
+
+        let t = 1.0 - distance / radius;
+        match self {
+            ProportionalFalloff::Smooth => t * t * (3.0 - 2.0 * t),
+            ProportionalFalloff::Sphere => (t * (2.0 - t)).sqrt(),
+            ProportionalFalloff::Root => t.sqrt(),
+            ProportionalFalloff::Linear => t,
+            ProportionalFalloff::Sharp => t * t,
+            ProportionalFalloff::Constant => 1.0,
+        }
+    }
+}
+
+impl ProportionalEditing {
+    const MIN_RADIUS: f32 = 0.001;
+    const MAX_RADIUS: f32 = 10000.0;
+    /// Growth of the radius per scroll line
+    const RADIUS_STEP: f32 = 1.1;
+
+    /// Grows the radius for positive `steps`, shrinks it for negative ones
+    pub fn scale_radius(&mut self, steps: f32) {
+        self.radius =
+            (self.radius * Self::RADIUS_STEP.powf(steps)).clamp(Self::MIN_RADIUS, Self::MAX_RADIUS);
+    }
+
+    /// Weights of the vertices a transform of `selected` moves, with `positions` giving the
+    /// world position of a vertex. Selected vertices weigh 1. Without proportional editing, only
+    /// they are returned.
+    pub fn weights(
+        &self,
+        mesh: &EditableMesh,
+        positions: impl Fn(VertexHandle) -> Vec3,
+        selected: &HashSet<VertexHandle>,
+    ) -> Vec<(VertexHandle, f32)> {
+        if !self.enabled || selected.is_empty() {
+            return selected.iter().map(|vertex| (*vertex, 1.0)).collect();
+        }
+
+        let distances = if self.connected_only {
+            geodesic_distances(mesh, &positions, selected, self.radius)
+        } else {
+            euclidean_distances(mesh, &positions, selected, self.radius)
+        };
+
+        distances
+            .into_iter()
+            .filter_map(|(vertex, distance)| {
+                let weight = if selected.contains(&vertex) {
+                    1.0
+                } else {
+                    self.falloff.weight(distance, self.radius)
+                };
+                (weight > 0.0).then_some((vertex, weight))
+            })
+            .collect()
+    }
+}
+
+/// Straight line distance to the closest selected vertex, for every vertex within `radius`
+fn euclidean_distances(
+    mesh: &EditableMesh,
+    positions: &impl Fn(VertexHandle) -> Vec3,
+    selected: &HashSet<VertexHandle>,
+    radius: f32,
+) -> HashMap<VertexHandle, f32> {
+    let selected_positions: Vec<Vec3> = selected.iter().map(|vertex| positions(*vertex)).collect();
+    let Some(first) = selected_positions.first() else {
+        return HashMap::new();
+    };
+
+    // Cheap rejection of the vertices far away from every selected one
+    let bounds = selected_positions
+        .iter()
+        .fold(Aabb3d::new(*first, Vec3::ZERO), |bounds, position| {
+            bounds.merge(&Aabb3d::new(*position, Vec3::ZERO))
+        })
+        .grow(Vec3::splat(radius));
+
+    mesh.structure
+        .vertex_handles()
+        .filter_map(|vertex| {
+            let position = positions(vertex);
+            if position.cmplt(bounds.min).any() || position.cmpgt(bounds.max).any() {
+                return None;
+            }
+
+            let distance = selected_positions
+                .iter()
+                .map(|selected| selected.distance(position))
+                .fold(f32::INFINITY, f32::min);
+            (distance < radius).then_some((vertex, distance))
+        })
+        .collect()
+}
+
+/// Vertex waiting in the geodesic search, ordered so the closest one is popped first
+#[derive(Clone, Copy, PartialEq)]
+struct GeodesicCandidate {
+    distance: f32,
+    vertex: VertexHandle,
+}
+
+impl Eq for GeodesicCandidate {}
+
+impl Ord for GeodesicCandidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+impl PartialOrd for GeodesicCandidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shortest distance along the edges to the selection, for every vertex within `radius`.
+/// Dijkstra's algorithm seeded with every selected vertex.
+fn geodesic_distances(
+    mesh: &EditableMesh,
+    positions: &impl Fn(VertexHandle) -> Vec3,
+    selected: &HashSet<VertexHandle>,
+    radius: f32,
+) -> HashMap<VertexHandle, f32> {
+    let mut distances: HashMap<VertexHandle, f32> =
+        selected.iter().map(|vertex| (*vertex, 0.0)).collect();
+    let mut queue: BinaryHeap<GeodesicCandidate> = selected
+        .iter()
+        .map(|vertex| GeodesicCandidate {
+            distance: 0.0,
+            vertex: *vertex,
+        })
+        .collect();
+
+    while let Some(GeodesicCandidate { distance, vertex }) = queue.pop() {
+        // Already reached through a shorter path
+        if distances.get(&vertex).is_some_and(|best| distance > *best) {
+            continue;
+        }
+
+        let position = positions(vertex);
+        for neighbor in mesh.structure.get_ref(vertex).adjacent_vertices() {
+            let neighbor = neighbor.handle();
+            let next = distance + position.distance(positions(neighbor));
+            if next >= radius || distances.get(&neighbor).is_some_and(|best| next >= *best) {
+                continue;
+            }
+
+            distances.insert(neighbor, next);
+            queue.push(GeodesicCandidate {
+                distance: next,
+                vertex: neighbor,
+            });
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::{prelude::*, utils::HashSet};
+    use lox::{core::Mesh as LoxMesh, VertexHandle};
+
+    use super::{ProportionalEditing, ProportionalFalloff};
+    use crate::core::editable_mesh::EditableMesh;
+
+    #[test]
+    fn test_falloff_curves() {
+        for falloff in [
+            ProportionalFalloff::Smooth,
+            ProportionalFalloff::Sphere,
+            ProportionalFalloff::Root,
+            ProportionalFalloff::Linear,
+            ProportionalFalloff::Sharp,
+            ProportionalFalloff::Constant,
+        ] {
+            assert_eq!(falloff.weight(0.0, 2.0), 1.0, "{falloff:?}");
+            assert_eq!(falloff.weight(2.0, 2.0), 0.0, "{falloff:?}");
+            assert_eq!(falloff.weight(3.0, 2.0), 0.0, "{falloff:?}");
+        }
+
+        assert_eq!(ProportionalFalloff::Linear.weight(0.5, 2.0), 0.75);
+        assert_eq!(ProportionalFalloff::Sharp.weight(1.0, 2.0), 0.25);
+        assert_eq!(ProportionalFalloff::Smooth.weight(1.0, 2.0), 0.5);
+        assert_eq!(ProportionalFalloff::Root.weight(1.5, 2.0), 0.5);
+        assert_eq!(ProportionalFalloff::Constant.weight(1.9, 2.0), 1.0);
+    }
+
+    #[test]
+    fn test_connected_only_stays_on_the_surface() {
+        // Every side of the cuboid has its own vertices, so the sides are not connected
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let position = |vertex: VertexHandle| mesh.vertex_positions[vertex];
+
+        let corner = Vec3::splat(0.5);
+        let top_corner = mesh
+            .structure
+            .vertex_handles()
+            .find(|vertex| {
+                mesh.vertex_normals[*vertex].y > 0.5 && position(*vertex).distance(corner) < 1e-5
+            })
+            .unwrap();
+        let selected = HashSet::from_iter([top_corner]);
+
+        let mut proportional = ProportionalEditing {
+            enabled: true,
+            falloff: ProportionalFalloff::Linear,
+            radius: 1.2,
+            connected_only: false,
+        };
+
+        // The same corner on the two other sides is at distance zero
+        let euclidean = proportional.weights(&mesh, position, &selected);
+        let at_corner = euclidean
+            .iter()
+            .filter(|(vertex, _)| position(*vertex).distance(corner) < 1e-5)
+            .count();
+        assert_eq!(at_corner, 3);
+        assert!(euclidean
+            .iter()
+            .all(|(vertex, weight)| position(*vertex).distance(corner) >= 1e-5 || *weight == 1.0));
+
+        // Only the top side is reached along the edges, its far corner is beyond the radius
+        proportional.connected_only = true;
+        let geodesic = proportional.weights(&mesh, position, &selected);
+        assert_eq!(geodesic.len(), 3);
+        assert!(geodesic
+            .iter()
+            .all(|(vertex, _)| mesh.vertex_normals[*vertex].y > 0.5));
+        let edge_neighbor = geodesic
+            .iter()
+            .find(|(vertex, _)| *vertex != top_corner)
+            .unwrap();
+        assert!((edge_neighbor.1 - (1.0 - 1.0 / 1.2)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_disabled_moves_only_the_selection() {
+        let mesh: EditableMesh = (&Cuboid::from_size(Vec3::splat(1.0)).mesh()).into();
+        let vertex = mesh.structure.vertex_handles().next().unwrap();
+
+        let weights = ProportionalEditing::default().weights(
+            &mesh,
+            |vertex| mesh.vertex_positions[vertex],
+            &HashSet::from_iter([vertex]),
+        );
+
+        assert_eq!(weights, vec![(vertex, 1.0)]);
+    }
+}
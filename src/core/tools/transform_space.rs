@@ -0,0 +1,420 @@
+use bevy::{prelude::*, utils::HashSet};
+use lox::{core::Mesh as LoxMesh, FaceHandle, Handle, VertexHandle};
+use wasm_bindgen::prelude::*;
+
+use crate::core::{
+    editable_mesh::{ActiveEdges, ActiveFaces, ActiveVertices, EditableMesh},
+    editor::{Cursor3d, Focused},
+    interaction::{InteractionCache, InteractionMode, InteractionSet},
+    pan_orbit_camera::PrimaryCamera,
+};
+
+use super::ToolSet;
+
+/// Orientation of the transform gizmos and where they rotate and scale around, resolved every
+/// frame into a [`TransformFrame`] for the Move, Rotate and Scale tools
+pub struct TransformSpacePlugin;
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TransformOrientation {
+    #[default]
+    Global,
+    /// Axes of the active object
+    Local,
+    /// Average normal of the selected elements in edit mode, local axes in object mode
+    Normal,
+    /// Aligned with the viewport, Z pointing at the camera
+    View,
+    Cursor,
+    /// The active saved orientation
+    Custom,
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PivotPoint {
+    #[default]
+    MedianPoint,
+    BoundingBoxCenter,
+    /// Rotates and scales every object around its own origin
+    IndividualOrigins,
+    Cursor,
+    /// The active object, or the selected vertex closest to the last click in edit mode
+    ActiveElement,
+}
+
+/// Orientation saved by the user under a name
+#[derive(Clone, Debug, PartialEq)]
+pub struct CustomOrientation {
+    pub name: String,
+    pub rotation: Quat,
+}
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct TransformSpace {
+    pub orientation: TransformOrientation,
+    pub pivot: PivotPoint,
+    pub custom_orientations: Vec<CustomOrientation>,
+    /// Index into `custom_orientations` used by [`TransformOrientation::Custom`]
+    pub active_custom: usize,
+}
+
+/// The resolved [`TransformSpace`] of the current selection
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TransformFrame {
+    /// Where the gizmos sit and what they rotate and scale around
+    pub pivot: Vec3,
+    /// Rotation from the gizmo axes to the world
+    pub orientation: Quat,
+    /// Rotations and scales leave the object origins in place
+    pub individual_origins: bool,
+}
+
+impl Default for TransformFrame {
+    fn default() -> Self {
+        Self {
+            pivot: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            individual_origins: false,
+        }
+    }
+}
+
+impl TransformSpace {
+    /// Saves `rotation` under `name`, replacing an orientation with the same name, and makes it
+    /// the active custom orientation
+    pub fn save_custom(&mut self, name: &str, rotation: Quat) {
+        let orientation = CustomOrientation {
+            name: name.to_string(),
+            rotation,
+        };
+
+        match self.custom_index(name) {
+            Some(index) => {
+                self.custom_orientations[index] = orientation;
+                self.active_custom = index;
+            }
+            None => {
+                self.custom_orientations.push(orientation);
+                self.active_custom = self.custom_orientations.len() - 1;
+            }
+        }
+    }
+
+    /// Makes a saved orientation the active one and switches to it
+    pub fn select_custom(&mut self, name: &str) -> bool {
+        let Some(index) = self.custom_index(name) else {
+            return false;
+        };
+
+        self.active_custom = index;
+        self.orientation = TransformOrientation::Custom;
+        true
+    }
+
+    pub fn remove_custom(&mut self, name: &str) -> bool {
+        let Some(index) = self.custom_index(name) else {
+            return false;
+        };
+
+        self.custom_orientations.remove(index);
+        if self.active_custom >= index {
+            self.active_custom = self.active_custom.saturating_sub(1);
+        }
+        if self.custom_orientations.is_empty() && self.orientation == TransformOrientation::Custom {
+            self.orientation = TransformOrientation::Global;
+        }
+        true
+    }
+
+    pub fn custom_rotation(&self) -> Option<Quat> {
+        self.custom_orientations
+            .get(self.active_custom)
+            .map(|orientation| orientation.rotation)
+    }
+
+    fn custom_index(&self, name: &str) -> Option<usize> {
+        self.custom_orientations
+            .iter()
+            .position(|orientation| orientation.name == name)
+    }
+}
+
+/// Mean of the points
+pub fn median_point(points: &[Vec3]) -> Option<Vec3> {
+    if points.is_empty() {
+        return None;
+    }
+
+    Some(points.iter().sum::<Vec3>() / points.len() as f32)
+}
+
+/// Center of the axis aligned box around the points
+pub fn bounding_box_center(points: &[Vec3]) -> Option<Vec3> {
+    let first = *points.first()?;
+    let (min, max) = points.iter().fold((first, first), |(min, max), point| {
+        (min.min(*point), max.max(*point))
+    });
+
+    Some((min + max) / 2.0)
+}
+
+/// Pivot of the selected `points`. `active` is the position of the active element, if any.
+pub fn pivot_point(
+    pivot: PivotPoint,
+    points: &[Vec3],
+    active: Option<Vec3>,
+    cursor: Vec3,
+) -> Option<Vec3> {
+    match pivot {
+        PivotPoint::MedianPoint | PivotPoint::IndividualOrigins => median_point(points),
+        PivotPoint::BoundingBoxCenter => bounding_box_center(points),
+        PivotPoint::Cursor => Some(cursor),
+        PivotPoint::ActiveElement => active.or_else(|| median_point(points)),
+    }
+}
+
+/// Orientation with Y along `normal`, and X as close as possible to the X axis of `reference`
+pub fn orientation_from_normal(normal: Vec3, reference: Quat) -> Option<Quat> {
+    let y = normal.try_normalize()?;
+
+    let x = [reference * Vec3::X, reference * Vec3::Z]
+        .into_iter()
+        .find_map(|axis| (axis - y * axis.dot(y)).try_normalize())?;
+    let z = x.cross(y);
+
+    Some(Quat::from_mat3(&Mat3::from_cols(x, y, z)))
+}
+
+impl Plugin for TransformSpacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TransformSpace>()
+            .init_resource::<TransformFrame>()
+            .add_systems(
+                Update,
+                Self::update_transform_frame
+                    .after(InteractionSet::ActivesUpdate)
+                    .before(ToolSet::Update),
+            );
+    }
+}
+
+impl TransformSpacePlugin {
+    /// Median of the selection: the origins of the focused objects, or the selected vertices of
+    /// the focused meshes in edit mode
+    pub fn selection_median(world: &mut World) -> Option<Vec3> {
+        let mode = *world.resource::<InteractionMode>();
+        let mut q_focused = world.query_filtered::<(
+            &Transform,
+            &GlobalTransform,
+            Option<&EditableMesh>,
+            Option<&ActiveVertices>,
+            Option<&ActiveEdges>,
+            Option<&ActiveFaces>,
+        ), With<Focused>>();
+
+        let mut points = vec![];
+        for (transform, global_transform, mesh, vertices, edges, faces) in q_focused.iter(world) {
+            match (mode, mesh, vertices, edges, faces) {
+                (InteractionMode::Edit, Some(mesh), Some(vertices), Some(edges), Some(faces)) => {
+                    points.extend(mesh.selected_vertices(vertices, edges, faces).iter().map(
+                        |vertex| global_transform.transform_point(mesh.vertex_positions[*vertex]),
+                    ));
+                }
+                _ => points.push(transform.translation),
+            }
+        }
+
+        median_point(&points)
+    }
+
+    fn update_transform_frame(
+        space: Res<TransformSpace>,
+        mode: Res<InteractionMode>,
+        cursor: Res<Cursor3d>,
+        q_focused: Query<
+            (
+                &Transform,
+                &GlobalTransform,
+                Option<&EditableMesh>,
+                Option<&ActiveVertices>,
+                Option<&ActiveEdges>,
+                Option<&ActiveFaces>,
+                Option<&InteractionCache>,
+            ),
+            With<Focused>,
+        >,
+        q_camera: Query<&Transform, (With<PrimaryCamera>, Without<Focused>)>,
+        mut frame: ResMut<TransformFrame>,
+    ) {
+        let Some((active_transform, active_global_transform, mesh, vertices, edges, faces, cache)) =
+            q_focused.iter().next()
+        else {
+            return;
+        };
+        // Transforms rather than global transforms, which lag a frame behind the tools
+        let (mut local_rotation, active_origin) =
+            (active_transform.rotation, active_transform.translation);
+
+        let mut points: Vec<Vec3> = q_focused
+            .iter()
+            .map(|(transform, ..)| transform.translation)
+            .collect();
+        let mut active = Some(active_origin);
+        let mut normal = None;
+
+        // In edit mode the selected elements of the focused mesh stand in for the objects
+        if let (InteractionMode::Edit, Some(mesh), Some(vertices), Some(edges), Some(faces)) =
+            (*mode, mesh, vertices, edges, faces)
+        {
+            let selected = mesh.selected_vertices(vertices, edges, faces);
+
+            if !selected.is_empty() {
+                // The object stays put while its elements are edited, so its global transform is
+                // current and places meshes under a parent correctly
+                let (_, rotation, _) = active_global_transform.to_scale_rotation_translation();
+                local_rotation = rotation;
+                points = selected
+                    .iter()
+                    .map(|vertex| {
+                        active_global_transform.transform_point(mesh.vertex_positions[*vertex])
+                    })
+                    .collect();
+
+                let clicked = cache.and_then(|cache| cache.0).map(|(_, point)| point);
+                active = clicked.and_then(|clicked| {
+                    points.iter().copied().min_by(|a, b| {
+                        a.distance_squared(clicked)
+                            .total_cmp(&b.distance_squared(clicked))
+                    })
+                });
+
+                normal = Some(Self::selection_normal(mesh, &selected, faces));
+            }
+        }
+
+        let camera_rotation = q_camera
+            .get_single()
+            .map_or(Quat::IDENTITY, |camera| camera.rotation);
+
+        let orientation = match space.orientation {
+            TransformOrientation::Global => Quat::IDENTITY,
+            TransformOrientation::Local => local_rotation,
+            TransformOrientation::Normal => normal
+                .and_then(|normal| orientation_from_normal(local_rotation * normal, local_rotation))
+                .unwrap_or(local_rotation),
+            TransformOrientation::View => camera_rotation,
+            TransformOrientation::Cursor => cursor.orientation,
+            TransformOrientation::Custom => space.custom_rotation().unwrap_or(Quat::IDENTITY),
+        };
+
+        let next = TransformFrame {
+            pivot: pivot_point(space.pivot, &points, active, cursor.position)
+                .unwrap_or(active_origin),
+            orientation,
+            individual_origins: space.pivot == PivotPoint::IndividualOrigins
+                && *mode == InteractionMode::Object,
+        };
+        frame.set_if_neq(next);
+    }
+
+    /// Average normal of the selection in mesh space: of the active faces if there are any,
+    /// otherwise of the selected vertices
+    fn selection_normal(
+        mesh: &EditableMesh,
+        selected: &HashSet<VertexHandle>,
+        faces: &ActiveFaces,
+    ) -> Vec3 {
+        let face_normals: Vec3 = faces
+            .iter()
+            .map(|index| FaceHandle::new(*index))
+            .filter(|face| mesh.structure.contains_face(*face))
+            .map(|face| mesh.face_normals[face])
+            .sum();
+
+        if face_normals != Vec3::ZERO {
+            return face_normals;
+        }
+
+        selected
+            .iter()
+            .map(|vertex| mesh.vertex_normals[*vertex])
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bevy::prelude::*;
+
+    use super::{
+        bounding_box_center, orientation_from_normal, pivot_point, PivotPoint,
+        TransformOrientation, TransformSpace,
+    };
+
+    #[test]
+    fn test_pivot_points() {
+        let points = [
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(5.0, 3.0, 0.0),
+        ];
+        let cursor = Vec3::new(0.0, 0.0, 9.0);
+
+        assert_eq!(
+            pivot_point(PivotPoint::MedianPoint, &points, None, cursor),
+            Some(Vec3::new(2.0, 1.0, 0.0))
+        );
+        assert_eq!(
+            pivot_point(PivotPoint::BoundingBoxCenter, &points, None, cursor),
+            Some(Vec3::new(2.5, 1.5, 0.0))
+        );
+        assert_eq!(
+            pivot_point(PivotPoint::Cursor, &points, None, cursor),
+            Some(cursor)
+        );
+        assert_eq!(
+            pivot_point(PivotPoint::ActiveElement, &points, Some(points[1]), cursor),
+            Some(points[1])
+        );
+        assert_eq!(bounding_box_center(&[]), None);
+    }
+
+    #[test]
+    fn test_orientation_from_normal() {
+        let normal = Vec3::new(1.0, 1.0, 0.0).normalize();
+        let rotation = orientation_from_normal(normal, Quat::IDENTITY).unwrap();
+
+        assert!((rotation * Vec3::Y - normal).length() < 1e-5);
+        // X stays in the plane of the reference X axis
+        assert!((rotation * Vec3::Z - Vec3::Z).length() < 1e-5);
+
+        // A normal along the reference X falls back to its Z axis
+        let rotation = orientation_from_normal(Vec3::X, Quat::IDENTITY).unwrap();
+        assert!((rotation * Vec3::Y - Vec3::X).length() < 1e-5);
+        assert!(orientation_from_normal(Vec3::ZERO, Quat::IDENTITY).is_none());
+    }
+
+    #[test]
+    fn test_custom_orientations() {
+        let mut space = TransformSpace::default();
+        let tilted = Quat::from_rotation_x(0.5);
+
+        space.save_custom("tilted", tilted);
+        space.save_custom("turned", Quat::from_rotation_y(1.0));
+        assert!(space.select_custom("tilted"));
+        assert_eq!(space.orientation, TransformOrientation::Custom);
+        assert_eq!(space.custom_rotation(), Some(tilted));
+
+        // Saving under an existing name replaces it
+        space.save_custom("tilted", Quat::IDENTITY);
+        assert_eq!(space.custom_orientations.len(), 2);
+        assert_eq!(space.custom_rotation(), Some(Quat::IDENTITY));
+
+        assert!(space.remove_custom("tilted"));
+        assert!(space.remove_custom("turned"));
+        assert!(!space.remove_custom("turned"));
+        assert_eq!(space.orientation, TransformOrientation::Global);
+    }
+}
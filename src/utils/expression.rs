@@ -0,0 +1,114 @@
+/// Evaluates a typed arithmetic expression like `2.5*4 - (1/2)`. Supports `+ - * /`, unary
+/// minus, parentheses and decimal numbers. Returns `None` if the text is not a complete expression.
+pub fn evaluate(text: &str) -> Option<f32> {
+    let tokens: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+
+    let value = parser.expression()?;
+
+    if parser.position != parser.tokens.len() || !value.is_finite() {
+        return None;
+    }
+
+    Some(value)
+}
+
+struct Parser {
+    tokens: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn eat(&mut self, token: char) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Option<f32> {
+        let mut value = self.term()?;
+
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    /// term := factor (('*' | '/') factor)*
+    fn term(&mut self) -> Option<f32> {
+        let mut value = self.factor()?;
+
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                value /= self.factor()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    /// factor := '-' factor | '(' expression ')' | number
+    fn factor(&mut self) -> Option<f32> {
+        if self.eat('-') {
+            return Some(-self.factor()?);
+        }
+
+        if self.eat('(') {
+            let value = self.expression()?;
+            return self.eat(')').then_some(value);
+        }
+
+        self.number()
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        let start = self.position;
+        while matches!(self.peek(), Some(c) if c.is_ascii_digit() || c == '.') {
+            self.position += 1;
+        }
+
+        let text: String = self.tokens[start..self.position].iter().collect();
+        text.parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::evaluate;
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("2.5"), Some(2.5));
+        assert_eq!(evaluate(" 1 + 2 * 3 "), Some(7.0));
+        assert_eq!(evaluate("(1 + 2) * 3"), Some(9.0));
+        assert_eq!(evaluate("-2*-3"), Some(6.0));
+        assert_eq!(evaluate("10/4-.5"), Some(2.0));
+    }
+
+    #[test]
+    fn test_evaluate_incomplete() {
+        assert_eq!(evaluate(""), None);
+        assert_eq!(evaluate("2*"), None);
+        assert_eq!(evaluate("(2"), None);
+        assert_eq!(evaluate("1.2.3"), None);
+        assert_eq!(evaluate("1/0"), None);
+    }
+}
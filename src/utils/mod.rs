@@ -1,3 +1,4 @@
+pub mod expression;
 pub mod multiples;
 pub mod projection;
 pub mod quartic;
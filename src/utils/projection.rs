@@ -1,4 +1,4 @@
-use bevy::math::Vec3;
+use bevy::{prelude::*, render::primitives::Frustum};
 
 pub fn project_to_plane(
     camera_position: Vec3,
@@ -23,3 +23,45 @@ pub fn compute_orientation_angles(direction: Vec3) -> (f32, f32, f32) {
         xy.angle_between(Vec3::Y),
     )
 }
+
+/// Builds the world-space frustum covering the screen rectangle between `min` and `max`
+/// (viewport coordinates, origin at the top left), cut off at `far` units from the camera.
+pub fn screen_rect_frustum(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    far: f32,
+    min: Vec2,
+    max: Vec2,
+) -> Option<Frustum> {
+    let target_size = camera.logical_viewport_size()?;
+
+    let to_ndc = |position: Vec2| {
+        Vec2::new(
+            position.x / target_size.x * 2.0 - 1.0,
+            1.0 - position.y / target_size.y * 2.0,
+        )
+    };
+
+    let (corner_a, corner_b) = (to_ndc(min), to_ndc(max));
+    let (ndc_min, ndc_max) = (corner_a.min(corner_b), corner_a.max(corner_b));
+    let ndc_size = ndc_max - ndc_min;
+
+    if ndc_size.x <= f32::EPSILON || ndc_size.y <= f32::EPSILON {
+        return None;
+    }
+
+    // Stretches the rectangle over the whole clip space, so the regular frustum extraction yields its planes
+    let ndc_center = (ndc_min + ndc_max) * 0.5;
+    let rect_to_clip = Mat4::from_scale(Vec3::new(2.0 / ndc_size.x, 2.0 / ndc_size.y, 1.0))
+        * Mat4::from_translation(Vec3::new(-ndc_center.x, -ndc_center.y, 0.0));
+
+    let view_projection =
+        rect_to_clip * camera.projection_matrix() * camera_transform.compute_matrix().inverse();
+
+    Some(Frustum::from_view_projection_custom_far(
+        &view_projection,
+        &camera_transform.translation(),
+        &camera_transform.back(),
+        far,
+    ))
+}